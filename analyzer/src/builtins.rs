@@ -0,0 +1,52 @@
+use crate::signature::{Arity, Signature};
+
+// functions every riven program can call without defining them,
+// `printf` comes from C and is kept for older programs like fib.riven
+pub const BUILTIN_FUNCTIONS: &[Signature] = &[
+  Signature {
+    name: "print",
+    arity: Arity::AtLeast(0),
+  },
+  Signature {
+    name: "println",
+    arity: Arity::AtLeast(0),
+  },
+  Signature {
+    name: "input",
+    arity: Arity::Exact(1),
+  },
+  Signature {
+    name: "input_number",
+    arity: Arity::Exact(1),
+  },
+  Signature {
+    name: "append",
+    arity: Arity::Exact(2),
+  },
+  Signature {
+    name: "len",
+    arity: Arity::Exact(1),
+  },
+  Signature {
+    name: "keys",
+    arity: Arity::Exact(1),
+  },
+  Signature {
+    name: "has",
+    arity: Arity::Exact(2),
+  },
+  Signature {
+    name: "printf",
+    arity: Arity::AtLeast(1),
+  },
+];
+
+pub fn find_builtin(name: &str) -> Option<&'static Signature> {
+  BUILTIN_FUNCTIONS
+    .iter()
+    .find(|signature| signature.name == name)
+}
+
+pub fn is_builtin(name: &str) -> bool {
+  find_builtin(name).is_some()
+}
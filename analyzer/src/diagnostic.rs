@@ -0,0 +1,55 @@
+use std::fmt::{self, Display};
+
+use parser::location::Location;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+  Error,
+  Warning,
+}
+
+impl Display for Severity {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      Severity::Error => write!(f, "error"),
+      Severity::Warning => write!(f, "warning"),
+    }
+  }
+}
+
+// a problem found in riven source code, e.g.
+// `line 3 column 5: error: undefined variable `nmae``
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+  pub severity: Severity,
+  pub message: String,
+  pub location: Location,
+}
+
+impl Diagnostic {
+  pub fn error(message: impl Into<String>, location: Location) -> Self {
+    Diagnostic {
+      severity: Severity::Error,
+      message: message.into(),
+      location,
+    }
+  }
+
+  pub fn warning(message: impl Into<String>, location: Location) -> Self {
+    Diagnostic {
+      severity: Severity::Warning,
+      message: message.into(),
+      location,
+    }
+  }
+
+  pub fn is_error(&self) -> bool {
+    self.severity == Severity::Error
+  }
+}
+
+impl Display for Diagnostic {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{}: {}: {}", self.location, self.severity, self.message)
+  }
+}
@@ -0,0 +1,161 @@
+use parser::ast::{
+  CompareExpr, Factor, FactorValue, FunctionStmt, IfStmt, LogicExpr, LogicOp, Primary,
+  PrimaryValue, Program, Statement, StatementValue, StmtList, WhileStmt,
+};
+use parser::location::Location;
+
+use crate::diagnostic::Diagnostic;
+use crate::infer::{statement_functions, statements, TypeInfo};
+use crate::types::Type;
+
+// whether running the statements always ends at a `return`; an if or a loop
+// may be skipped, so only a `return` directly in the block counts
+pub fn always_returns(body: &StmtList) -> bool {
+  statements(body)
+    .iter()
+    .any(|stmt| matches!(stmt.0, StatementValue::ReturnStmt(_)))
+}
+
+// what a function gives back when its body ends without a `return`: a
+// function returning a value on some paths only gives back the zero of its
+// return type on the others, None when it returns nothing or always returns
+pub fn default_return(types: &TypeInfo, path: &str, body: Option<&StmtList>) -> Option<Type> {
+  match types.return_type(path) {
+    Type::Void => None,
+    _ if body.is_some_and(always_returns) => None,
+    t => Some(t),
+  }
+}
+
+// whether running the statements never gets past their end: a `return` or a
+// `while (True)` is directly in the block, only a `return` leaves the loop
+fn never_ends(body: &StmtList) -> bool {
+  statements(body).iter().any(|stmt| match &stmt.0 {
+    StatementValue::ReturnStmt(_) => true,
+    StatementValue::WhileStmt(WhileStmt(condition, _)) => is_true(condition),
+    _ => false,
+  })
+}
+
+// a condition that is the literal `True`
+fn is_true(condition: &LogicExpr) -> bool {
+  let LogicExpr(LogicOp::Bool, Some(CompareExpr(expr, None, None)), _, _) = condition else {
+    return false;
+  };
+  matches!(
+    expr.as_factor(),
+    Some(Factor(FactorValue::Primary(Primary(PrimaryValue::Bool(
+      true
+    )))))
+  )
+}
+
+// whether some path through the statements ends at a `return`
+fn may_return(body: &StmtList) -> bool {
+  statements(body).iter().any(|stmt| match &stmt.0 {
+    StatementValue::ReturnStmt(_) => true,
+    StatementValue::IfStmt(IfStmt(_, Some(body)))
+    | StatementValue::WhileStmt(WhileStmt(_, Some(body))) => may_return(body),
+    _ => false,
+  })
+}
+
+// warn about functions returning a value on some paths only, and about
+// statements that follow a `return`
+pub fn check_returns(program: &Program) -> Vec<Diagnostic> {
+  let mut diagnostics = vec![];
+  check_block(&program.0, &mut diagnostics);
+  diagnostics
+}
+
+fn check_block(body: &StmtList, diagnostics: &mut Vec<Diagnostic>) {
+  let stmts = statements(body);
+  check_unreachable(&stmts, diagnostics);
+
+  for stmt in stmts {
+    for (function, location) in statement_functions(stmt) {
+      check_function(function, location, diagnostics);
+    }
+    if let StatementValue::IfStmt(IfStmt(_, Some(body)))
+    | StatementValue::WhileStmt(WhileStmt(_, Some(body))) = &stmt.0
+    {
+      check_block(body, diagnostics);
+    }
+  }
+}
+
+fn check_function(function: &FunctionStmt, location: &Location, diagnostics: &mut Vec<Diagnostic>) {
+  let Some(body) = &function.2 else {
+    return;
+  };
+  if may_return(body) && !never_ends(body) {
+    let name = function.0.to_string();
+    let message = if name.starts_with("fn@") {
+      "this function may reach its end without a `return`".to_owned()
+    } else {
+      format!("`{}` may reach its end without a `return`", name)
+    };
+    diagnostics.push(Diagnostic::warning(message, location.clone()));
+  }
+  check_block(body, diagnostics);
+}
+
+// functions and structs are not run where they are written, so only the
+// first other statement after a `return` is reported
+fn check_unreachable(stmts: &[&Statement], diagnostics: &mut Vec<Diagnostic>) {
+  let unreachable = stmts
+    .iter()
+    .skip_while(|stmt| !matches!(stmt.0, StatementValue::ReturnStmt(_)))
+    .skip(1)
+    .find(|stmt| {
+      !matches!(
+        stmt.0,
+        StatementValue::FunctionStmt(_) | StatementValue::StructStmt(_)
+      )
+    });
+  if let Some(stmt) = unreachable {
+    diagnostics.push(Diagnostic::warning(
+      "this statement is never run, it follows a `return`",
+      stmt.location().clone(),
+    ));
+  }
+}
+
+#[test]
+fn test_check_returns() {
+  use parser::{parse_program::parse_program, parser::Parser};
+
+  let code = "program {
+    fn sign(n) {
+      if (n > 0) {
+        return 1;
+      }
+    }
+    fn main() {
+      return sign(2);
+      println(1);
+    }
+    fn first(xs) {
+      i = 0;
+      while (True) {
+        if (xs[i] > 0) {
+          return i;
+        }
+        i = i + 1;
+      }
+    }
+  }";
+  let program = parse_program(&mut Parser::new(code));
+  let messages: Vec<String> = check_returns(&program)
+    .iter()
+    .map(|diagnostic| diagnostic.to_string())
+    .collect();
+
+  assert_eq!(
+    messages,
+    vec![
+      "line 2 column 5: warning: `sign` may reach its end without a `return`",
+      "line 9 column 7: warning: this statement is never run, it follows a `return`",
+    ]
+  );
+}
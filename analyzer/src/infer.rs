@@ -0,0 +1,1198 @@
+use std::collections::{HashMap, HashSet};
+
+use parser::ast::{
+  ApplyExpr, ArithmeticExpr, AssignStmt, BinOp, CallExpr, CompareExpr, CompareOp,
+  ComponentArithmeticExpr, ComponentFactor, ComponentFactorValue, ComponentTerm, Expression,
+  ExpressionValue, Factor, FactorValue, FieldAssignStmt, FieldExpr, FunctionStmt, IfStmt,
+  IndexAssignStmt, IndexExpr, LogicExpr, Param, Primary, PrimaryValue, Program, ReturnStmt,
+  Statement, StatementValue, StmtList, StructStmt, Term, TypeAnnotation, WhileStmt,
+};
+use parser::location::Location;
+
+use crate::diagnostic::Diagnostic;
+use crate::signature::Arity;
+use crate::types::Type;
+
+// types only grow while inferring, so passes stop by themselves,
+// the limit is for values like `x = [x]` whose type grows forever
+const MAX_PASSES: usize = 32;
+
+// the statements of a block in order
+pub fn statements(stmt_list: &StmtList) -> Vec<&Statement> {
+  let mut stmts = vec![];
+  let mut rest = Some(stmt_list);
+  while let Some(StmtList(stmt, next)) = rest {
+    stmts.extend(stmt.iter());
+    rest = next.as_deref();
+  }
+  stmts
+}
+
+// `fib` inside `main` is `main::fib`, the program itself is ``
+pub fn child_path(path: &str, name: &str) -> String {
+  if path.is_empty() {
+    name.to_owned()
+  } else {
+    format!("{}::{}", path, name)
+  }
+}
+
+fn parent_path(path: &str) -> Option<&str> {
+  if path.is_empty() {
+    return None;
+  }
+  Some(path.rsplit_once("::").map_or("", |(parent, _)| parent))
+}
+
+fn function_name(path: &str) -> &str {
+  path.rsplit_once("::").map_or(path, |(_, name)| name)
+}
+
+// how messages name a function: `area`, or the anonymous function `fn@1:27`
+// by where it is written
+fn quoted_function(path: &str) -> String {
+  let name = function_name(path);
+  match name.strip_prefix("fn@") {
+    Some(position) => format!("anonymous function defined at line {}", position),
+    None => format!("`{}`", name),
+  }
+}
+
+// `function `area`` or `anonymous function defined at line 1:27`
+fn describe_function(path: &str) -> String {
+  match function_name(path).starts_with("fn@") {
+    true => quoted_function(path),
+    false => format!("function {}", quoted_function(path)),
+  }
+}
+
+// the factors and operators of an arithmetic expression in source order,
+// parentheses only group, so they never change the type of the result
+pub(crate) fn operands(expr: &Expression) -> (Vec<&Factor>, Vec<&BinOp>) {
+  fn component_arithmetic_expr<'a>(
+    expr: &'a ComponentArithmeticExpr,
+    factors: &mut Vec<&'a Factor>,
+    ops: &mut Vec<&'a BinOp>,
+  ) {
+    component_term(&expr.0, factors, ops);
+    ops.extend(expr.1.iter());
+    if let Some(rest) = &expr.2 {
+      component_arithmetic_expr(rest, factors, ops);
+    }
+  }
+
+  fn component_term<'a>(
+    term: &'a ComponentTerm,
+    factors: &mut Vec<&'a Factor>,
+    ops: &mut Vec<&'a BinOp>,
+  ) {
+    component_factor(&term.0, factors, ops);
+    ops.extend(term.1.iter());
+    if let Some(rest) = &term.2 {
+      component_term(rest, factors, ops);
+    }
+  }
+
+  fn component_factor<'a>(
+    factor: &'a ComponentFactor,
+    factors: &mut Vec<&'a Factor>,
+    ops: &mut Vec<&'a BinOp>,
+  ) {
+    match &factor.1 {
+      ComponentFactorValue::ArithmeticExpr(expr) => arithmetic_expr(expr, factors, ops),
+      ComponentFactorValue::ComponentFactor(Some(inner)) => component_factor(inner, factors, ops),
+      ComponentFactorValue::ComponentFactor(None) => (),
+    }
+  }
+
+  fn arithmetic_expr<'a>(
+    expr: &'a ArithmeticExpr,
+    factors: &mut Vec<&'a Factor>,
+    ops: &mut Vec<&'a BinOp>,
+  ) {
+    term(&expr.0, factors, ops);
+    ops.extend(expr.1.iter());
+    if let Some(rest) = &expr.2 {
+      arithmetic_expr(rest, factors, ops);
+    }
+  }
+
+  fn term<'a>(term_: &'a Term, factors: &mut Vec<&'a Factor>, ops: &mut Vec<&'a BinOp>) {
+    factors.push(&term_.0);
+    ops.extend(term_.1.iter());
+    if let Some(rest) = &term_.2 {
+      term(rest, factors, ops);
+    }
+  }
+
+  let mut factors = vec![];
+  let mut ops = vec![];
+  let ExpressionValue::ComponentArithmeticExpr(expr) = &expr.0;
+  component_arithmetic_expr(expr, &mut factors, &mut ops);
+  (factors, ops)
+}
+
+// a factor and every factor inside it, in calls, lists, maps, indexes and
+// fields, but not in the statements of anonymous functions
+fn factor_tree<'a>(factor: &'a Factor, factors: &mut Vec<&'a Factor>) {
+  factors.push(factor);
+  match &factor.0 {
+    FactorValue::CallExpr(CallExpr(_, args)) => {
+      args.0.iter().for_each(|arg| expr_factors(arg, factors))
+    }
+    FactorValue::List(list) => list
+      .0
+       .0
+      .iter()
+      .for_each(|item| expr_factors(item, factors)),
+    FactorValue::Map(map) => map.0.iter().for_each(|entry| {
+      expr_factors(&entry.0, factors);
+      expr_factors(&entry.1, factors);
+    }),
+    FactorValue::Index(IndexExpr(target, key)) => {
+      factor_tree(target, factors);
+      expr_factors(key, factors);
+    }
+    FactorValue::Field(FieldExpr(target, _)) => factor_tree(target, factors),
+    FactorValue::Apply(ApplyExpr(target, args)) => {
+      factor_tree(target, factors);
+      args.0.iter().for_each(|arg| expr_factors(arg, factors));
+    }
+    FactorValue::Primary(_) | FactorValue::Identifier(_) | FactorValue::Function(_, _) => (),
+  }
+}
+
+pub(crate) fn expr_factors<'a>(expr: &'a Expression, factors: &mut Vec<&'a Factor>) {
+  let (operands, _) = operands(expr);
+  operands
+    .into_iter()
+    .for_each(|factor| factor_tree(factor, factors));
+}
+
+fn logic_factors<'a>(expr: &'a LogicExpr, factors: &mut Vec<&'a Factor>) {
+  if let Some(CompareExpr(left, _, right)) = &expr.1 {
+    expr_factors(left, factors);
+    if let Some(right) = right {
+      expr_factors(right, factors);
+    }
+  }
+  if let Some(left) = &expr.2 {
+    logic_factors(left, factors);
+  }
+  if let Some(right) = &expr.3 {
+    logic_factors(right, factors);
+  }
+}
+
+// the factors a statement evaluates itself, for an if only its condition
+pub(crate) fn statement_factors(stmt: &Statement) -> Vec<&Factor> {
+  let mut factors = vec![];
+  match &stmt.0 {
+    StatementValue::AssignStmt(AssignStmt(_, expr, _))
+    | StatementValue::ReturnStmt(ReturnStmt(expr)) => expr_factors(expr, &mut factors),
+    StatementValue::IndexAssignStmt(IndexAssignStmt(IndexExpr(target, key), value)) => {
+      factor_tree(target, &mut factors);
+      expr_factors(key, &mut factors);
+      expr_factors(value, &mut factors);
+    }
+    StatementValue::FieldAssignStmt(FieldAssignStmt(FieldExpr(target, _), value)) => {
+      factor_tree(target, &mut factors);
+      expr_factors(value, &mut factors);
+    }
+    StatementValue::CallStmt(CallExpr(_, args)) => args
+      .0
+      .iter()
+      .for_each(|arg| expr_factors(arg, &mut factors)),
+    StatementValue::ApplyStmt(ApplyExpr(target, args)) => {
+      factor_tree(target, &mut factors);
+      args
+        .0
+        .iter()
+        .for_each(|arg| expr_factors(arg, &mut factors));
+    }
+    StatementValue::IfStmt(IfStmt(condition, _))
+    | StatementValue::WhileStmt(WhileStmt(condition, _)) => logic_factors(condition, &mut factors),
+    StatementValue::FunctionStmt(_) | StatementValue::StructStmt(_) => (),
+  }
+  factors
+}
+
+// the functions defined by these statements, named or anonymous, with where
+// they start; the ones in if bodies are found by looking into the if
+pub fn defined_functions(stmt_list: &StmtList) -> Vec<(&FunctionStmt, &Location)> {
+  statements(stmt_list)
+    .into_iter()
+    .flat_map(statement_functions)
+    .collect()
+}
+
+// the functions defined in a function body, the ones in its ifs and loops
+// included but not the ones nested deeper: the backends lift them out of the
+// body and emit them after the function around them
+pub fn nested_functions(body: &StmtList) -> Vec<(&FunctionStmt, &Location)> {
+  let mut functions = vec![];
+  for stmt in statements(body) {
+    functions.extend(statement_functions(stmt));
+    if let StatementValue::IfStmt(IfStmt(_, Some(body)))
+    | StatementValue::WhileStmt(WhileStmt(_, Some(body))) = &stmt.0
+    {
+      functions.extend(nested_functions(body));
+    }
+  }
+  functions
+}
+
+pub fn statement_functions(stmt: &Statement) -> Vec<(&FunctionStmt, &Location)> {
+  let mut functions = vec![];
+  if let StatementValue::FunctionStmt(function) = &stmt.0 {
+    functions.push((function, stmt.location()));
+  }
+  for factor in statement_factors(stmt) {
+    if let FactorValue::Function(function, location) = &factor.0 {
+      functions.push((function.as_ref(), location));
+    }
+  }
+  functions
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FunctionType {
+  pub params: Vec<String>,
+  // the join of the types of all `return` statements
+  pub ret: Type,
+  // functions without any `return` give back nothing
+  pub returns: bool,
+}
+
+// a value passed by a direct call: a variable the caller captures for the
+// function, or an argument and the type of its parameter
+pub enum Passed<'e> {
+  Capture(String),
+  Arg(&'e Expression, Type),
+}
+
+// the types of every variable, parameter, function and struct field of a program
+#[derive(Debug, Clone, Default)]
+pub struct TypeInfo {
+  // by function path, see child_path
+  functions: HashMap<String, FunctionType>,
+  // variables and parameters by function path, then by name
+  variables: HashMap<String, HashMap<String, Type>>,
+  // fields in declaration order
+  structs: HashMap<String, Vec<(String, Type)>>,
+  // variables of the functions around a function that it uses, by function path
+  captures: HashMap<String, Vec<String>>,
+  // functions used as values, not only called by name
+  values: HashSet<String>,
+}
+
+impl TypeInfo {
+  // `fib` called in `main::helper` can be `main::helper::fib`, `main::fib` or `fib`
+  pub fn resolve_function(&self, path: &str, name: &str) -> Option<String> {
+    let mut scope = Some(path);
+    while let Some(current) = scope {
+      let function = child_path(current, name);
+      if self.functions.contains_key(&function) {
+        return Some(function);
+      }
+      scope = parent_path(current);
+    }
+    None
+  }
+
+  // the path of the function owning the variable, it can be one around `path`
+  pub fn resolve_variable(&self, path: &str, name: &str) -> Option<String> {
+    let mut scope = Some(path);
+    while let Some(current) = scope {
+      let found = self
+        .variables
+        .get(current)
+        .is_some_and(|variables| variables.contains_key(name));
+      if found {
+        return Some(current.to_owned());
+      }
+      scope = parent_path(current);
+    }
+    None
+  }
+
+  pub fn variable_type(&self, path: &str, name: &str) -> Type {
+    self
+      .resolve_variable(path, name)
+      .and_then(|owner| self.variables[&owner].get(name).cloned())
+      .unwrap_or_default()
+  }
+
+  pub fn param_types(&self, function: &str) -> Vec<Type> {
+    self
+      .functions
+      .get(function)
+      .map_or(vec![], |function_type| {
+        function_type
+          .params
+          .iter()
+          .map(|param| self.variables[function][param].clone())
+          .collect()
+      })
+  }
+
+  pub fn return_type(&self, function: &str) -> Type {
+    match self.functions.get(function) {
+      Some(FunctionType { returns: false, .. }) => Type::Void,
+      Some(function_type) => function_type.ret.clone(),
+      None => Type::Unknown,
+    }
+  }
+
+  pub fn is_struct(&self, name: &str) -> bool {
+    self.structs.contains_key(name)
+  }
+
+  // the variables a function uses from the functions around it, in order of first use
+  pub fn captures(&self, function: &str) -> &[String] {
+    self
+      .captures
+      .get(function)
+      .map_or(&[], |names| names.as_slice())
+  }
+
+  // what a function takes, in order: its captured variables are passed before
+  // its arguments, by direct calls and by closures alike
+  pub fn params(&self, function: &str) -> Vec<String> {
+    let mut params = self.captures(function).to_vec();
+    if let Some(function_type) = self.functions.get(function) {
+      params.extend(function_type.params.iter().cloned());
+    }
+    params
+  }
+
+  // what a direct call of a function passes, in the order of `params`
+  pub fn passed<'e>(&self, function: &str, args: &'e [Expression]) -> Vec<Passed<'e>> {
+    let param_types = self.param_types(function);
+    let captures = self.captures(function).iter().cloned().map(Passed::Capture);
+    let args = args
+      .iter()
+      .enumerate()
+      .map(|(idx, arg)| Passed::Arg(arg, param_types.get(idx).cloned().unwrap_or_default()));
+    captures.chain(args).collect()
+  }
+
+  pub fn is_value(&self, function: &str) -> bool {
+    self.values.contains(function)
+  }
+
+  // what calling a function value gives back, e.g. `f(3)` for a parameter `f`
+  pub fn value_return_type(&self, functions: &[String]) -> Type {
+    functions.iter().fold(Type::Unknown, |ret, function| {
+      ret.join_item(&self.return_type(function))
+    })
+  }
+
+  pub fn field_type(&self, name: &str, field: &str) -> Option<Type> {
+    let fields = self.structs.get(name)?;
+    fields
+      .iter()
+      .find(|(field_name, _)| field_name == field)
+      .map(|(_, field_type)| field_type.clone())
+  }
+
+  // the type of an expression evaluated inside the function at `path`
+  pub fn type_of(&self, path: &str, expr: &Expression) -> Type {
+    Typer { info: self, path }.expr(expr)
+  }
+
+  pub fn type_of_factor(&self, path: &str, factor: &Factor) -> Type {
+    Typer { info: self, path }.factor(factor)
+  }
+}
+
+// computes types from what is known so far, without learning anything
+struct Typer<'a> {
+  info: &'a TypeInfo,
+  path: &'a str,
+}
+
+impl<'a> Typer<'a> {
+  fn expr(&self, expr: &Expression) -> Type {
+    let (factors, _) = operands(expr);
+    if factors.len() == 1 {
+      return self.factor(factors[0]);
+    }
+
+    let types: Vec<Type> = factors.iter().map(|factor| self.factor(factor)).collect();
+    if !types.iter().all(|t| t.is_number() || *t == Type::Unknown) {
+      Type::Unknown
+    } else if types.contains(&Type::Float) {
+      Type::Float
+    } else if types.contains(&Type::Int) {
+      Type::Int
+    } else {
+      Type::Unknown
+    }
+  }
+
+  fn factor(&self, factor: &Factor) -> Type {
+    match &factor.0 {
+      FactorValue::Primary(Primary(PrimaryValue::Number(_))) => Type::Int,
+      FactorValue::Primary(Primary(PrimaryValue::Float(_))) => Type::Float,
+      FactorValue::Primary(Primary(PrimaryValue::String(_))) => Type::Str,
+      FactorValue::Primary(Primary(PrimaryValue::Bool(_))) => Type::Bool,
+      // a variable, or a function used as a value
+      FactorValue::Identifier(name) => {
+        let name = name.to_string();
+        match self.info.resolve_variable(self.path, &name) {
+          Some(_) => self.info.variable_type(self.path, &name),
+          None => self
+            .info
+            .resolve_function(self.path, &name)
+            .map_or(Type::Unknown, |function| Type::function_of(&function)),
+        }
+      }
+      FactorValue::Function(function, _) => {
+        Type::function_of(&child_path(self.path, &function.0.to_string()))
+      }
+      FactorValue::CallExpr(call_expr) => self.call(call_expr),
+      FactorValue::List(list) => Type::list_of(
+        list
+          .0
+           .0
+          .iter()
+          .fold(Type::Unknown, |item, expr| item.join_item(&self.expr(expr))),
+      ),
+      FactorValue::Map(map) => {
+        let key = map.0.iter().fold(Type::Unknown, |key, entry| {
+          key.join_item(&self.expr(&entry.0))
+        });
+        let value = map.0.iter().fold(Type::Unknown, |value, entry| {
+          value.join_item(&self.expr(&entry.1))
+        });
+        Type::map_of(key, value)
+      }
+      FactorValue::Index(IndexExpr(target, _)) => match self.factor(target) {
+        Type::List(item) => *item,
+        Type::Map(_, value) => *value,
+        Type::Any => Type::Any,
+        _ => Type::Unknown,
+      },
+      FactorValue::Field(FieldExpr(target, field)) => match self.factor(target) {
+        Type::Struct(name) => self
+          .info
+          .field_type(&name, &field.to_string())
+          .unwrap_or_default(),
+        Type::Any => Type::Any,
+        _ => Type::Unknown,
+      },
+      FactorValue::Apply(ApplyExpr(target, _)) => self.value_call(self.factor(target)),
+    }
+  }
+
+  // what calling a value of type `t` gives back
+  fn value_call(&self, t: Type) -> Type {
+    match t {
+      Type::Function(functions) => self.info.value_return_type(&functions),
+      Type::Any => Type::Any,
+      _ => Type::Unknown,
+    }
+  }
+
+  fn call(&self, call_expr: &CallExpr) -> Type {
+    let name = call_expr.0.to_string();
+    if self.info.resolve_variable(self.path, &name).is_some() {
+      return self.value_call(self.info.variable_type(self.path, &name));
+    }
+    if let Some(function) = self.info.resolve_function(self.path, &name) {
+      return self.info.return_type(&function);
+    }
+    if self.info.is_struct(&name) {
+      return Type::Struct(name);
+    }
+
+    match name.as_str() {
+      "input" => Type::Str,
+      "input_number" => Type::Float,
+      "len" | "printf" => Type::Int,
+      "has" => Type::Bool,
+      "keys" => match call_expr.1 .0.first().map(|map| self.expr(map)) {
+        Some(Type::Map(key, _)) => Type::List(key),
+        _ => Type::list_of(Type::Unknown),
+      },
+      "print" | "println" | "append" => Type::Void,
+      _ => Type::Unknown,
+    }
+  }
+}
+
+// the result of type inference
+pub struct Inference {
+  pub types: TypeInfo,
+  pub diagnostics: Vec<Diagnostic>,
+}
+
+// infer the type of every variable, parameter, return value and struct field
+pub fn infer(program: &Program) -> Inference {
+  let mut checker = TypeChecker::default();
+  checker.declare_structs(&program.0);
+  checker.declare(&program.0, "");
+
+  for _ in 0..MAX_PASSES {
+    checker.changed = false;
+    checker.visit_stmt_list(&program.0);
+    if !checker.changed {
+      break;
+    }
+  }
+
+  // types are final now, so mismatches found in this pass are real
+  checker.report = true;
+  checker.visit_stmt_list(&program.0);
+
+  let mut diagnostics = checker.diagnostics;
+  diagnostics.sort_by_key(|diagnostic| (diagnostic.location.row(), diagnostic.location.column()));
+  Inference {
+    types: checker.info,
+    diagnostics,
+  }
+}
+
+// whether the statements return, without looking into nested functions
+fn has_return(stmt_list: &StmtList) -> bool {
+  statements(stmt_list).iter().any(|stmt| match &stmt.0 {
+    StatementValue::ReturnStmt(_) => true,
+    StatementValue::IfStmt(IfStmt(_, Some(body)))
+    | StatementValue::WhileStmt(WhileStmt(_, Some(body))) => has_return(body),
+    _ => false,
+  })
+}
+
+#[derive(Default)]
+struct TypeChecker {
+  info: TypeInfo,
+  // path of the function being checked
+  path: String,
+  location: Location,
+  // diagnostics are only collected once types stop changing
+  report: bool,
+  changed: bool,
+  diagnostics: Vec<Diagnostic>,
+  // annotated variables and parameters by function path, then by name
+  declared: HashMap<String, HashMap<String, Type>>,
+  // annotated return types by function path
+  declared_returns: HashMap<String, Type>,
+}
+
+impl TypeChecker {
+  fn error(&mut self, message: String) {
+    let diagnostic = Diagnostic::error(message, self.location.clone());
+    if self.report && !self.diagnostics.contains(&diagnostic) {
+      self.diagnostics.push(diagnostic);
+    }
+  }
+
+  // problems found while declaring are reported right away, declaring happens once
+  fn declare_error(&mut self, message: String) {
+    let diagnostic = Diagnostic::error(message, self.location.clone());
+    if !self.diagnostics.contains(&diagnostic) {
+      self.diagnostics.push(diagnostic);
+    }
+  }
+
+  fn type_of(&self, expr: &Expression) -> Type {
+    self.info.type_of(&self.path, expr)
+  }
+
+  fn annotation_type(&mut self, annotation: &TypeAnnotation) -> Type {
+    let name = annotation.to_string();
+    if let Some(t) = Type::from_annotation(&name) {
+      return t;
+    }
+    if self.info.is_struct(&name) {
+      return Type::Struct(name);
+    }
+    self.declare_error(format!("unknown type `{}`", name));
+    Type::Unknown
+  }
+
+  // seed an annotated variable or parameter with its declared type
+  fn declare_variable(&mut self, path: &str, name: &str, annotation: &TypeAnnotation) {
+    let t = self.annotation_type(annotation);
+    if t == Type::Unknown {
+      return;
+    }
+    let declared = self.declared.entry(path.to_owned()).or_default();
+    match declared.get(name) {
+      Some(old) if *old != t => {
+        let message = format!("`{}` is already declared as {}", name, old);
+        return self.declare_error(message);
+      }
+      Some(_) => return,
+      None => declared.insert(name.to_owned(), t.clone()),
+    };
+    self
+      .info
+      .variables
+      .entry(path.to_owned())
+      .or_default()
+      .insert(name.to_owned(), t);
+  }
+
+  fn declared_type(&self, path: &str, name: &str) -> Option<&Type> {
+    self.declared.get(path)?.get(name)
+  }
+
+  // struct fields are found by annotations anywhere in the program
+  fn declare_structs(&mut self, stmt_list: &StmtList) {
+    for stmt in statements(stmt_list) {
+      match &stmt.0 {
+        StatementValue::StructStmt(StructStmt(name, fields)) => {
+          let fields = fields
+            .iter()
+            .map(|field| (field.to_string(), Type::Unknown))
+            .collect();
+          self.info.structs.insert(name.to_string(), fields);
+        }
+        StatementValue::IfStmt(IfStmt(_, Some(body)))
+        | StatementValue::WhileStmt(WhileStmt(_, Some(body))) => self.declare_structs(body),
+        _ => (),
+      }
+    }
+    for (function, _) in defined_functions(stmt_list) {
+      if let Some(body) = &function.2 {
+        self.declare_structs(body);
+      }
+    }
+  }
+
+  fn type_of_factor(&self, factor: &Factor) -> Type {
+    self.info.type_of_factor(&self.path, factor)
+  }
+
+  // register functions, their parameters and annotated variables
+  // before any statement is checked
+  fn declare(&mut self, stmt_list: &StmtList, path: &str) {
+    self.info.variables.entry(path.to_owned()).or_default();
+    for stmt in statements(stmt_list) {
+      self.location = stmt.location().clone();
+      match &stmt.0 {
+        StatementValue::AssignStmt(AssignStmt(name, _, Some(annotation))) => {
+          self.declare_variable(path, &name.to_string(), annotation);
+        }
+        StatementValue::IfStmt(IfStmt(_, Some(body)))
+        | StatementValue::WhileStmt(WhileStmt(_, Some(body))) => self.declare(body, path),
+        _ => (),
+      }
+    }
+
+    for (function, location) in defined_functions(stmt_list) {
+      self.location = location.clone();
+      self.declare_function(path, function);
+    }
+  }
+
+  fn declare_function(&mut self, path: &str, function_stmt: &FunctionStmt) {
+    let FunctionStmt(name, params, body, return_type) = function_stmt;
+    let function = child_path(path, &name.to_string());
+    let variables = self.info.variables.entry(function.clone()).or_default();
+    params.iter().for_each(|param| {
+      variables.insert(param.to_string(), Type::Unknown);
+    });
+    for Param(param, annotation) in params {
+      if let Some(annotation) = annotation {
+        self.declare_variable(&function, &param.to_string(), annotation);
+      }
+    }
+
+    let returns = body.as_deref().is_some_and(has_return);
+    let ret = match return_type {
+      Some(annotation) => self.annotation_type(annotation),
+      None => Type::Unknown,
+    };
+    if ret != Type::Unknown {
+      if !returns {
+        self.declare_error(format!(
+          "{} must return {}, but never returns",
+          quoted_function(&function),
+          ret
+        ));
+      }
+      self.declared_returns.insert(function.clone(), ret.clone());
+    }
+    self.info.functions.insert(
+      function.clone(),
+      FunctionType {
+        params: params.iter().map(|param| param.to_string()).collect(),
+        ret,
+        returns,
+      },
+    );
+    if let Some(body) = body {
+      self.declare(body, &function);
+    }
+  }
+
+  // `name` belongs to the function at `owner`, every function between it and the
+  // current one needs the variable to reach it
+  fn capture(&mut self, owner: &str, name: &str) {
+    let mut scope = self.path.clone();
+    while !owner.is_empty() && scope != owner && !scope.is_empty() {
+      let captures = self.info.captures.entry(scope.clone()).or_default();
+      if !captures.iter().any(|captured| captured == name) {
+        captures.push(name.to_owned());
+        self.changed = true;
+      }
+      scope = parent_path(&scope).unwrap_or_default().to_owned();
+    }
+  }
+
+  // calling a function, or making a value of it, needs the variables it captures
+  fn require_captures(&mut self, function: &str) {
+    for name in self.info.captures(function).to_vec() {
+      if let Some(owner) = self.info.resolve_variable(&self.path, &name) {
+        self.capture(&owner, &name);
+      }
+    }
+  }
+
+  fn use_function_value(&mut self, function: &str) {
+    if self.info.values.insert(function.to_owned()) {
+      self.changed = true;
+    }
+    self.require_captures(function);
+  }
+
+  fn visit_function(&mut self, function_stmt: &FunctionStmt) {
+    let function = child_path(&self.path, &function_stmt.0.to_string());
+    let outer = std::mem::replace(&mut self.path, function);
+    let location = self.location.clone();
+    if let Some(body) = &function_stmt.2 {
+      self.visit_stmt_list(body);
+    }
+    self.path = outer;
+    self.location = location;
+  }
+
+  // widen the type of a variable, returns its old type when `t` doesn't fit
+  fn join_variable(&mut self, path: &str, name: &str, t: &Type) -> Result<(), Type> {
+    if let Some(declared) = self.declared_type(path, name) {
+      if !declared.accepts(t) {
+        return Err(declared.clone());
+      }
+    }
+    let variables = self.info.variables.entry(path.to_owned()).or_default();
+    let old = variables.get(name).cloned().unwrap_or_default();
+    match old.join(t) {
+      Some(new) if new != old => {
+        variables.insert(name.to_owned(), new);
+        self.changed = true;
+        Ok(())
+      }
+      Some(_) => Ok(()),
+      None => Err(old),
+    }
+  }
+
+  fn assign_field(&mut self, name: &str, field: &str, t: &Type) {
+    let Some(fields) = self.info.structs.get_mut(name) else {
+      return;
+    };
+    let Some((_, old)) = fields
+      .iter_mut()
+      .find(|(field_name, _)| field_name == field)
+    else {
+      return self.error(format!("struct `{}` has no field `{}`", name, field));
+    };
+    match old.join(t) {
+      Some(new) if new != *old => {
+        *old = new;
+        self.changed = true;
+      }
+      Some(_) => (),
+      None => {
+        let message = format!(
+          "field `{}` of `{}` is {}, cannot assign {} to it",
+          field, name, old, t
+        );
+        self.error(message);
+      }
+    }
+  }
+
+  // `scores[0] = "a"` or `append(scores, "a")` change the item type of `scores`
+  fn refine(&mut self, target: &Factor, t: Type) {
+    match &target.0 {
+      FactorValue::Identifier(name) => {
+        let name = name.to_string();
+        let owner = self
+          .info
+          .resolve_variable(&self.path, &name)
+          .unwrap_or_else(|| self.path.clone());
+        // the target is already known to be a list or a map, so this always fits
+        let _ = self.join_variable(&owner, &name, &t);
+      }
+      FactorValue::Field(FieldExpr(object, field)) => {
+        if let Type::Struct(name) = self.type_of_factor(object) {
+          self.assign_field(&name, &field.to_string(), &t);
+        }
+      }
+      FactorValue::Index(IndexExpr(container, key)) => match self.type_of_factor(container) {
+        Type::List(_) => self.refine(container, Type::list_of(t)),
+        Type::Map(_, _) => {
+          let key = self.type_of(key);
+          self.refine(container, Type::map_of(key, t))
+        }
+        _ => (),
+      },
+      _ => (),
+    }
+  }
+
+  // the type of a value used by an assignment, an argument or a return
+  fn value_type(&mut self, expr: &Expression) -> Type {
+    let t = self.type_of(expr);
+    if t == Type::Void {
+      if let Some(Factor(FactorValue::CallExpr(CallExpr(name, _)))) = expr.as_factor() {
+        self.error(format!("`{}` does not return a value", name));
+      }
+    }
+    t
+  }
+
+  fn visit_stmt_list(&mut self, stmt_list: &StmtList) {
+    statements(stmt_list)
+      .iter()
+      .for_each(|stmt| self.visit_stmt(stmt));
+  }
+
+  fn visit_stmt(&mut self, stmt: &Statement) {
+    self.location = stmt.location().clone();
+    match &stmt.0 {
+      StatementValue::AssignStmt(AssignStmt(name, expr, _)) => {
+        self.check_expr(expr);
+        let t = self.value_type(expr);
+        if t == Type::Void {
+          return;
+        }
+        let name = name.to_string();
+        let path = self.path.clone();
+        if let Err(old) = self.join_variable(&path, &name, &t) {
+          self.error(format!("`{}` is {}, cannot assign {} to it", name, old, t));
+        }
+      }
+      StatementValue::IndexAssignStmt(IndexAssignStmt(IndexExpr(target, key), value)) => {
+        self.check_factor(target);
+        self.check_expr(key);
+        self.check_expr(value);
+        let t = self.value_type(value);
+        match self.type_of_factor(target) {
+          Type::List(_) => {
+            self.check_list_index(key);
+            self.refine(target, Type::list_of(t));
+          }
+          Type::Map(_, _) => {
+            let key = self.type_of(key);
+            self.refine(target, Type::map_of(key, t));
+          }
+          Type::Unknown | Type::Any => (),
+          other => self.error(format!("{} has no items", other)),
+        }
+      }
+      StatementValue::FieldAssignStmt(FieldAssignStmt(FieldExpr(target, field), value)) => {
+        self.check_factor(target);
+        self.check_expr(value);
+        let t = self.value_type(value);
+        match self.type_of_factor(target) {
+          Type::Struct(name) => self.assign_field(&name, &field.to_string(), &t),
+          Type::Unknown | Type::Any => (),
+          other => self.error(format!("{} has no fields", other)),
+        }
+      }
+      StatementValue::CallStmt(call_expr) => self.check_call(call_expr),
+      StatementValue::ApplyStmt(apply_expr) => self.check_apply(apply_expr),
+      StatementValue::ReturnStmt(ReturnStmt(expr)) => {
+        self.check_expr(expr);
+        let t = self.value_type(expr);
+        if let Some(declared) = self.declared_returns.get(&self.path) {
+          if !declared.accepts(&t) {
+            let message = format!(
+              "{} must return {}, got {}",
+              quoted_function(&self.path),
+              declared,
+              t
+            );
+            return self.error(message);
+          }
+        }
+        let Some(function) = self.info.functions.get_mut(&self.path) else {
+          return;
+        };
+        match function.ret.join(&t) {
+          Some(new) if new != function.ret => {
+            function.ret = new;
+            self.changed = true;
+          }
+          Some(_) => (),
+          None => {
+            let message = format!(
+              "{} returns {} here, but {} elsewhere",
+              quoted_function(&self.path),
+              t,
+              function.ret
+            );
+            self.error(message);
+          }
+        }
+      }
+      StatementValue::FunctionStmt(function_stmt) => self.visit_function(function_stmt),
+      StatementValue::StructStmt(_) => (),
+      StatementValue::IfStmt(IfStmt(condition, body))
+      | StatementValue::WhileStmt(WhileStmt(condition, body)) => {
+        self.check_logic_expr(condition);
+        if let Some(body) = body {
+          self.visit_stmt_list(body);
+        }
+      }
+    }
+  }
+
+  fn check_list_index(&mut self, index: &Expression) {
+    let t = self.type_of(index);
+    if t.is_known() && t != Type::Int {
+      self.error(format!("a list index must be an int, got {}", t));
+    }
+  }
+
+  fn check_expr(&mut self, expr: &Expression) {
+    let (factors, ops) = operands(expr);
+    factors.iter().for_each(|factor| self.check_factor(factor));
+    if ops.is_empty() {
+      return;
+    }
+
+    for (idx, factor) in factors.iter().enumerate() {
+      let t = self.type_of_factor(factor);
+      if !t.is_number() && t != Type::Unknown {
+        let op = ops[idx.saturating_sub(1)].to_string();
+        self.error(format!("`{}` needs numbers, got {}", op.trim(), t));
+      }
+    }
+  }
+
+  fn check_factor(&mut self, factor: &Factor) {
+    match &factor.0 {
+      FactorValue::Primary(_) => (),
+      FactorValue::Identifier(name) => {
+        let name = name.to_string();
+        match self.info.resolve_variable(&self.path, &name) {
+          Some(owner) => self.capture(&owner, &name),
+          None => {
+            if let Some(function) = self.info.resolve_function(&self.path, &name) {
+              self.use_function_value(&function);
+            }
+          }
+        }
+      }
+      FactorValue::Function(function_stmt, _) => {
+        let function = child_path(&self.path, &function_stmt.0.to_string());
+        self.use_function_value(&function);
+        self.visit_function(function_stmt);
+      }
+      FactorValue::CallExpr(call_expr) => self.check_call(call_expr),
+      FactorValue::Apply(apply_expr) => self.check_apply(apply_expr),
+      FactorValue::List(list) => list.0 .0.iter().for_each(|item| self.check_expr(item)),
+      FactorValue::Map(map) => map.0.iter().for_each(|entry| {
+        self.check_expr(&entry.0);
+        self.check_expr(&entry.1);
+      }),
+      FactorValue::Index(IndexExpr(target, key)) => {
+        self.check_factor(target);
+        self.check_expr(key);
+        match self.type_of_factor(target) {
+          Type::List(_) => self.check_list_index(key),
+          Type::Map(_, _) | Type::Unknown | Type::Any => (),
+          other => self.error(format!("{} has no items", other)),
+        }
+      }
+      FactorValue::Field(FieldExpr(target, field)) => {
+        self.check_factor(target);
+        match self.type_of_factor(target) {
+          Type::Struct(name) => {
+            if self.info.field_type(&name, &field.to_string()).is_none() {
+              self.error(format!("struct `{}` has no field `{}`", name, field));
+            }
+          }
+          Type::Unknown | Type::Any => (),
+          other => self.error(format!("{} has no fields", other)),
+        }
+      }
+    }
+  }
+
+  fn check_call(&mut self, call_expr: &CallExpr) {
+    let args = &call_expr.1 .0;
+    args.iter().for_each(|arg| self.check_expr(arg));
+    let types: Vec<Type> = args.iter().map(|arg| self.value_type(arg)).collect();
+    let name = call_expr.0.to_string();
+
+    // a variable holding a function value
+    if let Some(owner) = self.info.resolve_variable(&self.path, &name) {
+      self.capture(&owner, &name);
+      let t = self.info.variable_type(&self.path, &name);
+      return self.call_value(Some(&name), t, &types);
+    }
+
+    if let Some(function) = self.info.resolve_function(&self.path, &name) {
+      self.require_captures(&function);
+      self.pass_arguments(&function, &name, &types);
+      return;
+    }
+
+    if self.info.is_struct(&name) {
+      let fields: Vec<String> = self.info.structs[&name]
+        .iter()
+        .map(|(field, _)| field.clone())
+        .collect();
+      fields
+        .iter()
+        .zip(types.iter())
+        .for_each(|(field, t)| self.assign_field(&name, field, t));
+      return;
+    }
+
+    let first = types.first().cloned().unwrap_or_default();
+    match name.as_str() {
+      "input" | "input_number" if first.is_known() && first != Type::Str => {
+        self.error(format!("`{}` needs a text prompt, got {}", name, first));
+      }
+      "len" => match first {
+        Type::List(_) | Type::Map(_, _) | Type::Str | Type::Unknown | Type::Any => (),
+        other => self.error(format!("`len` needs a list, map or text, got {}", other)),
+      },
+      "append" => match first {
+        Type::List(_) => {
+          if let (Some(list), Some(item)) = (args[0].as_factor(), types.get(1)) {
+            self.refine(list, Type::list_of(item.clone()));
+          }
+        }
+        Type::Unknown | Type::Any => (),
+        other => self.error(format!("`append` needs a list, got {}", other)),
+      },
+      "keys" | "has" => match first {
+        Type::Map(_, _) | Type::Unknown | Type::Any => (),
+        other => self.error(format!("`{}` needs a map, got {}", name, other)),
+      },
+      _ => (),
+    }
+  }
+
+  // make_adder(2)(3), fs[0](4) or s.f(1)
+  fn check_apply(&mut self, apply_expr: &ApplyExpr) {
+    let ApplyExpr(target, args) = apply_expr;
+    self.check_factor(target);
+    args.0.iter().for_each(|arg| self.check_expr(arg));
+    let types: Vec<Type> = args.0.iter().map(|arg| self.value_type(arg)).collect();
+    let t = self.type_of_factor(target);
+    self.call_value(None, t, &types);
+  }
+
+  // a call of a value of type `t`, named in errors by the variable holding it
+  fn call_value(&mut self, name: Option<&str>, t: Type, types: &[Type]) {
+    match t {
+      Type::Function(functions) => {
+        for function in functions {
+          let params = self.info.functions[&function].params.len();
+          if params != types.len() {
+            let given = match types.len() {
+              1 => "1 was given".to_owned(),
+              count => format!("{} were given", count),
+            };
+            let message = format!(
+              "{} takes {} but {}",
+              describe_function(&function),
+              Arity::Exact(params),
+              given
+            );
+            self.error(message);
+          } else {
+            let name = name.unwrap_or_else(|| function_name(&function)).to_owned();
+            self.pass_arguments(&function, &name, types);
+          }
+        }
+      }
+      Type::Unknown | Type::Any => (),
+      other => match name {
+        Some(name) => self.error(format!("`{}` is {}, not a function", name, other)),
+        None => self.error(format!("{} is not a function", other)),
+      },
+    }
+  }
+
+  // the arguments of a call widen the types of the parameters
+  fn pass_arguments(&mut self, function: &str, name: &str, types: &[Type]) {
+    let params = self.info.functions[function].params.clone();
+    for (idx, (param, t)) in params.iter().zip(types.iter()).enumerate() {
+      if let Some(declared) = self.declared_type(function, param) {
+        if !declared.accepts(t) {
+          let message = format!(
+            "argument {} of `{}` must be {}, got {}",
+            idx + 1,
+            name,
+            declared,
+            t
+          );
+          self.error(message);
+          continue;
+        }
+      }
+      if let Err(old) = self.join_variable(function, param, t) {
+        let message = format!(
+          "argument {} of `{}` is {} here, but {} in another call",
+          idx + 1,
+          name,
+          t,
+          old
+        );
+        self.error(message);
+      }
+    }
+  }
+
+  fn check_logic_expr(&mut self, expr: &LogicExpr) {
+    if let Some(CompareExpr(left, op, right)) = &expr.1 {
+      self.check_expr(left);
+      let left_type = self.type_of(left);
+      let right_type = right.as_ref().map(|right| {
+        self.check_expr(right);
+        self.type_of(right)
+      });
+
+      match (op, right_type) {
+        (Some(CompareOp::Eq), Some(right_type)) => {
+          if left_type.join(&right_type).is_none() {
+            self.error(format!("cannot compare {} with {}", left_type, right_type));
+          }
+        }
+        (Some(op), Some(right_type)) => {
+          for t in [left_type, right_type] {
+            if !t.is_number() && t != Type::Unknown {
+              self.error(format!(
+                "`{}` needs numbers, got {}",
+                op.to_string().trim(),
+                t
+              ));
+            }
+          }
+        }
+        _ => match left_type {
+          Type::Bool | Type::Int | Type::Float | Type::Unknown | Type::Any => (),
+          other => self.error(format!("a condition must be a bool, got {}", other)),
+        },
+      }
+    }
+
+    if let Some(left) = &expr.2 {
+      self.check_logic_expr(left);
+    }
+    if let Some(right) = &expr.3 {
+      self.check_logic_expr(right);
+    }
+  }
+}
@@ -0,0 +1,24 @@
+//! Checks a riven program before code generation, so mistakes like a typo in
+//! a variable name are reported with riven source locations instead of
+//! surfacing as gcc errors about the generated C file.
+//!
+//! ```
+//! use analyzer::semantic::analyze;
+//! use parser::{parse_program::parse_program, parser::Parser};
+//!
+//! let mut p = Parser::new("program { fn main() { println(nmae); } }");
+//! let diagnostics = analyze(&parse_program(&mut p));
+//! assert_eq!(diagnostics[0].message, "undefined variable `nmae`");
+//! ```
+
+pub mod builtins;
+pub mod diagnostic;
+pub mod flow;
+pub mod infer;
+pub mod locals;
+pub mod operands;
+pub mod optimize;
+pub mod scope;
+pub mod semantic;
+pub mod signature;
+pub mod types;
@@ -0,0 +1,209 @@
+use std::collections::{HashMap, HashSet};
+
+use parser::ast::{
+  AssignStmt, FactorValue, FunctionStmt, IfStmt, Program, StatementValue, StmtList, WhileStmt,
+};
+use parser::location::Location;
+
+use crate::infer::{statement_factors, statements};
+
+// where generated code declares the variables of each function
+#[derive(Debug, Clone, Default)]
+pub struct Declarations {
+  // assignments to a variable declared before them, by statement location
+  reassignments: HashSet<Location>,
+  // variables declared at the top of a block, by the location of the
+  // function, if or while statement owning the block
+  hoisted: HashMap<Location, Vec<String>>,
+}
+
+impl Declarations {
+  // whether the assignment at `location` declares its variable
+  pub fn declares(&self, location: &Location) -> bool {
+    !self.reassignments.contains(location)
+  }
+
+  pub fn hoisted(&self, block: &Location) -> &[String] {
+    self
+      .hoisted
+      .get(block)
+      .map_or(&[], |names| names.as_slice())
+  }
+}
+
+// a variable is declared by its first assignment, unless it is used outside the
+// block of that assignment, e.g. first assigned inside an if but printed after it:
+// then it is declared at the top of the innermost block holding all its uses
+pub fn plan_declarations(program: &Program) -> Declarations {
+  let mut declarations = Declarations::default();
+  plan_block(&program.0, Location::default(), &[], &mut declarations);
+  declarations
+}
+
+// a read or an assignment of a variable
+struct Use {
+  name: String,
+  // locations of the function or if statements around the use, outermost first
+  blocks: Vec<Location>,
+  // location of the assignment statement, None for reads
+  assignment: Option<Location>,
+}
+
+fn plan_block(
+  body: &StmtList,
+  owner: Location,
+  params: &[String],
+  declarations: &mut Declarations,
+) {
+  let mut uses = vec![];
+  let mut blocks = vec![owner];
+  collect_uses(body, &mut blocks, &mut uses, declarations);
+
+  // parameters are declared by the function itself
+  for use_ in &uses {
+    if let (true, Some(location)) = (params.contains(&use_.name), &use_.assignment) {
+      declarations.reassignments.insert(location.clone());
+    }
+  }
+
+  // names assigned in this function in order of first use, the others are parameters,
+  // variables of an outer function or undefined
+  let mut names: Vec<&str> = vec![];
+  for use_ in &uses {
+    let assigned = uses
+      .iter()
+      .any(|other| other.name == use_.name && other.assignment.is_some());
+    if assigned && !params.contains(&use_.name) && !names.contains(&use_.name.as_str()) {
+      names.push(&use_.name);
+    }
+  }
+
+  for name in names {
+    let sites: Vec<&Use> = uses.iter().filter(|use_| use_.name == name).collect();
+    let block = sites
+      .iter()
+      .skip(1)
+      .fold(sites[0].blocks.as_slice(), |block, use_| {
+        let common = block
+          .iter()
+          .zip(use_.blocks.iter())
+          .take_while(|(a, b)| a == b)
+          .count();
+        &block[..common]
+      });
+
+    // a variable of the program is read by the functions too, it is declared
+    // before them
+    let global = block.len() == 1 && block[0] == Location::default();
+    let declared_inline = !global && sites[0].assignment.is_some() && sites[0].blocks == block;
+    if !declared_inline {
+      let owner = block.last().cloned().unwrap_or_default();
+      declarations
+        .hoisted
+        .entry(owner)
+        .or_default()
+        .push(name.to_owned());
+    }
+
+    let skip = if declared_inline { 1 } else { 0 };
+    for use_ in sites.iter().skip(skip) {
+      if let Some(location) = &use_.assignment {
+        declarations.reassignments.insert(location.clone());
+      }
+    }
+  }
+}
+
+fn collect_uses(
+  stmt_list: &StmtList,
+  blocks: &mut Vec<Location>,
+  uses: &mut Vec<Use>,
+  declarations: &mut Declarations,
+) {
+  for stmt in statements(stmt_list) {
+    // what the statement reads, for an if or a while only its condition
+    let mut names = vec![];
+    for factor in statement_factors(stmt) {
+      match &factor.0 {
+        FactorValue::Identifier(name) => names.push(name.to_string()),
+        FactorValue::Function(function, location) => {
+          names.extend(plan_function(function, location, declarations))
+        }
+        _ => (),
+      }
+    }
+    push_reads(names, blocks, uses);
+
+    match &stmt.0 {
+      StatementValue::AssignStmt(AssignStmt(name, _, _)) => uses.push(Use {
+        name: name.to_string(),
+        blocks: blocks.clone(),
+        assignment: Some(stmt.location().clone()),
+      }),
+      StatementValue::IfStmt(IfStmt(_, Some(body)))
+      | StatementValue::WhileStmt(WhileStmt(_, Some(body))) => {
+        blocks.push(stmt.location().clone());
+        collect_uses(body, blocks, uses, declarations);
+        blocks.pop();
+      }
+      StatementValue::FunctionStmt(function) => {
+        // captured variables are passed where the function is called, which can
+        // be anywhere in the function around it
+        let names = plan_function(function, stmt.location(), declarations);
+        push_reads(names, &blocks[..1], uses);
+      }
+      _ => (),
+    }
+  }
+}
+
+// a nested function has variables of its own, returns every name it uses
+// as some of them can be variables captured from the function around it
+fn plan_function(
+  function: &FunctionStmt,
+  location: &Location,
+  declarations: &mut Declarations,
+) -> Vec<String> {
+  let Some(body) = &function.2 else {
+    return vec![];
+  };
+  let params: Vec<String> = function.1.iter().map(|param| param.to_string()).collect();
+  plan_block(body, location.clone(), &params, declarations);
+
+  let mut uses = vec![];
+  let mut blocks = vec![location.clone()];
+  collect_uses(body, &mut blocks, &mut uses, &mut Declarations::default());
+  uses.into_iter().map(|use_| use_.name).collect()
+}
+
+fn push_reads(names: Vec<String>, blocks: &[Location], uses: &mut Vec<Use>) {
+  uses.extend(names.into_iter().map(|name| Use {
+    name,
+    blocks: blocks.to_vec(),
+    assignment: None,
+  }));
+}
+
+#[test]
+fn test_plan_declarations() {
+  use parser::{parse_program::parse_program, parser::Parser};
+
+  let code = "program {
+    fn main() {
+      x = 1;
+      x = x + 1;
+      if (x > 1) {
+        y = 2;
+        z = 3;
+      }
+      println(y);
+    }
+  }";
+  let declarations = plan_declarations(&parse_program(&mut Parser::new(code)));
+
+  assert!(declarations.declares(&Location::new(3, 7)));
+  assert!(!declarations.declares(&Location::new(4, 7)));
+  assert!(!declarations.declares(&Location::new(6, 9)));
+  assert!(declarations.declares(&Location::new(7, 9)));
+  assert_eq!(declarations.hoisted(&Location::new(2, 5)), ["y"]);
+}
@@ -0,0 +1,140 @@
+use parser::ast::{
+  ArithmeticExpr, BinOp, ComponentArithmeticExpr, ComponentFactor, ComponentFactorValue,
+  ComponentTerm, Expression, ExpressionValue, Factor, Term,
+};
+
+// an arithmetic expression as the operations computing it, for the backends,
+// the interpreter, the IR and the optimizer alike: the parser keeps the values
+// and operators in source order, here `*` and `/` come before `+` and `-`, from
+// left to right. Walking the left operand first visits the values in source
+// order
+pub enum Operation<'p> {
+  Factor(&'p Factor),
+  // what is in parentheses
+  Group(Box<Operation<'p>>),
+  Binary(Box<Operation<'p>>, BinOp, Box<Operation<'p>>),
+}
+
+impl<'p> Operation<'p> {
+  pub fn of_expr(expr: &'p Expression) -> Self {
+    let ExpressionValue::ComponentArithmeticExpr(expr) = &expr.0;
+    Operation::of_component_arithmetic_expr(expr)
+  }
+
+  pub fn of_component_arithmetic_expr(expr: &'p ComponentArithmeticExpr) -> Self {
+    let mut operands = Operands::default();
+    operands.component_arithmetic_expr(expr);
+    operands.into_operation()
+  }
+
+  pub fn of_component_term(term: &'p ComponentTerm) -> Self {
+    let mut operands = Operands::default();
+    operands.component_term(term);
+    operands.into_operation()
+  }
+
+  pub fn of_component_factor(factor: &'p ComponentFactor) -> Self {
+    let mut operands = Operands::default();
+    operands.component_factor(factor);
+    operands.into_operation()
+  }
+
+  pub fn of_arithmetic_expr(expr: &'p ArithmeticExpr) -> Self {
+    let mut operands = Operands::default();
+    operands.arithmetic_expr(expr);
+    operands.into_operation()
+  }
+
+  pub fn of_term(term: &'p Term) -> Self {
+    let mut operands = Operands::default();
+    operands.term(term);
+    operands.into_operation()
+  }
+
+  fn binary(left: Operation<'p>, op: BinOp, right: Operation<'p>) -> Self {
+    Operation::Binary(Box::new(left), op, Box::new(right))
+  }
+}
+
+// the values and operators in source order, each parenthesized group already
+// made an operation
+#[derive(Default)]
+struct Operands<'p> {
+  values: Vec<Operation<'p>>,
+  ops: Vec<BinOp>,
+}
+
+impl<'p> Operands<'p> {
+  fn into_operation(self) -> Operation<'p> {
+    let mut values = self.values.into_iter();
+    let mut product = values.next().expect("the parser gives every group a value");
+    let mut sum: Option<(Operation, BinOp)> = None;
+    for (op, value) in self.ops.into_iter().zip(values) {
+      if op.is_time() || op.is_div() {
+        product = Operation::binary(product, op, value);
+        continue;
+      }
+      if let Some((left, sum_op)) = sum.take() {
+        product = Operation::binary(left, sum_op, product);
+      }
+      sum = Some((product, op));
+      product = value;
+    }
+    match sum {
+      Some((left, op)) => Operation::binary(left, op, product),
+      None => product,
+    }
+  }
+
+  fn component_arithmetic_expr(&mut self, expr: &'p ComponentArithmeticExpr) {
+    self.component_term(&expr.0);
+    self.ops.extend(expr.1.clone());
+    if let Some(rest) = &expr.2 {
+      self.component_arithmetic_expr(rest);
+    }
+  }
+
+  fn component_term(&mut self, term: &'p ComponentTerm) {
+    self.component_factor(&term.0);
+    self.ops.extend(term.1.clone());
+    if let Some(rest) = &term.2 {
+      self.component_term(rest);
+    }
+  }
+
+  fn component_factor(&mut self, factor: &'p ComponentFactor) {
+    if factor.0 {
+      let mut group = Operands::default();
+      group.component_factor_value(&factor.1);
+      self
+        .values
+        .push(Operation::Group(Box::new(group.into_operation())));
+      return;
+    }
+    self.component_factor_value(&factor.1);
+  }
+
+  fn component_factor_value(&mut self, value: &'p ComponentFactorValue) {
+    match value {
+      ComponentFactorValue::ArithmeticExpr(expr) => self.arithmetic_expr(expr),
+      ComponentFactorValue::ComponentFactor(Some(inner)) => self.component_factor(inner),
+      ComponentFactorValue::ComponentFactor(None) => (),
+    }
+  }
+
+  fn arithmetic_expr(&mut self, expr: &'p ArithmeticExpr) {
+    self.term(&expr.0);
+    self.ops.extend(expr.1.clone());
+    if let Some(rest) = &expr.2 {
+      self.arithmetic_expr(rest);
+    }
+  }
+
+  fn term(&mut self, term: &'p Term) {
+    self.values.push(Operation::Factor(&term.0));
+    self.ops.extend(term.1.clone());
+    if let Some(rest) = &term.2 {
+      self.term(rest);
+    }
+  }
+}
@@ -0,0 +1,430 @@
+use std::collections::HashSet;
+
+use parser::ast::{
+  ArithmeticExpr, AssignStmt, BinOp, CallExpr, CompareExpr, CompareOp, ComponentArithmeticExpr,
+  ComponentFactor, ComponentFactorValue, ComponentTerm, Expression, ExpressionValue, Factor,
+  FactorValue, FunctionStmt, IfStmt, LogicExpr, LogicOp, Primary, PrimaryValue, Program, Statement,
+  StatementValue, StmtList, Term, WhileStmt,
+};
+use parser::visit::{
+  walk_call_expr, walk_component_factor_mut, walk_expr, walk_expr_mut, walk_factor, Visitor,
+  VisitorMut,
+};
+
+use crate::operands::Operation;
+
+// simplify a checked program before code generation: fold constant arithmetic
+// and comparisons, drop ifs whose condition is always false, unwrap the ones
+// always true, and remove assignments to variables that are never read
+pub fn optimize(program: Program) -> Program {
+  let mut reads = Reads::default();
+  reads.visit_program(&program);
+  Program(optimize_block(program.0, &reads.0))
+}
+
+// a value known before the program runs
+#[derive(Debug, Clone, PartialEq)]
+enum Constant {
+  Int(i64),
+  Float(f64),
+  Bool(bool),
+  Str(String),
+}
+
+impl Constant {
+  fn of(primary: &PrimaryValue) -> Self {
+    match primary {
+      PrimaryValue::Number(n) => Constant::Int(*n as i64),
+      PrimaryValue::Float(n) => Constant::Float(*n),
+      PrimaryValue::Bool(b) => Constant::Bool(*b),
+      PrimaryValue::String(s) => Constant::Str(s.clone()),
+    }
+  }
+
+  fn number(&self) -> Option<f64> {
+    match self {
+      Constant::Int(n) => Some(*n as f64),
+      Constant::Float(n) => Some(*n),
+      _ => None,
+    }
+  }
+
+  // what C makes of it in a condition
+  fn truth(&self) -> Option<bool> {
+    match self {
+      Constant::Bool(b) => Some(*b),
+      Constant::Int(n) => Some(*n != 0),
+      Constant::Float(n) => Some(*n != 0.0),
+      Constant::Str(_) => None,
+    }
+  }
+
+  fn into_primary(self) -> PrimaryValue {
+    match self {
+      Constant::Int(n) => PrimaryValue::Number(n as f64),
+      Constant::Float(n) => PrimaryValue::Float(n),
+      Constant::Bool(b) => PrimaryValue::Bool(b),
+      Constant::Str(s) => PrimaryValue::String(s),
+    }
+  }
+}
+
+// `a op b` the way the generated C computes it: ints stay ints and must fit
+// a C int, `7 / 2` is 3, and nothing is folded that C would not compute
+fn apply(left: &Constant, op: &BinOp, right: &Constant) -> Option<Constant> {
+  if let (Constant::Int(a), Constant::Int(b)) = (left, right) {
+    let value = match op {
+      BinOp::Add => a.checked_add(*b)?,
+      BinOp::Min => a.checked_sub(*b)?,
+      BinOp::Time => a.checked_mul(*b)?,
+      BinOp::Div => a.checked_div(*b)?,
+    };
+    return i32::try_from(value).ok().map(|_| Constant::Int(value));
+  }
+
+  let (a, b) = (left.number()?, right.number()?);
+  let value = match op {
+    BinOp::Add => a + b,
+    BinOp::Min => a - b,
+    BinOp::Time => a * b,
+    BinOp::Div => a / b,
+  };
+  value.is_finite().then_some(Constant::Float(value))
+}
+
+fn compare(left: &Constant, op: &CompareOp, right: &Constant) -> Option<bool> {
+  match (op, left, right) {
+    (CompareOp::Eq, Constant::Str(a), Constant::Str(b)) => Some(a == b),
+    (CompareOp::Eq, Constant::Bool(a), Constant::Bool(b)) => Some(a == b),
+    (CompareOp::Eq, _, _) => Some(left.number()? == right.number()?),
+    (CompareOp::Gt, _, _) => Some(left.number()? > right.number()?),
+    (CompareOp::Lt, _, _) => Some(left.number()? < right.number()?),
+  }
+}
+
+// the constant an operation computes, None when some value is not constant
+fn value(operation: &Operation) -> Option<Constant> {
+  match operation {
+    Operation::Factor(Factor(FactorValue::Primary(Primary(primary)))) => {
+      Some(Constant::of(primary))
+    }
+    Operation::Factor(_) => None,
+    Operation::Group(inner) => value(inner),
+    Operation::Binary(left, op, right) => apply(&value(left)?, op, &value(right)?),
+  }
+}
+
+// whether a `/` divides by something not known to be a non-zero number
+fn may_divide_by_zero(operation: &Operation) -> bool {
+  match operation {
+    Operation::Factor(_) => false,
+    Operation::Group(inner) => may_divide_by_zero(inner),
+    Operation::Binary(left, op, right) => {
+      let divisor = value(right);
+      may_divide_by_zero(left)
+        || may_divide_by_zero(right)
+        || op.is_div()
+          && !matches!(divisor.as_ref().and_then(Constant::number), Some(n) if n != 0.0)
+    }
+  }
+}
+
+fn group_value(factor: &ComponentFactor) -> Option<Constant> {
+  value(&Operation::of_component_factor(factor))
+}
+
+fn expr_value(expr: &Expression) -> Option<Constant> {
+  value(&Operation::of_expr(expr))
+}
+
+fn literal_arithmetic_expr(value: Constant) -> ArithmeticExpr {
+  let factor = Factor(FactorValue::Primary(Primary(value.into_primary())));
+  ArithmeticExpr(Term(factor, None, None), None, None)
+}
+
+fn literal_expr(value: Constant) -> Expression {
+  let factor = ComponentFactor(
+    false,
+    ComponentFactorValue::ArithmeticExpr(literal_arithmetic_expr(value)),
+  );
+  Expression(ExpressionValue::ComponentArithmeticExpr(
+    ComponentArithmeticExpr(ComponentTerm(factor, None, None), None, None),
+  ))
+}
+
+fn literal_logic_expr(value: bool) -> LogicExpr {
+  let compare = CompareExpr(literal_expr(Constant::Bool(value)), None, None);
+  LogicExpr(LogicOp::Bool, Some(compare), None, None)
+}
+
+// `(1 + 1) * 2` becomes `4`, in `x * (1 + 1)` only the group is folded;
+// anonymous functions met on the way are optimized whole
+struct Folder;
+
+impl VisitorMut for Folder {
+  fn visit_expr_mut(&mut self, expr: &mut Expression) {
+    walk_expr_mut(self, expr);
+    if let Some(value) = expr_value(expr) {
+      *expr = literal_expr(value);
+    }
+  }
+
+  fn visit_component_factor_mut(&mut self, factor: &mut ComponentFactor) {
+    walk_component_factor_mut(self, factor);
+    if factor.0 {
+      if let Some(value) = group_value(factor) {
+        *factor = ComponentFactor(
+          false,
+          ComponentFactorValue::ArithmeticExpr(literal_arithmetic_expr(value)),
+        );
+      }
+    }
+  }
+
+  fn visit_function_stmt_mut(&mut self, function: &mut FunctionStmt) {
+    optimize_function(function);
+  }
+}
+
+fn fold_expr(expr: &mut Expression) {
+  Folder.visit_expr_mut(expr);
+}
+
+// fold a condition, returns its value when it is known
+fn fold_logic_expr(expr: &mut LogicExpr) -> Option<bool> {
+  let value = match expr.0 {
+    LogicOp::Bool => match &mut expr.1 {
+      Some(CompareExpr(left, op, right)) => {
+        fold_expr(left);
+        if let Some(right) = right {
+          fold_expr(right);
+        }
+        let left = expr_value(left)?;
+        match (op, right) {
+          (Some(op), Some(right)) => compare(&left, op, &expr_value(right)?),
+          _ => left.truth(),
+        }
+      }
+      None => None,
+    },
+    LogicOp::Not => expr.2.as_deref_mut().and_then(fold_logic_expr).map(|b| !b),
+    // `and` and `or` skip their right side like in C, so `and(False, f())` never calls f
+    LogicOp::And | LogicOp::Or => {
+      let is_and = expr.0 == LogicOp::And;
+      let left = expr.2.as_deref_mut().and_then(fold_logic_expr);
+      let right = expr.3.as_deref_mut().and_then(fold_logic_expr);
+      match (left, right) {
+        (Some(left), _) if left != is_and => Some(left),
+        (Some(_), Some(right)) => Some(right),
+        (Some(_), None) => {
+          if let Some(right) = expr.3.take() {
+            *expr = *right;
+          }
+          None
+        }
+        (None, Some(right)) if right == is_and => {
+          if let Some(left) = expr.2.take() {
+            *expr = *left;
+          }
+          None
+        }
+        _ => None,
+      }
+    }
+  };
+
+  if let Some(value) = value {
+    *expr = literal_logic_expr(value);
+  }
+  value
+}
+
+fn into_statements(stmt_list: StmtList) -> Vec<Statement> {
+  let mut stmts = vec![];
+  let mut rest = Some(stmt_list);
+  while let Some(StmtList(stmt, next)) = rest {
+    stmts.extend(stmt);
+    rest = next.map(|next| *next);
+  }
+  stmts
+}
+
+fn from_statements(stmts: Vec<Statement>) -> StmtList {
+  stmts
+    .into_iter()
+    .rev()
+    .fold(StmtList(None, None), |rest, stmt| match rest {
+      StmtList(None, None) => StmtList(Some(stmt), None),
+      rest => StmtList(Some(stmt), Some(Box::new(rest))),
+    })
+}
+
+// `reads` are the names read anywhere in the function, or the program, around the block
+fn optimize_block(stmt_list: StmtList, reads: &HashSet<String>) -> StmtList {
+  let mut stmts = vec![];
+  for mut stmt in into_statements(stmt_list) {
+    let is_if = matches!(stmt.0, StatementValue::IfStmt(_));
+    match &mut stmt.0 {
+      StatementValue::IfStmt(IfStmt(condition, body))
+      | StatementValue::WhileStmt(WhileStmt(condition, body)) => {
+        match fold_logic_expr(condition) {
+          // a variable is declared where it is assigned, a body never run is
+          // kept when it holds an assignment of a variable read elsewhere
+          Some(false) if !assigns_read_variable(body.as_deref(), reads) => continue,
+          // the body of an if runs every time, without the if around it
+          Some(true) if is_if => {
+            if let Some(body) = body.take() {
+              stmts.extend(into_statements(optimize_block(*body, reads)));
+            }
+            continue;
+          }
+          _ => {
+            *body = body
+              .take()
+              .map(|body| Box::new(optimize_block(*body, reads)))
+          }
+        }
+      }
+      _ => Folder.visit_stmt_mut(&mut stmt),
+    }
+    stmts.push(stmt);
+  }
+  from_statements(stmts)
+}
+
+fn optimize_function(function: &mut FunctionStmt) {
+  let Some(body) = function.2.take() else {
+    return;
+  };
+  let mut reads = Reads::default();
+  reads.visit_stmt_list(&body);
+  let mut body = optimize_block(*body, &reads.0);
+  // removing an assignment can leave the variable it read unused too
+  loop {
+    let mut reads = Reads::default();
+    reads.visit_stmt_list(&body);
+    let mut removed = false;
+    body = remove_dead_assignments(body, &reads.0, &mut removed);
+    if !removed {
+      break;
+    }
+  }
+  function.2 = Some(Box::new(body));
+}
+
+fn assigns_read_variable(body: Option<&StmtList>, reads: &HashSet<String>) -> bool {
+  let mut assigned = Assigned::default();
+  if let Some(body) = body {
+    assigned.visit_stmt_list(body);
+  }
+  assigned.0.iter().any(|name| reads.contains(name))
+}
+
+// the variables a block assigns, not counting the ones of the functions in it
+#[derive(Default)]
+struct Assigned(HashSet<String>);
+
+impl Visitor for Assigned {
+  fn visit_assign_stmt(&mut self, stmt: &AssignStmt) {
+    self.0.insert(stmt.0.to_string());
+  }
+
+  fn visit_function_stmt(&mut self, _function: &FunctionStmt) {}
+}
+
+// every name read, including by the functions nested in the statements
+#[derive(Default)]
+struct Reads(HashSet<String>);
+
+impl Visitor for Reads {
+  fn visit_factor(&mut self, factor: &Factor) {
+    if let FactorValue::Identifier(name) = &factor.0 {
+      self.0.insert(name.to_string());
+    }
+    walk_factor(self, factor);
+  }
+
+  fn visit_call_expr(&mut self, call_expr: &CallExpr) {
+    self.0.insert(call_expr.0.to_string());
+    walk_call_expr(self, call_expr);
+  }
+}
+
+// an assignment can go when nothing reads its variable and computing its value
+// cannot call a function or stop at a runtime error, like a missing list item
+fn remove_dead_assignments(
+  stmt_list: StmtList,
+  reads: &HashSet<String>,
+  removed: &mut bool,
+) -> StmtList {
+  let mut stmts = vec![];
+  for mut stmt in into_statements(stmt_list) {
+    match &mut stmt.0 {
+      StatementValue::AssignStmt(AssignStmt(name, expr, _))
+        if !reads.contains(&name.to_string()) && is_pure(expr) =>
+      {
+        *removed = true;
+        continue;
+      }
+      StatementValue::IfStmt(IfStmt(_, body)) | StatementValue::WhileStmt(WhileStmt(_, body)) => {
+        *body = body
+          .take()
+          .map(|body| Box::new(remove_dead_assignments(*body, reads, removed)));
+      }
+      _ => (),
+    }
+    stmts.push(stmt);
+  }
+  from_statements(stmts)
+}
+
+fn is_pure(expr: &Expression) -> bool {
+  let mut impure = Impure(false);
+  impure.visit_expr(expr);
+  !impure.0
+}
+
+// whether computing an expression calls a function or can stop at a runtime
+// error: a missing item or field, or a division by zero
+struct Impure(bool);
+
+impl Visitor for Impure {
+  fn visit_expr(&mut self, expr: &Expression) {
+    self.0 |= may_divide_by_zero(&Operation::of_expr(expr));
+    walk_expr(self, expr);
+  }
+
+  fn visit_factor(&mut self, factor: &Factor) {
+    match factor.0 {
+      FactorValue::CallExpr(_)
+      | FactorValue::Apply(_)
+      | FactorValue::Index(_)
+      | FactorValue::Field(_) => self.0 = true,
+      _ => walk_factor(self, factor),
+    }
+  }
+
+  // making a function value runs none of its statements
+  fn visit_function_stmt(&mut self, _function: &FunctionStmt) {}
+}
+
+#[test]
+fn test_fold_expr() {
+  use parser::{parse_expression::parse_expression, parser::Parser};
+
+  let folded = |code: &str| {
+    let mut expr = parse_expression(&mut Parser::new(code));
+    fold_expr(&mut expr);
+    expr.as_factor().map(|factor| factor.0.clone())
+  };
+  let literal = |value| Some(FactorValue::Primary(Primary(value)));
+
+  assert_eq!(folded("(1+1) * 2"), literal(PrimaryValue::Number(4.0)));
+  assert_eq!(
+    folded("1 + 2 * 3 - 4 / 3"),
+    literal(PrimaryValue::Number(6.0))
+  );
+  assert_eq!(folded("7 / 2 * 1.5"), literal(PrimaryValue::Float(4.5)));
+  assert_eq!(folded("1 / 0"), None);
+  assert_eq!(folded("x * 2"), None);
+}
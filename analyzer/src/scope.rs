@@ -0,0 +1,232 @@
+use std::collections::HashMap;
+use std::fmt::{self, Display};
+
+use parser::location::Location;
+
+use crate::signature::Arity;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolKind {
+  Variable,
+  Parameter,
+  Function,
+  Struct,
+}
+
+impl SymbolKind {
+  pub fn is_callable(&self) -> bool {
+    matches!(self, SymbolKind::Function | SymbolKind::Struct)
+  }
+}
+
+impl Display for SymbolKind {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      SymbolKind::Variable => write!(f, "variable"),
+      SymbolKind::Parameter => write!(f, "parameter"),
+      SymbolKind::Function => write!(f, "function"),
+      SymbolKind::Struct => write!(f, "struct"),
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Symbol {
+  pub name: String,
+  pub kind: SymbolKind,
+  // where the symbol is declared
+  pub location: Location,
+  // how many arguments a function or struct constructor takes
+  pub arity: Option<Arity>,
+}
+
+impl Symbol {
+  pub fn new(name: impl Into<String>, kind: SymbolKind, location: Location) -> Self {
+    Symbol {
+      name: name.into(),
+      kind,
+      location,
+      arity: None,
+    }
+  }
+
+  pub fn with_arity(mut self, arity: Arity) -> Self {
+    self.arity = Some(arity);
+    self
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScopeKind {
+  Program,
+  Function,
+  If,
+}
+
+#[derive(Debug)]
+pub struct Scope {
+  pub kind: ScopeKind,
+  symbols: HashMap<String, Symbol>,
+}
+
+impl Scope {
+  pub fn new(kind: ScopeKind) -> Self {
+    Scope {
+      kind,
+      symbols: HashMap::new(),
+    }
+  }
+
+  pub fn get(&self, name: &str) -> Option<&Symbol> {
+    self.symbols.get(name)
+  }
+}
+
+// nested scopes, the innermost one is the last
+#[derive(Debug)]
+pub struct SymbolTable {
+  scopes: Vec<Scope>,
+}
+
+impl Default for SymbolTable {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl SymbolTable {
+  // a symbol table with the program scope already opened
+  pub fn new() -> Self {
+    SymbolTable {
+      scopes: vec![Scope::new(ScopeKind::Program)],
+    }
+  }
+
+  pub fn push_scope(&mut self, kind: ScopeKind) {
+    self.scopes.push(Scope::new(kind));
+  }
+
+  pub fn pop_scope(&mut self) -> Option<Scope> {
+    self.scopes.pop()
+  }
+
+  pub fn current_scope(&self) -> &Scope {
+    self
+      .scopes
+      .last()
+      .expect("the program scope is never popped")
+  }
+
+  // declare a symbol in the innermost scope, returns the symbol it replaces
+  pub fn declare(&mut self, symbol: Symbol) -> Option<Symbol> {
+    let scope = self
+      .scopes
+      .last_mut()
+      .expect("the program scope is never popped");
+    scope.symbols.insert(symbol.name.clone(), symbol)
+  }
+
+  // declare a symbol in the innermost function or program scope, so a variable
+  // first assigned inside an if is still known after it
+  pub fn declare_local(&mut self, symbol: Symbol) -> Option<Symbol> {
+    let scope = self
+      .scopes
+      .iter_mut()
+      .rev()
+      .find(|scope| scope.kind != ScopeKind::If)
+      .expect("the program scope is never popped");
+    scope.symbols.insert(symbol.name.clone(), symbol)
+  }
+
+  // find a variable or parameter of a function around the innermost one, which
+  // the innermost function only gets a copy of
+  pub fn lookup_captured(&self, name: &str) -> Option<&Symbol> {
+    let mut outside = false;
+    for scope in self.scopes.iter().rev() {
+      if let Some(symbol) = scope.get(name) {
+        let captured = outside
+          && scope.kind != ScopeKind::Program
+          && matches!(symbol.kind, SymbolKind::Variable | SymbolKind::Parameter);
+        return captured.then_some(symbol);
+      }
+      outside = outside || scope.kind == ScopeKind::Function;
+    }
+    None
+  }
+
+  // find a symbol in any scope, the innermost one wins
+  pub fn lookup(&self, name: &str) -> Option<&Symbol> {
+    self.scopes.iter().rev().find_map(|scope| scope.get(name))
+  }
+
+  // find a symbol declared in the innermost scope only
+  pub fn lookup_current(&self, name: &str) -> Option<&Symbol> {
+    self.current_scope().get(name)
+  }
+
+  // find a symbol owned by the function being analyzed: its if scopes,
+  // its body and its parameters, but not the scopes around the function
+  pub fn lookup_local(&self, name: &str) -> Option<&Symbol> {
+    for scope in self.scopes.iter().rev() {
+      if let Some(symbol) = scope.get(name) {
+        return Some(symbol);
+      }
+      if scope.kind != ScopeKind::If {
+        break;
+      }
+    }
+    None
+  }
+
+  // whether the innermost scope other than an if is a function, not the program
+  pub fn in_function(&self) -> bool {
+    self
+      .scopes
+      .iter()
+      .rev()
+      .find(|scope| scope.kind != ScopeKind::If)
+      .is_some_and(|scope| scope.kind == ScopeKind::Function)
+  }
+}
+
+#[test]
+fn test_symbol_table_scopes() {
+  let mut table = SymbolTable::new();
+  table.declare(Symbol::new(
+    "fib",
+    SymbolKind::Function,
+    Location::new(2, 3),
+  ));
+  table.push_scope(ScopeKind::Function);
+  table.declare(Symbol::new("n", SymbolKind::Parameter, Location::new(2, 3)));
+  table.push_scope(ScopeKind::If);
+  table.declare(Symbol::new("x", SymbolKind::Variable, Location::new(4, 5)));
+
+  assert_eq!(
+    table.lookup("fib").map(|s| s.kind),
+    Some(SymbolKind::Function)
+  );
+  assert_eq!(
+    table.lookup_local("n").map(|s| s.kind),
+    Some(SymbolKind::Parameter)
+  );
+  assert_eq!(table.lookup_local("fib"), None);
+  assert_eq!(table.lookup_current("n"), None);
+  assert_eq!(table.lookup_captured("n"), None);
+
+  table.push_scope(ScopeKind::Function);
+  assert_eq!(
+    table.lookup_captured("n").map(|s| s.kind),
+    Some(SymbolKind::Parameter)
+  );
+  assert_eq!(table.lookup_captured("fib"), None);
+  table.pop_scope();
+
+  table.declare_local(Symbol::new("y", SymbolKind::Variable, Location::new(5, 5)));
+  table.pop_scope();
+  assert_eq!(table.lookup("x"), None);
+  assert_eq!(
+    table.lookup_current("y").map(|s| s.kind),
+    Some(SymbolKind::Variable)
+  );
+}
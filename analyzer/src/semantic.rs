@@ -0,0 +1,414 @@
+use std::collections::HashSet;
+
+use parser::ast::{
+  ApplyExpr, ArithmeticExpr, CallExpr, CompareExpr, ComponentArithmeticExpr, ComponentFactor,
+  ComponentFactorValue, ComponentTerm, Expression, ExpressionValue, Factor, FactorValue,
+  FunctionStmt, IfStmt, LogicExpr, Primary, Program, Statement, StatementValue, StmtList,
+  StructStmt, Term, WhileStmt,
+};
+use parser::location::Location;
+
+use crate::builtins::find_builtin;
+use crate::diagnostic::Diagnostic;
+use crate::flow::check_returns;
+use crate::scope::{ScopeKind, Symbol, SymbolKind, SymbolTable};
+use crate::signature::Arity;
+
+// check every name used in the program, returns the problems in source order
+pub fn analyze(program: &Program) -> Vec<Diagnostic> {
+  let mut analyzer = SemanticAnalyzer::new();
+  analyzer.visit_program(program);
+
+  let mut diagnostics = analyzer.diagnostics;
+  diagnostics.extend(check_returns(program));
+  diagnostics.sort_by_key(|diagnostic| (diagnostic.location.row(), diagnostic.location.column()));
+  diagnostics
+}
+
+fn statements(stmt_list: &StmtList) -> Vec<&Statement> {
+  let mut stmts = vec![];
+  let mut rest = Some(stmt_list);
+  while let Some(StmtList(stmt, next)) = rest {
+    stmts.extend(stmt.iter());
+    rest = next.as_deref();
+  }
+  stmts
+}
+
+pub struct SemanticAnalyzer {
+  symbols: SymbolTable,
+  diagnostics: Vec<Diagnostic>,
+  // location of the statement being analyzed, expressions have no location of their own
+  location: Location,
+  // variables first assigned inside each open if or while of the current function
+  if_assigned: Vec<Vec<String>>,
+  // variables only assigned inside an if or while that has ended, reading them
+  // may find no value
+  maybe_unassigned: HashSet<String>,
+}
+
+impl Default for SemanticAnalyzer {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl SemanticAnalyzer {
+  pub fn new() -> Self {
+    SemanticAnalyzer {
+      symbols: SymbolTable::new(),
+      diagnostics: vec![],
+      location: Location::default(),
+      if_assigned: vec![],
+      maybe_unassigned: HashSet::new(),
+    }
+  }
+
+  pub fn diagnostics(&self) -> &[Diagnostic] {
+    &self.diagnostics
+  }
+
+  fn error(&mut self, message: String) {
+    self
+      .diagnostics
+      .push(Diagnostic::error(message, self.location.clone()));
+  }
+
+  fn warning(&mut self, message: String) {
+    self
+      .diagnostics
+      .push(Diagnostic::warning(message, self.location.clone()));
+  }
+
+  fn declare(&mut self, name: String, kind: SymbolKind) {
+    self.declare_symbol(Symbol::new(name, kind, self.location.clone()));
+  }
+
+  // declare a symbol in the current scope, reporting duplicates and shadowing
+  fn declare_symbol(&mut self, symbol: Symbol) {
+    let Symbol { name, kind, .. } = &symbol;
+    if let Some(previous) = self.symbols.lookup_current(name) {
+      let message = format!(
+        "{} `{}` is already defined at {}",
+        previous.kind, name, previous.location
+      );
+      self.error(message);
+      return;
+    }
+
+    if let Some(outer) = self.symbols.lookup(name) {
+      let message = format!(
+        "{} `{}` shadows the {} defined at {}",
+        kind, name, outer.kind, outer.location
+      );
+      self.warning(message);
+    }
+
+    self.symbols.declare(symbol);
+  }
+
+  // variables belong to the whole function, even when first assigned inside an if
+  fn declare_variable(&mut self, name: String) {
+    if let Some(outer) = self.symbols.lookup(&name) {
+      let message = format!(
+        "variable `{}` shadows the {} defined at {}",
+        name, outer.kind, outer.location
+      );
+      self.warning(message);
+    }
+
+    if let Some(assigned) = self.if_assigned.last_mut() {
+      assigned.push(name.clone());
+    }
+    let symbol = Symbol::new(name, SymbolKind::Variable, self.location.clone());
+    self.symbols.declare_local(symbol);
+  }
+
+  // functions and structs can be used before the statement defining them,
+  // so they are declared before the statements of their body are analyzed
+  fn declare_items(&mut self, stmts: &[&Statement]) {
+    for stmt in stmts {
+      self.location = stmt.location().clone();
+      match &stmt.0 {
+        StatementValue::FunctionStmt(FunctionStmt(name, params, ..)) => {
+          let symbol = Symbol::new(
+            name.to_string(),
+            SymbolKind::Function,
+            self.location.clone(),
+          );
+          self.declare_symbol(symbol.with_arity(Arity::Exact(params.len())));
+        }
+        // Point(1, 2) takes one argument per field
+        StatementValue::StructStmt(StructStmt(name, fields)) => {
+          let symbol = Symbol::new(name.to_string(), SymbolKind::Struct, self.location.clone());
+          self.declare_symbol(symbol.with_arity(Arity::Exact(fields.len())));
+        }
+        _ => (),
+      }
+    }
+  }
+
+  fn visit_body(&mut self, kind: ScopeKind, params: &[String], body: Option<&StmtList>) {
+    // a nested function starts without open ifs
+    let (outer_ifs, outer_unassigned) = match kind {
+      ScopeKind::Function => (
+        std::mem::take(&mut self.if_assigned),
+        std::mem::take(&mut self.maybe_unassigned),
+      ),
+      _ => (vec![], HashSet::new()),
+    };
+    if kind == ScopeKind::If {
+      self.if_assigned.push(vec![]);
+    }
+
+    self.symbols.push_scope(kind);
+    for param in params {
+      self.declare(param.to_owned(), SymbolKind::Parameter);
+    }
+    if let Some(body) = body {
+      let stmts = statements(body);
+      self.declare_items(&stmts);
+      stmts.iter().for_each(|stmt| self.visit_stmt(stmt));
+    }
+    self.symbols.pop_scope();
+
+    match kind {
+      ScopeKind::Function => {
+        self.if_assigned = outer_ifs;
+        self.maybe_unassigned = outer_unassigned;
+      }
+      // the variables may still have no value after the if, and after the ifs around it
+      ScopeKind::If => {
+        let assigned = self.if_assigned.pop().unwrap_or_default();
+        if let Some(outer) = self.if_assigned.last_mut() {
+          outer.extend(assigned.iter().cloned());
+        }
+        self.maybe_unassigned.extend(assigned);
+      }
+      ScopeKind::Program => (),
+    }
+  }
+
+  pub fn visit_program(&mut self, program: &Program) {
+    let stmts = statements(&program.0);
+    self.declare_items(&stmts);
+    // the statements outside functions run before main, so every function
+    // sees the variables they assign
+    let (functions, others): (Vec<&Statement>, Vec<&Statement>) = stmts
+      .iter()
+      .partition(|stmt| matches!(stmt.0, StatementValue::FunctionStmt(_)));
+    others.iter().for_each(|stmt| self.visit_stmt(stmt));
+    functions.iter().for_each(|stmt| self.visit_stmt(stmt));
+
+    let has_main = functions.iter().any(|stmt| {
+      matches!(&stmt.0, StatementValue::FunctionStmt(FunctionStmt(name, ..)) if name.to_string() == "main")
+    });
+    if !has_main {
+      self.location = Location::new(1, 1);
+      self.error("the program has no `main` function".to_owned());
+    }
+  }
+
+  pub fn visit_stmt(&mut self, stmt: &Statement) {
+    self.location = stmt.location().clone();
+    match &stmt.0 {
+      StatementValue::AssignStmt(stmt) => {
+        self.visit_expr(&stmt.1);
+        let name = stmt.0.to_string();
+        match self.symbols.lookup_local(&name).map(|symbol| symbol.kind) {
+          Some(kind) if kind.is_callable() => {
+            self.error(format!("cannot assign to {} `{}`", kind, name))
+          }
+          // reassignment of a variable of this function
+          // it has a value from now on, but only until the if around it ends
+          Some(_) => {
+            if self.maybe_unassigned.remove(&name) {
+              if let Some(assigned) = self.if_assigned.last_mut() {
+                assigned.push(name);
+              }
+            }
+          }
+          // a copy of the variable would change, not the variable
+          None => match self.symbols.lookup_captured(&name) {
+            Some(symbol) => {
+              let message = format!(
+                "a nested function cannot assign the {} `{}` defined at {}, pass it as an argument",
+                symbol.kind, name, symbol.location
+              );
+              self.error(message);
+            }
+            None => self.declare_variable(name),
+          },
+        }
+      }
+      StatementValue::IndexAssignStmt(stmt) => {
+        self.visit_factor(&stmt.0 .0);
+        self.visit_expr(&stmt.0 .1);
+        self.visit_expr(&stmt.1);
+      }
+      StatementValue::FieldAssignStmt(stmt) => {
+        self.visit_factor(&stmt.0 .0);
+        self.visit_expr(&stmt.1);
+      }
+      StatementValue::CallStmt(call_expr) => self.visit_call_expr(call_expr),
+      StatementValue::ApplyStmt(apply_expr) => self.visit_apply_expr(apply_expr),
+      StatementValue::ReturnStmt(stmt) => {
+        if !self.symbols.in_function() {
+          self.error("`return` outside a function".to_owned());
+        }
+        self.visit_expr(&stmt.0);
+      }
+      StatementValue::FunctionStmt(FunctionStmt(_, params, body, _)) => {
+        let params: Vec<String> = params.iter().map(|param| param.to_string()).collect();
+        self.visit_body(ScopeKind::Function, &params, body.as_deref());
+      }
+      StatementValue::StructStmt(StructStmt(name, fields)) => {
+        for (idx, field) in fields.iter().enumerate() {
+          if fields[..idx].contains(field) {
+            self.error(format!(
+              "field `{}` is repeated in struct `{}`",
+              field, name
+            ));
+          }
+        }
+      }
+      // a loop body may not run at all, so it is checked like an if body
+      StatementValue::IfStmt(IfStmt(condition, body))
+      | StatementValue::WhileStmt(WhileStmt(condition, body)) => {
+        self.visit_logic_expr(condition);
+        self.visit_body(ScopeKind::If, &[], body.as_deref());
+      }
+    }
+  }
+
+  fn visit_logic_expr(&mut self, expr: &LogicExpr) {
+    if let Some(CompareExpr(left, _, right)) = &expr.1 {
+      self.visit_expr(left);
+      if let Some(right) = right {
+        self.visit_expr(right);
+      }
+    }
+    if let Some(left) = &expr.2 {
+      self.visit_logic_expr(left);
+    }
+    if let Some(right) = &expr.3 {
+      self.visit_logic_expr(right);
+    }
+  }
+
+  pub fn visit_expr(&mut self, expr: &Expression) {
+    let ExpressionValue::ComponentArithmeticExpr(expr) = &expr.0;
+    self.visit_component_arithmetic_expr(expr);
+  }
+
+  fn visit_component_arithmetic_expr(&mut self, expr: &ComponentArithmeticExpr) {
+    self.visit_component_term(&expr.0);
+    if let Some(rest) = &expr.2 {
+      self.visit_component_arithmetic_expr(rest);
+    }
+  }
+
+  fn visit_component_term(&mut self, term: &ComponentTerm) {
+    self.visit_component_factor(&term.0);
+    if let Some(rest) = &term.2 {
+      self.visit_component_term(rest);
+    }
+  }
+
+  fn visit_component_factor(&mut self, factor: &ComponentFactor) {
+    match &factor.1 {
+      ComponentFactorValue::ArithmeticExpr(expr) => self.visit_arithmetic_expr(expr),
+      ComponentFactorValue::ComponentFactor(Some(inner)) => self.visit_component_factor(inner),
+      ComponentFactorValue::ComponentFactor(None) => (),
+    }
+  }
+
+  fn visit_arithmetic_expr(&mut self, expr: &ArithmeticExpr) {
+    self.visit_term(&expr.0);
+    if let Some(rest) = &expr.2 {
+      self.visit_arithmetic_expr(rest);
+    }
+  }
+
+  fn visit_term(&mut self, term: &Term) {
+    self.visit_factor(&term.0);
+    if let Some(rest) = &term.2 {
+      self.visit_term(rest);
+    }
+  }
+
+  fn visit_factor(&mut self, factor: &Factor) {
+    match &factor.0 {
+      FactorValue::Primary(Primary(_)) => (),
+      FactorValue::Identifier(name) => {
+        let name = name.to_string();
+        match self.symbols.lookup(&name).map(|symbol| symbol.kind) {
+          None => return self.error(format!("undefined variable `{}`", name)),
+          Some(SymbolKind::Struct) => {
+            return self.error(format!("struct `{}` cannot be used as a value", name))
+          }
+          _ => (),
+        }
+        if self.maybe_unassigned.remove(&name) {
+          self.warning(format!(
+            "`{}` may have no value here, it is only assigned inside a block that may not run",
+            name
+          ));
+        }
+      }
+      FactorValue::CallExpr(call_expr) => self.visit_call_expr(call_expr),
+      FactorValue::List(list) => list.0 .0.iter().for_each(|item| self.visit_expr(item)),
+      FactorValue::Map(map) => map.0.iter().for_each(|entry| {
+        self.visit_expr(&entry.0);
+        self.visit_expr(&entry.1);
+      }),
+      FactorValue::Index(index) => {
+        self.visit_factor(&index.0);
+        self.visit_expr(&index.1);
+      }
+      // fields are looked up by name at runtime
+      FactorValue::Field(field) => self.visit_factor(&field.0),
+      FactorValue::Apply(apply_expr) => self.visit_apply_expr(apply_expr),
+      FactorValue::Function(function, location) => {
+        let FunctionStmt(_, params, body, _) = function.as_ref();
+        let params: Vec<String> = params.iter().map(|param| param.to_string()).collect();
+        let outer = std::mem::replace(&mut self.location, location.clone());
+        self.visit_body(ScopeKind::Function, &params, body.as_deref());
+        self.location = outer;
+      }
+    }
+  }
+
+  // a function value, its arguments are checked once its type is known
+  fn visit_apply_expr(&mut self, apply_expr: &ApplyExpr) {
+    self.visit_factor(&apply_expr.0);
+    apply_expr.1 .0.iter().for_each(|arg| self.visit_expr(arg));
+  }
+
+  fn visit_call_expr(&mut self, call_expr: &CallExpr) {
+    call_expr.1 .0.iter().for_each(|arg| self.visit_expr(arg));
+
+    let name = call_expr.0.to_string();
+    let (kind, arity) = match self.symbols.lookup(&name) {
+      Some(symbol) if symbol.kind.is_callable() => (symbol.kind.to_string(), symbol.arity),
+      // a function value, its arguments are checked once its type is known
+      Some(_) => return,
+      None => match find_builtin(&name) {
+        Some(signature) => ("builtin".to_owned(), Some(signature.arity)),
+        None => return self.error(format!("undefined function `{}`", name)),
+      },
+    };
+
+    let count = call_expr.1 .0.len();
+    match arity {
+      Some(arity) if !arity.accepts(count) => {
+        let given = match count {
+          1 => "1 was given".to_owned(),
+          _ => format!("{} were given", count),
+        };
+        self.error(format!("{} `{}` takes {} but {}", kind, name, arity, given));
+      }
+      _ => (),
+    }
+  }
+}
@@ -0,0 +1,52 @@
+use std::fmt::{self, Display};
+
+// how many arguments a function accepts
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Arity {
+  Exact(usize),
+  // variadic functions like printf
+  AtLeast(usize),
+}
+
+impl Arity {
+  pub fn accepts(&self, count: usize) -> bool {
+    match *self {
+      Arity::Exact(n) => count == n,
+      Arity::AtLeast(n) => count >= n,
+    }
+  }
+}
+
+fn arguments(count: usize) -> String {
+  match count {
+    1 => "1 argument".to_owned(),
+    _ => format!("{} arguments", count),
+  }
+}
+
+// `1 argument`, `2 arguments` or `at least 1 argument`
+impl Display for Arity {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      Arity::Exact(n) => write!(f, "{}", arguments(n)),
+      Arity::AtLeast(n) => write!(f, "at least {}", arguments(n)),
+    }
+  }
+}
+
+// a function riven knows without a FunctionStmt, see builtins.rs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Signature {
+  pub name: &'static str,
+  pub arity: Arity,
+}
+
+#[test]
+fn test_arity() {
+  assert!(Arity::Exact(1).accepts(1));
+  assert!(!Arity::Exact(1).accepts(2));
+  assert!(Arity::AtLeast(1).accepts(3));
+  assert!(!Arity::AtLeast(1).accepts(0));
+  assert_eq!(Arity::Exact(2).to_string(), "2 arguments");
+  assert_eq!(Arity::AtLeast(1).to_string(), "at least 1 argument");
+}
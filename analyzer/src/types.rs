@@ -0,0 +1,149 @@
+use std::fmt::{self, Display};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum Type {
+  // nothing is known about the value yet, e.g. a parameter of a function never called
+  #[default]
+  Unknown,
+  Int,
+  // a number with decimals, e.g. 1.5 or input_number("age?")
+  Float,
+  Bool,
+  Str,
+  // the type of the items
+  List(Box<Type>),
+  // the types of the keys and of the values
+  Map(Box<Type>, Box<Type>),
+  Struct(String),
+  // a function used as a value, with the paths of every function it can be
+  Function(Vec<String>),
+  // a boxed value whose kind is only known at runtime, e.g. an item of [1, "a"]
+  Any,
+  // what a function without `return` gives back
+  Void,
+}
+
+impl Type {
+  pub fn list_of(item: Type) -> Self {
+    Type::List(Box::new(item))
+  }
+
+  pub fn map_of(key: Type, value: Type) -> Self {
+    Type::Map(Box::new(key), Box::new(value))
+  }
+
+  // the type written in an annotation like `x: number = 1`, structs are looked up by the caller
+  pub fn from_annotation(name: &str) -> Option<Type> {
+    match name {
+      "int" => Some(Type::Int),
+      "number" => Some(Type::Float),
+      "bool" => Some(Type::Bool),
+      "text" => Some(Type::Str),
+      "list" => Some(Type::list_of(Type::Unknown)),
+      "map" => Some(Type::map_of(Type::Unknown, Type::Unknown)),
+      "function" => Some(Type::Function(vec![])),
+      "any" => Some(Type::Any),
+      _ => None,
+    }
+  }
+
+  // whether a value of type `t` can be stored where this type is declared,
+  // an int fits in a number and any list fits in a `list`
+  pub fn accepts(&self, t: &Type) -> bool {
+    match (self, t) {
+      (_, Type::Unknown) | (Type::Any, _) | (Type::Float, Type::Int) => true,
+      (Type::List(item), Type::List(other)) => !item.is_known() || item.accepts(other),
+      (Type::Map(_, value), Type::Map(_, other)) => !value.is_known() || value.accepts(other),
+      (Type::Function(_), Type::Function(_)) => true,
+      (a, b) => a == b,
+    }
+  }
+
+  pub fn function_of(function: &str) -> Self {
+    Type::Function(vec![function.to_owned()])
+  }
+
+  pub fn is_number(&self) -> bool {
+    matches!(self, Type::Int | Type::Float)
+  }
+
+  pub fn is_known(&self) -> bool {
+    !matches!(self, Type::Unknown | Type::Any)
+  }
+
+  // the type able to hold values of both types, None when they don't mix:
+  // an int and a number make a number, but an int and a text make nothing
+  pub fn join(&self, other: &Type) -> Option<Type> {
+    match (self, other) {
+      (Type::Unknown, t) | (t, Type::Unknown) => Some(t.clone()),
+      (Type::Any, _) | (_, Type::Any) => Some(Type::Any),
+      (Type::Int, Type::Float) | (Type::Float, Type::Int) => Some(Type::Float),
+      (Type::List(a), Type::List(b)) => Some(Type::list_of(a.join_item(b))),
+      (Type::Map(ka, va), Type::Map(kb, vb)) => {
+        Some(Type::map_of(ka.join_item(kb), va.join_item(vb)))
+      }
+      (Type::Function(a), Type::Function(b)) => {
+        let mut functions = a.clone();
+        functions.extend(b.iter().cloned());
+        functions.sort();
+        functions.dedup();
+        Some(Type::Function(functions))
+      }
+      (a, b) if a == b => Some(a.clone()),
+      _ => None,
+    }
+  }
+
+  // items of lists and maps are boxed, so a list can mix ints and texts
+  pub fn join_item(&self, other: &Type) -> Type {
+    self.join(other).unwrap_or(Type::Any)
+  }
+}
+
+// the names used in diagnostics, the same words students write in annotations
+impl Display for Type {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      Type::Unknown => write!(f, "unknown"),
+      Type::Int => write!(f, "int"),
+      Type::Float => write!(f, "number"),
+      Type::Bool => write!(f, "bool"),
+      Type::Str => write!(f, "text"),
+      Type::List(item) if item.is_known() => write!(f, "list of {}", item),
+      Type::List(_) => write!(f, "list"),
+      Type::Map(_, value) if value.is_known() => write!(f, "map of {}", value),
+      Type::Map(_, _) => write!(f, "map"),
+      Type::Struct(name) => write!(f, "{}", name),
+      Type::Function(_) => write!(f, "function"),
+      Type::Any => write!(f, "any"),
+      Type::Void => write!(f, "nothing"),
+    }
+  }
+}
+
+#[test]
+fn test_join() {
+  assert_eq!(Type::Int.join(&Type::Float), Some(Type::Float));
+  assert_eq!(Type::Unknown.join(&Type::Str), Some(Type::Str));
+  assert_eq!(Type::Int.join(&Type::Str), None);
+  assert_eq!(
+    Type::list_of(Type::Int).join(&Type::list_of(Type::Str)),
+    Some(Type::list_of(Type::Any))
+  );
+  assert_eq!(Type::list_of(Type::Str).to_string(), "list of text");
+  assert_eq!(
+    Type::function_of("twice").join(&Type::function_of("half")),
+    Some(Type::Function(vec!["half".to_owned(), "twice".to_owned()]))
+  );
+}
+
+#[test]
+fn test_accepts() {
+  assert!(Type::Float.accepts(&Type::Int));
+  assert!(!Type::Int.accepts(&Type::Float));
+  assert!(!Type::Int.accepts(&Type::Any));
+  let list = Type::from_annotation("list").unwrap();
+  assert!(list.accepts(&Type::list_of(Type::Str)));
+  assert!(!list.accepts(&Type::Str));
+  assert_eq!(Type::from_annotation("numbr"), None);
+}
@@ -0,0 +1,232 @@
+use analyzer::{
+  infer::{infer, Inference},
+  types::Type,
+};
+use parser::{parse_program::parse_program, parser::Parser};
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn run(code: &str) -> Inference {
+    let mut p = Parser::new(code);
+    infer(&parse_program(&mut p))
+  }
+
+  fn messages(code: &str) -> Vec<String> {
+    run(code)
+      .diagnostics
+      .iter()
+      .map(|diagnostic| diagnostic.to_string())
+      .collect()
+  }
+
+  #[test]
+  fn test_infer_variables() {
+    let inference = run(
+      "program {
+        fn main() {
+          n = 1;
+          half = n / 2.0;
+          name = input(\"name?\");
+          scores = [1, 2];
+          append(scores, 3.5);
+          ages = {\"tom\": 8};
+          known = has(ages, \"amy\");
+        }
+      }",
+    );
+    let types = inference.types;
+    assert_eq!(types.variable_type("main", "n"), Type::Int);
+    assert_eq!(types.variable_type("main", "half"), Type::Float);
+    assert_eq!(types.variable_type("main", "name"), Type::Str);
+    assert_eq!(
+      types.variable_type("main", "scores"),
+      Type::list_of(Type::Float)
+    );
+    assert_eq!(
+      types.variable_type("main", "ages"),
+      Type::map_of(Type::Str, Type::Int)
+    );
+    assert_eq!(types.variable_type("main", "known"), Type::Bool);
+    assert!(inference.diagnostics.is_empty());
+  }
+
+  #[test]
+  fn test_infer_functions() {
+    let inference = run(
+      "program {
+        struct Point { x, y }
+        fn fib(n) {
+          if (n < 2) {
+            return n;
+          }
+          return fib(n - 1) + fib(n - 2);
+        }
+        fn greet(name) {
+          println(\"hi\", name);
+        }
+        fn main() {
+          greet(\"ann\");
+          p = Point(fib(10), 2.5);
+          x = p.x;
+        }
+      }",
+    );
+    let types = inference.types;
+    assert_eq!(types.param_types("fib"), vec![Type::Int]);
+    assert_eq!(types.return_type("fib"), Type::Int);
+    assert_eq!(types.param_types("greet"), vec![Type::Str]);
+    assert_eq!(types.return_type("greet"), Type::Void);
+    assert_eq!(
+      types.variable_type("main", "p"),
+      Type::Struct("Point".to_owned())
+    );
+    assert_eq!(types.field_type("Point", "y"), Some(Type::Float));
+    assert_eq!(types.variable_type("main", "x"), Type::Int);
+    assert!(inference.diagnostics.is_empty());
+  }
+
+  #[test]
+  fn test_type_mismatch() {
+    let code = "program {
+      struct Point { x, y }
+      fn twice(n) {
+        return n * 2;
+      }
+      fn main() {
+        x = 1;
+        x = \"one\";
+        y = twice(\"a\") + 1;
+        twice(3);
+        p = Point(1, 2);
+        println(p.z);
+        if (x > \"a\") {
+          return 1;
+        }
+      }
+    }";
+    assert_eq!(
+      messages(code),
+      vec![
+        "line 4 column 9: error: `*` needs numbers, got text",
+        "line 8 column 9: error: `x` is int, cannot assign text to it",
+        "line 10 column 9: error: argument 1 of `twice` is int here, but text in another call",
+        "line 12 column 9: error: struct `Point` has no field `z`",
+        "line 13 column 9: error: `>` needs numbers, got text",
+      ]
+    );
+  }
+
+  #[test]
+  fn test_type_annotations() {
+    let inference = run(
+      "program {
+        fn area(w: number, h: number) -> number {
+          return w * h;
+        }
+        fn main() {
+          a = area(2, 3);
+          greeting: text = \"hi\";
+          names: list = [];
+        }
+      }",
+    );
+    let types = inference.types;
+    assert_eq!(types.param_types("area"), vec![Type::Float, Type::Float]);
+    assert_eq!(types.return_type("area"), Type::Float);
+    assert_eq!(types.variable_type("main", "a"), Type::Float);
+    assert_eq!(types.variable_type("main", "greeting"), Type::Str);
+    assert_eq!(
+      types.variable_type("main", "names"),
+      Type::list_of(Type::Unknown)
+    );
+    assert!(inference.diagnostics.is_empty());
+  }
+
+  #[test]
+  fn test_annotation_mismatch() {
+    let code = "program {
+      fn area(w: number, h) -> int {
+        return w * h;
+      }
+      fn main() {
+        a = area(\"two\", 3);
+        count: int = 2.5;
+        name: txt = \"ann\";
+        count: text = \"two\";
+      }
+    }";
+    assert_eq!(
+      messages(code),
+      vec![
+        "line 3 column 9: error: `area` must return int, got number",
+        "line 6 column 9: error: argument 1 of `area` must be number, got text",
+        "line 7 column 9: error: `count` is int, cannot assign number to it",
+        "line 8 column 9: error: unknown type `txt`",
+        "line 9 column 9: error: `count` is already declared as int",
+        "line 9 column 9: error: `count` is int, cannot assign text to it",
+      ]
+    );
+  }
+
+  #[test]
+  fn test_function_values() {
+    let inference = run(
+      "program {
+        fn main() {
+          step = 2;
+          fn double(n) {
+            return n * 2;
+          }
+          fn apply(f, x) {
+            return f(x);
+          }
+          a = apply(double, 3);
+          next = fn(n) {
+            return n + step;
+          };
+          b = next(1);
+        }
+      }",
+    );
+    let types = inference.types;
+    assert_eq!(
+      types.param_types("main::apply"),
+      vec![Type::function_of("main::double"), Type::Int]
+    );
+    assert_eq!(types.variable_type("main", "a"), Type::Int);
+    assert_eq!(types.variable_type("main", "b"), Type::Int);
+    assert_eq!(types.captures("main::fn@11:18"), ["step"]);
+    assert!(types.is_value("main::double"));
+    assert!(!types.is_value("main::apply"));
+    assert!(inference.diagnostics.is_empty());
+  }
+
+  #[test]
+  fn test_function_value_mismatch() {
+    let code = "program {
+      fn two(a, b) {
+        return a + b;
+      }
+      fn main() {
+        x = 1;
+        x(2);
+        f = two;
+        f(1);
+        g = fn(n) {
+          return n;
+        };
+        g();
+      }
+    }";
+    assert_eq!(
+      messages(code),
+      vec![
+        "line 7 column 9: error: `x` is int, not a function",
+        "line 9 column 9: error: function `two` takes 2 arguments but 1 was given",
+        "line 13 column 9: error: anonymous function defined at line 10:13 takes 1 argument but 0 were given",
+      ]
+    );
+  }
+}
@@ -0,0 +1,132 @@
+use analyzer::optimize::optimize;
+use parser::{
+  ast::{
+    AssignStmt, CallExpr, FactorValue, Primary, PrimaryValue, Program, Statement, StatementValue,
+    StmtList,
+  },
+  parse_program::parse_program,
+  parser::Parser,
+};
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn run(code: &str) -> Program {
+    let mut p = Parser::new(code);
+    optimize(parse_program(&mut p))
+  }
+
+  // the statements of the last function of the program
+  fn last_body(program: &Program) -> Vec<Statement> {
+    let mut stmts = vec![];
+    let mut rest = Some(&program.0);
+    while let Some(StmtList(stmt, next)) = rest {
+      if let Some(Statement(StatementValue::FunctionStmt(function), _)) = stmt {
+        stmts.clear();
+        let mut body = function.2.as_deref();
+        while let Some(StmtList(stmt, next)) = body {
+          stmts.extend(stmt.clone());
+          body = next.as_deref();
+        }
+      }
+      rest = next.as_deref();
+    }
+    stmts
+  }
+
+  fn number(n: f64) -> Option<FactorValue> {
+    Some(FactorValue::Primary(Primary(PrimaryValue::Number(n))))
+  }
+
+  #[test]
+  fn test_fold_constants() {
+    let program = run(
+      "program {
+        fn main() {
+          x = (1 + 1) * 2;
+          println((10 - 4) * x, 7 / 2.0);
+        }
+      }",
+    );
+    let body = last_body(&program);
+    let StatementValue::AssignStmt(AssignStmt(_, value, _)) = &body[0].0 else {
+      panic!("expected an assignment, got {:?}", body[0]);
+    };
+    assert_eq!(value.as_factor().map(|f| f.0.clone()), number(4.0));
+
+    let StatementValue::CallStmt(CallExpr(_, args)) = &body[1].0 else {
+      panic!("expected a call, got {:?}", body[1]);
+    };
+    assert_eq!(args.0[0].as_factor(), None);
+    assert_eq!(
+      args.0[1].as_factor().map(|f| f.0.clone()),
+      Some(FactorValue::Primary(Primary(PrimaryValue::Float(3.5))))
+    );
+  }
+
+  #[test]
+  fn test_constant_ifs() {
+    let program = run(
+      "program {
+        fn main() {
+          if (1 < 2) {
+            println(1);
+          }
+          if (or(1 > 2, \"a\" = \"b\")) {
+            println(2);
+          }
+          if (and(True, x > 1)) {
+            println(3);
+          }
+          if (1 > 2) {
+            w = \"s\";
+          }
+          println(w);
+        }
+      }",
+    );
+    let body = last_body(&program);
+    assert_eq!(body.len(), 4);
+    assert_eq!(body[0].location().row(), 4);
+    assert!(matches!(body[0].0, StatementValue::CallStmt(_)));
+    assert!(matches!(body[1].0, StatementValue::IfStmt(_)));
+    // the only assignment of `w` stays, the if around it never runs
+    assert_eq!(body[2].location().row(), 12);
+    assert!(matches!(body[2].0, StatementValue::IfStmt(_)));
+  }
+
+  #[test]
+  fn test_dead_assignments() {
+    let program = run(
+      "program {
+        fn next() {
+          return 1;
+        }
+        fn main() {
+          a = 1;
+          b = a + 1;
+          c = next();
+          d = 2;
+          f = fn() {
+            return d;
+          };
+          f();
+          zero = 0;
+          e = 7 / zero;
+          g = (1 + zero) / 2;
+          h = (zero) / (zero - 1);
+        }
+      }",
+    );
+    let names: Vec<String> = last_body(&program)
+      .iter()
+      .filter_map(|stmt| match &stmt.0 {
+        StatementValue::AssignStmt(AssignStmt(name, _, _)) => Some(name.to_string()),
+        _ => None,
+      })
+      .collect();
+    // dividing by zero is a runtime error, only a known non-zero divisor is pure
+    assert_eq!(names, vec!["c", "d", "f", "zero", "e", "h"]);
+  }
+}
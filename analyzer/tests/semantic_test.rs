@@ -0,0 +1,274 @@
+use analyzer::{
+  diagnostic::{Diagnostic, Severity},
+  semantic::analyze,
+};
+use parser::{parse_program::parse_program, parser::Parser};
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn check(code: &str) -> Vec<Diagnostic> {
+    let mut p = Parser::new(code);
+    analyze(&parse_program(&mut p))
+  }
+
+  fn messages(code: &str) -> Vec<String> {
+    check(code)
+      .iter()
+      .map(|diagnostic| diagnostic.to_string())
+      .collect()
+  }
+
+  #[test]
+  fn test_valid_program() {
+    let code = "program {
+      fn main() {
+        n = fib(10);
+        println(n);
+      }
+
+      fn fib(n) {
+        if (n < 2) {
+          return n;
+        }
+        return fib(n - 1) + fib(n - 2);
+      }
+    }";
+    assert_eq!(messages(code), Vec::<String>::new());
+  }
+
+  #[test]
+  fn test_undefined_variable() {
+    let code = "program {
+      fn main() {
+        name = \"ann\";
+        println(nmae);
+      }
+    }";
+    assert_eq!(
+      messages(code),
+      vec!["line 4 column 9: error: undefined variable `nmae`"]
+    );
+  }
+
+  #[test]
+  fn test_undefined_function() {
+    let code = "program {
+      fn main() {
+        fibb(3);
+        x = name(1);
+      }
+    }";
+    assert_eq!(
+      messages(code),
+      vec![
+        "line 3 column 9: error: undefined function `fibb`",
+        "line 4 column 9: error: undefined function `name`",
+      ]
+    );
+  }
+
+  #[test]
+  fn test_if_scope() {
+    let code = "program {
+      fn main() {
+        if (1 < 2) {
+          x = 1;
+          println(x);
+        }
+        println(x);
+        println(x);
+        if (x > 0) {
+          y = 2;
+        }
+        y = 3;
+        println(y);
+      }
+    }";
+    assert_eq!(
+      messages(code),
+      vec!["line 7 column 9: warning: `x` may have no value here, it is only assigned inside a block that may not run"]
+    );
+  }
+
+  #[test]
+  fn test_while_scope() {
+    let code = "program {
+      fn main() {
+        i = 0;
+        while (i < 3) {
+          last = i;
+          i = i + 1;
+        }
+        println(i, last);
+      }
+    }";
+    assert_eq!(
+      messages(code),
+      vec!["line 8 column 9: warning: `last` may have no value here, it is only assigned inside a block that may not run"]
+    );
+  }
+
+  #[test]
+  fn test_shadowing() {
+    let code = "program {
+      fn fib(n) {
+        fn fib(fib) {
+          return fib;
+        }
+        return fib(n);
+      }
+      fn main() {
+        println(fib(1));
+      }
+    }";
+    let diagnostics = check(code);
+    assert!(diagnostics
+      .iter()
+      .all(|diagnostic| diagnostic.severity == Severity::Warning));
+    assert_eq!(
+      messages(code),
+      vec![
+        "line 3 column 9: warning: function `fib` shadows the function defined at line 2 column 7",
+        "line 3 column 9: warning: parameter `fib` shadows the function defined at line 3 column 9",
+      ]
+    );
+  }
+
+  #[test]
+  fn test_already_defined() {
+    let code = "program {
+      struct Point { x, x }
+      fn f(a, a) {
+        return a;
+      }
+      fn f() {
+        return 1;
+      }
+      fn main() {
+        println(1);
+      }
+    }";
+    assert_eq!(
+      messages(code),
+      vec![
+        "line 2 column 7: error: field `x` is repeated in struct `Point`",
+        "line 3 column 7: error: parameter `a` is already defined at line 3 column 7",
+        "line 6 column 7: error: function `f` is already defined at line 3 column 7",
+      ]
+    );
+  }
+
+  #[test]
+  fn test_function_arity() {
+    let code = "program {
+      struct Point { x, y }
+      fn fib(n) {
+        return fib(n - 1, 2);
+      }
+      fn main() {
+        p = Point(1);
+        fib();
+      }
+    }";
+    assert_eq!(
+      messages(code),
+      vec![
+        "line 4 column 9: error: function `fib` takes 1 argument but 2 were given",
+        "line 7 column 9: error: struct `Point` takes 2 arguments but 1 was given",
+        "line 8 column 9: error: function `fib` takes 1 argument but 0 were given",
+      ]
+    );
+  }
+
+  #[test]
+  fn test_builtin_arity() {
+    let code = "program {
+      fn main() {
+        scores = [1];
+        append(scores);
+        n = len(scores, 1);
+        println();
+        printf(\"%d\\n\", n);
+        printf();
+      }
+    }";
+    assert_eq!(
+      messages(code),
+      vec![
+        "line 4 column 9: error: builtin `append` takes 2 arguments but 1 was given",
+        "line 5 column 9: error: builtin `len` takes 1 argument but 2 were given",
+        "line 8 column 9: error: builtin `printf` takes at least 1 argument but 0 were given",
+      ]
+    );
+  }
+
+  #[test]
+  fn test_function_values() {
+    let code = "program {
+      struct Point { x, y }
+      count = 0;
+      fn main() {
+        step = 2;
+        fn next(n) {
+          return n + step + count;
+        }
+        f = fn(n) {
+          return next(n) + missing;
+        };
+        p = Point;
+        println(f(1));
+      }
+    }";
+    assert_eq!(
+      messages(code),
+      vec![
+        "line 10 column 11: error: undefined variable `missing`",
+        "line 12 column 9: error: struct `Point` cannot be used as a value",
+      ]
+    );
+  }
+
+  #[test]
+  fn test_captured_assignment() {
+    let code = "program {
+      fn main() {
+        total = 10;
+        fn add() {
+          total = total + 1;
+          return total;
+        }
+        inc = fn(step) {
+          step = step + 1;
+          return step + total;
+        };
+        println(add(), inc(1));
+      }
+    }";
+    assert_eq!(
+      messages(code),
+      vec!["line 5 column 11: error: a nested function cannot assign the variable `total` defined at line 3 column 9, pass it as an argument"]
+    );
+  }
+
+  #[test]
+  fn test_program_statements() {
+    let code = "program {
+      limit = 3;
+      fn show() {
+        println(limit, names);
+      }
+      show();
+      names = [];
+      return limit;
+    }";
+    assert_eq!(
+      messages(code),
+      vec![
+        "line 1 column 1: error: the program has no `main` function",
+        "line 8 column 7: error: `return` outside a function",
+      ]
+    );
+  }
+}
@@ -0,0 +1,582 @@
+use std::collections::HashMap;
+
+use analyzer::infer::{infer, TypeInfo};
+use analyzer::types::Type;
+use ir::ir::{BlockId, Constant, Function, Inst, Operand, Terminator, Var};
+use ir::{lower::lower, optimize::optimize};
+use parser::ast::{BinOp, CompareOp, Program};
+
+use crate::codegen::{c_function_name, Emitter};
+
+// the registers the first six arguments of a call are passed in, the others
+// are pushed on the stack (System V AMD64 calling convention)
+const ARG_REGISTERS: [&str; 6] = ["%rdi", "%rsi", "%rdx", "%rcx", "%r8", "%r9"];
+
+// riven_fail stops the program with the line in %edi and the message in %rsi,
+// after what was printed. C int division, called with a in %edi, b in %esi
+// and the line in %edx: dividing by zero is a runtime error, and the smallest
+// int divided by -1 wraps around instead of trapping like `idivl` does
+const ASM_RUNTIME: &str = "riven_fail:
+\tpushq\t%rbx
+\tpushq\t%r12
+\tsubq\t$8, %rsp
+\tmovl\t%edi, %ebx
+\tmovq\t%rsi, %r12
+\txorl\t%edi, %edi
+\tcall\tfflush@PLT
+\tmovl\t$2, %edi
+\tleaq\t.LC0(%rip), %rsi
+\tmovl\t%ebx, %edx
+\tmovq\t%r12, %rcx
+\txorl\t%eax, %eax
+\tcall\tdprintf@PLT
+\tmovl\t$1, %edi
+\tcall\texit@PLT
+
+riven_idiv:
+\ttestl\t%esi, %esi
+\tje\t.Lidiv_zero
+\tcmpl\t$-1, %esi
+\tje\t.Lidiv_negate
+\tmovl\t%edi, %eax
+\tcltd
+\tidivl\t%esi
+\tret
+.Lidiv_negate:
+\tmovl\t%edi, %eax
+\tnegl\t%eax
+\tret
+.Lidiv_zero:
+\tmovl\t%edx, %edi
+\tleaq\t.LC1(%rip), %rsi
+\tjmp\triven_fail
+";
+
+// `.string "a\n"`, every byte that is not plain ASCII is written in octal
+fn asm_string(bytes: &[u8]) -> String {
+  let mut text = String::from("\"");
+  for byte in bytes {
+    match byte {
+      b'"' | b'\\' => text.push_str(&format!("\\{}", *byte as char)),
+      0x20..=0x7e => text.push(*byte as char),
+      _ => text.push_str(&format!("\\{:03o}", byte)),
+    }
+  }
+  text.push('"');
+  text
+}
+
+// what the asm target cannot hold in a variable, None for ints and bools
+fn unsupported_type(t: &Type) -> Option<&'static str> {
+  match t {
+    Type::Float => Some("numbers with a fraction are"),
+    Type::Str => Some("texts other than printed ones are"),
+    Type::List(_) => Some("lists are"),
+    Type::Map(_, _) => Some("maps are"),
+    Type::Struct(_) => Some("structs are"),
+    Type::Function(_) => Some("functions used as values are"),
+    Type::Int | Type::Bool | Type::Any | Type::Unknown | Type::Void => None,
+  }
+}
+
+// an argument of a call, put in %rax before it is pushed
+enum Arg<'o> {
+  Value(&'o Operand),
+  // a bool printed by printf as the text True or False
+  Bool(&'o Operand),
+  // the address of a text
+  Text(String),
+}
+
+// generates the x86-64 assembly of a program from its optimized IR, see
+// ir::lower: every variable and temporary has 8 bytes below %rbp, and each
+// instruction loads its operands in registers and stores its result
+pub struct AsmCodeGen<'a> {
+  emitter: &'a mut Emitter,
+  // source line of the instruction being generated, used by runtime errors
+  line: usize,
+  types: TypeInfo,
+  // the type of each variable of the function being generated, a temporary
+  // has the type of the value first stored in it
+  var_types: HashMap<Var, Type>,
+  // where each variable of the function being generated lives, e.g. `-8(%rbp)`,
+  // or `.Lglobal_limit(%rip)` for a global
+  slots: HashMap<Var, String>,
+  // name and type of every variable assigned outside functions
+  globals: Vec<(String, Type)>,
+  // whether main first calls riven_init, the statements outside functions
+  has_init: bool,
+  // the name of the function being generated, its blocks are `.L<name>_bb<index>`
+  name: String,
+  // the texts of the program, each is a `.LC<index>` in .rodata
+  strings: Vec<Vec<u8>>,
+  // what the program uses that the asm target cannot handle, see unsupported()
+  unsupported: Vec<String>,
+}
+
+impl<'a> AsmCodeGen<'a> {
+  pub fn new(emitter: &'a mut Emitter) -> Self {
+    emitter.push_header("# generated by riven");
+    emitter.push_header("\t.text");
+
+    AsmCodeGen {
+      emitter,
+      line: 0,
+      types: TypeInfo::default(),
+      var_types: HashMap::new(),
+      slots: HashMap::new(),
+      globals: vec![],
+      has_init: false,
+      name: String::new(),
+      strings: vec![],
+      unsupported: vec![],
+    }
+  }
+
+  // `line 3: lists are not supported by the asm target`, for every use of
+  // something besides ints and bools: numbers with a fraction, texts other
+  // than printed ones, lists, maps, structs and functions used as values
+  pub fn unsupported(&self) -> &[String] {
+    &self.unsupported
+  }
+
+  // the first thing the asm target cannot handle on each line
+  fn report(&mut self, what: &str) {
+    let prefix = format!("line {}: ", self.line);
+    if !self
+      .unsupported
+      .iter()
+      .any(|message| message.starts_with(&prefix))
+    {
+      let message = format!("{}{} not supported by the asm target", prefix, what);
+      self.unsupported.push(message);
+    }
+  }
+
+  fn report_type(&mut self, t: &Type) {
+    if let Some(what) = unsupported_type(t) {
+      self.report(what);
+    }
+  }
+
+  // `\tmovl\t$1, %eax`
+  fn emit(&mut self, instruction: &str, operands: &str) {
+    let code = match operands.is_empty() {
+      true => format!("\t{}\n", instruction),
+      false => format!("\t{}\t{}\n", instruction, operands),
+    };
+    self.emitter.emmit(&code);
+  }
+
+  fn block_label(&self, block: BlockId) -> String {
+    format!(".L{}_bb{}", self.name, block)
+  }
+
+  // the label of a text, each text is stored once; .LC0 and .LC1 are the
+  // texts of riven_fail and riven_idiv
+  fn string(&mut self, text: &str) -> String {
+    let bytes = text.as_bytes().to_vec();
+    let index = match self.strings.iter().position(|string| *string == bytes) {
+      Some(index) => index,
+      None => {
+        self.strings.push(bytes);
+        self.strings.len() - 1
+      }
+    };
+    format!(".LC{}", index)
+  }
+
+  fn type_of(&self, operand: &Operand) -> Type {
+    match operand {
+      Operand::Const(Constant::Int(_)) => Type::Int,
+      Operand::Const(Constant::Float(_)) => Type::Float,
+      Operand::Const(Constant::Bool(_)) => Type::Bool,
+      Operand::Const(Constant::Str(_)) => Type::Str,
+      Operand::Var(var) => self.var_types.get(var).cloned().unwrap_or_default(),
+    }
+  }
+
+  // the value of an operand in a 32-bit register, e.g. `%eax`
+  fn load(&mut self, operand: &Operand, register: &str) {
+    match operand {
+      Operand::Const(Constant::Int(n)) => self.emit("movl", &format!("${}, {}", n, register)),
+      Operand::Const(Constant::Bool(b)) => {
+        self.emit("movl", &format!("${}, {}", *b as i32, register))
+      }
+      Operand::Const(constant) => {
+        let t = self.type_of(&Operand::Const(constant.clone()));
+        self.report_type(&t);
+      }
+      Operand::Var(var) => {
+        let slot = self.slots[var].clone();
+        self.emit("movl", &format!("{}, {}", slot, register));
+      }
+    }
+  }
+
+  // the result of an instruction is in %eax
+  fn store(&mut self, dest: &Var) {
+    let slot = self.slots[dest].clone();
+    self.emit("movl", &format!("%eax, {}", slot));
+  }
+
+  // 0 or 1 in %eax from the flags of the last `testl` or `cmpl`
+  fn set(&mut self, condition: &str) {
+    self.emit(&format!("set{}", condition), "%al");
+    self.emit("movzbl", "%al, %eax");
+  }
+
+  // the arguments are computed from the last one and pushed, then the first
+  // six are popped into their registers. printf and other variadic functions
+  // are told in %al that no argument is in a vector register
+  fn call(&mut self, function: &str, args: &[Arg]) {
+    let on_stack = args.len().saturating_sub(ARG_REGISTERS.len());
+    let padding = on_stack % 2;
+    if padding == 1 {
+      self.emit("subq", "$8, %rsp");
+    }
+    for arg in args.iter().rev() {
+      match arg {
+        Arg::Value(value) => self.load(value, "%eax"),
+        Arg::Bool(value) => {
+          let (yes, no) = (self.string("True"), self.string("False"));
+          self.load(value, "%eax");
+          self.emit("leaq", &format!("{}(%rip), %rcx", yes));
+          self.emit("leaq", &format!("{}(%rip), %rdx", no));
+          self.emit("testl", "%eax, %eax");
+          self.emit("cmovne", "%rcx, %rdx");
+          self.emit("movq", "%rdx, %rax");
+        }
+        Arg::Text(label) => self.emit("leaq", &format!("{}(%rip), %rax", label)),
+      }
+      self.emit("pushq", "%rax");
+    }
+    for register in ARG_REGISTERS.iter().take(args.len()) {
+      self.emit("popq", register);
+    }
+    if function.ends_with("@PLT") {
+      self.emit("xorl", "%eax, %eax");
+    }
+    self.emit("call", function);
+    if on_stack + padding > 0 {
+      self.emit("addq", &format!("${}, %rsp", 8 * (on_stack + padding)));
+    }
+  }
+
+  // print(n), print(" "), print(True) => printf("%d %s", n, "True"), the
+  // values printed one after another make one format
+  fn print(&mut self, values: &[&Operand]) {
+    let mut format = String::new();
+    let mut args = vec![];
+    for value in values {
+      if let Operand::Const(Constant::Str(text)) = value {
+        format.push_str(&text.replace('%', "%%"));
+        continue;
+      }
+      match self.type_of(value) {
+        Type::Bool => {
+          format.push_str("%s");
+          args.push(Arg::Bool(value));
+        }
+        Type::Int | Type::Unknown => {
+          format.push_str("%d");
+          args.push(Arg::Value(value));
+        }
+        t => return self.report(format!("printing {} is", t).as_str()),
+      }
+    }
+    args.insert(0, Arg::Text(self.string(&format)));
+    self.call("printf@PLT", &args);
+  }
+
+  // printf("%d\n", n), the values are ints
+  fn printf(&mut self, args: &[Operand]) {
+    let Some(Operand::Const(Constant::Str(format))) = args.first() else {
+      return self.report("a printf format that is not a text literal is");
+    };
+    if args[1..].iter().any(|arg| self.type_of(arg) == Type::Str) {
+      return self.report("printing a text with printf is");
+    }
+    let mut values = vec![Arg::Text(self.string(format))];
+    values.extend(args[1..].iter().map(Arg::Value));
+    self.call("printf@PLT", &values);
+  }
+
+  // remember the type of the value a temporary gets first
+  fn learn(&mut self, inst: &Inst) {
+    let Some(dest @ Var::Temp(_)) = inst.dest() else {
+      return;
+    };
+    if self.var_types.contains_key(dest) {
+      return;
+    }
+    let t = match inst {
+      Inst::Copy(_, a) => self.type_of(a),
+      Inst::Binary(_, _, a, b) => match (self.type_of(a), self.type_of(b)) {
+        (Type::Float, _) | (_, Type::Float) => Type::Float,
+        _ => Type::Int,
+      },
+      Inst::Compare(_, _, _, _) | Inst::Not(_, _) => Type::Bool,
+      Inst::Convert(_, t, _) | Inst::Zero(_, t) => t.clone(),
+      Inst::Call(_, function, _) => self.types.return_type(function),
+      Inst::Builtin(_, name, _) if name == "print" || name == "printf" => Type::Int,
+      _ => Type::Any,
+    };
+    self.var_types.insert(dest.clone(), t);
+  }
+
+  fn visit_inst(&mut self, inst: &Inst) {
+    self.learn(inst);
+    match inst {
+      Inst::Copy(dest, a) => {
+        self.load(a, "%eax");
+        self.store(dest);
+      }
+      // ints wrap around like a C int
+      Inst::Binary(dest, op, a, b) => {
+        match op {
+          BinOp::Div => {
+            self.load(a, "%edi");
+            self.load(b, "%esi");
+            self.emit("movl", &format!("${}, %edx", self.line));
+            self.call("riven_idiv", &[]);
+          }
+          _ => {
+            self.load(a, "%eax");
+            self.load(b, "%ecx");
+            let instruction = match op {
+              BinOp::Add => "addl",
+              BinOp::Min => "subl",
+              _ => "imull",
+            };
+            self.emit(instruction, "%ecx, %eax");
+          }
+        }
+        self.store(dest);
+      }
+      Inst::Compare(dest, op, a, b) => {
+        self.load(a, "%eax");
+        self.load(b, "%ecx");
+        self.emit("cmpl", "%ecx, %eax");
+        match op {
+          CompareOp::Eq => self.set("e"),
+          CompareOp::Gt => self.set("g"),
+          CompareOp::Lt => self.set("l"),
+        }
+        self.store(dest);
+      }
+      Inst::Not(dest, a) => {
+        self.load(a, "%eax");
+        self.emit("testl", "%eax, %eax");
+        self.set("e");
+        self.store(dest);
+      }
+      // a bool is already the int 0 or 1
+      Inst::Convert(dest, t, a) => {
+        self.report_type(t);
+        self.load(a, "%eax");
+        self.store(dest);
+      }
+      Inst::Zero(_, t) => self.report_type(t),
+      Inst::Call(dest, function, values) => {
+        let args: Vec<Arg> = values.iter().map(Arg::Value).collect();
+        self.call(&c_function_name(function), &args);
+        self.store(dest);
+      }
+      Inst::CallValue(_, _, _) | Inst::Closure(_, _, _) => {
+        self.report("functions used as values are")
+      }
+      Inst::Builtin(dest, name, args) => {
+        match name.as_str() {
+          "printf" => self.printf(args),
+          _ => return self.report(format!("the builtin `{}` is", name).as_str()),
+        }
+        self.store(dest);
+      }
+      Inst::NewList(_, _) => self.report("lists are"),
+      Inst::NewMap(_, _) => self.report("maps are"),
+      Inst::GetItem(_, _, _) | Inst::SetItem(_, _, _) => self.report("lists and maps are"),
+      Inst::NewStruct(_, _, _) | Inst::GetField(_, _, _) | Inst::SetField(_, _, _) => {
+        self.report("structs are")
+      }
+    }
+  }
+
+  // the block after this one needs no jump
+  fn visit_terminator(&mut self, end: &Terminator, next: BlockId) {
+    match end {
+      Terminator::Jump(target) => {
+        if *target != next {
+          let label = self.block_label(*target);
+          self.emit("jmp", &label);
+        }
+      }
+      Terminator::Branch(condition, yes, no) => {
+        self.load(condition, "%eax");
+        self.emit("testl", "%eax, %eax");
+        let label = self.block_label(*no);
+        self.emit("je", &label);
+        if *yes != next {
+          let label = self.block_label(*yes);
+          self.emit("jmp", &label);
+        }
+      }
+      // the value is returned in %eax, main's is the exit code
+      Terminator::Return(value) => {
+        self.load(value, "%eax");
+        self.emit("leave", "");
+        self.emit("ret", "");
+      }
+      Terminator::Fail(message) => {
+        let message = self.string(message);
+        self.emit("movl", &format!("${}, %edi", self.line));
+        self.emit("leaq", &format!("{}(%rip), %rsi", message));
+        self.emit("call", "riven_fail");
+      }
+    }
+  }
+
+  // the parameters are the captured variables and then the arguments, the
+  // first six are stored below %rbp and the others stay above it; a variable
+  // is zero until its first assignment. The statements outside functions are
+  // riven_init, which main calls first
+  fn visit_function(&mut self, function: &Function) {
+    self.name = match function.path.is_empty() {
+      true => "riven_init".to_owned(),
+      false => c_function_name(&function.path),
+    };
+    let globals = self
+      .globals
+      .iter()
+      .map(|(name, t)| (Var::Global(name.clone()), t));
+    let locals = function
+      .locals
+      .iter()
+      .map(|(name, t)| (Var::Local(name.clone()), t));
+    self.var_types = globals
+      .chain(locals)
+      .map(|(var, t)| (var, t.clone()))
+      .collect();
+    self.slots = self
+      .globals
+      .iter()
+      .map(|(name, _)| {
+        (
+          Var::Global(name.clone()),
+          format!(".Lglobal_{}(%rip)", name),
+        )
+      })
+      .collect();
+    let mut frame = 0;
+    for (idx, param) in function.params.iter().enumerate() {
+      let slot = match idx < ARG_REGISTERS.len() {
+        true => {
+          frame += 8;
+          format!("-{}(%rbp)", frame)
+        }
+        false => format!("{}(%rbp)", 16 + 8 * (idx - ARG_REGISTERS.len())),
+      };
+      self.slots.insert(Var::Local(param.clone()), slot);
+    }
+    let locals = function
+      .locals
+      .iter()
+      .map(|(name, _)| Var::Local(name.clone()));
+    let locals: Vec<Var> = locals.filter(|var| !self.slots.contains_key(var)).collect();
+    let temps = (0..function.temps).map(Var::Temp);
+    for var in locals.iter().cloned().chain(temps) {
+      frame += 8;
+      self.slots.insert(var, format!("-{}(%rbp)", frame));
+    }
+
+    let name = self.name.clone();
+    self.emitter.emmit("\n");
+    if function.path == "main" {
+      self.emitter.emmit("\t.globl\tmain\n");
+    }
+    self
+      .emitter
+      .emmit(&format!("\t.type\t{}, @function\n", name));
+    self.emitter.emmit(&format!("{}:\n", name));
+    self.emit("pushq", "%rbp");
+    self.emit("movq", "%rsp, %rbp");
+    if frame > 0 {
+      self.emit("subq", &format!("${}, %rsp", (frame + 15) / 16 * 16));
+    }
+    for (param, register) in function.params.iter().zip(ARG_REGISTERS) {
+      let slot = self.slots[&Var::Local(param.clone())].clone();
+      self.emit("movq", &format!("{}, {}", register, slot));
+    }
+    for var in &locals {
+      let slot = self.slots[var].clone();
+      self.emit("movl", &format!("$0, {}", slot));
+    }
+    if function.path == "main" && self.has_init {
+      self.emit("call", "riven_init");
+    }
+
+    for (idx, block) in function.blocks.iter().enumerate() {
+      if idx > 0 {
+        let label = self.block_label(idx);
+        self.emitter.emmit(&format!("{}:\n", label));
+      }
+      let mut code = block.code.iter().zip(&block.lines).peekable();
+      while let Some((inst, line)) = code.next() {
+        self.line = *line;
+        let Inst::Builtin(_, name, args) = inst else {
+          self.visit_inst(inst);
+          continue;
+        };
+        if name != "print" {
+          self.visit_inst(inst);
+          continue;
+        }
+        let mut values: Vec<&Operand> = args.iter().collect();
+        while let Some((Inst::Builtin(_, name, args), _)) = code.peek() {
+          if name != "print" {
+            break;
+          }
+          values.extend(args);
+          code.next();
+        }
+        self.print(&values);
+      }
+      self.line = block.end_line;
+      self.visit_terminator(&block.end, idx + 1);
+    }
+    self
+      .emitter
+      .emmit(&format!("\t.size\t{}, .-{}\n", name, name));
+  }
+
+  pub fn visit_program(&mut self, program: &Program) {
+    self.types = infer(program).types;
+    let module = optimize(lower(program));
+    self.globals = module.globals.clone();
+    self.has_init = module.init.is_some();
+
+    self.string("runtime error at line %d: %s\n");
+    self.string("division by zero");
+    self.emitter.emmit("\n");
+    self.emitter.emmit(ASM_RUNTIME);
+    for function in module.init.iter().chain(&module.functions) {
+      self.visit_function(function);
+    }
+
+    // the globals and texts, after the functions using them; the stack is
+    // not executable
+    let mut data = vec![];
+    if !self.globals.is_empty() {
+      data.push("\t.data".to_owned());
+    }
+    for (name, _) in &self.globals {
+      data.push(format!(".Lglobal_{}:\n\t.long\t0", name));
+    }
+    data.push("\t.section\t.rodata".to_owned());
+    for (idx, bytes) in self.strings.iter().enumerate() {
+      data.push(format!(".LC{}:\n\t.string\t{}", idx, asm_string(bytes)));
+    }
+    data.push("\t.section\t.note.GNU-stack,\"\",@progbits\n".to_owned());
+    self.emitter.push_tail(&data.join("\n"));
+  }
+}
@@ -1,8 +1,21 @@
+use analyzer::flow::default_return;
+use analyzer::infer::{child_path, infer, nested_functions, statement_functions, statements, Passed, TypeInfo};
+use analyzer::locals::{plan_declarations, Declarations};
+use analyzer::operands::Operation;
+use analyzer::types::Type;
 use parser::ast::{
-  ArithmeticExpr, AssignStmt, CallExpr, CompareExpr, CompareOp, ComponentArithmeticExpr,
-  ComponentFactor, ComponentFactorValue, ComponentTerm, Expression, ExpressionValue, Factor,
-  FactorValue, FunctionStmt, Identifier, IfStmt, LogicExpr, LogicOp, Primary, PrimaryValue,
-  Program, ReturnStmt, Statement, StatementValue, StmtList, Term,
+  ApplyExpr, ArithmeticExpr, AssignStmt, BinOp, CallExpr, CompareExpr, CompareOp,
+  ComponentArithmeticExpr, ComponentFactor, ComponentFactorValue, ComponentTerm, Expression,
+  ExpressionValue, Factor, FactorValue, FieldAssignStmt, FieldExpr, FunctionStmt, Identifier,
+  IfStmt, IndexAssignStmt, IndexExpr, ListExpr, LogicExpr, LogicOp, MapExpr, Primary, PrimaryValue,
+  Program, ReturnStmt, Statement, StatementValue, StmtList, StructStmt, Term, WhileStmt,
+};
+use parser::location::Location;
+use std::fmt::Display;
+
+use crate::runtime::{
+  find_builtin, Builtin, FUNCTION_RUNTIME, INDEX_RUNTIME, INPUT_RUNTIME, LIST_RUNTIME, MAP_RUNTIME,
+  OBJECT_RUNTIME, PRINT_RUNTIME, VALUE_RUNTIME,
 };
 
 /// code generator
@@ -12,15 +25,33 @@ use parser::ast::{
 #[derive(Debug)]
 pub struct Emitter {
   headers: Vec<String>,
-  lines: Vec<String>,
+  // finished lines end with a newline, the last one may still be growing
+  body: String,
+  // how many lines of the body are finished
+  lines: usize,
+  // indentation of the lines started from now on, `indent_unit` per level
+  indent: usize,
+  indent_unit: &'static str,
   tail: Vec<String>,
 }
 
+// the pieces of a line are separated by a space, except around the
+// punctuation C is written without one: `f(a, b);`, `*riven_field(...)`
+fn needs_space(before: char, after: char) -> bool {
+  !(before.is_whitespace()
+    || after.is_whitespace()
+    || matches!(before, '(' | '[' | '*')
+    || matches!(after, ')' | ']' | ',' | ';'))
+}
+
 impl Emitter {
   pub fn new() -> Self {
     Emitter {
       headers: vec![],
-      lines: vec![],
+      body: String::new(),
+      lines: 0,
+      indent: 0,
+      indent_unit: "  ",
       tail: vec![],
     }
   }
@@ -29,8 +60,79 @@ impl Emitter {
     self.headers.push(code.into());
   }
 
+  fn push(&mut self, code: &str) {
+    self.lines += code.matches('\n').count();
+    self.body.push_str(code);
+  }
+
+  // add a piece to the current line, a piece ending with a newline ends it
   pub fn emmit(&mut self, code: &str) {
-    self.lines.push(code.into());
+    match self.body.chars().last() {
+      None | Some('\n') => {
+        if !code.starts_with('\n') {
+          self.push(&self.indent_unit.repeat(self.indent));
+        }
+      }
+      Some(before) => {
+        if code.chars().next().is_some_and(|after| needs_space(before, after)) {
+          self.push(" ");
+        }
+      }
+    }
+    self.push(code);
+  }
+
+  // the next piece starts a new line
+  pub fn end_line(&mut self) {
+    if !self.body.is_empty() && !self.body.ends_with('\n') {
+      self.push("\n");
+    }
+  }
+
+  pub fn emit_line(&mut self, code: &str) {
+    self.emmit(code);
+    self.end_line();
+  }
+
+  // a blank line in front of what comes next, e.g. between two functions,
+  // none at the start of the body or after another one
+  pub fn blank_line(&mut self) {
+    self.end_line();
+    if !self.body.is_empty() && !self.body.ends_with("\n\n") {
+      self.push("\n");
+    }
+  }
+
+  // a preprocessor directive, on a line of its own whatever the indentation
+  pub fn emit_directive(&mut self, code: &str) {
+    self.end_line();
+    self.push(code);
+    self.push("\n");
+  }
+
+  // the finished lines of the body, the current one is the next
+  pub fn line_count(&self) -> usize {
+    self.lines
+  }
+
+  // the lines started until the matching dedent are one level deeper
+  pub fn indent(&mut self) {
+    self.indent += 1;
+  }
+
+  pub fn dedent(&mut self) {
+    self.indent = self.indent.saturating_sub(1);
+  }
+
+  // two spaces like C unless the target language wants another indentation
+  pub fn set_indent_unit(&mut self, unit: &'static str) {
+    self.indent_unit = unit;
+  }
+
+  // the code emitted so far without the headers and the tail, e.g. the C
+  // program without the runtime in front of it
+  pub fn body(&self) -> &str {
+    &self.body
   }
 
   pub fn push_tail(&mut self, code: &str) {
@@ -38,20 +140,145 @@ impl Emitter {
   }
 
   pub fn gen_code(&self) -> String {
-    let source_code_header = self.headers.join("\n");
-    let souce_code_body = self.lines.join(" ");
-    let source_code_tail = self.tail.join("\n");
-
-    format!(
-      "{}\n{}\n{}",
-      source_code_header, souce_code_body, source_code_tail
-    )
+    let mut code = self.headers.join("\n");
+    code.push('\n');
+    code.push_str(&self.body);
+    if !self.tail.is_empty() {
+      code.push('\n');
+      code.push_str(&self.tail.join("\n"));
+    } else if !self.body.ends_with('\n') {
+      code.push('\n');
+    }
+    code
   }
 
   // write generate source code to file
   pub(crate) fn write_file(&self) {}
 }
 
+// list items, map values and struct fields are stored boxed
+pub(crate) fn is_stored_value(factor: &Factor) -> bool {
+  factor.0.is_index() || factor.0.is_field()
+}
+
+// C keywords, what the headers of the runtime declare, and the names the
+// generated closure wrappers and structs use for themselves
+const C_RESERVED: &str = "\
+  auto bool break case char const continue default do double else enum extern false float \
+  for goto if inline int long register restrict return short signed sizeof static struct \
+  switch true typedef union unsigned void volatile while EOF FILE NULL abort abs atexit atof \
+  atoi atol bsearch calloc div exit fclose feof fflush fgetc fgets fopen fprintf fputc fputs \
+  fread free fscanf fseek ftell fwrite getc getchar getenv getline gets labs malloc memchr \
+  memcmp memcpy memmove memset perror printf putc putchar puts qsort rand realloc remove \
+  rename rewind scanf size_t snprintf sprintf srand sscanf stderr stdin stdout strcat strchr \
+  strcmp strcpy strdup strerror strlen strncat strncmp strncpy strrchr strstr strtod strtok \
+  strtol strtoul system va_arg va_copy va_end va_list va_start vfprintf vprintf vsnprintf \
+  args captured env line self type";
+
+// the C name of a Riven variable, function, struct or field
+fn c_name(name: &str) -> String {
+  let reserved = C_RESERVED.split_whitespace().any(|word| word == name);
+  if reserved || name.starts_with("riven_") || name.starts_with("Riven") {
+    format!("{}_", name)
+  } else {
+    name.to_owned()
+  }
+}
+
+// the C type used for values of a riven type, unknown values are ints
+fn c_type(t: &Type) -> String {
+  let c_type = match t {
+    Type::Float => "double",
+    Type::Bool => "bool",
+    Type::Str => "char *",
+    Type::List(_) => "RivenList *",
+    Type::Map(_, _) => "RivenMap *",
+    Type::Struct(name) => return format!("{} *", c_name(name)),
+    Type::Function(_) => "RivenClosure *",
+    Type::Any => "RivenValue",
+    Type::Void => "void",
+    Type::Int | Type::Unknown => "int",
+  };
+  c_type.to_owned()
+}
+
+// `int n`, `char *s`
+fn c_declaration(t: &Type, name: &str) -> String {
+  let c_type = c_type(t);
+  match c_type.ends_with('*') {
+    true => format!("{}{}", c_type, c_name(name)),
+    false => format!("{} {}", c_type, c_name(name)),
+  }
+}
+
+// the value a hoisted variable starts with, before its first assignment
+fn c_zero(t: &Type) -> &'static str {
+  match t {
+    Type::Float => "0.0",
+    Type::Bool => "false",
+    Type::Str => "\"\"",
+    Type::List(_) => "riven_list_new()",
+    Type::Map(_, _) => "riven_map_new()",
+    // boxed and printed as the int 0, see riven_box_object
+    Type::Struct(_) | Type::Function(_) => "NULL",
+    Type::Any => "riven_box_int(0)",
+    Type::Int | Type::Unknown | Type::Void => "0",
+  }
+}
+
+// C has no nested functions, so `helper` defined inside `main` is lifted
+// to the top level as `main__helper`, an anonymous `fn@3:12` as `main__fn_at_3_12`
+pub(crate) fn c_function_name(function: &str) -> String {
+  function.replace("::", "__").replace('@', "_at_").replace(':', "_")
+}
+
+// what goes around a RivenValue to get the C type inferred for it, None for RivenValue
+fn c_unbox(t: &Type, line: impl Display) -> Option<(String, String)> {
+  let unbox = match t {
+    Type::Any => return None,
+    Type::Float => "riven_unbox_double",
+    Type::Bool => "riven_unbox_bool",
+    Type::Str => "riven_unbox_str",
+    Type::List(_) => "riven_unbox_list",
+    Type::Map(_, _) => "riven_unbox_map",
+    Type::Function(_) => "riven_unbox_closure",
+    Type::Struct(name) => {
+      return Some((
+        format!("(({} *)riven_unbox_object(", c_name(name)),
+        format!(", &riven_{}_type, {}))", name, line),
+      ))
+    }
+    _ => "riven_unbox_int",
+  };
+  Some((format!("{}(", unbox), format!(", {})", line)))
+}
+
+// what a call of a function the checks did not find passes, its arguments as they are
+pub(crate) fn unknown_args(args: &[Expression]) -> Vec<Passed<'_>> {
+  args.iter().map(|arg| Passed::Arg(arg, Type::Unknown)).collect()
+}
+
+// collect struct declarations, wherever they are, so they can be emitted first
+pub(crate) fn collect_structs<'s>(stmt_list: &'s StmtList, structs: &mut Vec<&'s StructStmt>) {
+  if let Some(stmt) = &stmt_list.0 {
+    match &stmt.0 {
+      StatementValue::StructStmt(struct_stmt) => structs.push(struct_stmt),
+      StatementValue::IfStmt(IfStmt(_, Some(body)))
+      | StatementValue::WhileStmt(WhileStmt(_, Some(body))) => collect_structs(body, structs),
+      _ => (),
+    }
+    for (function_stmt, _) in statement_functions(stmt) {
+      if let Some(body) = &function_stmt.2 {
+        collect_structs(body, structs);
+      }
+    }
+  }
+
+  if let Some(rest_stmts) = &stmt_list.1 {
+    collect_structs(rest_stmts, structs);
+  }
+}
+
 pub trait CodeGenerator {
   fn visit_identifier(&mut self, identifier: &Identifier);
   fn visit_primary(&mut self, primary: &Primary);
@@ -62,14 +289,22 @@ pub trait CodeGenerator {
   fn visit_component_term(&mut self, term: &ComponentTerm);
   fn visit_component_arithmetic_expr(&mut self, arith_expr: &ComponentArithmeticExpr);
   fn visit_call_expr(&mut self, call_expr: &CallExpr);
+  fn visit_list_expr(&mut self, list_expr: &ListExpr);
+  fn visit_map_expr(&mut self, map_expr: &MapExpr);
+  fn visit_index_expr(&mut self, index_expr: &IndexExpr);
+  fn visit_field_expr(&mut self, field_expr: &FieldExpr);
   fn visit_call_stmt(&mut self, call_expr: &CallExpr);
   fn visit_expr(&mut self, expr: &Expression);
   fn visit_compare_expr(&mut self, compare_expr: &CompareExpr);
   fn visit_logic_expr(&mut self, logic_expr: &LogicExpr);
   fn visit_assign_stmt(&mut self, stmt: &AssignStmt);
+  fn visit_index_assign_stmt(&mut self, stmt: &IndexAssignStmt);
+  fn visit_field_assign_stmt(&mut self, stmt: &FieldAssignStmt);
   fn visit_return_stmt(&mut self, stmt: &ReturnStmt);
   fn visit_if_stmt(&mut self, stmt: &IfStmt);
+  fn visit_while_stmt(&mut self, stmt: &WhileStmt);
   fn visit_function_stmt(&mut self, stmt: &FunctionStmt);
+  fn visit_struct_stmt(&mut self, stmt: &StructStmt);
   fn visit_stmt(&mut self, stmt: &Statement);
   fn visit_stmt_list(&mut self, stmt_list: &StmtList);
   fn visit_program(&mut self, program: &Program);
@@ -77,14 +312,481 @@ pub trait CodeGenerator {
 
 pub struct CCodeGenManager<'a> {
   emitter: &'a mut Emitter,
+  // source line of the statement being generated, used by runtime errors
+  line: usize,
+  // location of the statement being generated, identifies it in `declarations`
+  location: Location,
+  // names of the structs already emitted
+  structs: Vec<String>,
+  // inferred by visit_program, empty when single statements are generated
+  types: TypeInfo,
+  // path of the function being generated, see analyzer::infer::child_path
+  path: String,
+  // planned by visit_program, without a plan every assignment declares
+  declarations: Declarations,
+  // the Riven file named by the #line directives, none without it
+  source_file: Option<String>,
+  // the source line and the body line of the line after the last #line directive
+  line_mark: Option<(usize, usize)>,
+  // whether main first calls riven_init, the statements outside functions
+  has_init: bool,
 }
 
 impl<'a> CCodeGenManager<'a> {
   pub fn new(emitter: &'a mut Emitter) -> Self {
     emitter.push_header("#include<stdio.h>");
     emitter.push_header("#include<stdlib.h>");
+    emitter.push_header("#include<stdbool.h>");
+    emitter.push_header("#include<stdarg.h>");
+    emitter.push_header("#include<string.h>");
+    emitter.push_header(VALUE_RUNTIME);
+    emitter.push_header(LIST_RUNTIME);
+    emitter.push_header(MAP_RUNTIME);
+    emitter.push_header(OBJECT_RUNTIME);
+    emitter.push_header(FUNCTION_RUNTIME);
+    emitter.push_header(INDEX_RUNTIME);
+    emitter.push_header(PRINT_RUNTIME);
+    emitter.push_header(INPUT_RUNTIME);
 
-    CCodeGenManager { emitter }
+    CCodeGenManager {
+      emitter,
+      line: 0,
+      location: Location::default(),
+      structs: vec![],
+      types: TypeInfo::default(),
+      path: String::new(),
+      declarations: Declarations::default(),
+      source_file: None,
+      line_mark: None,
+      has_init: false,
+    }
+  }
+
+  // point gcc diagnostics and gdb at the lines of `source_file`, e.g. fib.riven
+  pub fn with_source_file(mut self, source_file: &str) -> Self {
+    self.source_file = Some(source_file.to_owned());
+    self
+  }
+
+  // `#line 12 "fib.riven"` in front of the code of the statement at self.line,
+  // unless gcc already counts that line from the last directive
+  fn visit_line_directive(&mut self) {
+    let Some(source_file) = &self.source_file else {
+      return;
+    };
+    self.emitter.end_line();
+    let next = self.emitter.line_count();
+    if let Some((line, at)) = self.line_mark {
+      if line + (next - at) == self.line {
+        return;
+      }
+    }
+    // the name is a C string, only `\` and `"` are escaped so café.riven stays readable
+    let name = source_file.replace('\\', "\\\\").replace('"', "\\\"");
+    let directive = format!("#line {} \"{}\"", self.line, name);
+    self.emitter.emit_directive(directive.as_str());
+    self.line_mark = Some((self.line, next + 1));
+  }
+
+  fn is_struct(&self, name: &Identifier) -> bool {
+    self.structs.contains(&name.to_string())
+  }
+
+  fn type_of(&self, expr: &Expression) -> Type {
+    self.types.type_of(&self.path, expr)
+  }
+
+  // `double area(double w, double h)`, self.path is the path of the function
+  fn visit_function_signature(&mut self) {
+    // C wants `int main`, even when main returns nothing
+    let return_type = match self.types.return_type(&self.path) {
+      _ if self.path == "main" => Type::Int,
+      return_type => return_type,
+    };
+    self.emitter.emmit(c_type(&return_type).as_str());
+    self.emitter.emmit(format!("{}(", c_name(&c_function_name(&self.path))).as_str());
+    let params: Vec<(Type, String)> = self
+      .types
+      .params(&self.path)
+      .into_iter()
+      .map(|name| (self.types.variable_type(&self.path, &name), name))
+      .collect();
+    params.iter().enumerate().for_each(|(idx, (param_type, name))| {
+      if idx > 0 {
+        self.emitter.emmit(",");
+      }
+      self.emitter.emmit(c_declaration(param_type, name).as_str());
+    });
+    self.emitter.emmit(")");
+  }
+
+  // a function used as a value is called through riven_call_<name>, which takes
+  // boxed arguments and its captured variables from a riven_env_<name>
+  fn visit_closure_prototype(&mut self) {
+    let name = c_function_name(&self.path);
+    let captures = self.types.captures(&self.path).to_vec();
+    if !captures.is_empty() {
+      self.emitter.emit_line("typedef struct {");
+      self.emitter.indent();
+      for capture in &captures {
+        let capture_type = self.types.variable_type(&self.path, capture);
+        self.emitter.emit_line(format!("{};", c_declaration(&capture_type, capture)).as_str());
+      }
+      self.emitter.dedent();
+      self.emitter.emit_line(format!("}} riven_env_{};", name).as_str());
+    }
+    self.emitter.emit_line(
+      format!("static RivenValue riven_call_{}(void *env, RivenValue *args, int line);", name).as_str(),
+    );
+  }
+
+  // static RivenValue riven_call_double(void *env, RivenValue *args, int line) {
+  //   return riven_box(double(riven_unbox_int(args[0], line)));
+  // }
+  fn visit_closure_wrapper(&mut self) {
+    let name = c_function_name(&self.path);
+    let captures = self.types.captures(&self.path).to_vec();
+    self.emitter.blank_line();
+    self.visit_line_directive();
+    self.emitter.emit_line(
+      format!("static RivenValue riven_call_{}(void *env, RivenValue *args, int line) {{", name).as_str(),
+    );
+    self.emitter.indent();
+    if !captures.is_empty() {
+      self.emitter.emit_line(format!("riven_env_{} *captured = env;", name).as_str());
+    }
+
+    let mut args: Vec<String> = captures
+      .iter()
+      .map(|capture| format!("captured->{}", c_name(capture)))
+      .collect();
+    args.extend(
+      self
+        .types
+        .param_types(&self.path)
+        .iter()
+        .enumerate()
+        .map(|(idx, param_type)| match c_unbox(param_type, "line") {
+          Some((unbox, tail)) => format!("{}args[{}]{}", unbox, idx, tail),
+          None => format!("args[{}]", idx),
+        }),
+    );
+    let call = format!("{}({})", c_name(&name), args.join(", "));
+    match self.types.return_type(&self.path) {
+      Type::Void if self.path != "main" => {
+        self.emitter.emit_line(format!("{};", call).as_str());
+        self.emitter.emit_line("return riven_box_int(0);");
+      }
+      _ => self.emitter.emit_line(format!("return riven_box({});", call).as_str()),
+    }
+    self.emitter.dedent();
+    self.emitter.emit_line("}");
+  }
+
+  // double => riven_closure_new("double", 1, riven_call_double, NULL), the
+  // captured variables are copied into the closure when it is made
+  fn visit_closure(&mut self, function: &str) {
+    let name = c_function_name(function);
+    let display_name = function.rsplit("::").next().unwrap_or(function);
+    let arity = self.types.param_types(function).len();
+    self.emitter.emmit(
+      format!("riven_closure_new({:?}, {}, riven_call_{},", display_name, arity, name).as_str(),
+    );
+    let captures = self.types.captures(function).to_vec();
+    if captures.is_empty() {
+      self.emitter.emmit("NULL)");
+    } else {
+      self.emitter.emmit(format!("riven_env_copy(&(riven_env_{}){{", name).as_str());
+      let captures: Vec<String> = captures.iter().map(|capture| c_name(capture)).collect();
+      self.emitter.emmit(captures.join(", ").as_str());
+      self.emitter.emmit(format!("}}, sizeof(riven_env_{})))", name).as_str());
+    }
+  }
+
+  // f(3) where f is a variable => riven_unbox_int(riven_call(f, 1, (RivenValue[]){ riven_box(3) }, line), line),
+  // `call` is the whole call and gives the type of its result
+  fn visit_value_call(&mut self, function: &Factor, args: &[Expression], call: &Factor) {
+    let return_type = self.types.type_of_factor(&self.path, call);
+    let unbox = match return_type {
+      Type::Void => None,
+      _ => c_unbox(&return_type, self.line),
+    };
+
+    if let Some((unbox, _)) = &unbox {
+      self.emitter.emmit(unbox);
+    }
+    self.emitter.emmit("riven_call(");
+    match self.types.type_of_factor(&self.path, function) {
+      Type::Function(_) => self.visit_factor(function),
+      _ => {
+        self.emitter.emmit("riven_unbox_closure(");
+        self.visit_postfix_target(function);
+        self.emitter.emmit(format!(", {})", self.line).as_str());
+      }
+    }
+    self.emitter.emmit(format!(", {},", args.len()).as_str());
+    if args.is_empty() {
+      self.emitter.emmit("NULL");
+    } else {
+      self.emitter.emmit("(RivenValue[]){");
+      args.iter().enumerate().for_each(|(idx, arg)| {
+        if idx > 0 {
+          self.emitter.emmit(",");
+        }
+        self.visit_boxed_expr(arg);
+      });
+      self.emitter.emmit("}");
+    }
+    self.emitter.emmit(format!(", {})", self.line).as_str());
+    if let Some((_, tail)) = &unbox {
+      self.emitter.emmit(tail);
+    }
+  }
+
+  // a prototype for every function, so functions can call each other in any order
+  fn visit_prototypes(&mut self, stmt_list: &StmtList) {
+    if let Some(stmt) = &stmt_list.0 {
+      for (function_stmt, _) in statement_functions(stmt) {
+        let function = child_path(&self.path, &function_stmt.0.to_string());
+        let outer = std::mem::replace(&mut self.path, function);
+        self.visit_function_signature();
+        self.emitter.emit_line(";");
+        if self.types.is_value(&self.path) {
+          self.visit_closure_prototype();
+        }
+        if let Some(body) = &function_stmt.2 {
+          self.visit_prototypes(body);
+        }
+        self.path = outer;
+      }
+      if let StatementValue::IfStmt(IfStmt(_, Some(body)))
+      | StatementValue::WhileStmt(WhileStmt(_, Some(body))) = &stmt.0
+      {
+        self.visit_prototypes(body);
+      }
+    }
+
+    if let Some(rest_stmts) = &stmt_list.1 {
+      self.visit_prototypes(rest_stmts);
+    }
+  }
+
+  fn visit_nested_functions(&mut self, stmt_list: &StmtList) {
+    for (function_stmt, location) in nested_functions(stmt_list) {
+      self.line = location.row();
+      self.location = location.clone();
+      self.visit_function_stmt(function_stmt);
+    }
+  }
+
+  // the variables assigned outside functions, at file scope so every function
+  // reads them; their first value is given by riven_init
+  fn visit_globals(&mut self) {
+    for name in self.declarations.hoisted(&Location::default()).to_vec() {
+      let var_type = self.types.variable_type(&self.path, &name);
+      self.emitter.emit_line(format!("{};", c_declaration(&var_type, &name)).as_str());
+    }
+  }
+
+  // void riven_init(void) { ... } runs the statements outside functions
+  fn visit_init(&mut self, stmt_list: &StmtList) {
+    self.emitter.blank_line();
+    self.emitter.emit_line("void riven_init(void) {");
+    self.emitter.indent();
+    for name in self.declarations.hoisted(&Location::default()).to_vec() {
+      let var_type = self.types.variable_type(&self.path, &name);
+      self.emitter.emit_line(format!("{} = {};", c_name(&name), c_zero(&var_type)).as_str());
+    }
+    for stmt in statements(stmt_list) {
+      if !matches!(stmt.0, StatementValue::FunctionStmt(_) | StatementValue::StructStmt(_)) {
+        self.visit_stmt(stmt);
+      }
+    }
+    self.emitter.dedent();
+    self.emitter.emit_line("}");
+  }
+
+  // declare the variables of a block used outside the if where they are first assigned
+  fn visit_hoisted(&mut self, block: &Location) {
+    for name in self.declarations.hoisted(block).to_vec() {
+      let var_type = self.types.variable_type(&self.path, &name);
+      let declaration = format!("{} = {};", c_declaration(&var_type, &name), c_zero(&var_type));
+      self.emitter.emit_line(declaration.as_str());
+    }
+  }
+
+  // emit an expression where a value of type `expected` is wanted,
+  // a RivenValue variable or parameter takes any value once boxed
+  fn visit_expr_as(&mut self, expr: &Expression, expected: &Type) {
+    if *expected == Type::Any && self.type_of(expr) != Type::Any {
+      self.visit_boxed_expr(expr);
+    } else {
+      self.visit_expr(expr);
+    }
+  }
+
+  // unbox a list item, map value or struct field into the C type inferred for it
+  fn visit_unboxed_value(&mut self, factor: &Factor) {
+    let value_type = self.types.type_of_factor(&self.path, factor);
+    let Some((unbox, tail)) = c_unbox(&value_type, self.line) else {
+      return self.visit_stored_value(factor);
+    };
+
+    self.emitter.emmit(unbox.as_str());
+    match &factor.0 {
+      FactorValue::Field(FieldExpr(target, field)) => {
+        self.emitter.emmit("*");
+        self.visit_field_ref(target, field);
+      }
+      _ => self.visit_stored_value(factor),
+    }
+    self.emitter.emmit(tail.as_str());
+  }
+
+  // emit the boxed RivenValue stored at `list[index]`, `map[key]` or `p.x`
+  fn visit_stored_value(&mut self, factor: &Factor) {
+    match &factor.0 {
+      FactorValue::Index(IndexExpr(target, index)) => {
+        self.emitter.emmit("riven_get(");
+        self.visit_postfix_target(target);
+        self.emitter.emmit(",");
+        self.visit_boxed_expr(index);
+        self.emitter.emmit(format!(", {})", self.line).as_str());
+      }
+      FactorValue::Field(FieldExpr(target, field)) => {
+        self.emitter.emmit("(*");
+        self.visit_field_ref(target, field);
+        self.emitter.emmit(")");
+      }
+      _ => self.visit_factor(factor),
+    }
+  }
+
+  // `grid[0]` in `grid[0][1]` stays boxed, riven_get checks its kind at runtime
+  fn visit_postfix_target(&mut self, target: &Factor) {
+    if is_stored_value(target) {
+      self.visit_stored_value(target);
+    } else {
+      self.visit_factor(target);
+    }
+  }
+
+  // p.x => riven_field(riven_box(p), "x", line), a pointer to the boxed field
+  fn visit_field_ref(&mut self, target: &Factor, field: &Identifier) {
+    self.emitter.emmit("riven_field(riven_box(");
+    self.visit_postfix_target(target);
+    self.emitter.emmit(format!("), \"{}\", {})", field, self.line).as_str());
+  }
+
+  // emit an expression as RivenValue, stored values keep their own kind
+  fn visit_boxed_expr(&mut self, expr: &Expression) {
+    match expr.as_factor() {
+      Some(factor) if is_stored_value(factor) => self.visit_stored_value(factor),
+      _ => {
+        self.emitter.emmit("riven_box(");
+        self.visit_expr(expr);
+        self.emitter.emmit(")");
+      }
+    }
+  }
+
+  // Point(1, 2) => riven_new_Point(riven_box(1), riven_box(2))
+  fn visit_struct_new(&mut self, call_expr: &CallExpr) {
+    self.emitter.emmit(format!("riven_new_{}(", call_expr.0).as_str());
+    call_expr.1 .0.iter().enumerate().for_each(|(idx, expr)| {
+      if idx > 0 {
+        self.emitter.emmit(",");
+      }
+      self.visit_boxed_expr(expr);
+    });
+    self.emitter.emmit(")");
+  }
+
+  // append(scores, 4) => riven_list_append(scores, riven_box(4))
+  fn visit_builtin_call(&mut self, builtin: &Builtin, call_expr: &CallExpr) {
+    self.emitter.emmit(format!("{}(", builtin.c_name).as_str());
+    let args = &call_expr.1 .0;
+    args.iter().enumerate().for_each(|(idx, expr)| {
+      if idx > 0 {
+        self.emitter.emmit(",");
+      }
+      if builtin.box_last_arg && idx == args.len() - 1 {
+        self.visit_boxed_expr(expr);
+      } else {
+        self.visit_expr(expr);
+      }
+    });
+    if builtin.line_arg {
+      self.emitter.emmit(format!(", {}", self.line).as_str());
+    }
+    self.emitter.emmit(")");
+  }
+
+  // print(a, b) => (riven_print_value(a), putchar(' '), riven_print_value(b))
+  fn visit_print_call(&mut self, call_expr: &CallExpr, newline: bool) {
+    let args = &call_expr.1 .0;
+    if args.is_empty() && !newline {
+      self.emitter.emmit("((void)0)");
+      return;
+    }
+
+    self.emitter.emmit("(");
+    args.iter().enumerate().for_each(|(idx, expr)| {
+      if idx > 0 {
+        self.emitter.emmit(", putchar(' '),");
+      }
+      self.emitter.emmit("riven_print_value(");
+      match expr.as_factor() {
+        Some(factor) if is_stored_value(factor) => self.visit_stored_value(factor),
+        _ => self.visit_expr(expr),
+      }
+      self.emitter.emmit(")");
+    });
+
+    if newline {
+      if !args.is_empty() {
+        self.emitter.emmit(",");
+      }
+      self.emitter.emmit("putchar('\\n')");
+    }
+    self.emitter.emmit(")");
+  }
+
+  // C gives `*` and `/` the same precedence as Riven, only int `/` needs more
+  // than its operator: the runtime checks the divisor
+  fn visit_operation(&mut self, operation: &Operation) {
+    match operation {
+      Operation::Factor(factor) => self.visit_factor(factor),
+      Operation::Group(inner) => {
+        self.emitter.emmit("(");
+        self.visit_operation(inner);
+        self.emitter.emmit(")");
+      }
+      Operation::Binary(left, BinOp::Div, right) if self.is_int(left) && self.is_int(right) => {
+        self.emitter.emmit("riven_div(");
+        self.visit_operation(left);
+        self.emitter.emmit(",");
+        self.visit_operation(right);
+        self.emitter.emmit(format!(", {})", self.line).as_str());
+      }
+      Operation::Binary(left, op, right) => {
+        self.visit_operation(left);
+        self.emitter.emmit(op.to_string().as_str());
+        self.visit_operation(right);
+      }
+    }
+  }
+
+  // bools are ints in C arithmetic
+  fn is_int(&self, operation: &Operation) -> bool {
+    match operation {
+      Operation::Factor(factor) => matches!(
+        self.types.type_of_factor(&self.path, factor),
+        Type::Int | Type::Bool
+      ),
+      Operation::Group(inner) => self.is_int(inner),
+      Operation::Binary(left, _, right) => self.is_int(left) && self.is_int(right),
+    }
   }
 }
 
@@ -93,19 +795,43 @@ impl<'a> CodeGenerator for CCodeGenManager<'a> {
     match primary {
       Primary(PrimaryValue::String(s)) => self.emitter.emmit(format!("{:?}", s).as_str()),
       Primary(PrimaryValue::Number(n)) => self.emitter.emmit(format!("{}", n).as_str()),
+      // `{:?}` keeps the decimal point, so 2.0 stays a double in C
+      Primary(PrimaryValue::Float(n)) => self.emitter.emmit(format!("{:?}", n).as_str()),
+      // `true` is a plain int in C, cast it so `_Generic` sees a bool
+      Primary(PrimaryValue::Bool(b)) => self.emitter.emmit(format!("((bool){})", b).as_str()),
     }
   }
 
   fn visit_identifier(&mut self, identifier: &Identifier) {
-    let id = identifier.to_string();
-    self.emitter.emmit(id.as_str());
+    self.emitter.emmit(c_name(&identifier.to_string()).as_str());
   }
 
   fn visit_factor(&mut self, factor: &Factor) {
     match factor {
       Factor(FactorValue::Primary(primary)) => self.visit_primary(primary),
-      Factor(FactorValue::Identifier(identifier)) => self.visit_identifier(identifier),
+      // a function used as a value becomes a closure
+      Factor(FactorValue::Identifier(identifier)) => {
+        let name = identifier.to_string();
+        let function = match self.types.resolve_variable(&self.path, &name) {
+          Some(_) => None,
+          None => self.types.resolve_function(&self.path, &name),
+        };
+        match function {
+          Some(function) => self.visit_closure(&function),
+          None => self.visit_identifier(identifier),
+        }
+      }
+      Factor(FactorValue::Function(function, _)) => {
+        self.visit_closure(&child_path(&self.path, &function.0.to_string()))
+      }
       Factor(FactorValue::CallExpr(call_expr)) => self.visit_call_expr(call_expr),
+      Factor(FactorValue::List(list_expr)) => self.visit_list_expr(list_expr),
+      Factor(FactorValue::Map(map_expr)) => self.visit_map_expr(map_expr),
+      Factor(FactorValue::Index(index_expr)) => self.visit_index_expr(index_expr),
+      Factor(FactorValue::Field(field_expr)) => self.visit_field_expr(field_expr),
+      Factor(FactorValue::Apply(ApplyExpr(function, args))) => {
+        self.visit_value_call(function, &args.0, factor)
+      }
     }
   }
 
@@ -138,23 +864,88 @@ impl<'a> CodeGenerator for CCodeGenManager<'a> {
   }
 
   fn visit_call_expr(&mut self, call_expr: &CallExpr) {
-    self.visit_identifier(&call_expr.0);
-    self.emitter.emmit("(");
-    let args = &call_expr.1 .0;
-    args.iter().enumerate().for_each(|(idx, id)| {
-      self.visit_expr(id);
-      // for last identifier, dont emmit ","
-      if idx < (args.len() - 1) {
+    match call_expr.0.to_string().as_str() {
+      "print" => return self.visit_print_call(call_expr, false),
+      "println" => return self.visit_print_call(call_expr, true),
+      _ => (),
+    }
+
+    if let Some(builtin) = find_builtin(&call_expr.0.to_string()) {
+      return self.visit_builtin_call(builtin, call_expr);
+    }
+
+    if self.is_struct(&call_expr.0) {
+      return self.visit_struct_new(call_expr);
+    }
+
+    if self.types.resolve_variable(&self.path, &call_expr.0.to_string()).is_some() {
+      let function = Factor::from(FactorValue::Identifier(call_expr.0.clone()));
+      let call = Factor::from(FactorValue::CallExpr(call_expr.clone()));
+      return self.visit_value_call(&function, &call_expr.1 .0, &call);
+    }
+
+    let function = self.types.resolve_function(&self.path, &call_expr.0.to_string());
+    let (name, passed) = match &function {
+      Some(function) => (c_name(&c_function_name(function)), self.types.passed(function, &call_expr.1 .0)),
+      None => (call_expr.0.to_string(), unknown_args(&call_expr.1 .0)),
+    };
+    self.emitter.emmit(format!("{}(", name).as_str());
+    passed.iter().enumerate().for_each(|(idx, passed)| {
+      if idx > 0 {
         self.emitter.emmit(",");
       }
+      match passed {
+        Passed::Capture(name) => self.emitter.emmit(c_name(name).as_str()),
+        Passed::Arg(arg, param_type) => self.visit_expr_as(arg, param_type),
+      }
     });
 
     self.emitter.emmit(")");
   }
 
+  fn visit_list_expr(&mut self, list_expr: &ListExpr) {
+    let items = &list_expr.0 .0;
+    if items.is_empty() {
+      self.emitter.emmit("riven_list_new()");
+      return;
+    }
+
+    self.emitter.emmit(format!("riven_list_of({}", items.len()).as_str());
+    items.iter().for_each(|item| {
+      self.emitter.emmit(",");
+      self.visit_boxed_expr(item);
+    });
+    self.emitter.emmit(")");
+  }
+
+  fn visit_map_expr(&mut self, map_expr: &MapExpr) {
+    let entries = &map_expr.0;
+    if entries.is_empty() {
+      self.emitter.emmit("riven_map_new()");
+      return;
+    }
+
+    self.emitter.emmit(format!("riven_map_of({}", entries.len()).as_str());
+    entries.iter().for_each(|entry| {
+      self.emitter.emmit(",");
+      self.visit_boxed_expr(&entry.0);
+      self.emitter.emmit(",");
+      self.visit_boxed_expr(&entry.1);
+    });
+    self.emitter.emmit(")");
+  }
+
+  fn visit_index_expr(&mut self, index_expr: &IndexExpr) {
+    self.visit_unboxed_value(&Factor::from(FactorValue::Index(index_expr.clone())));
+  }
+
+  fn visit_field_expr(&mut self, field_expr: &FieldExpr) {
+    self.visit_unboxed_value(&Factor::from(FactorValue::Field(field_expr.clone())));
+  }
+
   fn visit_call_stmt(&mut self, call_expr: &CallExpr) {
     self.visit_call_expr(call_expr);
-    self.emitter.emmit(";");
+    self.emitter.emit_line(";");
   }
 
   fn visit_expr(&mut self, expr: &Expression) {
@@ -165,6 +956,29 @@ impl<'a> CodeGenerator for CCodeGenManager<'a> {
   }
 
   fn visit_compare_expr(&mut self, compare_expr: &CompareExpr) {
+    if let (Some(CompareOp::Eq), Some(right)) = (&compare_expr.1, &compare_expr.2) {
+      let left = &compare_expr.0;
+      match (self.type_of(left), self.type_of(right)) {
+        (Type::Str, Type::Str) => {
+          self.emitter.emmit("strcmp(");
+          self.visit_expr(left);
+          self.emitter.emmit(",");
+          self.visit_expr(right);
+          self.emitter.emmit(") == 0");
+          return;
+        }
+        (Type::Any, _) | (_, Type::Any) => {
+          self.emitter.emmit("riven_value_equal(");
+          self.visit_boxed_expr(left);
+          self.emitter.emmit(",");
+          self.visit_boxed_expr(right);
+          self.emitter.emmit(")");
+          return;
+        }
+        _ => (),
+      }
+    }
+
     self.visit_expr(&compare_expr.0);
     match compare_expr.1 {
       Some(CompareOp::Eq) => self.emitter.emmit("=="),
@@ -219,72 +1033,199 @@ impl<'a> CodeGenerator for CCodeGenManager<'a> {
         self.emitter.emmit(")");
       }
       LogicOp::Not => {
-        if logic_expr.2.is_some() {
-          self.emitter.emmit("!");
-          if let Some(expr) = &logic_expr.2 {
-            self.visit_logic_expr(expr);
-          }
+        // `not(k = 7)` is `!(k == 7)`, without parentheses C reads `!k == 7`
+        if let Some(expr) = &logic_expr.2 {
+          self.emitter.emmit("!(");
+          self.visit_logic_expr(expr);
+          self.emitter.emmit(")");
         }
       }
     }
   }
 
   fn visit_assign_stmt(&mut self, stmt: &AssignStmt) {
-    // TODO: check variable type here
-    // assume it's int type
-    self.emitter.emmit("int");
+    let var_type = match self.types.variable_type(&self.path, &stmt.0.to_string()) {
+      Type::Unknown => self.type_of(&stmt.1),
+      var_type => var_type,
+    };
+    if self.declarations.declares(&self.location) {
+      self.emitter.emmit(c_type(&var_type).as_str());
+    }
 
     self.visit_identifier(&stmt.0);
     self.emitter.emmit("=");
-    self.visit_expr(&stmt.1);
-    self.emitter.emmit(";");
+    self.visit_expr_as(&stmt.1, &var_type);
+    self.emitter.emit_line(";");
+  }
+
+  fn visit_index_assign_stmt(&mut self, stmt: &IndexAssignStmt) {
+    self.emitter.emmit("riven_set(");
+    self.visit_postfix_target(&stmt.0 .0);
+    self.emitter.emmit(",");
+    self.visit_boxed_expr(&stmt.0 .1);
+    self.emitter.emmit(",");
+    self.visit_boxed_expr(&stmt.1);
+    self.emitter.emit_line(format!(", {});", self.line).as_str());
+  }
+
+  fn visit_field_assign_stmt(&mut self, stmt: &FieldAssignStmt) {
+    self.emitter.emmit("*");
+    self.visit_field_ref(&stmt.0 .0, &stmt.0 .1);
+    self.emitter.emmit("=");
+    self.visit_boxed_expr(&stmt.1);
+    self.emitter.emit_line(";");
   }
 
   fn visit_return_stmt(&mut self, stmt: &ReturnStmt) {
     self.emitter.emmit("return");
-    self.visit_expr(&stmt.0);
-    self.emitter.emmit(";");
+    let return_type = self.types.return_type(&self.path);
+    self.visit_expr_as(&stmt.0, &return_type);
+    self.emitter.emit_line(";");
   }
 
   fn visit_if_stmt(&mut self, stmt: &IfStmt) {
+    let block = self.location.clone();
     self.emitter.emmit("if (");
     self.visit_logic_expr(&stmt.0);
-    self.emitter.emmit(") {");
+    self.emitter.emit_line(") {");
+    self.emitter.indent();
+    self.visit_hoisted(&block);
 
     if let Some(stmt_list) = &stmt.1 {
       self.visit_stmt_list(&stmt_list);
     }
 
-    self.emitter.emmit("}");
+    self.emitter.dedent();
+    self.emitter.emit_line("}");
   }
 
+  fn visit_while_stmt(&mut self, stmt: &WhileStmt) {
+    let block = self.location.clone();
+    self.emitter.emmit("while (");
+    self.visit_logic_expr(&stmt.0);
+    self.emitter.emit_line(") {");
+    self.emitter.indent();
+    self.visit_hoisted(&block);
+
+    if let Some(stmt_list) = &stmt.1 {
+      self.visit_stmt_list(stmt_list);
+    }
+
+    self.emitter.dedent();
+    self.emitter.emit_line("}");
+  }
+
+  // the functions nested in a function body, or in its ifs and loops, follow its definition
   fn visit_function_stmt(&mut self, stmt: &FunctionStmt) {
-    // TODO: check function return type, assume int type here
-    self.emitter.emmit("int");
-    self.visit_identifier(&stmt.0);
-    self.emitter.emmit("(");
-    stmt.1.iter().enumerate().for_each(|(idx, id)| {
-      // FIXME: assume int type
-      self.emitter.emmit("int");
+    let block = self.location.clone();
+    let function = child_path(&self.path, &stmt.0.to_string());
+    let outer = std::mem::replace(&mut self.path, function);
 
-      self.visit_identifier(id);
-      if idx < (stmt.1.len() - 1) {
-        self.emitter.emmit(",");
-      }
-    });
-    self.emitter.emmit(") {");
+    let line = self.line;
+    self.emitter.blank_line();
+    self.visit_line_directive();
+    self.visit_function_signature();
+    self.emitter.emit_line("{");
+    self.emitter.indent();
+    self.visit_hoisted(&block);
+    if self.path == "main" && self.has_init {
+      self.emitter.emit_line("riven_init();");
+    }
     if let Some(stmt_list) = &stmt.2 {
       self.visit_stmt_list(stmt_list);
     }
-    self.emitter.emmit("}");
+    if let Some(return_type) = default_return(&self.types, &self.path, stmt.2.as_deref()) {
+      self.emitter.emit_line(format!("return {};", c_zero(&return_type)).as_str());
+    }
+    self.emitter.dedent();
+    self.emitter.emit_line("}");
+    // the wrapper of a function used as a value is counted as its definition
+    if self.types.is_value(&self.path) {
+      self.line = line;
+      self.visit_closure_wrapper();
+    }
+
+    if let Some(stmt_list) = &stmt.2 {
+      self.visit_nested_functions(stmt_list);
+    }
+    self.path = outer;
+  }
+
+  // struct Point { x, y } =>
+  //   typedef struct Point {
+  //     const RivenType *type;
+  //     RivenValue x;
+  //     RivenValue y;
+  //   } Point;
+  //   plus its RivenType and a riven_new_Point constructor
+  fn visit_struct_stmt(&mut self, stmt: &StructStmt) {
+    let name = stmt.0.to_string();
+    let fields: Vec<String> = stmt.1.iter().map(|field| field.to_string()).collect();
+    self.structs.push(name.clone());
+    // the C names, riven_<name>_type and riven_<name>_fields keep the Riven ones
+    let c_struct = c_name(&name);
+    let members: Vec<String> = fields.iter().map(|field| c_name(field)).collect();
+
+    self.emitter.blank_line();
+    self.emitter.emit_line(format!("typedef struct {} {{", c_struct).as_str());
+    self.emitter.indent();
+    self.emitter.emit_line("const RivenType *type;");
+    members.iter().for_each(|member| {
+      self.emitter.emit_line(format!("RivenValue {};", member).as_str());
+    });
+    self.emitter.dedent();
+    self.emitter.emit_line(format!("}} {};", c_struct).as_str());
+
+    let field_names: Vec<String> = fields.iter().map(|field| format!("{:?},", field)).collect();
+    self.emitter.emit_line(
+      format!("static const char *riven_{}_fields[] = {{ {} NULL }};", name, field_names.join(" ")).as_str(),
+    );
+    self.emitter.emit_line(
+      format!(
+        "static const RivenType riven_{0}_type = {{ {0:?}, {1}, riven_{0}_fields }};",
+        name,
+        fields.len()
+      )
+      .as_str(),
+    );
+
+    let params: Vec<String> = members.iter().map(|member| format!("RivenValue {}", member)).collect();
+    let params = if params.is_empty() { "void".to_owned() } else { params.join(", ") };
+    self.emitter.emit_line(format!("static inline {} *riven_new_{}({}) {{", c_struct, name, params).as_str());
+    self.emitter.indent();
+    self.emitter.emit_line(format!("{0} *self = malloc(sizeof({0}));", c_struct).as_str());
+    self.emitter.emit_line(format!("self->type = &riven_{}_type;", name).as_str());
+    members.iter().for_each(|member| {
+      self.emitter.emit_line(format!("self->{0} = {0};", member).as_str());
+    });
+    self.emitter.emit_line("return self;");
+    self.emitter.dedent();
+    self.emitter.emit_line("}");
   }
 
   fn visit_stmt(&mut self, stmt: &Statement) {
+    self.line = stmt.location().row();
+    self.location = stmt.location().clone();
+    // functions place their own directive, structs are emitted before any code
+    if !matches!(stmt.0, StatementValue::FunctionStmt(_) | StatementValue::StructStmt(_)) {
+      self.visit_line_directive();
+    }
     match &stmt.0 {
       StatementValue::AssignStmt(stmt) => self.visit_assign_stmt(stmt),
+      StatementValue::IndexAssignStmt(stmt) => self.visit_index_assign_stmt(stmt),
+      StatementValue::FieldAssignStmt(stmt) => self.visit_field_assign_stmt(stmt),
       StatementValue::CallStmt(stmt) => self.visit_call_stmt(stmt),
-      StatementValue::FunctionStmt(stmt) => self.visit_function_stmt(stmt),
+      StatementValue::ApplyStmt(stmt) => {
+        self.visit_factor(&Factor::from(FactorValue::Apply(stmt.clone())));
+        self.emitter.emit_line(";");
+      }
+      // emitted by visit_nested_functions
+      StatementValue::FunctionStmt(_) => (),
+      // structs are emitted by visit_program before everything else
+      StatementValue::StructStmt(stmt) if self.is_struct(&stmt.0) => (),
+      StatementValue::StructStmt(stmt) => self.visit_struct_stmt(stmt),
       StatementValue::IfStmt(stmt) => self.visit_if_stmt(stmt),
+      StatementValue::WhileStmt(stmt) => self.visit_while_stmt(stmt),
       StatementValue::ReturnStmt(stmt) => self.visit_return_stmt(stmt),
     }
   }
@@ -300,7 +1241,25 @@ impl<'a> CodeGenerator for CCodeGenManager<'a> {
   }
 
   fn visit_program(&mut self, program: &Program) {
-    self.visit_stmt_list(&program.0);
+    self.types = infer(program).types;
+    self.declarations = plan_declarations(program);
+
+    let mut structs = vec![];
+    collect_structs(&program.0, &mut structs);
+    structs.iter().for_each(|stmt| self.visit_struct_stmt(stmt));
+
+    // the globals, then the prototypes, each group after a blank line
+    self.emitter.blank_line();
+    self.visit_globals();
+    self.emitter.blank_line();
+    self.visit_prototypes(&program.0);
+    self.has_init = statements(&program.0).iter().any(|stmt| {
+      !matches!(stmt.0, StatementValue::FunctionStmt(_) | StatementValue::StructStmt(_))
+    });
+    if self.has_init {
+      self.visit_init(&program.0);
+    }
+    self.visit_nested_functions(&program.0);
   }
 
   fn visit_component_factor(&mut self, factor: &ComponentFactor) {
@@ -335,16 +1294,6 @@ impl<'a> CodeGenerator for CCodeGenManager<'a> {
   }
 
   fn visit_component_arithmetic_expr(&mut self, arithmetic_expr: &ComponentArithmeticExpr) {
-    self.visit_component_term(&arithmetic_expr.0);
-    let opt_bin_op = &arithmetic_expr.1;
-    let opt_box_arithmetic_expr = &arithmetic_expr.2;
-
-    if let Some(bin_op) = opt_bin_op {
-      self.emitter.emmit(bin_op.to_string().as_str());
-    }
-
-    if let Some(arit_expr) = opt_box_arithmetic_expr {
-      self.visit_component_arithmetic_expr(arit_expr);
-    }
+    self.visit_operation(&Operation::of_component_arithmetic_expr(arithmetic_expr));
   }
 }
@@ -0,0 +1,514 @@
+use analyzer::flow::default_return;
+use analyzer::infer::{child_path, Passed};
+use analyzer::operands::Operation;
+use analyzer::types::Type;
+use parser::ast::{
+  ArithmeticExpr, AssignStmt, BinOp, CallExpr, CompareExpr, CompareOp, ComponentArithmeticExpr,
+  ComponentFactor, ComponentTerm, Expression, ExpressionValue, Factor, FactorValue,
+  FieldAssignStmt, FieldExpr, FunctionStmt, Identifier, IfStmt, IndexAssignStmt, IndexExpr,
+  ListExpr, LogicExpr, LogicOp, MapExpr, Primary, PrimaryValue, Program, ReturnStmt, Statement,
+  StmtList, StructStmt, Term, WhileStmt,
+};
+use parser::location::Location;
+
+use crate::codegen::{c_function_name, unknown_args, CodeGenerator, Emitter};
+use crate::js_runtime::{JS_INDEX_RUNTIME, JS_INPUT_RUNTIME, JS_PRINT_RUNTIME, JS_VALUE_RUNTIME};
+use crate::runtime::find_builtin;
+use crate::script_codegen::{has_init, Arithmetic, Script, ScriptCodeGen};
+
+// names a Riven program may use that JavaScript keeps for itself, or that the
+// runtime needs, a variable named `new` becomes `new_`
+const JS_RESERVED: &str = "\
+  arguments await break case catch class const continue debugger default delete do else \
+  enum eval export extends false finally for function if implements import in instanceof \
+  interface let new null package private protected public return static super switch this \
+  throw true try typeof undefined var void while with yield Array Buffer Error Infinity \
+  Map Math NaN Number Object RivenClosure RivenError RivenObject String TextEncoder \
+  console document process require window";
+
+// the JavaScript name of a Riven variable, function or struct
+fn js_name(name: &str) -> String {
+  let reserved = JS_RESERVED.split_whitespace().any(|word| word == name);
+  if reserved || name.starts_with("riven_") {
+    format!("{}_", name)
+  } else {
+    name.to_owned()
+  }
+}
+
+// the value a variable has before its first assignment, see runtime::zero of the interpreter
+fn js_zero(t: &Type) -> &'static str {
+  match t {
+    Type::Float => "0.0",
+    Type::Bool => "false",
+    Type::Str => "\"\"",
+    Type::List(_) => "[]",
+    Type::Map(_, _) => "new Map()",
+    _ => "0",
+  }
+}
+
+pub struct JsCodeGen<'a> {
+  script: Script<'a>,
+}
+
+impl<'a> JsCodeGen<'a> {
+  pub fn new(emitter: &'a mut Emitter) -> Self {
+    emitter.push_header(JS_VALUE_RUNTIME.trim_start());
+    emitter.push_header(JS_INDEX_RUNTIME.trim_start());
+    emitter.push_header(JS_PRINT_RUNTIME.trim_start());
+    emitter.push_header(JS_INPUT_RUNTIME.trim_start());
+
+    JsCodeGen {
+      script: Script::new(emitter),
+    }
+  }
+
+  // an expression stored where a value of type `expected` is wanted, a double
+  // stored into an int loses its fraction like in C
+  fn expr_code_as(&mut self, expr: &Expression, expected: &Type) -> String {
+    let code = self.expr_code(expr);
+    match (expected, self.type_of(expr)) {
+      (Type::Int, Type::Float | Type::Any) => format!("riven_int({})", code),
+      _ => code,
+    }
+  }
+
+  // the statements of an if, a loop or a function, one level deeper
+  fn visit_block(&mut self, block: &Location, stmt_list: Option<&StmtList>) {
+    self.script.emitter.indent();
+    self.visit_hoisted(block);
+    if let Some(stmt_list) = stmt_list {
+      self.visit_stmt_list(stmt_list);
+    }
+    self.script.emitter.dedent();
+  }
+}
+
+impl<'a> ScriptCodeGen<'a> for JsCodeGen<'a> {
+  fn script(&self) -> &Script<'a> {
+    &self.script
+  }
+
+  fn script_mut(&mut self) -> &mut Script<'a> {
+    &mut self.script
+  }
+
+  fn name(name: &str) -> String {
+    js_name(name)
+  }
+
+  fn string(text: &str) -> String {
+    format!("{:?}", text)
+  }
+
+  fn statement(code: &str) -> String {
+    format!("{};", code)
+  }
+
+  fn hoisted_code(name: &str, var_type: &Type) -> String {
+    format!("let {} = {};", js_name(name), js_zero(var_type))
+  }
+
+  // ints wrap around like a C int, `7 / 2` is 3 and dividing by zero stops the
+  // program; an operation inside another one is always parenthesized
+  fn combine(&self, left: Arithmetic, op: &BinOp, right: Arithmetic) -> Arithmetic {
+    let (a, b) = (left.operand(u8::MAX, false), right.operand(u8::MAX, false));
+    let int = left.int && right.int;
+    let (code, binary) = match op {
+      BinOp::Add if int => (format!("{} + {} | 0", a, b), true),
+      BinOp::Min if int => (format!("{} - {} | 0", a, b), true),
+      BinOp::Time if int => (format!("Math.imul({}, {})", left.code, right.code), false),
+      BinOp::Div if int => (
+        format!(
+          "riven_idiv({}, {}, {})",
+          left.code, right.code, self.script.line
+        ),
+        false,
+      ),
+      // BinOp prints with the spaces around it
+      op => (format!("{}{}{}", a, op, b), true),
+    };
+    Arithmetic {
+      code,
+      int,
+      precedence: binary as u8,
+    }
+  }
+
+  // function riven_init() { ... } runs the statements outside functions, riven_run
+  // calls it before main
+  fn visit_init(&mut self, stmt_list: &StmtList) {
+    self.script.emitter.blank_line();
+    self.emit_line("function riven_init() {");
+    self.script.emitter.indent();
+    self.visit_init_stmts(stmt_list);
+    self.script.emitter.dedent();
+    self.emit_line("}");
+  }
+}
+
+impl<'a> CodeGenerator for JsCodeGen<'a> {
+  fn visit_primary(&mut self, primary: &Primary) {
+    let code = match &primary.0 {
+      PrimaryValue::String(s) => format!("{:?}", s),
+      // a C int literal
+      PrimaryValue::Number(n) => format!("{}", *n as i32),
+      PrimaryValue::Float(n) if n.is_nan() => "NaN".to_owned(),
+      PrimaryValue::Float(n) if n.is_infinite() && *n > 0.0 => "Infinity".to_owned(),
+      PrimaryValue::Float(n) if n.is_infinite() => "-Infinity".to_owned(),
+      PrimaryValue::Float(n) => format!("{:?}", n),
+      PrimaryValue::Bool(b) => format!("{}", b),
+    };
+    self.push(&code);
+  }
+
+  fn visit_identifier(&mut self, identifier: &Identifier) {
+    let name = js_name(&identifier.to_string());
+    self.push(&name);
+  }
+
+  fn visit_factor(&mut self, factor: &Factor) {
+    self.visit_script_factor(factor);
+  }
+
+  fn visit_term(&mut self, term: &Term) {
+    self.visit_operation(&Operation::of_term(term));
+  }
+
+  fn visit_arithmetic_expr(&mut self, arithmetic_expr: &ArithmeticExpr) {
+    self.visit_operation(&Operation::of_arithmetic_expr(arithmetic_expr));
+  }
+
+  fn visit_call_expr(&mut self, call_expr: &CallExpr) {
+    match call_expr.0.to_string().as_str() {
+      "print" => return self.visit_print_call("riven_print", call_expr),
+      "println" => return self.visit_print_call("riven_println", call_expr),
+      "printf" => return self.visit_print_call("riven_printf", call_expr),
+      _ => (),
+    }
+
+    if let Some(builtin) = find_builtin(&call_expr.0.to_string()) {
+      let mut args = self.exprs_code(&call_expr.1 .0);
+      if builtin.line_arg {
+        args.push(self.script.line.to_string());
+      }
+      return self.push(&format!("{}({})", builtin.c_name, args.join(", ")));
+    }
+
+    if self.is_struct(&call_expr.0) {
+      let args = self.exprs_code(&call_expr.1 .0);
+      let code = format!(
+        "new {}({})",
+        js_name(&call_expr.0.to_string()),
+        args.join(", ")
+      );
+      return self.push(&code);
+    }
+
+    if self
+      .script
+      .types
+      .resolve_variable(&self.script.path, &call_expr.0.to_string())
+      .is_some()
+    {
+      let function = Factor::from(FactorValue::Identifier(call_expr.0.clone()));
+      return self.visit_value_call(&function, &call_expr.1 .0);
+    }
+
+    let function = self
+      .script
+      .types
+      .resolve_function(&self.script.path, &call_expr.0.to_string());
+    let (name, passed) = match &function {
+      Some(function) => (
+        js_name(&c_function_name(function)),
+        self.script.types.passed(function, &call_expr.1 .0),
+      ),
+      None => (
+        js_name(&call_expr.0.to_string()),
+        unknown_args(&call_expr.1 .0),
+      ),
+    };
+    let args: Vec<String> = passed
+      .into_iter()
+      .map(|passed| match passed {
+        Passed::Capture(name) => js_name(&name),
+        Passed::Arg(arg, param_type) => self.expr_code_as(arg, &param_type),
+      })
+      .collect();
+    self.push(format!("{}({})", name, args.join(", ")).as_str());
+  }
+
+  fn visit_list_expr(&mut self, list_expr: &ListExpr) {
+    let items = self.exprs_code(&list_expr.0 .0);
+    self.push(format!("[{}]", items.join(", ")).as_str());
+  }
+
+  fn visit_map_expr(&mut self, map_expr: &MapExpr) {
+    if map_expr.0.is_empty() {
+      return self.push("new Map()");
+    }
+    let entries: Vec<String> = map_expr
+      .0
+      .iter()
+      .map(|entry| {
+        format!(
+          "[{}, {}]",
+          self.expr_code(&entry.0),
+          self.expr_code(&entry.1)
+        )
+      })
+      .collect();
+    self.push(format!("new Map([{}])", entries.join(", ")).as_str());
+  }
+
+  fn visit_index_expr(&mut self, index_expr: &IndexExpr) {
+    let target = self.factor_code(&index_expr.0);
+    let index = self.expr_code(&index_expr.1);
+    self.push(format!("riven_get({}, {}, {})", target, index, self.script.line).as_str());
+  }
+
+  fn visit_field_expr(&mut self, field_expr: &FieldExpr) {
+    let code = match self.field_code(&field_expr.0, &field_expr.1) {
+      (target, true) => format!("{}.{}", target, field_expr.1),
+      (target, false) => format!(
+        "riven_field({}, \"{}\", {})",
+        target, field_expr.1, self.script.line
+      ),
+    };
+    self.push(&code);
+  }
+
+  fn visit_call_stmt(&mut self, call_expr: &CallExpr) {
+    let code = self.generate(|gen| gen.visit_call_expr(call_expr));
+    self.emit_line(&Self::statement(&code));
+  }
+
+  fn visit_expr(&mut self, expr: &Expression) {
+    let expr_value = &expr.0;
+    match expr_value {
+      ExpressionValue::ComponentArithmeticExpr(arith_expr) => {
+        self.visit_component_arithmetic_expr(arith_expr)
+      }
+    }
+  }
+
+  fn visit_compare_expr(&mut self, compare_expr: &CompareExpr) {
+    let left = self.expr_code(&compare_expr.0);
+    let (op, right) = match (&compare_expr.1, &compare_expr.2) {
+      (Some(op), Some(right)) => (op, right),
+      // a value used as a condition, texts, lists and maps are always true
+      _ => {
+        let code = match self.type_of(&compare_expr.0) {
+          Type::Int | Type::Float | Type::Bool => left,
+          _ => format!("riven_truth({})", left),
+        };
+        return self.push(&code);
+      }
+    };
+
+    let right = self.expr_code(right);
+    let wrap = |code: String, expr: &Expression| match expr.as_factor() {
+      Some(_) => code,
+      None => format!("({})", code),
+    };
+    let left = wrap(left, &compare_expr.0);
+    let right = wrap(right, compare_expr.2.as_ref().unwrap_or(&compare_expr.0));
+    let op = match op {
+      CompareOp::Eq => "===",
+      CompareOp::Gt => ">",
+      CompareOp::Lt => "<",
+    };
+    self.push(format!("{} {} {}", left, op, right).as_str());
+  }
+
+  fn visit_logic_expr(&mut self, logic_expr: &LogicExpr) {
+    match logic_expr.0 {
+      LogicOp::Bool => {
+        if let Some(compare_expr) = &logic_expr.1 {
+          self.visit_compare_expr(compare_expr);
+        }
+      }
+      LogicOp::And | LogicOp::Or => {
+        let op = match logic_expr.0 {
+          LogicOp::And => " && ",
+          _ => " || ",
+        };
+        let operands: Vec<String> = [&logic_expr.2, &logic_expr.3]
+          .into_iter()
+          .flatten()
+          .map(|expr| self.generate(|gen| gen.visit_logic_expr(expr)))
+          .collect();
+        self.push(format!("({})", operands.join(op)).as_str());
+      }
+      LogicOp::Not => {
+        if let Some(expr) = &logic_expr.2 {
+          let code = self.generate(|gen| gen.visit_logic_expr(expr));
+          self.push(format!("!({})", code).as_str());
+        }
+      }
+    }
+  }
+
+  fn visit_assign_stmt(&mut self, stmt: &AssignStmt) {
+    let var_type = match self
+      .script
+      .types
+      .variable_type(&self.script.path, &stmt.0.to_string())
+    {
+      Type::Unknown => self.type_of(&stmt.1),
+      var_type => var_type,
+    };
+    let value = self.expr_code_as(&stmt.1, &var_type);
+    let name = js_name(&stmt.0.to_string());
+    match self.script.declarations.declares(&self.script.location) {
+      true => self.emit_line(format!("let {} = {};", name, value).as_str()),
+      false => self.emit_line(format!("{} = {};", name, value).as_str()),
+    }
+  }
+
+  fn visit_index_assign_stmt(&mut self, stmt: &IndexAssignStmt) {
+    let target = self.factor_code(&stmt.0 .0);
+    let index = self.expr_code(&stmt.0 .1);
+    let value = self.expr_code(&stmt.1);
+    let code = format!(
+      "riven_set({}, {}, {}, {});",
+      target, index, value, self.script.line
+    );
+    self.emit_line(&code);
+  }
+
+  fn visit_field_assign_stmt(&mut self, stmt: &FieldAssignStmt) {
+    let FieldExpr(target, field) = &stmt.0;
+    let code = match self.field_code(target, field) {
+      (target, true) => format!("{}.{} = {};", target, field, self.expr_code(&stmt.1)),
+      (target, false) => format!(
+        "riven_set_field({}, \"{}\", {}, {});",
+        target,
+        field,
+        self.expr_code(&stmt.1),
+        self.script.line
+      ),
+    };
+    self.emit_line(&code);
+  }
+
+  fn visit_return_stmt(&mut self, stmt: &ReturnStmt) {
+    let return_type = self.script.types.return_type(&self.script.path);
+    let value = self.expr_code_as(&stmt.0, &return_type);
+    self.emit_line(format!("return {};", value).as_str());
+  }
+
+  fn visit_if_stmt(&mut self, stmt: &IfStmt) {
+    let block = self.script.location.clone();
+    let condition = self.condition_code(&stmt.0);
+    self.emit_line(format!("if ({}) {{", condition).as_str());
+    self.visit_block(&block, stmt.1.as_deref());
+    self.emit_line("}");
+  }
+
+  fn visit_while_stmt(&mut self, stmt: &WhileStmt) {
+    let block = self.script.location.clone();
+    let condition = self.condition_code(&stmt.0);
+    self.emit_line(format!("while ({}) {{", condition).as_str());
+    self.visit_block(&block, stmt.1.as_deref());
+    self.emit_line("}");
+  }
+
+  // functions are lifted to the top level like in C, the functions nested in
+  // them follow them
+  fn visit_function_stmt(&mut self, stmt: &FunctionStmt) {
+    let block = self.script.location.clone();
+    let function = child_path(&self.script.path, &stmt.0.to_string());
+    let outer = std::mem::replace(&mut self.script.path, function);
+
+    let params: Vec<String> = self
+      .script
+      .types
+      .params(&self.script.path)
+      .iter()
+      .map(|name| js_name(name))
+      .collect();
+    self.script.emitter.blank_line();
+    let name = js_name(&c_function_name(&self.script.path));
+    self.emit_line(format!("function {}({}) {{", name, params.join(", ")).as_str());
+    self.visit_block(&block, stmt.2.as_deref());
+    if let Some(return_type) =
+      default_return(&self.script.types, &self.script.path, stmt.2.as_deref())
+    {
+      self.emit_line(format!("  return {};", js_zero(&return_type)).as_str());
+    }
+    self.emit_line("}");
+
+    if let Some(stmt_list) = &stmt.2 {
+      self.visit_nested_functions(stmt_list);
+    }
+    self.script.path = outer;
+  }
+
+  // struct Point { x, y } =>
+  //   class Point extends RivenObject { constructor(x, y) { super(); this.x = x; this.y = y; } }
+  fn visit_struct_stmt(&mut self, stmt: &StructStmt) {
+    let name = stmt.0.to_string();
+    let class = js_name(&name);
+    self.script.structs.push(name.clone());
+
+    let params: Vec<String> = stmt
+      .1
+      .iter()
+      .map(|field| js_name(&field.to_string()))
+      .collect();
+    self.script.emitter.blank_line();
+    self.emit_line(format!("class {} extends RivenObject {{", class).as_str());
+    self.emit_line(format!("  constructor({}) {{", params.join(", ")).as_str());
+    self.emit_line("    super();");
+    for (field, param) in stmt.1.iter().zip(&params) {
+      self.emit_line(format!("    this.{} = {};", field, param).as_str());
+    }
+    self.emit_line("  }");
+    self.emit_line("}");
+    // printed with its Riven name
+    if class != name {
+      self.emit_line(format!("{}.riven_name = {:?};", class, name).as_str());
+    }
+  }
+
+  fn visit_stmt(&mut self, stmt: &Statement) {
+    self.visit_script_stmt(stmt);
+  }
+
+  fn visit_stmt_list(&mut self, stmt_list: &StmtList) {
+    if let Some(stmt) = &stmt_list.0 {
+      self.visit_stmt(stmt);
+    }
+
+    if let Some(rests_stmts) = &stmt_list.1 {
+      self.visit_stmt_list(rests_stmts);
+    }
+  }
+
+  fn visit_program(&mut self, program: &Program) {
+    self.visit_script_program(program);
+    if self.script.types.resolve_function("", "main").is_some() {
+      let run = if has_init(&program.0) {
+        "riven_run(main, riven_init);\n"
+      } else {
+        "riven_run(main);\n"
+      };
+      self.script.emitter.push_tail(run);
+    }
+  }
+
+  fn visit_component_factor(&mut self, factor: &ComponentFactor) {
+    self.visit_operation(&Operation::of_component_factor(factor));
+  }
+
+  fn visit_component_term(&mut self, term: &ComponentTerm) {
+    self.visit_operation(&Operation::of_component_term(term));
+  }
+
+  fn visit_component_arithmetic_expr(&mut self, arith_expr: &ComponentArithmeticExpr) {
+    self.visit_operation(&Operation::of_component_arithmetic_expr(arith_expr));
+  }
+}
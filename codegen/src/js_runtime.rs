@@ -0,0 +1,461 @@
+//! JavaScript runtime support emitted in front of every program generated by
+//! `JsCodeGen`.
+//!
+//! The generated code runs in a browser, where the output goes to a
+//! `<pre id="riven-output">` and `input` asks with `prompt`, or in node, where
+//! it uses stdin and stdout. Values print and fail the way the C runtime does.
+
+// runtime errors, function values, structs and C int division
+pub const JS_VALUE_RUNTIME: &str = r#"
+"use strict";
+
+class RivenError extends Error {
+  constructor(line, message) {
+    super(message);
+    this.line = line;
+  }
+}
+
+function riven_error(line, message) {
+  throw new RivenError(line, message);
+}
+
+// a function used as a value, with a copy of the variables it captured
+class RivenClosure {
+  constructor(name, arity, code, env) {
+    this.name = name;
+    this.arity = arity;
+    this.code = code;
+    this.env = env;
+  }
+}
+
+function riven_closure(name, arity, code, env = []) {
+  return new RivenClosure(name, arity, code, env);
+}
+
+function riven_call(closure, args, line) {
+  if (!(closure instanceof RivenClosure)) {
+    riven_error(line, "expected a function");
+  }
+  if (args.length !== closure.arity) {
+    riven_error(line, `function ${closure.name} takes ${closure.arity} argument(s) but ${args.length} were given`);
+  }
+  return closure.code(...closure.env, ...args);
+}
+
+// every struct is a class extending RivenObject, its fields in declaration order
+class RivenObject {}
+
+function riven_struct_name(object) {
+  return object.constructor.riven_name || object.constructor.name;
+}
+
+function riven_field(target, field, line) {
+  if (!(target instanceof RivenObject)) {
+    riven_error(line, "only structs have fields");
+  }
+  if (!Object.prototype.hasOwnProperty.call(target, field)) {
+    riven_error(line, `${riven_struct_name(target)} has no field named ${field}`);
+  }
+  return target[field];
+}
+
+function riven_set_field(target, field, value, line) {
+  riven_field(target, field, line);
+  target[field] = value;
+}
+
+// ints are C ints: `7 / 2` is 3
+function riven_idiv(a, b, line) {
+  if (+b === 0) {
+    riven_error(line, "division by zero");
+  }
+  return (a / b) | 0;
+}
+
+// a number stored where an int is inferred, like assigning a double to a C int
+function riven_int(value) {
+  return Math.trunc(+value) | 0;
+}
+
+// what C makes of a value in a condition, texts and lists are never NULL here
+function riven_truth(value) {
+  return value !== 0 && value !== false;
+}
+"#;
+
+// lists are arrays and maps are Maps, both keep their items in order
+pub const JS_INDEX_RUNTIME: &str = r#"
+function riven_list_index(list, key, line) {
+  if (typeof key !== "number") {
+    riven_error(line, "a list index must be a number");
+  }
+  const index = Math.trunc(key) | 0;
+  if (index < 0 || index >= list.length) {
+    riven_error(line, `index ${index} is out of range, the list has ${list.length} item(s)`);
+  }
+  return index;
+}
+
+function riven_get(target, key, line) {
+  if (Array.isArray(target)) {
+    return target[riven_list_index(target, key, line)];
+  }
+  if (target instanceof Map) {
+    if (!target.has(key)) {
+      riven_error(line, typeof key === "string" ? `key "${key}" is not in the map` : "key is not in the map");
+    }
+    return target.get(key);
+  }
+  riven_error(line, "only lists and maps can be indexed");
+}
+
+function riven_set(target, key, value, line) {
+  if (Array.isArray(target)) {
+    target[riven_list_index(target, key, line)] = value;
+  } else if (target instanceof Map) {
+    target.set(key, value);
+  } else {
+    riven_error(line, "only lists and maps can be indexed");
+  }
+}
+
+// the length of a text is its number of bytes, like strlen
+function riven_len(value) {
+  if (typeof value === "string") {
+    return new TextEncoder().encode(value).length;
+  }
+  return value instanceof Map ? value.size : value.length;
+}
+
+function riven_map_keys(map) {
+  return Array.from(map.keys());
+}
+
+function riven_list_append(list, value) {
+  list.push(value);
+}
+
+function riven_map_has(map, key) {
+  return map.has(key);
+}
+"#;
+
+// print and printf, numbers are printed like C's `%.15g`
+pub const JS_PRINT_RUNTIME: &str = r##"
+const riven_node = typeof process !== "undefined" && process.versions != null && process.versions.node != null;
+
+function riven_write(text) {
+  if (riven_node) {
+    process.stdout.write(text);
+  } else if (typeof document !== "undefined") {
+    let output = document.getElementById("riven-output");
+    if (output === null) {
+      output = document.createElement("pre");
+      output.id = "riven-output";
+      document.body.appendChild(output);
+    }
+    output.textContent += text;
+  } else {
+    console.log(text);
+  }
+}
+
+function riven_non_finite(value) {
+  return Number.isNaN(value) ? "nan" : value > 0 ? "inf" : "-inf";
+}
+
+function riven_strip_zeros(number) {
+  return number.indexOf(".") < 0 ? number : number.replace(/0+$/, "").replace(/\.$/, "");
+}
+
+// a finite value as mantissa * 2 ** exponent, both exact
+function riven_exact(value) {
+  const view = new DataView(new ArrayBuffer(8));
+  view.setFloat64(0, value);
+  const bits = view.getBigUint64(0);
+  const biased = Number((bits >> 52n) & 0x7ffn);
+  const fraction = bits & 0xfffffffffffffn;
+  return biased === 0 ? [fraction, -1074] : [fraction | (1n << 52n), biased - 1075];
+}
+
+// |value| * 10 ** shift rounded to an integer the way C does, half to even:
+// toFixed and toExponential round 2.25 up to 2.3 where printf gives 2.2
+function riven_round(value, shift) {
+  const [mantissa, exponent] = riven_exact(Math.abs(value));
+  let numerator = mantissa;
+  let denominator = 1n;
+  if (exponent > 0) {
+    numerator <<= BigInt(exponent);
+  } else {
+    denominator <<= BigInt(-exponent);
+  }
+  if (shift > 0) {
+    numerator *= 10n ** BigInt(shift);
+  } else {
+    denominator *= 10n ** BigInt(-shift);
+  }
+  const quotient = numerator / denominator;
+  const twice = (numerator % denominator) * 2n;
+  const odd = quotient % 2n === 1n;
+  return twice > denominator || (twice === denominator && odd) ? quotient + 1n : quotient;
+}
+
+// C's `%.<digits>f` of a finite value: 2.250 => 2.2 with one digit
+function riven_fixed(value, digits) {
+  const text = riven_round(value, digits).toString().padStart(digits + 1, "0");
+  const body = digits === 0 ? text : `${text.slice(0, -digits)}.${text.slice(-digits)}`;
+  return value < 0 ? `-${body}` : body;
+}
+
+// the digits of C's `%.<precision>e` of a finite value and its exponent: 1.500000 and 2
+function riven_exponential(value, precision) {
+  let exponent = Number(Math.abs(value).toExponential().split("e")[1]);
+  let digits = riven_round(value, precision - exponent).toString().padStart(precision + 1, "0");
+  // 9.96 rounds to 10.0
+  if (digits.length > precision + 1) {
+    exponent += 1;
+    digits = digits.slice(0, -1);
+  }
+  const body = precision === 0 ? digits : `${digits[0]}.${digits.slice(1)}`;
+  return [value < 0 ? `-${body}` : body, exponent];
+}
+
+// C's `%.<precision>e`: 1.500000e+02
+function riven_format_e(value, precision, alternate) {
+  if (!isFinite(value)) {
+    return riven_non_finite(value);
+  }
+  let [mantissa, exponent] = riven_exponential(value, precision);
+  if (alternate && precision === 0) {
+    mantissa += ".";
+  }
+  const digits = Math.abs(exponent);
+  return `${mantissa}e${exponent < 0 ? "-" : "+"}${digits < 10 ? "0" : ""}${digits}`;
+}
+
+// C's `%.<precision>g`: fixed notation unless the exponent is too small or too large
+function riven_format_g(value, precision, alternate) {
+  if (!isFinite(value)) {
+    return riven_non_finite(value);
+  }
+  precision = Math.max(precision, 1);
+  const exponent = riven_exponential(value, precision - 1)[1];
+  if (exponent < -4 || exponent >= precision) {
+    const formatted = riven_format_e(value, precision - 1, alternate);
+    const [mantissa, rest] = formatted.split("e");
+    return alternate ? formatted : `${riven_strip_zeros(mantissa)}e${rest}`;
+  }
+  const formatted = riven_fixed(value, precision - 1 - exponent);
+  if (alternate) {
+    return formatted.indexOf(".") < 0 ? `${formatted}.` : formatted;
+  }
+  return riven_strip_zeros(formatted);
+}
+
+// strings inside a list, map or struct are quoted, so ["1"] and [1] look different
+function riven_str(value, item = false) {
+  switch (typeof value) {
+    case "number":
+      return Object.is(value, -0) ? "-0" : riven_format_g(value, 15, false);
+    case "boolean":
+      return value ? "True" : "False";
+    case "string":
+      return item ? `"${value}"` : value;
+  }
+  if (Array.isArray(value)) {
+    return `[${value.map((v) => riven_str(v, true)).join(", ")}]`;
+  }
+  if (value instanceof Map) {
+    const entries = Array.from(value, ([k, v]) => `${riven_str(k, true)}: ${riven_str(v, true)}`);
+    return `{${entries.join(", ")}}`;
+  }
+  if (value instanceof RivenClosure) {
+    return `<function ${value.name}>`;
+  }
+  if (value instanceof RivenObject) {
+    const fields = Object.keys(value).map((field) => `${field}=${riven_str(value[field], true)}`);
+    return `${riven_struct_name(value)}(${fields.join(", ")})`;
+  }
+  return String(value);
+}
+
+function riven_print(...values) {
+  riven_write(values.map((value) => riven_str(value)).join(" "));
+}
+
+function riven_println(...values) {
+  riven_write(values.map((value) => riven_str(value)).join(" ") + "\n");
+}
+
+// C's printf: flags, width, precision and the d i u x X o c f e g s conversions
+function riven_printf(format, ...args) {
+  let next = 0;
+  const arg = () => args[next++];
+  const pattern = /%([-+ 0#]*)(\*|\d*)(?:\.(\*|\d*))?[hlLqjzt]*([diuxXocfFeEgGs%])/g;
+  const text = format.replace(pattern, (spec, flags, width, precision, conversion) => {
+    if (conversion === "%") {
+      return "%";
+    }
+    width = width === "*" ? arg() | 0 : Number(width);
+    let left = flags.indexOf("-") >= 0 || width < 0;
+    width = Math.abs(width);
+    precision = precision === undefined ? undefined : precision === "*" ? Math.max(arg() | 0, 0) : Number(precision);
+    const zero = flags.indexOf("0") >= 0 && !left;
+    const alternate = flags.indexOf("#") >= 0;
+    const sign = (negative) => negative ? "-" : flags.indexOf("+") >= 0 ? "+" : flags.indexOf(" ") >= 0 ? " " : "";
+    const pad = (prefix, body, zeros) => {
+      const fill = Math.max(width - prefix.length - body.length, 0);
+      if (left) {
+        return prefix + body + " ".repeat(fill);
+      }
+      return zeros ? prefix + "0".repeat(fill) + body : " ".repeat(fill) + prefix + body;
+    };
+    const digits = (number) => {
+      if (precision === 0 && number === "0") {
+        return "";
+      }
+      return precision !== undefined && precision > number.length ? "0".repeat(precision - number.length) + number : number;
+    };
+    const value = arg();
+    switch (conversion) {
+      case "d":
+      case "i": {
+        const number = Math.trunc(+value) | 0;
+        return pad(sign(number < 0), digits(String(Math.abs(number))), zero && precision === undefined);
+      }
+      case "u":
+      case "x":
+      case "X":
+      case "o": {
+        const number = (Math.trunc(+value) | 0) >>> 0;
+        const base = { u: 10, x: 16, X: 16, o: 8 }[conversion];
+        let body = digits(number.toString(base));
+        if (conversion === "X") {
+          body = body.toUpperCase();
+        }
+        let prefix = "";
+        if (alternate && number !== 0 && conversion !== "u" && conversion !== "o") {
+          prefix = conversion === "x" ? "0x" : "0X";
+        } else if (alternate && conversion === "o" && body[0] !== "0") {
+          prefix = "0";
+        }
+        return pad(prefix, body, zero && precision === undefined);
+      }
+      case "c":
+        return pad("", String.fromCharCode((Math.trunc(+value) | 0) & 255), false);
+      case "s": {
+        const body = riven_str(value);
+        return pad("", precision === undefined ? body : body.slice(0, precision), false);
+      }
+      default: {
+        const number = +value;
+        const lower = conversion.toLowerCase();
+        const digits = precision === undefined ? 6 : precision;
+        let body;
+        if (lower === "e") {
+          body = riven_format_e(Math.abs(number), digits, alternate);
+        } else if (lower === "g") {
+          body = riven_format_g(Math.abs(number), digits, alternate);
+        } else if (!isFinite(number)) {
+          body = riven_non_finite(Math.abs(number));
+        } else {
+          body = riven_fixed(Math.abs(number), digits) + (alternate && digits === 0 ? "." : "");
+        }
+        if (conversion !== lower) {
+          body = body.toUpperCase();
+        }
+        const negative = number < 0 || Object.is(number, -0);
+        return pad(sign(negative), body, zero && isFinite(number));
+      }
+    }
+  });
+  riven_write(text);
+  return text.length;
+}
+"##;
+
+// input reads stdin in node, and asks with `prompt` in a browser
+pub const JS_INPUT_RUNTIME: &str = r#"
+// the line typed, and whether the input ended with it
+function riven_read_line(prompt) {
+  if (!riven_node) {
+    const line = window.prompt(prompt);
+    riven_write(line === null ? "\n" : `${line}\n`);
+    return { line: line === null ? "" : line, ended: line === null };
+  }
+  const fs = require("fs");
+  const bytes = [];
+  const buffer = Buffer.alloc(1);
+  for (;;) {
+    let count;
+    try {
+      count = fs.readSync(0, buffer, 0, 1, null);
+    } catch (error) {
+      if (error.code === "EAGAIN") {
+        continue;
+      }
+      if (error.code !== "EOF") {
+        throw error;
+      }
+      count = 0;
+    }
+    if (count === 0 || buffer[0] === 10) {
+      return { line: Buffer.from(bytes).toString(), ended: count === 0 };
+    }
+    if (buffer[0] !== 13) {
+      bytes.push(buffer[0]);
+    }
+  }
+}
+
+function riven_input(prompt) {
+  riven_write(prompt);
+  return riven_read_line(prompt).line;
+}
+
+// asks again until a number is typed
+function riven_input_number(prompt, line) {
+  for (;;) {
+    riven_write(prompt);
+    const { line: typed, ended } = riven_read_line(prompt);
+    const text = typed.replace(/^\s+/, "").replace(/[ \t]+$/, "");
+    const value = text === "" ? NaN : Number(text);
+    if (!Number.isNaN(value)) {
+      return value;
+    }
+    if (ended) {
+      riven_error(line, "no more input, expected a number");
+    }
+    riven_write("that is not a number, please try again\n");
+  }
+}
+
+// run init, the statements outside functions, then main: what main returns is the exit code;
+// a runtime error stops the program with its line, like the C runtime reports it
+function riven_run(main, init) {
+  let code = 0;
+  try {
+    if (init) {
+      init();
+    }
+    const returned = main();
+    code = typeof returned === "number" ? Math.trunc(returned) | 0 : 0;
+  } catch (error) {
+    if (!(error instanceof RivenError)) {
+      throw error;
+    }
+    const message = `runtime error at line ${error.line}: ${error.message}`;
+    if (riven_node) {
+      process.stderr.write(`${message}\n`);
+    } else {
+      riven_write(`${message}\n`);
+    }
+    code = 1;
+  }
+  if (riven_node) {
+    process.exitCode = code;
+  }
+}
+"#;
@@ -1 +1,10 @@
+pub mod asm_codegen;
 pub mod codegen;
+pub mod js_codegen;
+pub mod js_runtime;
+pub mod llvm_codegen;
+pub mod py_codegen;
+pub mod py_runtime;
+pub mod runtime;
+mod script_codegen;
+pub mod wat_codegen;
@@ -0,0 +1,909 @@
+use analyzer::infer::{child_path, infer, nested_functions, statements, Passed, TypeInfo};
+use analyzer::locals::{plan_declarations, Declarations};
+use analyzer::operands::Operation;
+use analyzer::types::Type;
+use parser::ast::{
+  ArithmeticExpr, AssignStmt, BinOp, CallExpr, CompareExpr, CompareOp, ComponentArithmeticExpr,
+  ComponentFactor, ComponentTerm, Expression, ExpressionValue, Factor, FactorValue,
+  FieldAssignStmt, FieldExpr, FunctionStmt, Identifier, IfStmt, IndexAssignStmt, IndexExpr,
+  ListExpr, LogicExpr, LogicOp, MapExpr, Primary, PrimaryValue, Program, ReturnStmt, Statement,
+  StatementValue, StmtList, StructStmt, Term, WhileStmt,
+};
+use parser::location::Location;
+
+use crate::codegen::{c_function_name, CodeGenerator, Emitter};
+use crate::wat_codegen::{assigned_variables, string_literal};
+
+// what the generated module needs from the C library, pointers are opaque
+// (`ptr`) like in LLVM 15 and later
+pub const LLVM_DECLARATIONS: &str = "\
+declare i32 @printf(ptr, ...)
+declare i32 @dprintf(i32, ptr, ...)
+declare i32 @fflush(ptr)
+declare void @exit(i32)";
+
+// the LLVM type of a value: ints are i32, floats double and bools i1, a type
+// LLVM IR has no value for here is None
+fn llvm_type(t: &Type) -> Option<&'static str> {
+  match t {
+    Type::Int | Type::Unknown => Some("i32"),
+    Type::Float => Some("double"),
+    Type::Bool => Some("i1"),
+    _ => None,
+  }
+}
+
+fn llvm_zero(t: &str) -> &'static str {
+  match t {
+    "double" => "0.0",
+    "i1" => "false",
+    _ => "0",
+  }
+}
+
+// a double constant, LLVM only takes decimals it can store exactly, others
+// are written as the hexadecimal bits of the double
+fn llvm_double(n: f64) -> String {
+  let exact = n.is_finite() && n.abs() < 1e15 && (n * 1024.0).fract() == 0.0;
+  if !exact {
+    return format!("0x{:016X}", n.to_bits());
+  }
+  let decimal = format!("{:.10}", n);
+  let decimal = decimal.trim_end_matches('0');
+  match decimal.ends_with('.') {
+    true => format!("{}0", decimal),
+    false => decimal.to_owned(),
+  }
+}
+
+// `c"a\0A\00"`, the bytes of a text ending with a NUL, every byte that is
+// not plain ASCII is escaped
+fn llvm_string(bytes: &[u8]) -> String {
+  let mut text = String::from("c\"");
+  for byte in bytes.iter().chain(&[0]) {
+    match byte {
+      b'"' | b'\\' => text.push_str(&format!("\\{:02X}", byte)),
+      0x20..=0x7e => text.push(*byte as char),
+      _ => text.push_str(&format!("\\{:02X}", byte)),
+    }
+  }
+  text.push('"');
+  text
+}
+
+pub struct LlvmCodeGen<'a> {
+  emitter: &'a mut Emitter,
+  // the value of the expression being generated and its LLVM type, a constant
+  // or a register; the instructions computing it are already emitted
+  value: String,
+  value_type: &'static str,
+  // the statement and function being generated and what visit_program found,
+  // as in CCodeGenManager
+  line: usize,
+  location: Location,
+  types: TypeInfo,
+  path: String,
+  declarations: Declarations,
+  // registers and labels of the function being generated are numbered
+  registers: usize,
+  labels: usize,
+  // the basic block instructions are emitted to, and whether it already ended
+  // with a `br` or a `ret`
+  block: String,
+  terminated: bool,
+  // the texts of the program, each is a global constant `@.str.<index>`
+  strings: Vec<Vec<u8>>,
+  // what the program uses that LLVM IR cannot express here, see unsupported()
+  unsupported: Vec<String>,
+  // whether main first calls @riven_init, the statements outside functions
+  has_init: bool,
+}
+
+impl<'a> LlvmCodeGen<'a> {
+  pub fn new(emitter: &'a mut Emitter) -> Self {
+    emitter.push_header("; generated by riven");
+    emitter.push_header(LLVM_DECLARATIONS);
+
+    LlvmCodeGen {
+      emitter,
+      value: String::new(),
+      value_type: "i32",
+      line: 0,
+      location: Location::default(),
+      types: TypeInfo::default(),
+      path: String::new(),
+      declarations: Declarations::default(),
+      registers: 0,
+      labels: 0,
+      block: String::new(),
+      terminated: false,
+      strings: vec![],
+      unsupported: vec![],
+      has_init: false,
+    }
+  }
+
+  // `line 3: lists are not supported by the llvm target`, for every use of
+  // something only the C target has: texts other than printed ones, lists,
+  // maps, structs and functions used as values
+  pub fn unsupported(&self) -> &[String] {
+    &self.unsupported
+  }
+
+  // the first thing LLVM IR cannot express on each line
+  fn report(&mut self, what: &str) {
+    let prefix = format!("line {}: ", self.line);
+    if !self
+      .unsupported
+      .iter()
+      .any(|message| message.starts_with(&prefix))
+    {
+      let message = format!("{}{} not supported by the llvm target", prefix, what);
+      self.unsupported.push(message);
+    }
+  }
+
+  // report what the expression uses, its value is never computed
+  fn unsupport(&mut self, what: &str) {
+    self.report(what);
+    self.push("undef", "i32");
+  }
+
+  fn push(&mut self, value: &str, value_type: &'static str) {
+    self.value = value.to_owned();
+    self.value_type = value_type;
+  }
+
+  // an instruction of the current block; the code following a `return` is
+  // never run, it still needs a block of its own
+  fn emit(&mut self, instruction: &str) {
+    if self.terminated {
+      let label = self.label("dead");
+      self.start_block(&label);
+    }
+    self.emitter.emmit(format!("  {}\n", instruction).as_str());
+  }
+
+  // an instruction giving a value, in the next register
+  fn assign(&mut self, instruction: &str) -> String {
+    if self.terminated {
+      let label = self.label("dead");
+      self.start_block(&label);
+    }
+    let register = format!("%{}", self.registers);
+    self.registers += 1;
+    self.emit(&format!("{} = {}", register, instruction));
+    register
+  }
+
+  // a `br` or `ret` ending the current block
+  fn terminate(&mut self, instruction: &str) {
+    self.emit(instruction);
+    self.terminated = true;
+  }
+
+  fn start_block(&mut self, label: &str) {
+    self.emitter.emmit(format!("{}:\n", label).as_str());
+    self.block = label.to_owned();
+    self.terminated = false;
+  }
+
+  // a new label like `if3`, the blocks of one statement share its number
+  fn label(&mut self, name: &str) -> String {
+    self.labels += 1;
+    format!("{}{}", name, self.labels)
+  }
+
+  // the global constant holding a text, each text is stored once
+  fn string(&mut self, text: &str) -> String {
+    let bytes = text.as_bytes().to_vec();
+    let index = match self.strings.iter().position(|string| *string == bytes) {
+      Some(index) => index,
+      None => {
+        self.strings.push(bytes);
+        self.strings.len() - 1
+      }
+    };
+    format!("@.str.{}", index)
+  }
+
+  // where a variable is stored: the alloca of a local, or the global of a
+  // variable assigned outside functions
+  fn address(&self, name: &str) -> String {
+    match self.types.resolve_variable(&self.path, name).as_deref() {
+      Some("") => format!("@global.{}", name),
+      _ => format!("%{}.addr", name),
+    }
+  }
+
+  // the type of a variable, the values LLVM has no type for are reported where they are made
+  fn variable_type(&self, name: &str) -> &'static str {
+    llvm_type(&self.types.variable_type(&self.path, name)).unwrap_or("i32")
+  }
+
+  // generate an expression on its own and give back its value and type
+  fn generate(&mut self, visit: impl FnOnce(&mut Self)) -> (String, &'static str) {
+    visit(self);
+    (std::mem::take(&mut self.value), self.value_type)
+  }
+
+  fn expr_value(&mut self, expr: &Expression) -> (String, &'static str) {
+    self.generate(|gen| gen.visit_expr(expr))
+  }
+
+  // an expression where a value of type `expected` is wanted
+  fn expr_value_as(&mut self, expr: &Expression, expected: &'static str) -> String {
+    let (value, t) = self.expr_value(expr);
+    self.convert(value, t, expected)
+  }
+
+  // convert a value of type `from` to type `to`, a double stored into an int
+  // loses its fraction like in C
+  fn convert(&mut self, value: String, from: &str, to: &str) -> String {
+    match (from, to) {
+      ("i32", "double") => self.assign(&format!("sitofp i32 {} to double", value)),
+      ("i1", "double") => self.assign(&format!("uitofp i1 {} to double", value)),
+      ("double", "i32") => self.assign(&format!("fptosi double {} to i32", value)),
+      ("i1", "i32") => self.assign(&format!("zext i1 {} to i32", value)),
+      ("i32", "i1") => self.assign(&format!("icmp ne i32 {}, 0", value)),
+      ("double", "i1") => self.assign(&format!("fcmp une double {}, 0.0", value)),
+      _ => value,
+    }
+  }
+
+  fn arithmetic(&mut self, operation: &Operation) -> (String, &'static str) {
+    match operation {
+      Operation::Factor(factor) => self.generate(|gen| gen.visit_factor(factor)),
+      Operation::Group(inner) => self.arithmetic(inner),
+      Operation::Binary(left, op, right) => {
+        let left = self.arithmetic(left);
+        let right = self.arithmetic(right);
+        self.combine(left, op, right)
+      }
+    }
+  }
+
+  // i32 wraps around like a C int, `7 / 2` is 3 and dividing by zero stops the program
+  fn combine(
+    &mut self,
+    (a, a_type): (String, &'static str),
+    op: &BinOp,
+    (b, b_type): (String, &'static str),
+  ) -> (String, &'static str) {
+    let t = if a_type == "double" || b_type == "double" {
+      "double"
+    } else {
+      "i32"
+    };
+    let (a, b) = (self.convert(a, a_type, t), self.convert(b, b_type, t));
+    let instruction = match (op, t) {
+      (BinOp::Add, "i32") => "add",
+      (BinOp::Min, "i32") => "sub",
+      (BinOp::Time, "i32") => "mul",
+      (BinOp::Div, "i32") => {
+        let call = format!(
+          "call i32 @riven_idiv(i32 {}, i32 {}, i32 {})",
+          a, b, self.line
+        );
+        return (self.assign(&call), t);
+      }
+      (BinOp::Add, _) => "fadd",
+      (BinOp::Min, _) => "fsub",
+      (BinOp::Time, _) => "fmul",
+      (BinOp::Div, _) => "fdiv",
+    };
+    let value = self.assign(&format!("{} {} {}, {}", instruction, t, a, b));
+    (value, t)
+  }
+
+  // a value used as a condition, 0 is false
+  fn condition_value(&mut self, logic_expr: &LogicExpr) -> String {
+    let (value, t) = self.generate(|gen| gen.visit_logic_expr(logic_expr));
+    self.convert(value, t, "i1")
+  }
+
+  // print(a, b) => printf("%d %d"), one format made of the values and the texts between them
+  fn print_call(&mut self, call_expr: &CallExpr, newline: bool) {
+    let mut format = String::new();
+    let mut args = vec![];
+    for (idx, arg) in call_expr.1 .0.iter().enumerate() {
+      if idx > 0 {
+        format.push(' ');
+      }
+      if let Some(text) = string_literal(arg) {
+        format.push_str(&text.replace('%', "%%"));
+        continue;
+      }
+      let t = self.types.type_of(&self.path, arg);
+      match t {
+        Type::Bool => {
+          let value = self.expr_value_as(arg, "i1");
+          let (yes, no) = (self.string("True"), self.string("False"));
+          let text = self.assign(&format!("select i1 {}, ptr {}, ptr {}", value, yes, no));
+          format.push_str("%s");
+          args.push(format!("ptr {}", text));
+        }
+        Type::Float => {
+          let value = self.expr_value_as(arg, "double");
+          format.push_str("%.15g");
+          args.push(format!("double {}", value));
+        }
+        Type::Int | Type::Unknown => {
+          let value = self.expr_value_as(arg, "i32");
+          format.push_str("%d");
+          args.push(format!("i32 {}", value));
+        }
+        _ => return self.unsupport(format!("printing {} is", t).as_str()),
+      }
+    }
+    if newline {
+      format.push('\n');
+    }
+    self.printf(&format, args);
+  }
+
+  // printf("%d %f\n", n, x), ints and doubles are passed like in C
+  fn printf_call(&mut self, call_expr: &CallExpr) {
+    let args = &call_expr.1 .0;
+    let Some(format) = args.first().and_then(string_literal) else {
+      return self.unsupport("a printf format that is not a text literal is");
+    };
+    let mut values = vec![];
+    for arg in &args[1..] {
+      let (value, t) = self.expr_value(arg);
+      match t {
+        "double" => values.push(format!("double {}", value)),
+        _ if self.types.type_of(&self.path, arg) == Type::Str => {
+          return self.unsupport("printing a text with printf is")
+        }
+        _ => {
+          let value = self.convert(value, t, "i32");
+          values.push(format!("i32 {}", value));
+        }
+      }
+    }
+    self.printf(&format, values);
+  }
+
+  fn printf(&mut self, format: &str, args: Vec<String>) {
+    let mut args = args;
+    args.insert(0, format!("ptr {}", self.string(format)));
+    let call = format!("call i32 (ptr, ...) @printf({})", args.join(", "));
+    let value = self.assign(&call);
+    self.push(&value, "i32");
+  }
+
+  // a variable read before its first assignment is zero, like the C target
+  // declares it at the top of the block
+  fn visit_hoisted(&mut self, block: &Location) {
+    for name in self.declarations.hoisted(block).to_vec() {
+      let t = self.variable_type(&name);
+      let address = self.address(&name);
+      self.emit(&format!("store {} {}, ptr {}", t, llvm_zero(t), address));
+    }
+  }
+
+  // @riven_init runs the statements outside functions, main calls it first
+  fn visit_init(&mut self, stmt_list: &StmtList) {
+    self.registers = 0;
+    self.labels = 0;
+    self
+      .emitter
+      .emmit("\ndefine internal void @riven_init() {\n");
+    self.start_block("entry");
+    for stmt in statements(stmt_list) {
+      if !matches!(
+        stmt.0,
+        StatementValue::FunctionStmt(_) | StatementValue::StructStmt(_)
+      ) {
+        self.visit_stmt(stmt);
+      }
+    }
+    if !self.terminated {
+      self.terminate("ret void");
+    }
+    self.emitter.emmit("}\n");
+  }
+
+  fn visit_nested_functions(&mut self, stmt_list: &StmtList) {
+    for (function_stmt, location) in nested_functions(stmt_list) {
+      self.line = location.row();
+      self.location = location.clone();
+      self.visit_function_stmt(function_stmt);
+    }
+  }
+
+  // C int division: dividing by zero is a runtime error, and the smallest int
+  // divided by -1 wraps around instead of trapping
+  fn emit_idiv(&mut self) {
+    let error = self.string("runtime error at line %d: %s\n");
+    let message = self.string("division by zero");
+    self.emitter.emmit("\n");
+    for line in [
+      "define internal i32 @riven_idiv(i32 %a, i32 %b, i32 %line) {",
+      "entry:",
+      "  %zero = icmp eq i32 %b, 0",
+      "  br i1 %zero, label %error, label %check",
+      "error:",
+      "  %flushed = call i32 @fflush(ptr null)",
+      &format!(
+        "  %printed = call i32 (i32, ptr, ...) @dprintf(i32 2, ptr {}, i32 %line, ptr {})",
+        error, message
+      ),
+      "  call void @exit(i32 1)",
+      "  unreachable",
+      "check:",
+      "  %minus_one = icmp eq i32 %b, -1",
+      "  br i1 %minus_one, label %negate, label %divide",
+      "negate:",
+      "  %negated = sub i32 0, %a",
+      "  ret i32 %negated",
+      "divide:",
+      "  %quotient = sdiv i32 %a, %b",
+      "  ret i32 %quotient",
+      "}",
+    ] {
+      self.emitter.emmit(format!("{}\n", line).as_str());
+    }
+  }
+}
+
+impl<'a> CodeGenerator for LlvmCodeGen<'a> {
+  fn visit_primary(&mut self, primary: &Primary) {
+    match &primary.0 {
+      PrimaryValue::String(_) => self.unsupport("texts other than printed ones are"),
+      // a C int literal
+      PrimaryValue::Number(n) => self.push(&format!("{}", *n as i32), "i32"),
+      PrimaryValue::Float(n) => self.push(&llvm_double(*n), "double"),
+      PrimaryValue::Bool(b) => self.push(&format!("{}", b), "i1"),
+    }
+  }
+
+  fn visit_identifier(&mut self, identifier: &Identifier) {
+    let name = identifier.to_string();
+    let t = self.variable_type(&name);
+    let address = self.address(&name);
+    let value = self.assign(&format!("load {}, ptr {}", t, address));
+    self.push(&value, t);
+  }
+
+  fn visit_factor(&mut self, factor: &Factor) {
+    match &factor.0 {
+      FactorValue::Primary(primary) => self.visit_primary(primary),
+      FactorValue::Identifier(identifier) => {
+        match self
+          .types
+          .resolve_variable(&self.path, &identifier.to_string())
+        {
+          Some(_) => self.visit_identifier(identifier),
+          None => self.unsupport("functions used as values are"),
+        }
+      }
+      FactorValue::Function(_, _) => self.unsupport("functions used as values are"),
+      FactorValue::CallExpr(call_expr) => self.visit_call_expr(call_expr),
+      FactorValue::List(list_expr) => self.visit_list_expr(list_expr),
+      FactorValue::Map(map_expr) => self.visit_map_expr(map_expr),
+      FactorValue::Index(index_expr) => self.visit_index_expr(index_expr),
+      FactorValue::Field(field_expr) => self.visit_field_expr(field_expr),
+      FactorValue::Apply(_) => self.unsupport("functions used as values are"),
+    }
+  }
+
+  fn visit_term(&mut self, term: &Term) {
+    let (value, t) = self.arithmetic(&Operation::of_term(term));
+    self.push(&value, t);
+  }
+
+  fn visit_arithmetic_expr(&mut self, arithmetic_expr: &ArithmeticExpr) {
+    let (value, t) = self.arithmetic(&Operation::of_arithmetic_expr(arithmetic_expr));
+    self.push(&value, t);
+  }
+
+  fn visit_call_expr(&mut self, call_expr: &CallExpr) {
+    let name = call_expr.0.to_string();
+    let is_builtin = self.types.resolve_variable(&self.path, &name).is_none()
+      && self.types.resolve_function(&self.path, &name).is_none();
+    match name.as_str() {
+      "print" | "println" if is_builtin => return self.print_call(call_expr, name == "println"),
+      "printf" if is_builtin => return self.printf_call(call_expr),
+      _ => (),
+    }
+
+    if self.types.resolve_variable(&self.path, &name).is_some() {
+      return self.unsupport("functions used as values are");
+    }
+    if self.types.is_struct(&name) {
+      return self.unsupport("structs are");
+    }
+    let Some(function) = self.types.resolve_function(&self.path, &name) else {
+      return self.unsupport(format!("the builtin `{}` is", name).as_str());
+    };
+
+    let mut args = vec![];
+    for passed in self.types.passed(&function, &call_expr.1 .0) {
+      let (t, value) = match passed {
+        Passed::Capture(name) => {
+          let t = self.variable_type(&name);
+          (t, self.assign(&format!("load {}, ptr %{}.addr", t, name)))
+        }
+        Passed::Arg(arg, param_type) => {
+          let t = llvm_type(&param_type).unwrap_or("i32");
+          (t, self.expr_value_as(arg, t))
+        }
+      };
+      args.push(format!("{} {}", t, value));
+    }
+    let call = format!("@{}({})", c_function_name(&function), args.join(", "));
+    match self.types.return_type(&function) {
+      Type::Void => {
+        self.emit(&format!("call void {}", call));
+        self.push("", "");
+      }
+      t => {
+        let t = llvm_type(&t).unwrap_or("i32");
+        let value = self.assign(&format!("call {} {}", t, call));
+        self.push(&value, t);
+      }
+    }
+  }
+
+  fn visit_list_expr(&mut self, _list_expr: &ListExpr) {
+    self.unsupport("lists are");
+  }
+
+  fn visit_map_expr(&mut self, _map_expr: &MapExpr) {
+    self.unsupport("maps are");
+  }
+
+  fn visit_index_expr(&mut self, _index_expr: &IndexExpr) {
+    self.unsupport("lists and maps are");
+  }
+
+  fn visit_field_expr(&mut self, _field_expr: &FieldExpr) {
+    self.unsupport("structs are");
+  }
+
+  // the value of a call statement is not used
+  fn visit_call_stmt(&mut self, call_expr: &CallExpr) {
+    self.visit_call_expr(call_expr);
+    self.value.clear();
+  }
+
+  fn visit_expr(&mut self, expr: &Expression) {
+    match &expr.0 {
+      ExpressionValue::ComponentArithmeticExpr(arith_expr) => {
+        self.visit_component_arithmetic_expr(arith_expr)
+      }
+    }
+  }
+
+  fn visit_compare_expr(&mut self, compare_expr: &CompareExpr) {
+    let (left, left_type) = self.expr_value(&compare_expr.0);
+    let (op, right) = match (&compare_expr.1, &compare_expr.2) {
+      (Some(op), Some(right)) => (op, right),
+      // a value used as a condition
+      _ => return self.push(&left, left_type),
+    };
+    let (right, right_type) = self.expr_value(right);
+    let t = match (left_type, right_type) {
+      ("double", _) | (_, "double") => "double",
+      ("i1", "i1") => "i1",
+      _ => "i32",
+    };
+    let (left, right) = (
+      self.convert(left, left_type, t),
+      self.convert(right, right_type, t),
+    );
+    let (instruction, op) = match (op, t) {
+      (CompareOp::Eq, "double") => ("fcmp", "oeq"),
+      (CompareOp::Gt, "double") => ("fcmp", "ogt"),
+      (CompareOp::Lt, "double") => ("fcmp", "olt"),
+      (CompareOp::Eq, _) => ("icmp", "eq"),
+      (CompareOp::Gt, _) => ("icmp", "sgt"),
+      (CompareOp::Lt, _) => ("icmp", "slt"),
+    };
+    let value = self.assign(&format!("{} {} {} {}, {}", instruction, op, t, left, right));
+    self.push(&value, "i1");
+  }
+
+  // `and` and `or` only compute their second condition when it is needed:
+  // a phi takes the first condition's answer, or the second condition
+  fn visit_logic_expr(&mut self, logic_expr: &LogicExpr) {
+    let (name, short_circuit) = match logic_expr.0 {
+      LogicOp::Bool => {
+        return match &logic_expr.1 {
+          Some(compare_expr) => self.visit_compare_expr(compare_expr),
+          None => self.push("false", "i1"),
+        };
+      }
+      LogicOp::Not => {
+        let value = match &logic_expr.2 {
+          Some(expr) => self.condition_value(expr),
+          None => "false".to_owned(),
+        };
+        let value = self.assign(&format!("xor i1 {}, true", value));
+        return self.push(&value, "i1");
+      }
+      LogicOp::And => ("and", "false"),
+      LogicOp::Or => ("or", "true"),
+    };
+
+    let label = self.label(name);
+    let (rhs, end) = (format!("{}.rhs", label), format!("{}.end", label));
+    let first = match &logic_expr.2 {
+      Some(expr) => self.condition_value(expr),
+      None => "false".to_owned(),
+    };
+    let first_block = self.block.clone();
+    match logic_expr.0 {
+      LogicOp::And => self.terminate(&format!("br i1 {}, label %{}, label %{}", first, rhs, end)),
+      _ => self.terminate(&format!("br i1 {}, label %{}, label %{}", first, end, rhs)),
+    }
+    self.start_block(&rhs);
+    let second = match &logic_expr.3 {
+      Some(expr) => self.condition_value(expr),
+      None => "false".to_owned(),
+    };
+    let second_block = self.block.clone();
+    self.terminate(&format!("br label %{}", end));
+    self.start_block(&end);
+    let value = self.assign(&format!(
+      "phi i1 [ {}, %{} ], [ {}, %{} ]",
+      short_circuit, first_block, second, second_block
+    ));
+    self.push(&value, "i1");
+  }
+
+  fn visit_assign_stmt(&mut self, stmt: &AssignStmt) {
+    let name = stmt.0.to_string();
+    let t = self.variable_type(&name);
+    let value = self.expr_value_as(&stmt.1, t);
+    let address = self.address(&name);
+    self.emit(&format!("store {} {}, ptr {}", t, value, address));
+  }
+
+  fn visit_index_assign_stmt(&mut self, _stmt: &IndexAssignStmt) {
+    self.report("lists and maps are");
+  }
+
+  fn visit_field_assign_stmt(&mut self, _stmt: &FieldAssignStmt) {
+    self.report("structs are");
+  }
+
+  // main gives back the exit code as an i32 like in C
+  fn visit_return_stmt(&mut self, stmt: &ReturnStmt) {
+    let return_type = match self.path.as_str() {
+      "main" => Some("i32"),
+      _ => llvm_type(&self.types.return_type(&self.path)),
+    };
+    match return_type {
+      Some(t) => {
+        let value = self.expr_value_as(&stmt.0, t);
+        self.terminate(&format!("ret {} {}", t, value));
+      }
+      None => self.terminate("ret void"),
+    }
+  }
+
+  // if (c) { ... } =>
+  //   br i1 %c, label %if1.then, label %if1.end  if1.then: ... br label %if1.end  if1.end:
+  fn visit_if_stmt(&mut self, stmt: &IfStmt) {
+    let block = self.location.clone();
+    let label = self.label("if");
+    let (then, end) = (format!("{}.then", label), format!("{}.end", label));
+    let condition = self.condition_value(&stmt.0);
+    self.terminate(&format!(
+      "br i1 {}, label %{}, label %{}",
+      condition, then, end
+    ));
+    self.start_block(&then);
+    self.visit_hoisted(&block);
+    if let Some(body) = &stmt.1 {
+      self.visit_stmt_list(body);
+    }
+    if !self.terminated {
+      self.terminate(&format!("br label %{}", end));
+    }
+    self.start_block(&end);
+  }
+
+  // the condition has a block of its own, the end of the body jumps back to it
+  fn visit_while_stmt(&mut self, stmt: &WhileStmt) {
+    let block = self.location.clone();
+    let label = self.label("while");
+    let (cond, body, end) = (
+      format!("{}.cond", label),
+      format!("{}.body", label),
+      format!("{}.end", label),
+    );
+    self.terminate(&format!("br label %{}", cond));
+    self.start_block(&cond);
+    let condition = self.condition_value(&stmt.0);
+    self.terminate(&format!(
+      "br i1 {}, label %{}, label %{}",
+      condition, body, end
+    ));
+    self.start_block(&body);
+    self.visit_hoisted(&block);
+    if let Some(stmt_list) = &stmt.1 {
+      self.visit_stmt_list(stmt_list);
+    }
+    if !self.terminated {
+      self.terminate(&format!("br label %{}", cond));
+    }
+    self.start_block(&end);
+  }
+
+  // functions are lifted to the module like in C, the functions nested in
+  // them follow them. Every variable
+  // lives in an alloca, `opt -passes=mem2reg` turns them into registers
+  fn visit_function_stmt(&mut self, stmt: &FunctionStmt) {
+    let block = self.location.clone();
+    let function = child_path(&self.path, &stmt.0.to_string());
+    let outer = std::mem::replace(&mut self.path, function);
+    self.registers = 0;
+    self.labels = 0;
+
+    let params = self.types.params(&self.path);
+    let return_type = match (self.path.as_str(), self.types.return_type(&self.path)) {
+      ("main", _) => "i32",
+      (_, Type::Void) => "void",
+      (_, t) => llvm_type(&t).unwrap_or("i32"),
+    };
+    let signature: Vec<String> = params
+      .iter()
+      .map(|param| format!("{} %{}", self.variable_type(param), param))
+      .collect();
+    self.emitter.emmit("\n");
+    self.emitter.emmit(
+      format!(
+        "define {} @{}({}) {{\n",
+        return_type,
+        c_function_name(&self.path),
+        signature.join(", ")
+      )
+      .as_str(),
+    );
+    self.start_block("entry");
+
+    let mut locals = vec![];
+    if let Some(body) = &stmt.2 {
+      assigned_variables(body, &mut locals);
+    }
+    locals.retain(|name| !params.contains(name));
+    for name in params.iter().chain(&locals) {
+      let t = self.variable_type(name);
+      self.emit(&format!("%{}.addr = alloca {}", name, t));
+    }
+    for name in &params {
+      let t = self.variable_type(name);
+      self.emit(&format!("store {} %{}, ptr %{}.addr", t, name, name));
+    }
+    self.visit_hoisted(&block);
+    if self.path == "main" && self.has_init {
+      self.emit("call void @riven_init()");
+    }
+
+    if let Some(body) = &stmt.2 {
+      self.visit_stmt_list(body);
+    }
+    // the end of the body is reachable, see flow::default_return
+    if !self.terminated {
+      match return_type {
+        "void" => self.terminate("ret void"),
+        t => self.terminate(&format!("ret {} {}", t, llvm_zero(t))),
+      }
+    }
+    self.emitter.emmit("}\n");
+
+    if let Some(stmt_list) = &stmt.2 {
+      self.visit_nested_functions(stmt_list);
+    }
+    self.path = outer;
+  }
+
+  fn visit_struct_stmt(&mut self, _stmt: &StructStmt) {
+    self.report("structs are");
+  }
+
+  fn visit_stmt(&mut self, stmt: &Statement) {
+    self.line = stmt.location().row();
+    self.location = stmt.location().clone();
+    match &stmt.0 {
+      StatementValue::AssignStmt(stmt) => self.visit_assign_stmt(stmt),
+      StatementValue::IndexAssignStmt(stmt) => self.visit_index_assign_stmt(stmt),
+      StatementValue::FieldAssignStmt(stmt) => self.visit_field_assign_stmt(stmt),
+      StatementValue::CallStmt(stmt) => self.visit_call_stmt(stmt),
+      StatementValue::ApplyStmt(_) => self.report("functions used as values are"),
+      // emitted by visit_nested_functions
+      StatementValue::FunctionStmt(_) => (),
+      StatementValue::StructStmt(stmt) => self.visit_struct_stmt(stmt),
+      StatementValue::IfStmt(stmt) => self.visit_if_stmt(stmt),
+      StatementValue::WhileStmt(stmt) => self.visit_while_stmt(stmt),
+      StatementValue::ReturnStmt(stmt) => self.visit_return_stmt(stmt),
+    }
+  }
+
+  fn visit_stmt_list(&mut self, stmt_list: &StmtList) {
+    if let Some(stmt) = &stmt_list.0 {
+      self.visit_stmt(stmt);
+    }
+
+    if let Some(rests_stmts) = &stmt_list.1 {
+      self.visit_stmt_list(rests_stmts);
+    }
+  }
+
+  fn visit_program(&mut self, program: &Program) {
+    self.types = infer(program).types;
+    self.declarations = plan_declarations(program);
+
+    self.emit_idiv();
+    for stmt in statements(&program.0) {
+      if let StatementValue::StructStmt(_) = stmt.0 {
+        self.visit_stmt(stmt);
+      }
+    }
+    self.has_init = statements(&program.0).iter().any(|stmt| {
+      !matches!(
+        stmt.0,
+        StatementValue::FunctionStmt(_) | StatementValue::StructStmt(_)
+      )
+    });
+    if self.has_init {
+      self.visit_init(&program.0);
+    }
+    self.visit_nested_functions(&program.0);
+
+    // the variables assigned outside functions, zero until then
+    let mut globals = vec![];
+    assigned_variables(&program.0, &mut globals);
+    if !globals.is_empty() {
+      let globals: Vec<String> = globals
+        .iter()
+        .map(|name| {
+          let t = self.variable_type(name);
+          format!(
+            "@global.{} = internal global {} {}\n",
+            name,
+            t,
+            llvm_zero(t)
+          )
+        })
+        .collect();
+      self.emitter.push_tail(&globals.concat());
+    }
+
+    // the texts, after the functions using them
+    let mut strings: Vec<String> = self
+      .strings
+      .iter()
+      .enumerate()
+      .map(|(idx, bytes)| {
+        format!(
+          "@.str.{} = private unnamed_addr constant [{} x i8] {}",
+          idx,
+          bytes.len() + 1,
+          llvm_string(bytes)
+        )
+      })
+      .collect();
+    strings.push(String::new());
+    self.emitter.push_tail(&strings.join("\n"));
+  }
+
+  fn visit_component_factor(&mut self, factor: &ComponentFactor) {
+    let (value, t) = self.arithmetic(&Operation::of_component_factor(factor));
+    self.push(&value, t);
+  }
+
+  fn visit_component_term(&mut self, term: &ComponentTerm) {
+    let (value, t) = self.arithmetic(&Operation::of_component_term(term));
+    self.push(&value, t);
+  }
+
+  fn visit_component_arithmetic_expr(&mut self, arith_expr: &ComponentArithmeticExpr) {
+    let (value, t) = self.arithmetic(&Operation::of_component_arithmetic_expr(arith_expr));
+    self.push(&value, t);
+  }
+}
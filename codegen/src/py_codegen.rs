@@ -0,0 +1,699 @@
+use analyzer::flow::default_return;
+use analyzer::infer::{child_path, Passed};
+use analyzer::operands::Operation;
+use analyzer::types::Type;
+use parser::ast::{
+  ArithmeticExpr, AssignStmt, BinOp, CallExpr, CompareExpr, CompareOp, ComponentArithmeticExpr,
+  ComponentFactor, ComponentTerm, Expression, ExpressionValue, Factor, FactorValue,
+  FieldAssignStmt, FieldExpr, FunctionStmt, Identifier, IfStmt, IndexAssignStmt, IndexExpr,
+  ListExpr, LogicExpr, LogicOp, MapExpr, Primary, PrimaryValue, Program, ReturnStmt, Statement,
+  StmtList, StructStmt, Term, WhileStmt,
+};
+use parser::location::Location;
+
+use crate::codegen::{c_function_name, unknown_args, CodeGenerator, Emitter};
+use crate::py_runtime::{PY_INDEX_RUNTIME, PY_INPUT_RUNTIME, PY_PRINT_RUNTIME, PY_VALUE_RUNTIME};
+use crate::script_codegen::{has_init, Arithmetic, Script, ScriptCodeGen};
+
+// names a Riven program may use that Python keeps for itself, or that the
+// generated code needs, a variable named `list` becomes `list_`
+const PY_RESERVED: &str = "\
+  False None True and as assert async await break class continue def del elif else except \
+  finally for from global if import in is lambda nonlocal not or pass raise return try while \
+  with yield abs bool dict float getattr input int isinstance len list print self setattr \
+  str sys tuple type vars Exception RivenClosure RivenError RivenKey RivenObject";
+
+// PEP 8 indents blocks by four spaces
+const PY_INDENT: &str = "    ";
+
+// the Python name of a Riven variable, function, struct or field
+fn py_name(name: &str) -> String {
+  let reserved = PY_RESERVED.split_whitespace().any(|word| word == name);
+  if reserved || name.starts_with("riven_") {
+    format!("{}_", name)
+  } else {
+    name.to_owned()
+  }
+}
+
+// a Python string literal, Rust's escapes like \u{1b} are not Python's
+fn py_string(text: &str) -> String {
+  let mut literal = String::from("\"");
+  for c in text.chars() {
+    match c {
+      '"' => literal.push_str("\\\""),
+      '\\' => literal.push_str("\\\\"),
+      '\n' => literal.push_str("\\n"),
+      '\t' => literal.push_str("\\t"),
+      '\r' => literal.push_str("\\r"),
+      c if c.is_control() => literal.push_str(format!("\\x{:02x}", c as u32).as_str()),
+      c => literal.push(c),
+    }
+  }
+  literal.push('"');
+  literal
+}
+
+// the value a variable has before its first assignment, see runtime::zero of the interpreter
+fn py_zero(t: &Type) -> &'static str {
+  match t {
+    Type::Float => "0.0",
+    Type::Bool => "False",
+    Type::Str => "\"\"",
+    Type::List(_) => "[]",
+    Type::Map(_, _) => "{}",
+    _ => "0",
+  }
+}
+
+// numbers and texts are map keys as they are, other keys are tagged by riven_key
+fn is_plain_key(t: &Type) -> bool {
+  matches!(t, Type::Int | Type::Float | Type::Str)
+}
+
+// values compared by identity, like pointers in the C runtime
+fn is_reference(t: &Type) -> bool {
+  matches!(
+    t,
+    Type::List(_) | Type::Map(_, _) | Type::Struct(_) | Type::Function(_)
+  )
+}
+
+// values Python's own print and `%` show the way the C runtime does, a double
+// like 3.0 is printed as 3 by C
+fn prints_plain(t: &Type) -> bool {
+  matches!(t, Type::Int | Type::Bool | Type::Str)
+}
+
+pub struct PythonCodeGen<'a> {
+  script: Script<'a>,
+  // whether a function or class was the last thing emitted at the top level,
+  // PEP 8 puts two blank lines around them
+  item_ended: bool,
+}
+
+impl<'a> PythonCodeGen<'a> {
+  pub fn new(emitter: &'a mut Emitter) -> Self {
+    emitter.push_header(PY_VALUE_RUNTIME.trim_start());
+    emitter.push_header(PY_INDEX_RUNTIME.trim_start());
+    emitter.push_header(PY_PRINT_RUNTIME.trim_start());
+    emitter.push_header(PY_INPUT_RUNTIME.trim_start());
+    emitter.set_indent_unit(PY_INDENT);
+
+    PythonCodeGen {
+      script: Script::new(emitter),
+      item_ended: false,
+    }
+  }
+
+  // two blank lines in front of a function or class, and after it
+  fn start_item(&mut self) {
+    if !self.script.emitter.body().is_empty() {
+      self.script.emitter.emmit("\n\n");
+    }
+    self.item_ended = false;
+  }
+
+  fn end_item(&mut self) {
+    self.item_ended = true;
+  }
+
+  // a map key, tagged with its type unless it is a number or a text
+  fn key_code(&mut self, expr: &Expression) -> String {
+    if is_plain_key(&self.type_of(expr)) {
+      self.operand_code(expr)
+    } else {
+      format!("riven_key({})", self.expr_code(expr))
+    }
+  }
+
+  // an expression used as the object of a method call or an `in`, parenthesized
+  // unless it is a single value
+  fn operand_code(&mut self, expr: &Expression) -> String {
+    let code = self.expr_code(expr);
+    match expr.as_factor() {
+      Some(_) => code,
+      None => format!("({})", code),
+    }
+  }
+
+  // an expression stored where a value of type `expected` is wanted, a double
+  // stored into an int loses its fraction like in C
+  fn expr_code_as(&mut self, expr: &Expression, expected: &Type) -> String {
+    let code = self.expr_code(expr);
+    match (expected, self.type_of(expr)) {
+      (Type::Int, Type::Float | Type::Any) => format!("int({})", code),
+      _ => code,
+    }
+  }
+
+  // the statements of an if, a loop or a function, one level deeper; a
+  // function gives back `zero` when they end without a `return`
+  fn visit_block(&mut self, block: &Location, stmt_list: Option<&StmtList>, zero: Option<Type>) {
+    self.script.emitter.indent();
+    let lines = self.script.emitter.line_count();
+    self.visit_hoisted(block);
+    if let Some(stmt_list) = stmt_list {
+      self.visit_stmt_list(stmt_list);
+    }
+    if let Some(return_type) = zero {
+      self.emit_line(format!("return {}", py_zero(&return_type)).as_str());
+    }
+    if self.script.emitter.line_count() == lines {
+      self.emit_line("pass");
+    }
+    self.script.emitter.dedent();
+  }
+
+  // a print statement written with Python's own print when it shows its values
+  // the way the C runtime does, e.g. println("n:", n) => print("n:", n)
+  fn plain_print_code(&mut self, call_expr: &CallExpr) -> Option<String> {
+    let name = call_expr.0.to_string();
+    let args = &call_expr.1 .0;
+    let plain = |gen: &Self, t: fn(&Type) -> bool| args.iter().all(|arg| t(&gen.type_of(arg)));
+    match name.as_str() {
+      _ if name != "printf" && !plain(self, prints_plain) => None,
+      "println" => Some(format!("print({})", self.exprs_code(args).join(", "))),
+      "print" => {
+        let mut args = self.exprs_code(args);
+        args.push("end=\"\"".to_owned());
+        Some(format!("print({})", args.join(", ")))
+      }
+      // printf("%d: %d\n", a, b) => print("%d: %d\n" % (a, b), end="")
+      // `%` formats numbers like C does, a double given to %d too
+      "printf" if plain(self, |t| prints_plain(t) || *t == Type::Float) => {
+        let format = match args.first().map(|arg| self.type_of(arg)) {
+          Some(Type::Str) => self.operand_code(&args[0]),
+          _ => return None,
+        };
+        let values = self.exprs_code(&args[1..]);
+        let text = match values.len() {
+          0 if !format.contains('%') => format,
+          1 => format!("{} % {}", format, self.operand_code(&args[1])),
+          _ => format!("{} % ({})", format, values.join(", ")),
+        };
+        Some(format!("print({}, end=\"\")", text))
+      }
+      _ => None,
+    }
+  }
+
+  // the builtins written in plain Python, e.g. append(scores, 4) => scores.append(4)
+  fn visit_builtin_call(&mut self, name: &str, args: &[Expression]) -> bool {
+    let code = match (name, args) {
+      ("input", [prompt]) => format!("riven_input({})", self.expr_code(prompt)),
+      ("input_number", [prompt]) => format!(
+        "riven_input_number({}, {})",
+        self.expr_code(prompt),
+        self.script.line
+      ),
+      ("len", [value]) => format!("riven_len({})", self.expr_code(value)),
+      ("keys", [map]) => match self.type_of(map) {
+        Type::Map(key, _) if is_plain_key(&key) => {
+          format!("list({}.keys())", self.operand_code(map))
+        }
+        _ => format!("riven_keys({})", self.expr_code(map)),
+      },
+      ("append", [list, value]) => format!(
+        "{}.append({})",
+        self.operand_code(list),
+        self.expr_code(value)
+      ),
+      ("has", [map, key]) => format!("({} in {})", self.key_code(key), self.operand_code(map)),
+      _ => return false,
+    };
+    self.push(&code);
+    true
+  }
+}
+
+impl<'a> ScriptCodeGen<'a> for PythonCodeGen<'a> {
+  fn script(&self) -> &Script<'a> {
+    &self.script
+  }
+
+  fn script_mut(&mut self) -> &mut Script<'a> {
+    &mut self.script
+  }
+
+  fn name(name: &str) -> String {
+    py_name(name)
+  }
+
+  fn string(text: &str) -> String {
+    py_string(text)
+  }
+
+  fn statement(code: &str) -> String {
+    code.to_owned()
+  }
+
+  fn hoisted_code(name: &str, var_type: &Type) -> String {
+    format!("{} = {}", py_name(name), py_zero(var_type))
+  }
+
+  // a blank line after a function or class ends it
+  fn emit_line(&mut self, code: &str) {
+    if self.item_ended {
+      self.script.emitter.emmit("\n\n");
+      self.item_ended = false;
+    }
+    self.script.emitter.emit_line(code);
+  }
+
+  // `7 / 2` is 3 like in C and dividing by zero stops the program, Python ints
+  // never overflow though
+  fn combine(&self, left: Arithmetic, op: &BinOp, right: Arithmetic) -> Arithmetic {
+    let int = left.int && right.int;
+    if op.is_div() && int {
+      return Arithmetic {
+        code: format!(
+          "riven_idiv({}, {}, {})",
+          left.code, right.code, self.script.line
+        ),
+        int,
+        precedence: 0,
+      };
+    }
+    let precedence = match op.is_time() || op.is_div() {
+      true => 2,
+      false => 1,
+    };
+    // BinOp prints with the spaces around it
+    let code = format!(
+      "{}{}{}",
+      left.operand(precedence, false),
+      op,
+      right.operand(precedence, true)
+    );
+    Arithmetic {
+      code,
+      int,
+      precedence,
+    }
+  }
+
+  // def riven_init(): ... runs the statements outside functions, riven_run calls
+  // it before main
+  fn visit_init(&mut self, stmt_list: &StmtList) {
+    self.start_item();
+    self.emit_line("def riven_init():");
+    self.script.emitter.indent();
+    let globals: Vec<String> = self
+      .script
+      .declarations
+      .hoisted(&Location::default())
+      .iter()
+      .map(|name| py_name(name))
+      .collect();
+    if !globals.is_empty() {
+      self.emit_line(format!("global {}", globals.join(", ")).as_str());
+    }
+    self.visit_init_stmts(stmt_list);
+    self.script.emitter.dedent();
+    self.end_item();
+  }
+}
+
+impl<'a> CodeGenerator for PythonCodeGen<'a> {
+  fn visit_primary(&mut self, primary: &Primary) {
+    let code = match &primary.0 {
+      PrimaryValue::String(s) => py_string(s),
+      // a C int literal
+      PrimaryValue::Number(n) => format!("{}", *n as i32),
+      PrimaryValue::Float(n) if n.is_nan() => "float(\"nan\")".to_owned(),
+      PrimaryValue::Float(n) if n.is_infinite() && *n > 0.0 => "float(\"inf\")".to_owned(),
+      PrimaryValue::Float(n) if n.is_infinite() => "float(\"-inf\")".to_owned(),
+      PrimaryValue::Float(n) => format!("{:?}", n),
+      PrimaryValue::Bool(true) => "True".to_owned(),
+      PrimaryValue::Bool(false) => "False".to_owned(),
+    };
+    self.push(&code);
+  }
+
+  fn visit_identifier(&mut self, identifier: &Identifier) {
+    let name = py_name(&identifier.to_string());
+    self.push(&name);
+  }
+
+  fn visit_factor(&mut self, factor: &Factor) {
+    self.visit_script_factor(factor);
+  }
+
+  fn visit_term(&mut self, term: &Term) {
+    self.visit_operation(&Operation::of_term(term));
+  }
+
+  fn visit_arithmetic_expr(&mut self, arithmetic_expr: &ArithmeticExpr) {
+    self.visit_operation(&Operation::of_arithmetic_expr(arithmetic_expr));
+  }
+
+  fn visit_call_expr(&mut self, call_expr: &CallExpr) {
+    let name = call_expr.0.to_string();
+    let is_function = self
+      .script
+      .types
+      .resolve_variable(&self.script.path, &name)
+      .is_some()
+      || self
+        .script
+        .types
+        .resolve_function(&self.script.path, &name)
+        .is_some();
+    if !is_function {
+      match name.as_str() {
+        "print" => return self.visit_print_call("riven_print", call_expr),
+        "println" => return self.visit_print_call("riven_println", call_expr),
+        "printf" => return self.visit_print_call("riven_printf", call_expr),
+        _ if self.visit_builtin_call(&name, &call_expr.1 .0) => return,
+        _ => (),
+      }
+    }
+
+    if self.is_struct(&call_expr.0) {
+      let args = self.exprs_code(&call_expr.1 .0);
+      return self.push(format!("{}({})", py_name(&name), args.join(", ")).as_str());
+    }
+
+    if self
+      .script
+      .types
+      .resolve_variable(&self.script.path, &name)
+      .is_some()
+    {
+      let function = Factor::from(FactorValue::Identifier(call_expr.0.clone()));
+      return self.visit_value_call(&function, &call_expr.1 .0);
+    }
+
+    let function = self.script.types.resolve_function(&self.script.path, &name);
+    let (name, passed) = match &function {
+      Some(function) => (
+        py_name(&c_function_name(function)),
+        self.script.types.passed(function, &call_expr.1 .0),
+      ),
+      None => (py_name(&name), unknown_args(&call_expr.1 .0)),
+    };
+    let args: Vec<String> = passed
+      .into_iter()
+      .map(|passed| match passed {
+        Passed::Capture(name) => py_name(&name),
+        Passed::Arg(arg, param_type) => self.expr_code_as(arg, &param_type),
+      })
+      .collect();
+    self.push(format!("{}({})", name, args.join(", ")).as_str());
+  }
+
+  fn visit_list_expr(&mut self, list_expr: &ListExpr) {
+    let items = self.exprs_code(&list_expr.0 .0);
+    self.push(format!("[{}]", items.join(", ")).as_str());
+  }
+
+  fn visit_map_expr(&mut self, map_expr: &MapExpr) {
+    let entries: Vec<String> = map_expr
+      .0
+      .iter()
+      .map(|entry| format!("{}: {}", self.key_code(&entry.0), self.expr_code(&entry.1)))
+      .collect();
+    self.push(format!("{{{}}}", entries.join(", ")).as_str());
+  }
+
+  fn visit_index_expr(&mut self, index_expr: &IndexExpr) {
+    let target = self.factor_code(&index_expr.0);
+    let index = self.expr_code(&index_expr.1);
+    self.push(format!("riven_get({}, {}, {})", target, index, self.script.line).as_str());
+  }
+
+  fn visit_field_expr(&mut self, field_expr: &FieldExpr) {
+    let field = py_name(&field_expr.1.to_string());
+    let code = match self.field_code(&field_expr.0, &field_expr.1) {
+      (target, true) => format!("{}.{}", target, field),
+      (target, false) => format!(
+        "riven_field({}, \"{}\", {})",
+        target, field, self.script.line
+      ),
+    };
+    self.push(&code);
+  }
+
+  fn visit_call_stmt(&mut self, call_expr: &CallExpr) {
+    let name = call_expr.0.to_string();
+    let is_builtin = self
+      .script
+      .types
+      .resolve_variable(&self.script.path, &name)
+      .is_none()
+      && self
+        .script
+        .types
+        .resolve_function(&self.script.path, &name)
+        .is_none();
+    let plain = match is_builtin {
+      true => self.plain_print_code(call_expr),
+      false => None,
+    };
+    let code = match plain {
+      Some(code) => code,
+      None => self.generate(|gen| gen.visit_call_expr(call_expr)),
+    };
+    self.emit_line(&code);
+  }
+
+  fn visit_expr(&mut self, expr: &Expression) {
+    let expr_value = &expr.0;
+    match expr_value {
+      ExpressionValue::ComponentArithmeticExpr(arith_expr) => {
+        self.visit_component_arithmetic_expr(arith_expr)
+      }
+    }
+  }
+
+  fn visit_compare_expr(&mut self, compare_expr: &CompareExpr) {
+    let left = self.expr_code(&compare_expr.0);
+    let (op, right) = match (&compare_expr.1, &compare_expr.2) {
+      (Some(op), Some(right)) => (op, right),
+      // a value used as a condition, texts, lists and maps are always true
+      _ => {
+        let code = match self.type_of(&compare_expr.0) {
+          Type::Int | Type::Float | Type::Bool => left,
+          _ => format!("riven_truth({})", left),
+        };
+        return self.push(&code);
+      }
+    };
+
+    // arithmetic binds tighter than comparisons in Python too
+    let types = (self.type_of(&compare_expr.0), self.type_of(right));
+    let right = self.expr_code(right);
+    let code = match (op, types) {
+      (CompareOp::Eq, (Type::Any, _) | (_, Type::Any)) => {
+        format!("riven_equal({}, {})", left, right)
+      }
+      // lists, maps, structs and functions are only equal to themselves
+      (CompareOp::Eq, (a, b)) if is_reference(&a) && is_reference(&b) => {
+        format!("{} is {}", left, right)
+      }
+      (CompareOp::Eq, _) => format!("{} == {}", left, right),
+      (CompareOp::Gt, _) => format!("{} > {}", left, right),
+      (CompareOp::Lt, _) => format!("{} < {}", left, right),
+    };
+    self.push(&code);
+  }
+
+  fn visit_logic_expr(&mut self, logic_expr: &LogicExpr) {
+    match logic_expr.0 {
+      LogicOp::Bool => {
+        if let Some(compare_expr) = &logic_expr.1 {
+          self.visit_compare_expr(compare_expr);
+        }
+      }
+      LogicOp::And | LogicOp::Or => {
+        let op = match logic_expr.0 {
+          LogicOp::And => " and ",
+          _ => " or ",
+        };
+        let operands: Vec<String> = [&logic_expr.2, &logic_expr.3]
+          .into_iter()
+          .flatten()
+          .map(|expr| self.generate(|gen| gen.visit_logic_expr(expr)))
+          .collect();
+        self.push(format!("({})", operands.join(op)).as_str());
+      }
+      LogicOp::Not => {
+        if let Some(expr) = &logic_expr.2 {
+          let code = self.generate(|gen| gen.visit_logic_expr(expr));
+          // `not` binds looser than a comparison, the parentheses are for the reader
+          let single = matches!(&expr.1, Some(CompareExpr(_, None, None)));
+          let code = match (&expr.0, single) {
+            (LogicOp::Bool, false) => format!("not ({})", code),
+            _ => format!("not {}", code),
+          };
+          self.push(&code);
+        }
+      }
+    }
+  }
+
+  fn visit_assign_stmt(&mut self, stmt: &AssignStmt) {
+    let var_type = match self
+      .script
+      .types
+      .variable_type(&self.script.path, &stmt.0.to_string())
+    {
+      Type::Unknown => self.type_of(&stmt.1),
+      var_type => var_type,
+    };
+    let value = self.expr_code_as(&stmt.1, &var_type);
+    let name = py_name(&stmt.0.to_string());
+    self.emit_line(format!("{} = {}", name, value).as_str());
+  }
+
+  fn visit_index_assign_stmt(&mut self, stmt: &IndexAssignStmt) {
+    let target = self.factor_code(&stmt.0 .0);
+    let index = self.expr_code(&stmt.0 .1);
+    let value = self.expr_code(&stmt.1);
+    let code = format!(
+      "riven_set({}, {}, {}, {})",
+      target, index, value, self.script.line
+    );
+    self.emit_line(&code);
+  }
+
+  fn visit_field_assign_stmt(&mut self, stmt: &FieldAssignStmt) {
+    let FieldExpr(target, field) = &stmt.0;
+    let name = py_name(&field.to_string());
+    let code = match self.field_code(target, field) {
+      (target, true) => format!("{}.{} = {}", target, name, self.expr_code(&stmt.1)),
+      (target, false) => format!(
+        "riven_set_field({}, \"{}\", {}, {})",
+        target,
+        name,
+        self.expr_code(&stmt.1),
+        self.script.line
+      ),
+    };
+    self.emit_line(&code);
+  }
+
+  fn visit_return_stmt(&mut self, stmt: &ReturnStmt) {
+    let return_type = self.script.types.return_type(&self.script.path);
+    let value = self.expr_code_as(&stmt.0, &return_type);
+    self.emit_line(format!("return {}", value).as_str());
+  }
+
+  fn visit_if_stmt(&mut self, stmt: &IfStmt) {
+    let block = self.script.location.clone();
+    let condition = self.condition_code(&stmt.0);
+    self.emit_line(format!("if {}:", condition).as_str());
+    self.visit_block(&block, stmt.1.as_deref(), None);
+  }
+
+  fn visit_while_stmt(&mut self, stmt: &WhileStmt) {
+    let block = self.script.location.clone();
+    let condition = self.condition_code(&stmt.0);
+    self.emit_line(format!("while {}:", condition).as_str());
+    self.visit_block(&block, stmt.1.as_deref(), None);
+  }
+
+  // functions are lifted to the top level like in C, the functions nested in
+  // them follow them
+  fn visit_function_stmt(&mut self, stmt: &FunctionStmt) {
+    let block = self.script.location.clone();
+    let function = child_path(&self.script.path, &stmt.0.to_string());
+    let outer = std::mem::replace(&mut self.script.path, function);
+
+    let params: Vec<String> = self
+      .script
+      .types
+      .params(&self.script.path)
+      .iter()
+      .map(|name| py_name(name))
+      .collect();
+    self.start_item();
+    let name = py_name(&c_function_name(&self.script.path));
+    self.emit_line(format!("def {}({}):", name, params.join(", ")).as_str());
+    let zero = default_return(&self.script.types, &self.script.path, stmt.2.as_deref());
+    self.visit_block(&block, stmt.2.as_deref(), zero);
+    self.end_item();
+
+    if let Some(stmt_list) = &stmt.2 {
+      self.visit_nested_functions(stmt_list);
+    }
+    self.script.path = outer;
+  }
+
+  // struct Point { x, y } =>
+  //   class Point(RivenObject): def __init__(self, x, y): self.x = x; self.y = y
+  fn visit_struct_stmt(&mut self, stmt: &StructStmt) {
+    let name = stmt.0.to_string();
+    let class = py_name(&name);
+    self.script.structs.push(name.clone());
+
+    let fields: Vec<String> = stmt
+      .1
+      .iter()
+      .map(|field| py_name(&field.to_string()))
+      .collect();
+    let mut params = vec!["self".to_owned()];
+    params.extend(fields.iter().cloned());
+    self.start_item();
+    self.emit_line(format!("class {}(RivenObject):", class).as_str());
+    self.script.emitter.indent();
+    // printed with its Riven name
+    if class != name {
+      self.emit_line(format!("riven_name = {}", py_string(&name)).as_str());
+      self.script.emitter.emmit("\n");
+    }
+    self.emit_line(format!("def __init__({}):", params.join(", ")).as_str());
+    self.script.emitter.indent();
+    for field in &fields {
+      self.emit_line(format!("self.{} = {}", field, field).as_str());
+    }
+    if fields.is_empty() {
+      self.emit_line("pass");
+    }
+    self.script.emitter.dedent();
+    self.script.emitter.dedent();
+    self.end_item();
+  }
+
+  fn visit_stmt(&mut self, stmt: &Statement) {
+    self.visit_script_stmt(stmt);
+  }
+
+  fn visit_stmt_list(&mut self, stmt_list: &StmtList) {
+    if let Some(stmt) = &stmt_list.0 {
+      self.visit_stmt(stmt);
+    }
+
+    if let Some(rests_stmts) = &stmt_list.1 {
+      self.visit_stmt_list(rests_stmts);
+    }
+  }
+
+  fn visit_program(&mut self, program: &Program) {
+    self.visit_script_program(program);
+    if self.script.types.resolve_function("", "main").is_some() {
+      let run = if has_init(&program.0) {
+        "riven_run(main, riven_init)"
+      } else {
+        "riven_run(main)"
+      };
+      self
+        .script
+        .emitter
+        .push_tail(format!("\nif __name__ == \"__main__\":\n    {}\n", run).as_str());
+    }
+  }
+
+  fn visit_component_factor(&mut self, factor: &ComponentFactor) {
+    self.visit_operation(&Operation::of_component_factor(factor));
+  }
+
+  fn visit_component_term(&mut self, term: &ComponentTerm) {
+    self.visit_operation(&Operation::of_component_term(term));
+  }
+
+  fn visit_component_arithmetic_expr(&mut self, arith_expr: &ComponentArithmeticExpr) {
+    self.visit_operation(&Operation::of_component_arithmetic_expr(arith_expr));
+  }
+}
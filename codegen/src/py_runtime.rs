@@ -0,0 +1,240 @@
+//! Python runtime support emitted in front of every program generated by
+//! `PythonCodeGen`.
+//!
+//! The generated code is meant to be read next to the Riven program it comes
+//! from, so it uses plain Python wherever Python behaves like the C runtime:
+//! these functions cover the rest. Values print and fail the way the C runtime
+//! does.
+
+// runtime errors, function values, structs and C int division
+pub const PY_VALUE_RUNTIME: &str = r#"
+import sys
+
+
+class RivenError(Exception):
+    def __init__(self, line, message):
+        super().__init__(message)
+        self.line = line
+
+
+def riven_error(line, message):
+    raise RivenError(line, message)
+
+
+# a function used as a value, with a copy of the variables it captured
+class RivenClosure:
+    def __init__(self, name, arity, code, env):
+        self.name = name
+        self.arity = arity
+        self.code = code
+        self.env = env
+
+
+def riven_closure(name, arity, code, env=()):
+    return RivenClosure(name, arity, code, list(env))
+
+
+def riven_call(closure, args, line):
+    if not isinstance(closure, RivenClosure):
+        riven_error(line, "expected a function")
+    if len(args) != closure.arity:
+        riven_error(line, f"function {closure.name} takes {closure.arity} argument(s) but {len(args)} were given")
+    return closure.code(*closure.env, *args)
+
+
+# every struct is a class extending RivenObject, its fields in declaration order
+class RivenObject:
+    pass
+
+
+def riven_struct_name(target):
+    return getattr(type(target), "riven_name", type(target).__name__)
+
+
+def riven_field(target, field, line):
+    if not isinstance(target, RivenObject):
+        riven_error(line, "only structs have fields")
+    if field not in vars(target):
+        riven_error(line, f"{riven_struct_name(target)} has no field named {field}")
+    return getattr(target, field)
+
+
+def riven_set_field(target, field, value, line):
+    riven_field(target, field, line)
+    setattr(target, field, value)
+
+
+# ints are C ints: `7 / 2` is 3, and `-7 / 2` is -3
+def riven_idiv(a, b, line):
+    if b == 0:
+        riven_error(line, "division by zero")
+    quotient = abs(a) // abs(b)
+    return quotient if (a < 0) == (b < 0) else -quotient
+
+
+# what C makes of a value in a condition, texts and lists are never NULL here
+def riven_truth(value):
+    if isinstance(value, (bool, int, float)):
+        return value != 0
+    return True
+
+
+# `=` on values of any type, like the C runtime: 1 and 1.0 are equal but True
+# is not 1, and lists, maps, structs and functions are only equal to themselves
+def riven_equal(a, b):
+    if isinstance(a, bool) or isinstance(b, bool):
+        return type(a) is type(b) and a == b
+    if isinstance(a, (int, float)) and isinstance(b, (int, float)):
+        return a == b
+    if isinstance(a, str) and isinstance(b, str):
+        return a == b
+    return a is b
+
+"#;
+
+// lists are lists and maps are dicts, both keep their items in order
+pub const PY_INDEX_RUNTIME: &str = r#"
+# a map key tagged with its type, so True is not the key 1 and a list is only
+# the key of itself, like in the C runtime
+class RivenKey:
+    def __init__(self, value):
+        self.value = value
+
+    def __eq__(self, other):
+        return isinstance(other, RivenKey) and riven_equal(self.value, other.value)
+
+    def __hash__(self):
+        return hash(self.value) if isinstance(self.value, bool) else id(self.value)
+
+
+# numbers and texts are their own key, 1 and 1.0 are the same key
+def riven_key(value):
+    if isinstance(value, (int, float, str)) and not isinstance(value, bool):
+        return value
+    return RivenKey(value)
+
+
+def riven_keys(target):
+    return [key.value if isinstance(key, RivenKey) else key for key in target]
+
+
+def riven_list_index(items, key, line):
+    if isinstance(key, bool) or not isinstance(key, (int, float)):
+        riven_error(line, "a list index must be a number")
+    index = int(key)
+    if index < 0 or index >= len(items):
+        riven_error(line, f"index {index} is out of range, the list has {len(items)} item(s)")
+    return index
+
+
+def riven_get(target, key, line):
+    if isinstance(target, list):
+        return target[riven_list_index(target, key, line)]
+    if isinstance(target, dict):
+        if riven_key(key) not in target:
+            riven_error(line, f'key "{key}" is not in the map' if isinstance(key, str) else "key is not in the map")
+        return target[riven_key(key)]
+    riven_error(line, "only lists and maps can be indexed")
+
+
+def riven_set(target, key, value, line):
+    if isinstance(target, list):
+        target[riven_list_index(target, key, line)] = value
+    elif isinstance(target, dict):
+        target[riven_key(key)] = value
+    else:
+        riven_error(line, "only lists and maps can be indexed")
+
+
+# the length of a text is its number of bytes, like strlen
+def riven_len(value):
+    if isinstance(value, str):
+        return len(value.encode())
+    return len(value)
+
+"#;
+
+// print and printf, numbers are printed like C's `%.15g`
+pub const PY_PRINT_RUNTIME: &str = r#"
+# strings inside a list, map or struct are quoted, so ["1"] and [1] look different
+def riven_str(value, item=False):
+    if isinstance(value, bool):
+        return "True" if value else "False"
+    if isinstance(value, float):
+        return "%.15g" % value
+    if isinstance(value, str):
+        return f'"{value}"' if item else value
+    if isinstance(value, list):
+        return "[" + ", ".join(riven_str(v, True) for v in value) + "]"
+    if isinstance(value, dict):
+        entries = zip(riven_keys(value), value.values())
+        return "{" + ", ".join(f"{riven_str(k, True)}: {riven_str(v, True)}" for k, v in entries) + "}"
+    if isinstance(value, RivenClosure):
+        return f"<function {value.name}>"
+    if isinstance(value, RivenObject):
+        fields = ", ".join(f"{field}={riven_str(v, True)}" for field, v in vars(value).items())
+        return f"{riven_struct_name(value)}({fields})"
+    return str(value)
+
+
+def riven_print(*values):
+    print(" ".join(riven_str(value) for value in values), end="")
+
+
+def riven_println(*values):
+    print(" ".join(riven_str(value) for value in values))
+
+
+# Python's `%` formats like C's printf, texts, lists and maps are given as riven_str
+def riven_printf(format, *args):
+    args = tuple(arg if isinstance(arg, (bool, int, float)) else riven_str(arg) for arg in args)
+    text = format % args
+    print(text, end="")
+    return len(text.encode())
+
+"#;
+
+// input and the start of the program
+pub const PY_INPUT_RUNTIME: &str = r#"
+# an empty text once the input ended, like the C runtime
+def riven_input(prompt):
+    try:
+        return input(prompt)
+    except EOFError:
+        return ""
+
+
+# asks again until a number is typed
+def riven_input_number(prompt, line):
+    while True:
+        try:
+            text = input(prompt).strip()
+        except EOFError:
+            riven_error(line, "no more input, expected a number")
+        try:
+            return float(text)
+        except ValueError:
+            print("that is not a number, please try again")
+
+
+# run init, the statements outside functions, then main: what main returns is the exit code;
+# a runtime error stops the program with its line, like the C runtime reports it
+def riven_run(main, init=None):
+    sys.setrecursionlimit(10000)
+    try:
+        if init:
+            init()
+        returned = main()
+        code = int(returned) if isinstance(returned, (int, float)) else 0
+    except RivenError as error:
+        message = f"runtime error at line {error.line}: {error}"
+        sys.stdout.flush()
+        print(message, file=sys.stderr)
+        code = 1
+    except RecursionError:
+        sys.stdout.flush()
+        print("\nruntime error: too many functions called inside each other", file=sys.stderr)
+        code = 1
+    sys.exit(code)
+
+"#;
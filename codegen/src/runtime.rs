@@ -0,0 +1,646 @@
+//! C runtime support emitted in front of every generated program.
+//!
+//! Builtins such as `print` and `println` are implemented here instead of
+//! being passed through to libc, so the generated code does not need to know
+//! the type of each argument: `_Generic` picks the right formatter.
+
+// values stored in lists and maps are boxed, so they can hold any kind of value
+pub const VALUE_RUNTIME: &str = r#"
+typedef struct RivenList RivenList;
+typedef struct RivenMap RivenMap;
+typedef struct RivenObject RivenObject;
+typedef struct RivenClosure RivenClosure;
+
+typedef enum {
+  RIVEN_INT, RIVEN_DOUBLE, RIVEN_BOOL, RIVEN_STR, RIVEN_LIST, RIVEN_MAP, RIVEN_OBJECT, RIVEN_FUNCTION
+} RivenKind;
+
+typedef struct {
+  RivenKind kind;
+  union {
+    int i;
+    double d;
+    bool b;
+    const char *s;
+    RivenList *l;
+    RivenMap *m;
+    RivenObject *o;
+    RivenClosure *f;
+  } as;
+} RivenValue;
+
+static inline RivenValue riven_box_int(int v) { return (RivenValue){ RIVEN_INT, { .i = v } }; }
+static inline RivenValue riven_box_double(double v) { return (RivenValue){ RIVEN_DOUBLE, { .d = v } }; }
+static inline RivenValue riven_box_bool(bool v) { return (RivenValue){ RIVEN_BOOL, { .b = v } }; }
+static inline RivenValue riven_box_str(const char *v) { return (RivenValue){ RIVEN_STR, { .s = v } }; }
+static inline RivenValue riven_box_list(RivenList *v) { return (RivenValue){ RIVEN_LIST, { .l = v } }; }
+static inline RivenValue riven_box_map(RivenMap *v) { return (RivenValue){ RIVEN_MAP, { .m = v } }; }
+static inline RivenValue riven_box_closure(RivenClosure *v) {
+  return v ? (RivenValue){ RIVEN_FUNCTION, { .f = v } } : riven_box_int(0);
+}
+static inline RivenValue riven_box_value(RivenValue v) { return v; }
+// every generated struct starts like a RivenObject, see OBJECT_RUNTIME; a
+// struct or function variable never assigned is NULL and stands for 0
+static inline RivenValue riven_box_object(void *v) {
+  return v ? (RivenValue){ RIVEN_OBJECT, { .o = v } } : riven_box_int(0);
+}
+
+#define riven_box(x) _Generic((x), \
+  bool: riven_box_bool, \
+  int: riven_box_int, \
+  double: riven_box_double, \
+  char *: riven_box_str, \
+  const char *: riven_box_str, \
+  RivenList *: riven_box_list, \
+  RivenMap *: riven_box_map, \
+  RivenClosure *: riven_box_closure, \
+  RivenValue: riven_box_value, \
+  default: riven_box_object)(x)
+
+static inline void riven_runtime_error(int line, const char *message) {
+  fflush(stdout);
+  fprintf(stderr, "runtime error at line %d: %s\n", line, message);
+  exit(1);
+}
+
+// C stops the program on an int division by zero, and on INT_MIN / -1, without
+// flushing what was printed
+static inline int riven_div(int a, int b, int line) {
+  if (b == 0) {
+    riven_runtime_error(line, "division by zero");
+  }
+  return b == -1 ? (int)(0u - (unsigned)a) : a / b;
+}
+
+static inline int riven_unbox_int(RivenValue value, int line) {
+  switch (value.kind) {
+  case RIVEN_INT: return value.as.i;
+  case RIVEN_DOUBLE: return (int)value.as.d;
+  case RIVEN_BOOL: return value.as.b;
+  default: riven_runtime_error(line, "expected a number"); return 0;
+  }
+}
+
+static inline double riven_unbox_double(RivenValue value, int line) {
+  switch (value.kind) {
+  case RIVEN_INT: return value.as.i;
+  case RIVEN_DOUBLE: return value.as.d;
+  default: riven_runtime_error(line, "expected a number"); return 0;
+  }
+}
+
+static inline bool riven_unbox_bool(RivenValue value, int line) {
+  if (value.kind != RIVEN_BOOL) {
+    riven_runtime_error(line, "expected True or False");
+  }
+  return value.as.b;
+}
+
+static inline char *riven_unbox_str(RivenValue value, int line) {
+  if (value.kind != RIVEN_STR) {
+    riven_runtime_error(line, "expected a text");
+  }
+  return (char *)value.as.s;
+}
+
+static inline RivenList *riven_unbox_list(RivenValue value, int line) {
+  if (value.kind != RIVEN_LIST) {
+    riven_runtime_error(line, "expected a list");
+  }
+  return value.as.l;
+}
+
+static inline RivenMap *riven_unbox_map(RivenValue value, int line) {
+  if (value.kind != RIVEN_MAP) {
+    riven_runtime_error(line, "expected a map");
+  }
+  return value.as.m;
+}
+
+static inline RivenClosure *riven_unbox_closure(RivenValue value, int line) {
+  if (value.kind != RIVEN_FUNCTION) {
+    riven_runtime_error(line, "expected a function");
+  }
+  return value.as.f;
+}
+
+static inline bool riven_is_number(RivenValue value) {
+  return value.kind == RIVEN_INT || value.kind == RIVEN_DOUBLE;
+}
+
+static inline double riven_number_of(RivenValue value) {
+  return value.kind == RIVEN_INT ? value.as.i : value.as.d;
+}
+
+// 1 and 1.0 are the same key, lists and maps are only equal to themselves
+static inline bool riven_value_equal(RivenValue a, RivenValue b) {
+  if (riven_is_number(a) && riven_is_number(b)) {
+    return riven_number_of(a) == riven_number_of(b);
+  }
+  if (a.kind != b.kind) {
+    return false;
+  }
+  switch (a.kind) {
+  case RIVEN_BOOL: return a.as.b == b.as.b;
+  case RIVEN_STR: return strcmp(a.as.s, b.as.s) == 0;
+  case RIVEN_LIST: return a.as.l == b.as.l;
+  case RIVEN_MAP: return a.as.m == b.as.m;
+  case RIVEN_OBJECT: return a.as.o == b.as.o;
+  case RIVEN_FUNCTION: return a.as.f == b.as.f;
+  default: return false;
+  }
+}
+"#;
+
+pub const LIST_RUNTIME: &str = r#"
+struct RivenList {
+  int len;
+  int cap;
+  RivenValue *items;
+};
+
+static inline RivenList *riven_list_new(void) {
+  RivenList *list = malloc(sizeof(RivenList));
+  list->len = 0;
+  list->cap = 0;
+  list->items = NULL;
+  return list;
+}
+
+static inline void riven_list_append(RivenList *list, RivenValue value) {
+  if (list->len == list->cap) {
+    list->cap = list->cap == 0 ? 8 : list->cap * 2;
+    list->items = realloc(list->items, sizeof(RivenValue) * list->cap);
+  }
+  list->items[list->len++] = value;
+}
+
+// riven_list_of(3, riven_box(1), riven_box(2), riven_box(3))
+static inline RivenList *riven_list_of(int count, ...) {
+  RivenList *list = riven_list_new();
+  va_list args;
+  va_start(args, count);
+  for (int i = 0; i < count; i++) {
+    riven_list_append(list, va_arg(args, RivenValue));
+  }
+  va_end(args);
+  return list;
+}
+
+static inline int riven_list_check_index(RivenList *list, RivenValue key, int line) {
+  if (!riven_is_number(key)) {
+    riven_runtime_error(line, "a list index must be a number");
+  }
+  int index = (int)riven_number_of(key);
+  if (index < 0 || index >= list->len) {
+    char message[128];
+    snprintf(message, sizeof(message),
+             "index %d is out of range, the list has %d item(s)", index, list->len);
+    riven_runtime_error(line, message);
+  }
+  return index;
+}
+
+static inline RivenValue riven_list_get(RivenList *list, RivenValue key, int line) {
+  return list->items[riven_list_check_index(list, key, line)];
+}
+
+static inline void riven_list_set(RivenList *list, RivenValue key, RivenValue value, int line) {
+  list->items[riven_list_check_index(list, key, line)] = value;
+}
+
+static inline int riven_list_len(RivenList *list) { return list->len; }
+static inline int riven_str_len(const char *str) { return (int)strlen(str); }
+"#;
+
+// a hash map keeping its keys in insertion order, so `keys` is predictable
+pub const MAP_RUNTIME: &str = r#"
+typedef struct {
+  RivenValue key;
+  RivenValue value;
+} RivenMapEntry;
+
+struct RivenMap {
+  int len;
+  int cap;
+  RivenMapEntry *entries;
+  // open addressing table of entry index + 1, 0 marks an empty slot
+  int *slots;
+  int slot_count;
+};
+
+static inline unsigned riven_hash(RivenValue key) {
+  unsigned hash = 2166136261u;
+  switch (key.kind) {
+  case RIVEN_STR:
+    for (const char *c = key.as.s; *c; c++) {
+      hash = (hash ^ (unsigned char)*c) * 16777619u;
+    }
+    return hash;
+  case RIVEN_INT:
+  case RIVEN_DOUBLE: {
+    double number = riven_number_of(key);
+    return (unsigned)(long long)number ^ (number < 0 ? 0x9e3779b9u : 0);
+  }
+  case RIVEN_BOOL: return key.as.b;
+  case RIVEN_LIST: return (unsigned)(size_t)key.as.l;
+  case RIVEN_MAP: return (unsigned)(size_t)key.as.m;
+  case RIVEN_OBJECT: return (unsigned)(size_t)key.as.o;
+  case RIVEN_FUNCTION: return (unsigned)(size_t)key.as.f;
+  }
+  return 0;
+}
+
+static inline RivenMap *riven_map_new(void) {
+  RivenMap *map = malloc(sizeof(RivenMap));
+  map->len = 0;
+  map->cap = 0;
+  map->entries = NULL;
+  map->slot_count = 16;
+  map->slots = calloc(map->slot_count, sizeof(int));
+  return map;
+}
+
+// index of the slot holding `key`, or of the empty slot where it belongs
+static inline int riven_map_find_slot(RivenMap *map, RivenValue key) {
+  int mask = map->slot_count - 1;
+  int slot = riven_hash(key) & mask;
+  while (map->slots[slot] != 0 &&
+         !riven_value_equal(map->entries[map->slots[slot] - 1].key, key)) {
+    slot = (slot + 1) & mask;
+  }
+  return slot;
+}
+
+static inline void riven_map_grow(RivenMap *map) {
+  free(map->slots);
+  map->slot_count *= 2;
+  map->slots = calloc(map->slot_count, sizeof(int));
+  for (int i = 0; i < map->len; i++) {
+    map->slots[riven_map_find_slot(map, map->entries[i].key)] = i + 1;
+  }
+}
+
+static inline void riven_map_set(RivenMap *map, RivenValue key, RivenValue value, int line) {
+  (void)line;
+  int slot = riven_map_find_slot(map, key);
+  if (map->slots[slot] != 0) {
+    map->entries[map->slots[slot] - 1].value = value;
+    return;
+  }
+
+  if (map->len == map->cap) {
+    map->cap = map->cap == 0 ? 8 : map->cap * 2;
+    map->entries = realloc(map->entries, sizeof(RivenMapEntry) * map->cap);
+  }
+  map->entries[map->len] = (RivenMapEntry){ key, value };
+  map->slots[slot] = ++map->len;
+  if (map->len * 2 > map->slot_count) {
+    riven_map_grow(map);
+  }
+}
+
+// riven_map_of(2, riven_box("tom"), riven_box(8), riven_box("amy"), riven_box(9))
+static inline RivenMap *riven_map_of(int count, ...) {
+  RivenMap *map = riven_map_new();
+  va_list args;
+  va_start(args, count);
+  for (int i = 0; i < count; i++) {
+    RivenValue key = va_arg(args, RivenValue);
+    RivenValue value = va_arg(args, RivenValue);
+    riven_map_set(map, key, value, 0);
+  }
+  va_end(args);
+  return map;
+}
+
+static inline bool riven_map_has(RivenMap *map, RivenValue key) {
+  return map->slots[riven_map_find_slot(map, key)] != 0;
+}
+
+static inline RivenValue riven_map_get(RivenMap *map, RivenValue key, int line) {
+  int slot = riven_map_find_slot(map, key);
+  if (map->slots[slot] == 0) {
+    char message[128];
+    if (key.kind == RIVEN_STR) {
+      snprintf(message, sizeof(message), "key \"%s\" is not in the map", key.as.s);
+    } else {
+      snprintf(message, sizeof(message), "key is not in the map");
+    }
+    riven_runtime_error(line, message);
+  }
+  return map->entries[map->slots[slot] - 1].value;
+}
+
+static inline RivenList *riven_map_keys(RivenMap *map) {
+  RivenList *keys = riven_list_new();
+  for (int i = 0; i < map->len; i++) {
+    riven_list_append(keys, map->entries[i].key);
+  }
+  return keys;
+}
+
+static inline int riven_map_len(RivenMap *map) { return map->len; }
+"#;
+
+// a struct declared in Riven is generated as a typedef'd C struct whose
+// fields are all boxed, so any struct can be used through a RivenObject
+pub const OBJECT_RUNTIME: &str = r#"
+typedef struct {
+  const char *name;
+  int field_count;
+  const char **field_names;
+} RivenType;
+
+struct RivenObject {
+  const RivenType *type;
+  RivenValue fields[];
+};
+
+static inline RivenValue *riven_field(RivenValue target, const char *name, int line) {
+  if (target.kind != RIVEN_OBJECT) {
+    riven_runtime_error(line, "only structs have fields");
+  }
+  RivenObject *object = target.as.o;
+  for (int i = 0; i < object->type->field_count; i++) {
+    if (strcmp(object->type->field_names[i], name) == 0) {
+      return &object->fields[i];
+    }
+  }
+
+  char message[128];
+  snprintf(message, sizeof(message), "%s has no field named %s", object->type->name, name);
+  riven_runtime_error(line, message);
+  return NULL;
+}
+
+// (Point *)riven_unbox_object(value, &riven_Point_type, line)
+static inline void *riven_unbox_object(RivenValue value, const RivenType *type, int line) {
+  if (value.kind != RIVEN_OBJECT || value.as.o->type != type) {
+    char message[128];
+    snprintf(message, sizeof(message), "expected a %s", type->name);
+    riven_runtime_error(line, message);
+  }
+  return value.as.o;
+}
+"#;
+
+// a function value is a closure: the C function to call through, plus a copy of
+// the variables it captured from the functions around it
+pub const FUNCTION_RUNTIME: &str = r#"
+struct RivenClosure {
+  const char *name;
+  int arity;
+  RivenValue (*call)(void *env, RivenValue *args, int line);
+  void *env;
+};
+
+static inline RivenClosure *riven_closure_new(const char *name, int arity,
+                                              RivenValue (*call)(void *, RivenValue *, int),
+                                              void *env) {
+  RivenClosure *closure = malloc(sizeof(RivenClosure));
+  *closure = (RivenClosure){ name, arity, call, env };
+  return closure;
+}
+
+// riven_env_copy(&(riven_env_main__next){ step }, sizeof(riven_env_main__next))
+static inline void *riven_env_copy(const void *env, size_t size) {
+  void *copy = malloc(size);
+  memcpy(copy, env, size);
+  return copy;
+}
+
+static inline RivenValue riven_call(RivenClosure *closure, int argc, RivenValue *args, int line) {
+  if (argc != closure->arity) {
+    char message[128];
+    snprintf(message, sizeof(message), "function %s takes %d argument(s) but %d were given",
+             closure->name, closure->arity, argc);
+    riven_runtime_error(line, message);
+  }
+  return closure->call(closure->env, args, line);
+}
+"#;
+
+// indexing works on lists, maps and boxed items like the `grid[0]` in `grid[0][1]`
+pub const INDEX_RUNTIME: &str = r#"
+static inline RivenValue riven_value_get(RivenValue target, RivenValue key, int line) {
+  switch (target.kind) {
+  case RIVEN_LIST: return riven_list_get(target.as.l, key, line);
+  case RIVEN_MAP: return riven_map_get(target.as.m, key, line);
+  default: riven_runtime_error(line, "only lists and maps can be indexed"); return target;
+  }
+}
+
+static inline void riven_value_set(RivenValue target, RivenValue key, RivenValue value, int line) {
+  switch (target.kind) {
+  case RIVEN_LIST: riven_list_set(target.as.l, key, value, line); break;
+  case RIVEN_MAP: riven_map_set(target.as.m, key, value, line); break;
+  default: riven_runtime_error(line, "only lists and maps can be indexed");
+  }
+}
+
+#define riven_get(target, key, line) _Generic((target), \
+  RivenList *: riven_list_get, \
+  RivenMap *: riven_map_get, \
+  RivenValue: riven_value_get)(target, key, line)
+
+#define riven_set(target, key, value, line) _Generic((target), \
+  RivenList *: riven_list_set, \
+  RivenMap *: riven_map_set, \
+  RivenValue: riven_value_set)(target, key, value, line)
+
+#define riven_len(x) _Generic((x), \
+  char *: riven_str_len, \
+  const char *: riven_str_len, \
+  RivenList *: riven_list_len, \
+  RivenMap *: riven_map_len)(x)
+"#;
+
+pub const PRINT_RUNTIME: &str = r#"
+static inline void riven_print_int(int value) { printf("%d", value); }
+static inline void riven_print_double(double value) { printf("%.15g", value); }
+static inline void riven_print_bool(bool value) { printf("%s", value ? "True" : "False"); }
+static inline void riven_print_str(const char *value) { printf("%s", value); }
+static inline void riven_print_list(RivenList *list);
+static inline void riven_print_map(RivenMap *map);
+static inline void riven_print_object(void *object);
+static inline void riven_print_closure(RivenClosure *closure) {
+  if (closure == NULL) {
+    printf("0");
+    return;
+  }
+  printf("<function %s>", closure->name);
+}
+
+static inline void riven_print_boxed(RivenValue value) {
+  switch (value.kind) {
+  case RIVEN_INT: riven_print_int(value.as.i); break;
+  case RIVEN_DOUBLE: riven_print_double(value.as.d); break;
+  case RIVEN_BOOL: riven_print_bool(value.as.b); break;
+  case RIVEN_STR: riven_print_str(value.as.s); break;
+  case RIVEN_LIST: riven_print_list(value.as.l); break;
+  case RIVEN_MAP: riven_print_map(value.as.m); break;
+  case RIVEN_OBJECT: riven_print_object(value.as.o); break;
+  case RIVEN_FUNCTION: riven_print_closure(value.as.f); break;
+  }
+}
+
+// strings inside a list or map are quoted, so ["1"] and [1] look different
+static inline void riven_print_item(RivenValue value) {
+  if (value.kind == RIVEN_STR) {
+    printf("\"%s\"", value.as.s);
+  } else {
+    riven_print_boxed(value);
+  }
+}
+
+static inline void riven_print_list(RivenList *list) {
+  putchar('[');
+  for (int i = 0; i < list->len; i++) {
+    if (i > 0) {
+      printf(", ");
+    }
+    riven_print_item(list->items[i]);
+  }
+  putchar(']');
+}
+
+static inline void riven_print_map(RivenMap *map) {
+  putchar('{');
+  for (int i = 0; i < map->len; i++) {
+    if (i > 0) {
+      printf(", ");
+    }
+    riven_print_item(map->entries[i].key);
+    printf(": ");
+    riven_print_item(map->entries[i].value);
+  }
+  putchar('}');
+}
+
+// Point(x=1, y=2)
+static inline void riven_print_object(void *value) {
+  RivenObject *object = value;
+  if (object == NULL) {
+    printf("0");
+    return;
+  }
+  printf("%s(", object->type->name);
+  for (int i = 0; i < object->type->field_count; i++) {
+    if (i > 0) {
+      printf(", ");
+    }
+    printf("%s=", object->type->field_names[i]);
+    riven_print_item(object->fields[i]);
+  }
+  putchar(')');
+}
+
+#define riven_print_value(x) _Generic((x), \
+  bool: riven_print_bool, \
+  int: riven_print_int, \
+  double: riven_print_double, \
+  char *: riven_print_str, \
+  const char *: riven_print_str, \
+  RivenList *: riven_print_list, \
+  RivenMap *: riven_print_map, \
+  RivenClosure *: riven_print_closure, \
+  RivenValue: riven_print_boxed, \
+  default: riven_print_object)(x)
+"#;
+
+pub const INPUT_RUNTIME: &str = r#"
+static inline char *riven_input(const char *prompt) {
+  printf("%s", prompt);
+  fflush(stdout);
+
+  size_t cap = 64, len = 0;
+  char *line = malloc(cap);
+  int c;
+  while ((c = getchar()) != EOF && c != '\n') {
+    if (len + 1 == cap) {
+      cap *= 2;
+      line = realloc(line, cap);
+    }
+    if (c != '\r') {
+      line[len++] = (char)c;
+    }
+  }
+  line[len] = '\0';
+  return line;
+}
+
+static inline double riven_input_number(const char *prompt, int line) {
+  for (;;) {
+    char *text = riven_input(prompt);
+    char *end;
+    double value = strtod(text, &end);
+    while (*end == ' ' || *end == '\t') {
+      end++;
+    }
+    bool ok = end != text && *end == '\0';
+    free(text);
+    if (ok) {
+      return value;
+    }
+    if (feof(stdin)) {
+      riven_runtime_error(line, "no more input, expected a number");
+    }
+    printf("that is not a number, please try again\n");
+  }
+}
+"#;
+
+/// A Riven builtin that maps one to one onto a runtime function.
+#[derive(Debug)]
+pub struct Builtin {
+  pub name: &'static str,
+  pub c_name: &'static str,
+  // the last argument is stored in a list or map, so it has to be boxed
+  pub box_last_arg: bool,
+  // the line of the call follows the arguments, for the runtime errors
+  pub line_arg: bool,
+}
+
+pub const BUILTIN_FUNCTIONS: &[Builtin] = &[
+  Builtin {
+    name: "input",
+    c_name: "riven_input",
+    box_last_arg: false,
+    line_arg: false,
+  },
+  Builtin {
+    name: "input_number",
+    c_name: "riven_input_number",
+    box_last_arg: false,
+    line_arg: true,
+  },
+  Builtin {
+    name: "len",
+    c_name: "riven_len",
+    box_last_arg: false,
+    line_arg: false,
+  },
+  Builtin {
+    name: "keys",
+    c_name: "riven_map_keys",
+    box_last_arg: false,
+    line_arg: false,
+  },
+  Builtin {
+    name: "append",
+    c_name: "riven_list_append",
+    box_last_arg: true,
+    line_arg: false,
+  },
+  Builtin {
+    name: "has",
+    c_name: "riven_map_has",
+    box_last_arg: true,
+    line_arg: false,
+  },
+];
+
+pub fn find_builtin(name: &str) -> Option<&'static Builtin> {
+  BUILTIN_FUNCTIONS.iter().find(|builtin| builtin.name == name)
+}
@@ -0,0 +1,326 @@
+use analyzer::infer::{child_path, infer, nested_functions, statements, TypeInfo};
+use analyzer::locals::{plan_declarations, Declarations};
+use analyzer::operands::Operation;
+use analyzer::types::Type;
+use parser::ast::{
+  ApplyExpr, BinOp, CallExpr, Expression, Factor, FactorValue, Identifier, LogicExpr, LogicOp,
+  Program, Statement, StatementValue, StmtList,
+};
+use parser::location::Location;
+
+use crate::codegen::{c_function_name, collect_structs, CodeGenerator, Emitter};
+
+// a number is computed like a C int unless a double is part of it, values only
+// known at runtime are computed as doubles
+pub(crate) fn is_int(t: &Type) -> bool {
+  matches!(t, Type::Int | Type::Bool | Type::Unknown)
+}
+
+// whether the program has statements outside functions, riven_init runs them
+pub(crate) fn has_init(stmt_list: &StmtList) -> bool {
+  statements(stmt_list)
+    .iter()
+    .any(|stmt| !is_declaration(stmt))
+}
+
+fn is_declaration(stmt: &Statement) -> bool {
+  matches!(
+    stmt.0,
+    StatementValue::FunctionStmt(_) | StatementValue::StructStmt(_)
+  )
+}
+
+// generated arithmetic: its code, whether it is a C int, and how tightly it
+// binds, 0 for a single value
+pub(crate) struct Arithmetic {
+  pub code: String,
+  pub int: bool,
+  pub precedence: u8,
+}
+
+impl Arithmetic {
+  // the code as an operand of an operator binding as tightly as `precedence`,
+  // the right operand of `a - (b - c)` keeps its parentheses
+  pub fn operand(&self, precedence: u8, right: bool) -> String {
+    let grouped = self.precedence > 0
+      && (self.precedence < precedence || (right && self.precedence == precedence));
+    match grouped {
+      true => format!("({})", self.code),
+      false => self.code.clone(),
+    }
+  }
+}
+
+// what the JavaScript and Python generators lower a program with, as in
+// CCodeGenManager
+pub(crate) struct Script<'a> {
+  pub emitter: &'a mut Emitter,
+  // the expression being generated, statements are emitted a line at a time
+  pub code: String,
+  // the statement and function being generated and what visit_program found
+  pub line: usize,
+  pub location: Location,
+  pub types: TypeInfo,
+  pub path: String,
+  pub declarations: Declarations,
+  // names of the structs already emitted
+  pub structs: Vec<String>,
+}
+
+impl<'a> Script<'a> {
+  pub fn new(emitter: &'a mut Emitter) -> Self {
+    Script {
+      emitter,
+      code: String::new(),
+      line: 0,
+      location: Location::default(),
+      types: TypeInfo::default(),
+      path: String::new(),
+      declarations: Declarations::default(),
+      structs: vec![],
+    }
+  }
+}
+
+// the lowering JavaScript and Python share, they only spell the result differently
+pub(crate) trait ScriptCodeGen<'a>: CodeGenerator + Sized {
+  fn script(&self) -> &Script<'a>;
+  fn script_mut(&mut self) -> &mut Script<'a>;
+
+  // the name of a Riven variable, function or struct in the target language
+  fn name(name: &str) -> String;
+  // a string literal
+  fn string(text: &str) -> String;
+  // an expression run for what it does, as a statement
+  fn statement(code: &str) -> String;
+  // a variable of a block declared before its first assignment
+  fn hoisted_code(name: &str, var_type: &Type) -> String;
+  fn combine(&self, left: Arithmetic, op: &BinOp, right: Arithmetic) -> Arithmetic;
+  // the function running the statements outside functions
+  fn visit_init(&mut self, stmt_list: &StmtList);
+
+  // emit a whole line at the indentation of the current block
+  fn emit_line(&mut self, code: &str) {
+    self.script_mut().emitter.emit_line(code);
+  }
+
+  fn push(&mut self, code: &str) {
+    self.script_mut().code.push_str(code);
+  }
+
+  fn is_struct(&self, name: &Identifier) -> bool {
+    self.script().structs.contains(&name.to_string())
+  }
+
+  fn type_of(&self, expr: &Expression) -> Type {
+    let script = self.script();
+    script.types.type_of(&script.path, expr)
+  }
+
+  // generate something on its own and give back its code
+  fn generate(&mut self, visit: impl FnOnce(&mut Self)) -> String {
+    let outer = std::mem::take(&mut self.script_mut().code);
+    visit(self);
+    std::mem::replace(&mut self.script_mut().code, outer)
+  }
+
+  fn expr_code(&mut self, expr: &Expression) -> String {
+    self.generate(|gen| gen.visit_expr(expr))
+  }
+
+  fn factor_code(&mut self, factor: &Factor) -> String {
+    self.generate(|gen| gen.visit_factor(factor))
+  }
+
+  fn exprs_code(&mut self, exprs: &[Expression]) -> Vec<String> {
+    exprs.iter().map(|expr| self.expr_code(expr)).collect()
+  }
+
+  fn arithmetic(&mut self, operation: &Operation) -> Arithmetic {
+    match operation {
+      Operation::Factor(factor) => {
+        let script = self.script();
+        let int = is_int(&script.types.type_of_factor(&script.path, factor));
+        Arithmetic {
+          code: self.factor_code(factor),
+          int,
+          precedence: 0,
+        }
+      }
+      Operation::Group(inner) => self.arithmetic(inner),
+      Operation::Binary(left, op, right) => {
+        let left = self.arithmetic(left);
+        let right = self.arithmetic(right);
+        self.combine(left, op, right)
+      }
+    }
+  }
+
+  fn visit_operation(&mut self, operation: &Operation) {
+    let code = self.arithmetic(operation).code;
+    self.push(&code);
+  }
+
+  fn visit_script_factor(&mut self, factor: &Factor) {
+    match factor {
+      Factor(FactorValue::Primary(primary)) => self.visit_primary(primary),
+      // a function used as a value becomes a closure
+      Factor(FactorValue::Identifier(identifier)) => {
+        let name = identifier.to_string();
+        let script = self.script();
+        let function = match script.types.resolve_variable(&script.path, &name) {
+          Some(_) => None,
+          None => script.types.resolve_function(&script.path, &name),
+        };
+        match function {
+          Some(function) => self.visit_closure(&function),
+          None => self.visit_identifier(identifier),
+        }
+      }
+      Factor(FactorValue::Function(function, _)) => {
+        let function = child_path(&self.script().path, &function.0.to_string());
+        self.visit_closure(&function)
+      }
+      Factor(FactorValue::CallExpr(call_expr)) => self.visit_call_expr(call_expr),
+      Factor(FactorValue::List(list_expr)) => self.visit_list_expr(list_expr),
+      Factor(FactorValue::Map(map_expr)) => self.visit_map_expr(map_expr),
+      Factor(FactorValue::Index(index_expr)) => self.visit_index_expr(index_expr),
+      Factor(FactorValue::Field(field_expr)) => self.visit_field_expr(field_expr),
+      Factor(FactorValue::Apply(ApplyExpr(function, args))) => {
+        self.visit_value_call(function, &args.0)
+      }
+    }
+  }
+
+  // f => riven_closure("f", 1, f), the captured variables are copied into the closure
+  fn visit_closure(&mut self, function: &str) {
+    let name = Self::name(&c_function_name(function));
+    let display_name = Self::string(function.rsplit("::").next().unwrap_or(function));
+    let types = &self.script().types;
+    let arity = types.param_types(function).len();
+    let captures: Vec<String> = types
+      .captures(function)
+      .iter()
+      .map(|capture| Self::name(capture))
+      .collect();
+    let code = match captures.is_empty() {
+      true => format!("riven_closure({}, {}, {})", display_name, arity, name),
+      false => format!(
+        "riven_closure({}, {}, {}, [{}])",
+        display_name,
+        arity,
+        name,
+        captures.join(", ")
+      ),
+    };
+    self.push(&code);
+  }
+
+  // f(3) where f is a variable => riven_call(f, [3], line)
+  fn visit_value_call(&mut self, function: &Factor, args: &[Expression]) {
+    let function = self.factor_code(function);
+    let args = self.exprs_code(args);
+    let code = format!(
+      "riven_call({}, [{}], {})",
+      function,
+      args.join(", "),
+      self.script().line
+    );
+    self.push(&code);
+  }
+
+  // p.x reads the property when p is known to be a struct with an x,
+  // riven_field checks it at runtime otherwise
+  fn field_code(&mut self, target: &Factor, field: &Identifier) -> (String, bool) {
+    let script = self.script();
+    let has_field = match script.types.type_of_factor(&script.path, target) {
+      Type::Struct(name) => script.types.field_type(&name, &field.to_string()).is_some(),
+      _ => false,
+    };
+    (self.factor_code(target), has_field)
+  }
+
+  // declare the variables of a block used outside the if where they are first assigned
+  fn visit_hoisted(&mut self, block: &Location) {
+    let script = self.script();
+    let lines: Vec<String> = script
+      .declarations
+      .hoisted(block)
+      .iter()
+      .map(|name| Self::hoisted_code(name, &script.types.variable_type(&script.path, name)))
+      .collect();
+    lines.iter().for_each(|line| self.emit_line(line));
+  }
+
+  // the statements outside functions, in the body of riven_init
+  fn visit_init_stmts(&mut self, stmt_list: &StmtList) {
+    for stmt in statements(stmt_list) {
+      if !is_declaration(stmt) {
+        self.visit_stmt(stmt);
+      }
+    }
+  }
+
+  fn visit_nested_functions(&mut self, stmt_list: &StmtList) {
+    for (function_stmt, location) in nested_functions(stmt_list) {
+      self.script_mut().line = location.row();
+      self.script_mut().location = location.clone();
+      self.visit_function_stmt(function_stmt);
+    }
+  }
+
+  // a condition of an if or a loop, without the parentheses around `and` and `or`
+  fn condition_code(&mut self, logic_expr: &LogicExpr) -> String {
+    let code = self.generate(|gen| gen.visit_logic_expr(logic_expr));
+    match logic_expr.0 {
+      LogicOp::And | LogicOp::Or => code[1..code.len() - 1].to_owned(),
+      _ => code,
+    }
+  }
+
+  // print(a, b) => riven_print(a, b)
+  fn visit_print_call(&mut self, function: &str, call_expr: &CallExpr) {
+    let args = self.exprs_code(&call_expr.1 .0);
+    self.push(format!("{}({})", function, args.join(", ")).as_str());
+  }
+
+  fn visit_script_stmt(&mut self, stmt: &Statement) {
+    self.script_mut().line = stmt.location().row();
+    self.script_mut().location = stmt.location().clone();
+    match &stmt.0 {
+      StatementValue::AssignStmt(stmt) => self.visit_assign_stmt(stmt),
+      StatementValue::IndexAssignStmt(stmt) => self.visit_index_assign_stmt(stmt),
+      StatementValue::FieldAssignStmt(stmt) => self.visit_field_assign_stmt(stmt),
+      StatementValue::CallStmt(stmt) => self.visit_call_stmt(stmt),
+      StatementValue::ApplyStmt(stmt) => {
+        let code = self.factor_code(&Factor::from(FactorValue::Apply(stmt.clone())));
+        self.emit_line(&Self::statement(&code));
+      }
+      // emitted by visit_nested_functions
+      StatementValue::FunctionStmt(_) => (),
+      // structs are emitted by visit_program before everything else
+      StatementValue::StructStmt(stmt) if self.is_struct(&stmt.0) => (),
+      StatementValue::StructStmt(stmt) => self.visit_struct_stmt(stmt),
+      StatementValue::IfStmt(stmt) => self.visit_if_stmt(stmt),
+      StatementValue::WhileStmt(stmt) => self.visit_while_stmt(stmt),
+      StatementValue::ReturnStmt(stmt) => self.visit_return_stmt(stmt),
+    }
+  }
+
+  // the structs, the variables of the program, riven_init and the functions,
+  // the target language adds how main is run
+  fn visit_script_program(&mut self, program: &Program) {
+    self.script_mut().types = infer(program).types;
+    self.script_mut().declarations = plan_declarations(program);
+
+    let mut structs = vec![];
+    collect_structs(&program.0, &mut structs);
+    structs.iter().for_each(|stmt| self.visit_struct_stmt(stmt));
+
+    self.visit_hoisted(&Location::default());
+    if has_init(&program.0) {
+      self.visit_init(&program.0);
+    }
+    self.visit_nested_functions(&program.0);
+  }
+}
@@ -0,0 +1,831 @@
+use analyzer::flow::default_return;
+use analyzer::infer::{child_path, infer, nested_functions, statements, Passed, TypeInfo};
+use analyzer::operands::Operation;
+use analyzer::types::Type;
+use parser::ast::{
+  ArithmeticExpr, AssignStmt, BinOp, CallExpr, CompareExpr, CompareOp, ComponentArithmeticExpr,
+  ComponentFactor, ComponentTerm, Expression, ExpressionValue, Factor, FactorValue,
+  FieldAssignStmt, FieldExpr, FunctionStmt, Identifier, IfStmt, IndexAssignStmt, IndexExpr,
+  ListExpr, LogicExpr, LogicOp, MapExpr, Primary, PrimaryValue, Program, ReturnStmt, Statement,
+  StatementValue, StmtList, StructStmt, Term, WhileStmt,
+};
+
+use crate::codegen::{CodeGenerator, Emitter};
+
+// what the host gives the module, e.g. from JavaScript:
+//   WebAssembly.instantiate(bytes, { riven: { print_i32: (n) => ..., ... } })
+// texts are passed as the offset and length of their UTF-8 bytes in the memory
+// exported as `memory`; runtime_error is given the line and the message, and
+// must not return
+pub const WAT_IMPORTS: &str = r#"  (import "riven" "print_i32" (func $print_i32 (param i32)))
+  (import "riven" "print_f64" (func $print_f64 (param f64)))
+  (import "riven" "print_bool" (func $print_bool (param i32)))
+  (import "riven" "print_str" (func $print_str (param i32 i32)))
+  (import "riven" "printf" (func $printf (param i32 i32 i32 i32) (result i32)))
+  (import "riven" "input_number" (func $input_number (param i32 i32) (result f64)))
+  (import "riven" "runtime_error" (func $runtime_error (param i32 i32 i32)))
+  (memory (export "memory") 1)"#;
+
+// printf is given the offset of its arguments, each stored as an f64 from here
+const PRINTF_ARGS: usize = 0;
+// the texts of the program follow the arguments of printf
+const DATA_START: usize = 256;
+
+// the wasm type of a value: ints and bools are i32, floats f64, a type wat
+// has no value for is None
+fn wasm_type(t: &Type) -> Option<&'static str> {
+  match t {
+    Type::Int | Type::Bool | Type::Unknown => Some("i32"),
+    Type::Float => Some("f64"),
+    _ => None,
+  }
+}
+
+// `"a\n"` as a wat string, every byte that is not plain ASCII is escaped
+fn wat_string(bytes: &[u8]) -> String {
+  let mut text = String::from("\"");
+  for byte in bytes {
+    match byte {
+      b'"' | b'\\' => text.push_str(&format!("\\{}", *byte as char)),
+      0x20..=0x7e => text.push(*byte as char),
+      _ => text.push_str(&format!("\\{:02x}", byte)),
+    }
+  }
+  text.push('"');
+  text
+}
+
+// the variables a function assigns, in the order of their first assignment;
+// those of the functions nested in it are theirs
+pub(crate) fn assigned_variables(body: &StmtList, variables: &mut Vec<String>) {
+  for stmt in statements(body) {
+    match &stmt.0 {
+      StatementValue::AssignStmt(AssignStmt(name, _, _)) => {
+        let name = name.to_string();
+        if !variables.contains(&name) {
+          variables.push(name);
+        }
+      }
+      StatementValue::IfStmt(IfStmt(_, Some(body)))
+      | StatementValue::WhileStmt(WhileStmt(_, Some(body))) => assigned_variables(body, variables),
+      _ => (),
+    }
+  }
+}
+
+pub struct WatCodeGen<'a> {
+  emitter: &'a mut Emitter,
+  // the expression being generated and its wasm type, statements are emitted a line at a time
+  code: String,
+  code_type: &'static str,
+  // how deep the statement being generated is nested
+  indent: usize,
+  // the statement and function being generated and what visit_program found,
+  // as in CCodeGenManager
+  line: usize,
+  types: TypeInfo,
+  path: String,
+  // the texts of the program with their offset in the memory
+  data: Vec<(Vec<u8>, usize)>,
+  data_end: usize,
+  // loops generated so far, they name the labels of the next one
+  loops: usize,
+  // what the program uses that wat cannot express, see unsupported()
+  unsupported: Vec<String>,
+  // whether main first calls $riven_init, the statements outside functions
+  has_init: bool,
+}
+
+impl<'a> WatCodeGen<'a> {
+  pub fn new(emitter: &'a mut Emitter) -> Self {
+    emitter.push_header("(module");
+    emitter.push_header(WAT_IMPORTS);
+
+    WatCodeGen {
+      emitter,
+      code: String::new(),
+      code_type: "i32",
+      indent: 1,
+      line: 0,
+      types: TypeInfo::default(),
+      path: String::new(),
+      data: vec![],
+      data_end: DATA_START,
+      loops: 0,
+      unsupported: vec![],
+      has_init: false,
+    }
+  }
+
+  // `line 3: lists are not supported by the wat target`, for every use of
+  // something only the C target has: texts other than printed ones, lists,
+  // maps, structs and functions used as values
+  pub fn unsupported(&self) -> &[String] {
+    &self.unsupported
+  }
+
+  // the first thing wat cannot express on each line
+  fn report(&mut self, what: &str) {
+    let prefix = format!("line {}: ", self.line);
+    if !self
+      .unsupported
+      .iter()
+      .any(|message| message.starts_with(&prefix))
+    {
+      let message = format!("{}{} not supported by the wat target", prefix, what);
+      self.unsupported.push(message);
+    }
+  }
+
+  // report what the expression uses, any type is fine for code that never runs
+  fn unsupport(&mut self, what: &str) {
+    self.report(what);
+    self.push("(unreachable)", "i32");
+  }
+
+  fn push(&mut self, code: &str, code_type: &'static str) {
+    self.code.push_str(code);
+    self.code_type = code_type;
+  }
+
+  // emit a whole line at the indentation of the current block
+  fn emit_line(&mut self, code: &str) {
+    let line = format!("{}{}\n", "  ".repeat(self.indent), code);
+    self.emitter.emmit(line.as_str());
+  }
+
+  // `(local.get $x)` or `(global.get $global.x)` for a variable assigned outside functions
+  fn variable(&self, name: &str) -> (&'static str, String) {
+    match self.types.resolve_variable(&self.path, name).as_deref() {
+      Some("") => ("global", format!("$global.{}", name)),
+      _ => ("local", format!("${}", name)),
+    }
+  }
+
+  // the type of a variable, the values wat has no type for are reported where they are made
+  fn variable_type(&self, name: &str) -> &'static str {
+    wasm_type(&self.types.variable_type(&self.path, name)).unwrap_or("i32")
+  }
+
+  // the offset of a text in the memory, each text is stored once
+  fn text(&mut self, text: &str) -> (usize, usize) {
+    let bytes = text.as_bytes().to_vec();
+    if let Some((_, offset)) = self.data.iter().find(|(data, _)| *data == bytes) {
+      return (*offset, bytes.len());
+    }
+    let offset = self.data_end;
+    self.data_end += bytes.len();
+    self.data.push((bytes, offset));
+    (offset, text.len())
+  }
+
+  // generate an expression on its own and give back its code and type
+  fn generate(&mut self, visit: impl FnOnce(&mut Self)) -> (String, &'static str) {
+    let outer = std::mem::take(&mut self.code);
+    let outer_type = self.code_type;
+    visit(self);
+    let code = std::mem::replace(&mut self.code, outer);
+    (code, std::mem::replace(&mut self.code_type, outer_type))
+  }
+
+  fn expr_code(&mut self, expr: &Expression) -> (String, &'static str) {
+    self.generate(|gen| gen.visit_expr(expr))
+  }
+
+  // an expression where a value of type `expected` is wanted
+  fn expr_code_as(&mut self, expr: &Expression, expected: &'static str) -> String {
+    let (code, t) = self.expr_code(expr);
+    convert(code, t, expected)
+  }
+
+  fn arithmetic(&mut self, operation: &Operation) -> (String, &'static str) {
+    match operation {
+      Operation::Factor(factor) => self.generate(|gen| gen.visit_factor(factor)),
+      Operation::Group(inner) => self.arithmetic(inner),
+      Operation::Binary(left, op, right) => {
+        let left = self.arithmetic(left);
+        let right = self.arithmetic(right);
+        self.combine(left, op, right)
+      }
+    }
+  }
+
+  // i32 wraps around like a C int, `7 / 2` is 3 and dividing by zero stops the program
+  fn combine(
+    &self,
+    (a, a_type): (String, &'static str),
+    op: &BinOp,
+    (b, b_type): (String, &'static str),
+  ) -> (String, &'static str) {
+    let t = if a_type == "f64" || b_type == "f64" {
+      "f64"
+    } else {
+      "i32"
+    };
+    let (a, b) = (convert(a, a_type, t), convert(b, b_type, t));
+    let code = match op {
+      BinOp::Add => format!("({}.add {} {})", t, a, b),
+      BinOp::Min => format!("({}.sub {} {})", t, a, b),
+      BinOp::Time => format!("({}.mul {} {})", t, a, b),
+      BinOp::Div if t == "i32" => {
+        format!("(call $riven_idiv {} {} (i32.const {}))", a, b, self.line)
+      }
+      BinOp::Div => format!("(f64.div {} {})", a, b),
+    };
+    (code, t)
+  }
+
+  // a value used as a condition, 0 is false
+  fn condition_code(&mut self, logic_expr: &LogicExpr) -> String {
+    self.generate(|gen| gen.visit_logic_expr(logic_expr)).0
+  }
+
+  // print(a, b) => one call printing each value, and one for each space between them
+  // print(a, b) => a call printing each value, and one for each space between them
+  fn print_calls(&mut self, call_expr: &CallExpr, newline: bool) -> Vec<String> {
+    let mut calls = vec![];
+    for (idx, arg) in call_expr.1 .0.iter().enumerate() {
+      if idx > 0 {
+        calls.push(self.print_str_code(" "));
+      }
+      if let Some(text) = string_literal(arg) {
+        calls.push(self.print_str_code(&text));
+        continue;
+      }
+      let t = self.types.type_of(&self.path, arg);
+      let print = match t {
+        Type::Bool => "$print_bool",
+        Type::Float => "$print_f64",
+        Type::Int | Type::Unknown => "$print_i32",
+        _ => {
+          self.report(format!("printing {} is", t).as_str());
+          calls.push("(unreachable)".to_owned());
+          continue;
+        }
+      };
+      let (code, _) = self.expr_code(arg);
+      calls.push(format!("(call {} {})", print, code));
+    }
+    if newline {
+      calls.push(self.print_str_code("\n"));
+    }
+    calls
+  }
+
+  fn print_str_code(&mut self, text: &str) -> String {
+    let (offset, len) = self.text(text);
+    format!(
+      "(call $print_str (i32.const {}) (i32.const {}))",
+      offset, len
+    )
+  }
+
+  // printf("%d %f\n", n, x) => the values are stored as f64 for the host to
+  // format, then the call giving back the number of bytes printed. Every value
+  // is computed before the first is stored, a printf in one of them reuses the
+  // same memory
+  fn printf_calls(&mut self, call_expr: &CallExpr) -> Vec<String> {
+    let args = &call_expr.1 .0;
+    let Some(format) = args.first().and_then(string_literal) else {
+      self.report("a printf format that is not a text literal is");
+      return vec!["(unreachable)".to_owned()];
+    };
+    let mut calls = vec![];
+    for (idx, arg) in args[1..].iter().enumerate() {
+      if self.types.type_of(&self.path, arg) == Type::Str {
+        self.report("printing a text with printf is");
+        return vec!["(unreachable)".to_owned()];
+      }
+      let value = self.expr_code_as(arg, "f64");
+      let address = PRINTF_ARGS + idx * 8;
+      calls.push(format!("(i32.const {}) {}", address, value));
+    }
+    // each store takes the last address and value left on the stack
+    calls.extend(args[1..].iter().map(|_| "f64.store".to_owned()));
+    let (offset, len) = self.text(&format);
+    calls.push(format!(
+      "(call $printf (i32.const {}) (i32.const {}) (i32.const {}) (i32.const {}))",
+      offset,
+      len,
+      PRINTF_ARGS,
+      args.len() - 1
+    ));
+    calls
+  }
+
+  // a blank line in front of every function
+  fn start_function(&mut self) {
+    self.emitter.emmit("\n");
+  }
+
+  // $riven_init runs the statements outside functions, main calls it first
+  fn visit_init(&mut self, stmt_list: &StmtList) {
+    self.start_function();
+    self.emit_line("(func $riven_init");
+    self.indent += 1;
+    for stmt in statements(stmt_list) {
+      if !matches!(
+        stmt.0,
+        StatementValue::FunctionStmt(_) | StatementValue::StructStmt(_)
+      ) {
+        self.visit_stmt(stmt);
+      }
+    }
+    self.indent -= 1;
+    self.emit_line(")");
+  }
+
+  fn visit_nested_functions(&mut self, stmt_list: &StmtList) {
+    for (function_stmt, location) in nested_functions(stmt_list) {
+      self.line = location.row();
+      self.visit_function_stmt(function_stmt);
+    }
+  }
+
+  // the statements of an if, a loop or a function, one level deeper
+  fn visit_block(&mut self, stmt_list: Option<&StmtList>) {
+    self.indent += 1;
+    if let Some(stmt_list) = stmt_list {
+      self.visit_stmt_list(stmt_list);
+    }
+    self.indent -= 1;
+  }
+
+  // C int division: dividing by zero is a runtime error, and the smallest int
+  // divided by -1 wraps around instead of trapping
+  fn emit_idiv(&mut self) {
+    let (offset, len) = self.text("division by zero");
+    self.start_function();
+    self
+      .emit_line("(func $riven_idiv (param $a i32) (param $b i32) (param $line i32) (result i32)");
+    self.emit_line("  (if (i32.eqz (local.get $b))");
+    self.emit_line("    (then");
+    self.emit_line(
+      format!(
+        "      (call $runtime_error (local.get $line) (i32.const {}) (i32.const {}))",
+        offset, len
+      )
+      .as_str(),
+    );
+    self.emit_line("      (unreachable)");
+    self.emit_line("    )");
+    self.emit_line("  )");
+    self.emit_line("  (if (i32.eq (local.get $b) (i32.const -1))");
+    self.emit_line("    (then");
+    self.emit_line("      (return (i32.sub (i32.const 0) (local.get $a)))");
+    self.emit_line("    )");
+    self.emit_line("  )");
+    self.emit_line("  (i32.div_s (local.get $a) (local.get $b))");
+    self.emit_line(")");
+  }
+}
+
+// convert a value of type `from` to type `to`, a float stored into an int
+// loses its fraction like in C
+fn convert(code: String, from: &str, to: &str) -> String {
+  match (from, to) {
+    ("i32", "f64") => format!("(f64.convert_i32_s {})", code),
+    ("f64", "i32") => format!("(i32.trunc_sat_f64_s {})", code),
+    _ => code,
+  }
+}
+
+// the text of an expression that is only a string literal
+pub(crate) fn string_literal(expr: &Expression) -> Option<String> {
+  match expr.as_factor() {
+    Some(Factor(FactorValue::Primary(Primary(PrimaryValue::String(s))))) => Some(s.clone()),
+    _ => None,
+  }
+}
+
+impl<'a> CodeGenerator for WatCodeGen<'a> {
+  fn visit_primary(&mut self, primary: &Primary) {
+    match &primary.0 {
+      PrimaryValue::String(_) => self.unsupport("texts other than printed ones are"),
+      // a C int literal
+      PrimaryValue::Number(n) => self.push(&format!("(i32.const {})", *n as i32), "i32"),
+      PrimaryValue::Float(n) if n.is_nan() => self.push("(f64.const nan)", "f64"),
+      PrimaryValue::Float(n) if n.is_infinite() && *n > 0.0 => self.push("(f64.const inf)", "f64"),
+      PrimaryValue::Float(n) if n.is_infinite() => self.push("(f64.const -inf)", "f64"),
+      PrimaryValue::Float(n) => self.push(&format!("(f64.const {:?})", n), "f64"),
+      PrimaryValue::Bool(b) => self.push(&format!("(i32.const {})", *b as i32), "i32"),
+    }
+  }
+
+  fn visit_identifier(&mut self, identifier: &Identifier) {
+    let name = identifier.to_string();
+    let t = self.variable_type(&name);
+    let (scope, variable) = self.variable(&name);
+    self.push(&format!("({}.get {})", scope, variable), t);
+  }
+
+  fn visit_factor(&mut self, factor: &Factor) {
+    match &factor.0 {
+      FactorValue::Primary(primary) => self.visit_primary(primary),
+      FactorValue::Identifier(identifier) => {
+        match self
+          .types
+          .resolve_variable(&self.path, &identifier.to_string())
+        {
+          Some(_) => self.visit_identifier(identifier),
+          None => self.unsupport("functions used as values are"),
+        }
+      }
+      FactorValue::Function(_, _) => self.unsupport("functions used as values are"),
+      FactorValue::CallExpr(call_expr) => self.visit_call_expr(call_expr),
+      FactorValue::List(list_expr) => self.visit_list_expr(list_expr),
+      FactorValue::Map(map_expr) => self.visit_map_expr(map_expr),
+      FactorValue::Index(index_expr) => self.visit_index_expr(index_expr),
+      FactorValue::Field(field_expr) => self.visit_field_expr(field_expr),
+      FactorValue::Apply(_) => self.unsupport("functions used as values are"),
+    }
+  }
+
+  fn visit_term(&mut self, term: &Term) {
+    let (code, t) = self.arithmetic(&Operation::of_term(term));
+    self.push(&code, t);
+  }
+
+  fn visit_arithmetic_expr(&mut self, arithmetic_expr: &ArithmeticExpr) {
+    let (code, t) = self.arithmetic(&Operation::of_arithmetic_expr(arithmetic_expr));
+    self.push(&code, t);
+  }
+
+  fn visit_call_expr(&mut self, call_expr: &CallExpr) {
+    let name = call_expr.0.to_string();
+    match name.as_str() {
+      "print" | "println" => {
+        let calls = self.print_calls(call_expr, name == "println");
+        let code = match calls.len() {
+          0 => "(nop)".to_owned(),
+          1 => calls[0].clone(),
+          _ => format!("(block {})", calls.join(" ")),
+        };
+        return self.push(&code, "");
+      }
+      "printf" => {
+        let calls = self.printf_calls(call_expr);
+        let code = match calls.len() {
+          1 => calls[0].clone(),
+          _ => format!("(block (result i32) {})", calls.join(" ")),
+        };
+        return self.push(&code, "i32");
+      }
+      "input_number" => {
+        let Some(prompt) = call_expr.1 .0.first().and_then(string_literal) else {
+          return self.unsupport("a prompt that is not a text literal is");
+        };
+        let (offset, len) = self.text(&prompt);
+        let code = format!(
+          "(call $input_number (i32.const {}) (i32.const {}))",
+          offset, len
+        );
+        return self.push(&code, "f64");
+      }
+      _ => (),
+    }
+
+    if self.types.resolve_variable(&self.path, &name).is_some() {
+      return self.unsupport("functions used as values are");
+    }
+    if self.types.is_struct(&name) {
+      return self.unsupport("structs are");
+    }
+    let Some(function) = self.types.resolve_function(&self.path, &name) else {
+      return self.unsupport(format!("the builtin `{}` is", name).as_str());
+    };
+
+    let args: Vec<String> = self
+      .types
+      .passed(&function, &call_expr.1 .0)
+      .into_iter()
+      .map(|passed| match passed {
+        Passed::Capture(name) => format!("(local.get ${})", name),
+        Passed::Arg(arg, param_type) => {
+          self.expr_code_as(arg, wasm_type(&param_type).unwrap_or("i32"))
+        }
+      })
+      .collect();
+    let result = match self.types.return_type(&function) {
+      Type::Void => "",
+      t => wasm_type(&t).unwrap_or("i32"),
+    };
+    let code = match args.is_empty() {
+      true => format!("(call ${})", function),
+      false => format!("(call ${} {})", function, args.join(" ")),
+    };
+    self.push(&code, result);
+  }
+
+  fn visit_list_expr(&mut self, _list_expr: &ListExpr) {
+    self.unsupport("lists are");
+  }
+
+  fn visit_map_expr(&mut self, _map_expr: &MapExpr) {
+    self.unsupport("maps are");
+  }
+
+  fn visit_index_expr(&mut self, _index_expr: &IndexExpr) {
+    self.unsupport("lists and maps are");
+  }
+
+  fn visit_field_expr(&mut self, _field_expr: &FieldExpr) {
+    self.unsupport("structs are");
+  }
+
+  // a value nobody uses is dropped
+  fn visit_call_stmt(&mut self, call_expr: &CallExpr) {
+    let name = call_expr.0.to_string();
+    let is_builtin = self.types.resolve_variable(&self.path, &name).is_none()
+      && self.types.resolve_function(&self.path, &name).is_none();
+    // what a print is made of, one call a line
+    let mut calls = match name.as_str() {
+      "print" | "println" if is_builtin => self.print_calls(call_expr, name == "println"),
+      "printf" if is_builtin => self.printf_calls(call_expr),
+      _ => vec![],
+    };
+    if name == "printf" {
+      if let Some(call) = calls.pop() {
+        calls.push(format!("(drop {})", call));
+      }
+    }
+    if is_builtin && name.starts_with("print") {
+      return calls.iter().for_each(|call| self.emit_line(call));
+    }
+
+    let (code, t) = self.generate(|gen| gen.visit_call_expr(call_expr));
+    match t {
+      "" => self.emit_line(&code),
+      _ => self.emit_line(&format!("(drop {})", code)),
+    }
+  }
+
+  fn visit_expr(&mut self, expr: &Expression) {
+    match &expr.0 {
+      ExpressionValue::ComponentArithmeticExpr(arith_expr) => {
+        self.visit_component_arithmetic_expr(arith_expr)
+      }
+    }
+  }
+
+  fn visit_compare_expr(&mut self, compare_expr: &CompareExpr) {
+    let (left, left_type) = self.expr_code(&compare_expr.0);
+    let (op, right) = match (&compare_expr.1, &compare_expr.2) {
+      (Some(op), Some(right)) => (op, right),
+      // a value used as a condition
+      _ => {
+        let code = match left_type {
+          "f64" => format!("(f64.ne {} (f64.const 0))", left),
+          _ => left,
+        };
+        return self.push(&code, "i32");
+      }
+    };
+    let (right, right_type) = self.expr_code(right);
+    let t = if left_type == "f64" || right_type == "f64" {
+      "f64"
+    } else {
+      "i32"
+    };
+    let op = match (op, t) {
+      (CompareOp::Eq, _) => "eq",
+      (CompareOp::Gt, "f64") => "gt",
+      (CompareOp::Lt, "f64") => "lt",
+      (CompareOp::Gt, _) => "gt_s",
+      (CompareOp::Lt, _) => "lt_s",
+    };
+    let code = format!(
+      "({}.{} {} {})",
+      t,
+      op,
+      convert(left, left_type, t),
+      convert(right, right_type, t)
+    );
+    self.push(&code, "i32");
+  }
+
+  // `and` and `or` only compute their second condition when it is needed
+  fn visit_logic_expr(&mut self, logic_expr: &LogicExpr) {
+    let operand = |gen: &mut Self, expr: &Option<Box<LogicExpr>>| match expr {
+      Some(expr) => gen.condition_code(expr),
+      None => "(i32.const 0)".to_owned(),
+    };
+    let code = match logic_expr.0 {
+      LogicOp::Bool => match &logic_expr.1 {
+        Some(compare_expr) => self.generate(|gen| gen.visit_compare_expr(compare_expr)).0,
+        None => "(i32.const 0)".to_owned(),
+      },
+      LogicOp::And => {
+        let (a, b) = (operand(self, &logic_expr.2), operand(self, &logic_expr.3));
+        format!("(if (result i32) {} (then {}) (else (i32.const 0)))", a, b)
+      }
+      LogicOp::Or => {
+        let (a, b) = (operand(self, &logic_expr.2), operand(self, &logic_expr.3));
+        format!("(if (result i32) {} (then (i32.const 1)) (else {}))", a, b)
+      }
+      LogicOp::Not => format!("(i32.eqz {})", operand(self, &logic_expr.2)),
+    };
+    self.push(&code, "i32");
+  }
+
+  fn visit_assign_stmt(&mut self, stmt: &AssignStmt) {
+    let name = stmt.0.to_string();
+    let var_type = self.variable_type(&name);
+    let value = self.expr_code_as(&stmt.1, var_type);
+    let (scope, variable) = self.variable(&name);
+    self.emit_line(&format!("({}.set {} {})", scope, variable, value));
+  }
+
+  fn visit_index_assign_stmt(&mut self, _stmt: &IndexAssignStmt) {
+    self.report("lists and maps are");
+    self.emit_line("(unreachable)");
+  }
+
+  fn visit_field_assign_stmt(&mut self, _stmt: &FieldAssignStmt) {
+    self.report("structs are");
+    self.emit_line("(unreachable)");
+  }
+
+  fn visit_return_stmt(&mut self, stmt: &ReturnStmt) {
+    let code = match wasm_type(&self.types.return_type(&self.path)) {
+      Some(t) => format!("(return {})", self.expr_code_as(&stmt.0, t)),
+      None => "(return)".to_owned(),
+    };
+    self.emit_line(&code);
+  }
+
+  fn visit_if_stmt(&mut self, stmt: &IfStmt) {
+    let condition = self.condition_code(&stmt.0);
+    self.emit_line(&format!("(if {}", condition));
+    self.emit_line("  (then");
+    self.indent += 1;
+    self.visit_block(stmt.1.as_deref());
+    self.indent -= 1;
+    self.emit_line("  )");
+    self.emit_line(")");
+  }
+
+  // while (i < 3) { ... } =>
+  //   (block $while_1_end (loop $while_1 (br_if $while_1_end (i32.eqz ...)) ... (br $while_1)))
+  fn visit_while_stmt(&mut self, stmt: &WhileStmt) {
+    self.loops += 1;
+    let label = format!("$while_{}", self.loops);
+    let condition = self.condition_code(&stmt.0);
+    self.emit_line(&format!("(block {}_end", label));
+    self.emit_line(&format!("  (loop {}", label));
+    self.emit_line(&format!(
+      "    (br_if {}_end (i32.eqz {}))",
+      label, condition
+    ));
+    self.indent += 1;
+    self.visit_block(stmt.1.as_deref());
+    self.indent -= 1;
+    self.emit_line(&format!("    (br {})", label));
+    self.emit_line("  )");
+    self.emit_line(")");
+  }
+
+  // functions are lifted to the module like in C, named by their path, the
+  // functions nested in them follow them
+  fn visit_function_stmt(&mut self, stmt: &FunctionStmt) {
+    let function = child_path(&self.path, &stmt.0.to_string());
+    let outer = std::mem::replace(&mut self.path, function);
+
+    let params = self.types.params(&self.path);
+    let mut signature = format!("(func ${}", self.path);
+    if self.path == "main" {
+      signature.push_str(" (export \"main\")");
+    }
+    for param in &params {
+      let t = self.variable_type(param);
+      signature.push_str(&format!(" (param ${} {})", param, t));
+    }
+    let return_type = self.types.return_type(&self.path);
+    let result = match return_type {
+      Type::Void => None,
+      _ => Some(wasm_type(&return_type).unwrap_or("i32")),
+    };
+    if let Some(result) = result {
+      signature.push_str(&format!(" (result {})", result));
+    }
+    self.start_function();
+    self.emit_line(&signature);
+
+    let mut locals = vec![];
+    if let Some(body) = &stmt.2 {
+      assigned_variables(body, &mut locals);
+    }
+    locals.retain(|name| !params.contains(name));
+    for name in locals {
+      let t = self.variable_type(&name);
+      self.emit_line(&format!("  (local ${} {})", name, t));
+    }
+    if self.path == "main" && self.has_init {
+      self.emit_line("  (call $riven_init)");
+    }
+
+    self.visit_block(stmt.2.as_deref());
+    if let Some(return_type) = default_return(&self.types, &self.path, stmt.2.as_deref()) {
+      let result = wasm_type(&return_type).unwrap_or("i32");
+      let zero = if result == "f64" { "0.0" } else { "0" };
+      self.emit_line(&format!("  ({}.const {})", result, zero));
+    }
+    self.emit_line(")");
+
+    if let Some(stmt_list) = &stmt.2 {
+      self.visit_nested_functions(stmt_list);
+    }
+    self.path = outer;
+  }
+
+  fn visit_struct_stmt(&mut self, _stmt: &StructStmt) {
+    self.report("structs are");
+  }
+
+  fn visit_stmt(&mut self, stmt: &Statement) {
+    self.line = stmt.location().row();
+    match &stmt.0 {
+      StatementValue::AssignStmt(stmt) => self.visit_assign_stmt(stmt),
+      StatementValue::IndexAssignStmt(stmt) => self.visit_index_assign_stmt(stmt),
+      StatementValue::FieldAssignStmt(stmt) => self.visit_field_assign_stmt(stmt),
+      StatementValue::CallStmt(stmt) => self.visit_call_stmt(stmt),
+      StatementValue::ApplyStmt(_) => self.report("functions used as values are"),
+      // emitted by visit_nested_functions
+      StatementValue::FunctionStmt(_) => (),
+      StatementValue::StructStmt(stmt) => self.visit_struct_stmt(stmt),
+      StatementValue::IfStmt(stmt) => self.visit_if_stmt(stmt),
+      StatementValue::WhileStmt(stmt) => self.visit_while_stmt(stmt),
+      StatementValue::ReturnStmt(stmt) => self.visit_return_stmt(stmt),
+    }
+  }
+
+  fn visit_stmt_list(&mut self, stmt_list: &StmtList) {
+    if let Some(stmt) = &stmt_list.0 {
+      self.visit_stmt(stmt);
+    }
+
+    if let Some(rests_stmts) = &stmt_list.1 {
+      self.visit_stmt_list(rests_stmts);
+    }
+  }
+
+  fn visit_program(&mut self, program: &Program) {
+    self.types = infer(program).types;
+
+    // the variables assigned outside functions, zero until then
+    let mut globals = vec![];
+    assigned_variables(&program.0, &mut globals);
+    if !globals.is_empty() {
+      self.emitter.emmit("\n");
+    }
+    for name in globals {
+      let t = self.variable_type(&name);
+      self.emit_line(&format!(
+        "(global $global.{0} (mut {1}) ({1}.const 0))",
+        name, t
+      ));
+    }
+
+    self.emit_idiv();
+    for stmt in statements(&program.0) {
+      if let StatementValue::StructStmt(_) = stmt.0 {
+        self.visit_stmt(stmt);
+      }
+    }
+    self.has_init = statements(&program.0).iter().any(|stmt| {
+      !matches!(
+        stmt.0,
+        StatementValue::FunctionStmt(_) | StatementValue::StructStmt(_)
+      )
+    });
+    if self.has_init {
+      self.visit_init(&program.0);
+    }
+    self.visit_nested_functions(&program.0);
+
+    let data: Vec<String> = self
+      .data
+      .iter()
+      .map(|(bytes, offset)| format!("  (data (i32.const {}) {})", offset, wat_string(bytes)))
+      .collect();
+    for data in data {
+      self.emitter.push_tail(&data);
+    }
+    self.emitter.push_tail(")\n");
+  }
+
+  fn visit_component_factor(&mut self, factor: &ComponentFactor) {
+    let (code, t) = self.arithmetic(&Operation::of_component_factor(factor));
+    self.push(&code, t);
+  }
+
+  fn visit_component_term(&mut self, term: &ComponentTerm) {
+    let (code, t) = self.arithmetic(&Operation::of_component_term(term));
+    self.push(&code, t);
+  }
+
+  fn visit_component_arithmetic_expr(&mut self, arith_expr: &ComponentArithmeticExpr) {
+    let (code, t) = self.arithmetic(&Operation::of_component_arithmetic_expr(arith_expr));
+    self.push(&code, t);
+  }
+}
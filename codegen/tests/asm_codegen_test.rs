@@ -0,0 +1,133 @@
+mod common;
+
+use common::gen_asm;
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_program_asm_codegen() {
+    let (code, unsupported) = gen_asm(
+      "program {
+        fn fib(n) {
+          if (n < 2) {
+            return n;
+          }
+          return fib(n - 1) + fib(n - 2);
+        }
+        fn main() {
+          i = 0;
+          while (i < 5) {
+            printf(\"fib(%d): %d\\n\", i, fib(i));
+            i = i + 1;
+          }
+          return 0;
+        }
+      }",
+    );
+    assert!(unsupported.is_empty());
+    assert!(code.starts_with("# generated by riven\n\t.text\n"));
+    // every variable and temporary has 8 bytes below %rbp
+    assert!(code.contains(
+      "\t.type\tfib, @function
+fib:
+\tpushq\t%rbp
+\tmovq\t%rsp, %rbp
+\tsubq\t$64, %rsp
+\tmovq\t%rdi, -8(%rbp)
+\tmovl\t-8(%rbp), %eax
+\tmovl\t$2, %ecx
+\tcmpl\t%ecx, %eax
+\tsetl\t%al
+\tmovzbl\t%al, %eax
+\tmovl\t%eax, -16(%rbp)
+\tmovl\t-16(%rbp), %eax
+\ttestl\t%eax, %eax
+\tje\t.Lfib_bb2
+.Lfib_bb1:
+\tmovl\t-8(%rbp), %eax
+\tleave
+\tret
+.Lfib_bb2:
+"
+    ));
+    assert!(code.contains("\tcall\tfib\n\tmovl\t%eax, -32(%rbp)\n"));
+    assert!(code.contains("\t.globl\tmain\n\t.type\tmain, @function\nmain:\n"));
+    // `i` is zero until its first assignment, the loop jumps back to its condition
+    assert!(code.contains("\tmovl\t$0, -8(%rbp)\n\tmovl\t$0, %eax\n\tmovl\t%eax, -8(%rbp)\n"));
+    assert!(code.contains("\tmovl\t%eax, -8(%rbp)\n\tjmp\t.Lmain_bb1\n.Lmain_bb3:\n"));
+    assert!(code.contains("\tpopq\t%rdx\n\txorl\t%eax, %eax\n\tcall\tprintf@PLT\n"));
+    assert!(code.contains(".LC2:\n\t.string\t\"fib(%d): %d\\012\"\n"));
+    assert!(code.ends_with("\t.section\t.note.GNU-stack,\"\",@progbits\n"));
+  }
+
+  #[test]
+  fn test_arguments_asm_codegen() {
+    let (code, unsupported) = gen_asm(
+      "program {
+        fn sum(a, b, c, d, e, f, g, h) {
+          return a + b + c + d + e + f + g + h / 2;
+        }
+        fn main() {
+          println(\"sum\", sum(1, 2, 3, 4, 5, 6, 7, 8), True);
+        }
+      }",
+    );
+    assert!(unsupported.is_empty());
+    // the seventh and eighth arguments are passed on the stack
+    assert!(code.contains("\tmovq\t%r9, -48(%rbp)\n"));
+    assert!(code.contains("\tmovl\t16(%rbp), %ecx\n"));
+    assert!(code.contains(
+      "\tmovl\t24(%rbp), %edi
+\tmovl\t$2, %esi
+\tmovl\t$3, %edx
+\tcall\triven_idiv
+"
+    ));
+    // the two arguments passed on the stack are removed after the call
+    assert!(code.contains("\tpopq\t%r9\n\tcall\tsum\n\taddq\t$16, %rsp\n"));
+    // what is printed before the call is printed before it runs
+    assert!(code.contains("\t.string\t\"sum \"\n"));
+    assert!(code.contains("\t.string\t\"%d %s\\012\"\n"));
+    assert!(code.contains("\tcmovne\t%rcx, %rdx\n"));
+  }
+
+  #[test]
+  fn test_globals_asm_codegen() {
+    let (code, unsupported) = gen_asm(
+      "program {
+        limit = 3;
+        fn main() {
+          println(limit);
+        }
+      }",
+    );
+    assert!(unsupported.is_empty());
+    assert!(code.contains("riven_init:\n\tpushq\t%rbp\n\tmovq\t%rsp, %rbp\n\tmovl\t$3, %eax\n\tmovl\t%eax, .Lglobal_limit(%rip)\n"));
+    assert!(code.contains("\tcall\triven_init\n\tmovl\t.Lglobal_limit(%rip), %eax\n"));
+    assert!(code.contains("\t.data\n.Lglobal_limit:\n\t.long\t0\n"));
+  }
+
+  #[test]
+  fn test_unsupported_asm_codegen() {
+    let (_, unsupported) = gen_asm(
+      "program {
+        fn half(n) {
+          return n / 2.0;
+        }
+        fn main() {
+          names = [\"ann\", \"bob\"];
+          println(names, half(3));
+        }
+      }",
+    );
+    assert_eq!(
+      unsupported[..2],
+      [
+        "line 3: numbers with a fraction are not supported by the asm target",
+        "line 6: lists are not supported by the asm target",
+      ]
+    );
+  }
+}
@@ -0,0 +1,86 @@
+mod common;
+
+use common::gen_c;
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_reassign_codegen() {
+    let code = gen_c(
+      "program {
+        fn main() {
+          x = 1;
+          x = x + 1;
+          println(x);
+        }
+      }",
+    );
+    assert!(code.contains("int main() {\n  int x = 1;\n  x = x + 1;\n"));
+  }
+
+  #[test]
+  fn test_hoisted_codegen() {
+    let code = gen_c(
+      "program {
+        fn main() {
+          n = 3;
+          if (n > 1) {
+            label = \"big\";
+            half = n / 2.0;
+            println(half);
+            items = [n];
+            ages = {\"bob\": n};
+          }
+          println(label);
+          println(items);
+          println(ages);
+        }
+      }",
+    );
+    assert!(code.contains("int main() {\n  char *label = \"\";\n"));
+    assert!(code.contains("  RivenList *items = riven_list_new();\n"));
+    assert!(code.contains("  RivenMap *ages = riven_map_new();\n"));
+    assert!(code.contains("  if (n > 1) {\n    label = \"big\";\n    double half = n / 2.0;\n"));
+  }
+
+  #[test]
+  fn test_while_codegen() {
+    let code = gen_c(
+      "program {
+        fn main() {
+          i = 0;
+          while (not(i = 3)) {
+            last = i;
+            i = i + 1;
+          }
+          println(last);
+        }
+      }",
+    );
+    assert!(code.contains("int main() {\n  int last = 0;\n  int i = 0;\n"));
+    assert!(code.contains("  while (!(i == 3)) {\n    last = i;\n    i = i + 1;\n  }\n"));
+  }
+
+  #[test]
+  fn test_reassign_param_codegen() {
+    let code = gen_c(
+      "program {
+        fn twice(n) {
+          n = n * 2;
+          return n;
+        }
+        fn main() {
+          fn down(k) {
+            k = k - 1;
+            return k;
+          }
+          println(twice(down(4)));
+        }
+      }",
+    );
+    assert!(code.contains("int twice(int n) {\n  n = n * 2;\n"));
+    assert!(code.contains("int main__down(int k) {\n  k = k - 1;\n"));
+  }
+}
@@ -0,0 +1,77 @@
+// every test file uses only the backends it tests
+#![allow(dead_code)]
+
+use codegen::asm_codegen::AsmCodeGen;
+use codegen::codegen::{CCodeGenManager, CodeGenerator, Emitter};
+use codegen::js_codegen::JsCodeGen;
+use codegen::js_runtime::JS_INPUT_RUNTIME;
+use codegen::llvm_codegen::LlvmCodeGen;
+use codegen::py_codegen::PythonCodeGen;
+use codegen::py_runtime::PY_INPUT_RUNTIME;
+use codegen::wat_codegen::WatCodeGen;
+use parser::{ast::Program, parse_program::parse_program, parser::Parser};
+
+fn parse(code: &str) -> Program {
+  parse_program(&mut Parser::new(code))
+}
+
+// the generated C program without the runtime in front of it
+pub fn gen_c(code: &str) -> String {
+  let mut emitter = Emitter::new();
+  CCodeGenManager::new(&mut emitter).visit_program(&parse(code));
+  emitter.body().to_owned()
+}
+
+// the generated C program, with #line directives naming `source_file`
+pub fn gen_c_with_lines(code: &str, source_file: &str) -> String {
+  let mut emitter = Emitter::new();
+  let mut codegen = CCodeGenManager::new(&mut emitter).with_source_file(source_file);
+  codegen.visit_program(&parse(code));
+  emitter.body().to_owned()
+}
+
+// the generated program after the runtime, which ends with the input helpers
+fn after_runtime(code: String, runtime: &str) -> String {
+  let runtime = runtime.trim_start();
+  let start = code.find(runtime).unwrap() + runtime.len();
+  code[start..].trim_start().to_owned()
+}
+
+pub fn gen_js(code: &str) -> String {
+  let mut emitter = Emitter::new();
+  JsCodeGen::new(&mut emitter).visit_program(&parse(code));
+  after_runtime(emitter.gen_code(), JS_INPUT_RUNTIME)
+}
+
+pub fn gen_py(code: &str) -> String {
+  let mut emitter = Emitter::new();
+  PythonCodeGen::new(&mut emitter).visit_program(&parse(code));
+  after_runtime(emitter.gen_code(), PY_INPUT_RUNTIME)
+}
+
+// the generated module and the code the wat target cannot handle
+pub fn gen_wat(code: &str) -> (String, Vec<String>) {
+  let mut emitter = Emitter::new();
+  let mut codegen = WatCodeGen::new(&mut emitter);
+  codegen.visit_program(&parse(code));
+  let unsupported = codegen.unsupported().to_vec();
+  (emitter.gen_code(), unsupported)
+}
+
+// the generated module and the code the llvm target cannot handle
+pub fn gen_llvm(code: &str) -> (String, Vec<String>) {
+  let mut emitter = Emitter::new();
+  let mut codegen = LlvmCodeGen::new(&mut emitter);
+  codegen.visit_program(&parse(code));
+  let unsupported = codegen.unsupported().to_vec();
+  (emitter.gen_code(), unsupported)
+}
+
+// the generated assembly and the code the asm target cannot handle
+pub fn gen_asm(code: &str) -> (String, Vec<String>) {
+  let mut emitter = Emitter::new();
+  let mut codegen = AsmCodeGen::new(&mut emitter);
+  codegen.visit_program(&parse(code));
+  let unsupported = codegen.unsupported().to_vec();
+  (emitter.gen_code(), unsupported)
+}
@@ -0,0 +1,200 @@
+mod common;
+
+use common::gen_c;
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_nested_function_codegen() {
+    let code = gen_c(
+      "program {
+        fn main() {
+          fn twice(n) {
+            return n * 2;
+          }
+          println(twice(3));
+        }
+        fn other() {
+          fn twice(s) {
+            return s;
+          }
+          return twice(\"a\");
+        }
+      }",
+    );
+    assert_eq!(
+      code,
+      "int main();
+int main__twice(int n);
+char *other();
+char *other__twice(char *s);
+
+int main() {
+  (riven_print_value(main__twice(3)), putchar('\\n'));
+}
+
+int main__twice(int n) {
+  return n * 2;
+}
+
+char *other() {
+  return other__twice(\"a\");
+}
+
+char *other__twice(char *s) {
+  return s;
+}
+"
+    );
+  }
+
+  #[test]
+  fn test_closure_codegen() {
+    let code = gen_c(
+      "program {
+        fn main() {
+          step = 2;
+          fn apply(f, x) {
+            return f(x);
+          }
+          next = fn(n) {
+            return n + step;
+          };
+          println(apply(next, 3));
+        }
+      }",
+    );
+    assert!(code.contains(
+      "int main__fn_at_7_18(int step, int n);
+typedef struct {
+  int step;
+} riven_env_main__fn_at_7_18;
+"
+    ));
+    assert!(code.contains(
+      "  RivenClosure *next = riven_closure_new(\"fn@7:18\", 1, riven_call_main__fn_at_7_18, riven_env_copy(&(riven_env_main__fn_at_7_18){ step }, sizeof(riven_env_main__fn_at_7_18)));\n"
+    ));
+    assert!(code.contains(
+      "  return riven_unbox_int(riven_call(f, 1, (RivenValue[]){ riven_box(x) }, 5), 5);\n"
+    ));
+    assert!(code.ends_with(
+      "
+static RivenValue riven_call_main__fn_at_7_18(void *env, RivenValue *args, int line) {
+  riven_env_main__fn_at_7_18 *captured = env;
+  return riven_box(main__fn_at_7_18(captured->step, riven_unbox_int(args[0], line)));
+}
+"
+    ));
+  }
+
+  #[test]
+  fn test_apply_codegen() {
+    let code = gen_c(
+      "program {
+        struct Shape { area }
+        fn make_adder(step) {
+          add = fn(v) {
+            return v + step;
+          };
+          return add;
+        }
+        fn main() {
+          fs = [make_adder(1)];
+          s = Shape(fs[0]);
+          println(make_adder(2)(3));
+          s.area(4);
+        }
+      }",
+    );
+    assert!(code.contains(
+      "riven_unbox_int(riven_call(make_adder(2), 1, (RivenValue[]){ riven_box(3) }, 12), 12)"
+    ));
+    assert!(code.contains(
+      "  riven_unbox_int(riven_call(riven_unbox_closure(*riven_field(riven_box(s), \"area\", 13), 13), 1, (RivenValue[]){ riven_box(4) }, 13), 13);\n"
+    ));
+  }
+
+  #[test]
+  fn test_reserved_name_codegen() {
+    let code = gen_c(
+      "program {
+        struct long { int }
+        fn double(char) {
+          return char * 2;
+        }
+        fn main() {
+          strlen = long(double(2));
+          println(strlen.int);
+        }
+      }",
+    );
+    assert!(code.contains(
+      "typedef struct long_ {\n  const RivenType *type;\n  RivenValue int_;\n} long_;\n"
+    ));
+    assert!(
+      code.contains("static const RivenType riven_long_type = { \"long\", 1, riven_long_fields };")
+    );
+    assert!(code.contains("int double_(int char_) {\n  return char_ * 2;\n}\n"));
+    assert!(code.contains("  long_ *strlen_ = riven_new_long(riven_box(double_(2)));\n"));
+  }
+
+  #[test]
+  fn test_default_return_codegen() {
+    let code = gen_c(
+      "program {
+        fn sign(n) {
+          if (n > 0) {
+            return 1.5;
+          }
+        }
+        fn label(n) {
+          if (n > 0) {
+            return \"big\";
+          }
+        }
+        fn digits(n) {
+          if (n > 0) {
+            return [n];
+          }
+        }
+        fn main() {
+          println(sign(2), label(0), digits(0));
+        }
+      }",
+    );
+    assert!(code.contains(
+      "double sign(int n) {
+  if (n > 0) {
+    return 1.5;
+  }
+  return 0.0;
+}
+"
+    ));
+    // the empty values the interpreter gives, not NULL
+    assert!(code.contains("    return \"big\";\n  }\n  return \"\";\n}\n"));
+    assert!(code.contains("  return riven_list_new();\n}\n"));
+    assert!(code.ends_with("putchar('\\n'));\n}\n"));
+  }
+
+  #[test]
+  fn test_globals_codegen() {
+    let code = gen_c(
+      "program {
+        limit = 3;
+        fn show() {
+          println(limit);
+        }
+        show();
+        fn main() {
+          show();
+        }
+      }",
+    );
+    assert!(code.starts_with("int limit;\n"));
+    assert!(code.contains("void riven_init(void) {\n  limit = 0;\n  limit = 3;\n  show();\n}\n"));
+    assert!(code.contains("int main() {\n  riven_init();\n  show();\n}\n"));
+  }
+}
@@ -0,0 +1,28 @@
+use codegen::codegen::{CCodeGenManager, CodeGenerator, Emitter};
+use parser::{parse_stmt::parse_stmt, parser::Parser};
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn gen_assign(code: &str) -> String {
+    let mut p = Parser::new(code);
+    let mut emitter = Emitter::new();
+    let mut codegen = CCodeGenManager::new(&mut emitter);
+    codegen.visit_stmt(&parse_stmt(&mut p));
+    emitter.gen_code()
+  }
+
+  #[test]
+  fn test_input_codegen() {
+    let code = gen_assign("name = input(\"name? \");");
+    assert!(code.contains("static inline char *riven_input(const char *prompt)"));
+    assert!(code.ends_with("char *name = riven_input(\"name? \");\n"));
+  }
+
+  #[test]
+  fn test_input_number_codegen() {
+    let code = gen_assign("guess = input_number(\"guess: \");");
+    assert!(code.ends_with("double guess = riven_input_number(\"guess: \", 1);\n"));
+  }
+}
@@ -0,0 +1,140 @@
+mod common;
+
+use common::gen_js;
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_program_js_codegen() {
+    let code = gen_js(
+      "program {
+        fn main() {
+          total = 0;
+          i = 1;
+          while (i < 10) {
+            if (and(i > 2, not(i = 5))) {
+              total = total + i * 2 - 1;
+            }
+            i = i + 1;
+          }
+          half = total / 2.0;
+          println(total / 3, half);
+          return (total - 1) * (i + 2);
+        }
+      }",
+    );
+    assert_eq!(
+      code,
+      "function main() {
+  let total = 0;
+  let i = 1;
+  while (i < 10) {
+    if (i > 2 && !(i === 5)) {
+      total = (total + Math.imul(i, 2) | 0) - 1 | 0;
+    }
+    i = i + 1 | 0;
+  }
+  let half = total / 2.0;
+  riven_println(riven_idiv(total, 3, 12), half);
+  return Math.imul(total - 1 | 0, i + 2 | 0);
+}
+
+riven_run(main);
+"
+    );
+  }
+
+  #[test]
+  fn test_struct_js_codegen() {
+    let code = gen_js(
+      "program {
+        struct Map { keys }
+        fn main() {
+          new = Map([1]);
+          new.keys = [2, 3];
+          println(new.keys[0]);
+        }
+      }",
+    );
+    assert!(code.starts_with(
+      "class Map_ extends RivenObject {
+  constructor(keys) {
+    super();
+    this.keys = keys;
+  }
+}
+Map_.riven_name = \"Map\";
+"
+    ));
+    assert!(code.contains("  let new_ = new Map_([1]);\n  new_.keys = [2, 3];\n"));
+    assert!(code.contains("  riven_println(riven_get(new_.keys, 0, 6));\n"));
+  }
+
+  #[test]
+  fn test_closure_js_codegen() {
+    let code = gen_js(
+      "program {
+        fn make_adder(step) {
+          add = fn(v) {
+            return v + step;
+          };
+          return add;
+        }
+        fn main() {
+          add3 = make_adder(3);
+          println(add3(4));
+        }
+      }",
+    );
+    assert!(code.contains(
+      "function make_adder(step) {
+  let add = riven_closure(\"fn@3:17\", 1, make_adder__fn_at_3_17, [step]);
+  return add;
+}
+
+function make_adder__fn_at_3_17(step, v) {
+  return v + step | 0;
+}
+"
+    ));
+    assert!(code.contains("  riven_println(riven_call(add3, [4], 10));\n"));
+  }
+
+  #[test]
+  fn test_globals_js_codegen() {
+    let code = gen_js(
+      "program {
+        limit = 3;
+        fn show() {
+          println(limit);
+        }
+        show();
+        fn main() {
+          show();
+        }
+      }",
+    );
+    assert_eq!(
+      code,
+      "let limit = 0;
+
+function riven_init() {
+  limit = 3;
+  show();
+}
+
+function show() {
+  riven_println(limit);
+}
+
+function main() {
+  show();
+}
+
+riven_run(main, riven_init);
+"
+    );
+  }
+}
@@ -0,0 +1,86 @@
+mod common;
+
+use common::gen_c_with_lines;
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_line_directives_codegen() {
+    let code = gen_c_with_lines(
+      "program {
+        fn fib(n) {
+          if (n < 2) {
+            return n;
+          }
+
+          return fib(n - 1) + fib(n - 2);
+        }
+        fn main() {
+          println(fib(5));
+        }
+      }",
+      "fib.riven",
+    );
+    // a directive is only needed where gcc would count another line
+    assert_eq!(
+      code,
+      "int fib(int n);
+int main();
+
+#line 2 \"fib.riven\"
+int fib(int n) {
+  if (n < 2) {
+    return n;
+  }
+#line 7 \"fib.riven\"
+  return fib(n - 1) + fib(n - 2);
+}
+
+#line 9 \"fib.riven\"
+int main() {
+  (riven_print_value(fib(5)), putchar('\\n'));
+}
+"
+    );
+  }
+
+  #[test]
+  fn test_closure_line_directives_codegen() {
+    let code = gen_c_with_lines(
+      "program {
+        fn main() {
+          twice = fn(n) {
+            return n * 2;
+          };
+          println(twice(2));
+        }
+      }",
+      "fib.riven",
+    );
+    // the wrapper calling a function used as a value is counted as its definition
+    assert!(code.contains(
+      "#line 3 \"fib.riven\"
+int main__fn_at_3_19(int n) {
+  return n * 2;
+}
+
+#line 3 \"fib.riven\"
+static RivenValue riven_call_main__fn_at_3_19("
+    ));
+  }
+
+  #[test]
+  fn test_escaped_file_name_codegen() {
+    let code = gen_c_with_lines(
+      "program {
+        fn main() {
+          println(1);
+        }
+      }",
+      "C:\\riven\\caf\u{e9} \"1\".riven",
+    );
+    assert!(code.starts_with("int main();\n\n#line 2 \"C:\\\\riven\\\\caf\u{e9} \\\"1\\\".riven\"\n"));
+  }
+}
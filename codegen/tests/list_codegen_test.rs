@@ -0,0 +1,45 @@
+use codegen::codegen::{CCodeGenManager, CodeGenerator, Emitter};
+use parser::{parse_stmt::parse_stmt, parser::Parser};
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn gen_stmt(code: &str) -> String {
+    let mut p = Parser::new(code);
+    let mut emitter = Emitter::new();
+    let mut codegen = CCodeGenManager::new(&mut emitter);
+    codegen.visit_stmt(&parse_stmt(&mut p));
+    emitter.body().trim_end().to_owned()
+  }
+
+  #[test]
+  fn test_list_literal_codegen() {
+    assert_eq!(
+      gen_stmt("scores = [1, \"two\"];"),
+      "RivenList *scores = riven_list_of(2, riven_box(1), riven_box(\"two\"));"
+    );
+    assert_eq!(gen_stmt("empty = [];"), "RivenList *empty = riven_list_new();");
+  }
+
+  #[test]
+  fn test_index_codegen() {
+    assert_eq!(
+      gen_stmt("\nfirst = scores[0] + 1;"),
+      "int first = riven_unbox_int(riven_get(scores, riven_box(0), 2), 2) + 1;"
+    );
+    assert_eq!(
+      gen_stmt("grid[1][0] = 5;"),
+      "riven_set(riven_get(grid, riven_box(1), 1), riven_box(0), riven_box(5), 1);"
+    );
+  }
+
+  #[test]
+  fn test_append_len_codegen() {
+    assert_eq!(
+      gen_stmt("append(scores, scores[0]);"),
+      "riven_list_append(scores, riven_get(scores, riven_box(0), 1));"
+    );
+    assert_eq!(gen_stmt("size = len(scores);"), "int size = riven_len(scores);");
+  }
+}
@@ -0,0 +1,137 @@
+mod common;
+
+use codegen::llvm_codegen::LLVM_DECLARATIONS;
+use common::gen_llvm;
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_program_llvm_codegen() {
+    let (code, unsupported) = gen_llvm(
+      "program {
+        fn fib(n) {
+          if (n < 2) {
+            return n;
+          }
+          return fib(n - 1) + fib(n - 2);
+        }
+        fn main() {
+          i = 0;
+          while (and(i < 5, i < 100)) {
+            printf(\"fib(%d): %d\\n\", i, fib(i));
+            i = i + 1;
+          }
+          return 0;
+        }
+      }",
+    );
+    assert!(unsupported.is_empty());
+    assert!(code.starts_with("; generated by riven\n"));
+    assert!(code.contains(LLVM_DECLARATIONS));
+    assert!(code.contains(
+      "define i32 @fib(i32 %n) {
+entry:
+  %n.addr = alloca i32
+  store i32 %n, ptr %n.addr
+  %0 = load i32, ptr %n.addr
+  %1 = icmp slt i32 %0, 2
+  br i1 %1, label %if1.then, label %if1.end
+if1.then:
+  %2 = load i32, ptr %n.addr
+  ret i32 %2
+if1.end:
+  %3 = load i32, ptr %n.addr
+  %4 = sub i32 %3, 1
+  %5 = call i32 @fib(i32 %4)
+  %6 = load i32, ptr %n.addr
+  %7 = sub i32 %6, 2
+  %8 = call i32 @fib(i32 %7)
+  %9 = add i32 %5, %8
+  ret i32 %9
+}
+"
+    ));
+    assert!(code.contains(
+      "while1.cond:
+  %0 = load i32, ptr %i.addr
+  %1 = icmp slt i32 %0, 5
+  br i1 %1, label %and2.rhs, label %and2.end
+and2.rhs:
+  %2 = load i32, ptr %i.addr
+  %3 = icmp slt i32 %2, 100
+  br label %and2.end
+and2.end:
+  %4 = phi i1 [ false, %while1.cond ], [ %3, %and2.rhs ]
+  br i1 %4, label %while1.body, label %while1.end
+"
+    ));
+    assert!(code.contains("  %8 = call i32 (ptr, ...) @printf(ptr @.str.2, i32 %5, i32 %7)\n"));
+    assert!(code
+      .ends_with("@.str.2 = private unnamed_addr constant [13 x i8] c\"fib(%d): %d\\0A\\00\"\n"));
+  }
+
+  #[test]
+  fn test_numbers_llvm_codegen() {
+    let (code, unsupported) = gen_llvm(
+      "program {
+        fn main() {
+          size = 7;
+          ratio = size / 2.0;
+          println(\"half\", size / 2, ratio, True);
+        }
+      }",
+    );
+    assert!(unsupported.is_empty());
+    assert!(code.contains("  %size.addr = alloca i32\n  %ratio.addr = alloca double\n"));
+    assert!(code.contains(
+      "  %1 = sitofp i32 %0 to double
+  %2 = fdiv double %1, 2.0
+  store double %2, ptr %ratio.addr
+"
+    ));
+    assert!(code.contains("  %4 = call i32 @riven_idiv(i32 %3, i32 2, i32 5)\n"));
+    assert!(code.contains("c\"half %d %.15g %s\\0A\\00\""));
+    assert!(code.contains("  ret i32 0\n}\n"));
+  }
+
+  #[test]
+  fn test_unsupported_llvm_codegen() {
+    let (_, unsupported) = gen_llvm(
+      "program {
+        fn main() {
+          names = [\"ann\", \"bob\"];
+          println(names);
+        }
+      }",
+    );
+    assert_eq!(
+      unsupported[0],
+      "line 3: lists are not supported by the llvm target"
+    );
+  }
+
+  #[test]
+  fn test_globals_llvm_codegen() {
+    let (code, unsupported) = gen_llvm(
+      "program {
+        limit = 3;
+        fn show() {
+          println(limit);
+        }
+        show();
+        fn main() {
+          show();
+        }
+      }",
+    );
+    assert!(unsupported.is_empty());
+    assert!(code.contains(
+      "define internal void @riven_init() {\nentry:\n  store i32 3, ptr @global.limit\n"
+    ));
+    assert!(code.contains("  %0 = load i32, ptr @global.limit\n"));
+    assert!(code.contains("define i32 @main() {\nentry:\n  call void @riven_init()\n"));
+    assert!(code.contains("\n@global.limit = internal global i32 0\n"));
+  }
+}
@@ -0,0 +1,45 @@
+use codegen::codegen::{CCodeGenManager, CodeGenerator, Emitter};
+use parser::{parse_stmt::parse_stmt, parser::Parser};
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn gen_stmt(code: &str) -> String {
+    let mut p = Parser::new(code);
+    let mut emitter = Emitter::new();
+    let mut codegen = CCodeGenManager::new(&mut emitter);
+    codegen.visit_stmt(&parse_stmt(&mut p));
+    emitter.body().trim_end().to_owned()
+  }
+
+  #[test]
+  fn test_map_literal_codegen() {
+    assert_eq!(
+      gen_stmt("ages = {\"tom\": 8, \"amy\": 9};"),
+      "RivenMap *ages = riven_map_of(2, riven_box(\"tom\"), riven_box(8), riven_box(\"amy\"), riven_box(9));"
+    );
+    assert_eq!(gen_stmt("empty = {};"), "RivenMap *empty = riven_map_new();");
+  }
+
+  #[test]
+  fn test_map_index_codegen() {
+    assert_eq!(
+      gen_stmt("ages[\"bob\"] = 10;"),
+      "riven_set(ages, riven_box(\"bob\"), riven_box(10), 1);"
+    );
+    assert_eq!(
+      gen_stmt("age = ages[\"tom\"];"),
+      "int age = riven_unbox_int(riven_get(ages, riven_box(\"tom\"), 1), 1);"
+    );
+  }
+
+  #[test]
+  fn test_keys_has_codegen() {
+    assert_eq!(gen_stmt("names = keys(ages);"), "RivenList *names = riven_map_keys(ages);");
+    assert_eq!(
+      gen_stmt("known = has(ages, \"tom\");"),
+      "bool known = riven_map_has(ages, riven_box(\"tom\"));"
+    );
+  }
+}
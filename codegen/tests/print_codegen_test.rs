@@ -0,0 +1,30 @@
+use codegen::codegen::{CCodeGenManager, CodeGenerator, Emitter};
+use parser::{parse_call_expr::parse_call_expr, parser::Parser};
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn gen_call(code: &str) -> String {
+    let mut p = Parser::new(code);
+    let mut emitter = Emitter::new();
+    let mut codegen = CCodeGenManager::new(&mut emitter);
+    codegen.visit_call_expr(&parse_call_expr(&mut p));
+    emitter.gen_code()
+  }
+
+  #[test]
+  fn test_print_codegen() {
+    let code = gen_call("print(\"age\", 8, 1.5, True)");
+    assert!(code.contains("#define riven_print_value(x) _Generic"));
+    assert!(code.contains(
+      "(riven_print_value(\"age\"), putchar(' '), riven_print_value(8), putchar(' '), riven_print_value(1.5), putchar(' '), riven_print_value(((bool)true)))"
+    ));
+  }
+
+  #[test]
+  fn test_println_codegen() {
+    assert!(gen_call("println(name)").contains("(riven_print_value(name), putchar('\\n'))"));
+    assert!(gen_call("println()").ends_with("(putchar('\\n'))\n"));
+  }
+}
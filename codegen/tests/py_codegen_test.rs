@@ -0,0 +1,181 @@
+mod common;
+
+use common::gen_py;
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_program_py_codegen() {
+    let code = gen_py(
+      "program {
+        fn main() {
+          total = 0;
+          i = 1;
+          while (i < 10) {
+            if (and(i > 2, not(i = 5))) {
+              total = total + i * 2 - 1;
+            }
+            i = i + 1;
+          }
+          half = total / 2.0;
+          printf(\"%d %f\\n\", total / 3, half);
+          println(\"total:\", total);
+          print(half);
+          if (half > 1000) {
+          }
+          return (total - 1) * (i + 2) - (1 - i);
+        }
+      }",
+    );
+    assert_eq!(
+      code,
+      "def main():
+    total = 0
+    i = 1
+    while i < 10:
+        if i > 2 and not (i == 5):
+            total = total + i * 2 - 1
+        i = i + 1
+    half = total / 2.0
+    print(\"%d %f\\n\" % (riven_idiv(total, 3, 12), half), end=\"\")
+    print(\"total:\", total)
+    riven_print(half)
+    if half > 1000:
+        pass
+    return (total - 1) * (i + 2) - (1 - i)
+
+
+if __name__ == \"__main__\":
+    riven_run(main)
+"
+    );
+  }
+
+  #[test]
+  fn test_struct_py_codegen() {
+    let code = gen_py(
+      "program {
+        struct list { items }
+        fn main() {
+          new = list([1]);
+          new.items = [2, 3];
+          append(new.items, 4);
+          println(new.items[0], has({\"a\": 1}, \"a\"));
+        }
+      }",
+    );
+    assert!(code.starts_with(
+      "class list_(RivenObject):
+    riven_name = \"list\"
+
+    def __init__(self, items):
+        self.items = items
+
+
+def main():
+    new = list_([1])
+    new.items = [2, 3]
+    new.items.append(4)
+    print(riven_get(new.items, 0, 7), (\"a\" in {\"a\": 1}))
+"
+    ));
+  }
+
+  #[test]
+  fn test_len_py_codegen() {
+    let code = gen_py(
+      "program {
+        fn main() {
+          println(len(\"caf\u{e9}\"), len([1, 2]));
+        }
+      }",
+    );
+    // riven_len counts the bytes of a text, like strlen
+    assert!(code.contains("    print(riven_len(\"caf\u{e9}\"), riven_len([1, 2]))\n"));
+  }
+
+  #[test]
+  fn test_closure_py_codegen() {
+    let code = gen_py(
+      "program {
+        fn make_adder(step) {
+          add = fn(v) {
+            return v + step;
+          };
+          return add;
+        }
+        fn sign(n) {
+          if (n < 0) {
+            return 0 - 1;
+          }
+        }
+        fn main() {
+          add3 = make_adder(3);
+          println(add3(4), sign(2));
+        }
+      }",
+    );
+    assert!(code.contains(
+      "def make_adder(step):
+    add = riven_closure(\"fn@3:17\", 1, make_adder__fn_at_3_17, [step])
+    return add
+
+
+def make_adder__fn_at_3_17(step, v):
+    return v + step
+
+
+def sign(n):
+    if n < 0:
+        return 0 - 1
+    return 0
+"
+    ));
+    assert!(code.contains("    print(riven_call(add3, [4], 15), sign(2))\n"));
+  }
+
+  #[test]
+  fn test_globals_py_codegen() {
+    let code = gen_py(
+      "program {
+        limit = 3;
+        fn show() {
+          println(limit);
+        }
+        show();
+        fn main() {
+          show();
+        }
+      }",
+    );
+    assert!(code.starts_with(
+      "limit = 0\n\n\ndef riven_init():\n    global limit\n    limit = 3\n    show()\n"
+    ));
+    assert!(code.ends_with("if __name__ == \"__main__\":\n    riven_run(main, riven_init)\n"));
+  }
+
+  #[test]
+  fn test_compare_py_codegen() {
+    let code = gen_py(
+      "program {
+        fn main() {
+          a = [1];
+          b = [1, True];
+          m = {1: \"one\", True: \"yes\"};
+          if (a = b) {
+            println(keys(m));
+          }
+          if (b[0] = b[1]) {
+            println(has(m, 1));
+          }
+        }
+      }",
+    );
+    assert!(code.contains("    m = {1: \"one\", riven_key(True): \"yes\"}\n"));
+    assert!(code.contains("    if a is b:\n        riven_println(riven_keys(m))\n"));
+    assert!(code.contains("    if riven_equal(riven_get(b, 0, 9), riven_get(b, 1, 9)):\n"));
+    assert!(code.contains("        print((1 in m))\n"));
+  }
+}
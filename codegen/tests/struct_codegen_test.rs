@@ -0,0 +1,50 @@
+use codegen::codegen::{CCodeGenManager, CodeGenerator, Emitter};
+use parser::{parse_program::parse_program, parse_stmt::parse_stmt, parser::Parser};
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn gen_stmt(code: &str) -> String {
+    let mut p = Parser::new(code);
+    let mut emitter = Emitter::new();
+    let mut codegen = CCodeGenManager::new(&mut emitter);
+    codegen.visit_stmt(&parse_stmt(&mut p));
+    emitter.body().trim_end().to_owned()
+  }
+
+  #[test]
+  fn test_struct_stmt_codegen() {
+    let code = gen_stmt("struct Point { x, y }");
+    assert!(code.starts_with(
+      "typedef struct Point {\n  const RivenType *type;\n  RivenValue x;\n  RivenValue y;\n} Point;\n"
+    ));
+    assert!(code
+      .contains("static const RivenType riven_Point_type = { \"Point\", 2, riven_Point_fields };"));
+    assert!(code.contains("static inline Point *riven_new_Point(RivenValue x, RivenValue y) {"));
+  }
+
+  #[test]
+  fn test_field_codegen() {
+    assert_eq!(
+      gen_stmt("p.x = 3;"),
+      "*riven_field(riven_box(p), \"x\", 1) = riven_box(3);"
+    );
+    assert_eq!(
+      gen_stmt("x = p.x;"),
+      "int x = riven_unbox_int(*riven_field(riven_box(p), \"x\", 1), 1);"
+    );
+  }
+
+  #[test]
+  fn test_struct_new_codegen() {
+    let mut p = Parser::new("program { struct Point { x, y } p = Point(1, 2); }");
+    let mut emitter = Emitter::new();
+    let mut codegen = CCodeGenManager::new(&mut emitter);
+    codegen.visit_program(&parse_program(&mut p));
+    let code = emitter.gen_code();
+    assert!(code.contains("\nPoint *p;\n"));
+    assert!(code.ends_with("  p = riven_new_Point(riven_box(1), riven_box(2));\n}\n"));
+    assert_eq!(code.matches("typedef struct Point").count(), 1);
+  }
+}
@@ -0,0 +1,83 @@
+mod common;
+
+use common::gen_c;
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_function_types_codegen() {
+    let code = gen_c(
+      "program {
+        fn area(w, h) {
+          return w * h;
+        }
+        fn greet(name) {
+          println(name);
+        }
+        fn main() {
+          a = area(2.5, 4);
+          greet(\"ann\");
+        }
+      }",
+    );
+    assert!(code.contains("double area(double w, int h) {\n"));
+    assert!(code.contains("void greet(char *name) {\n"));
+    assert!(code.contains("int main() {\n  double a = area(2.5, 4);\n"));
+  }
+
+  #[test]
+  fn test_item_types_codegen() {
+    let code = gen_c(
+      "program {
+        fn main() {
+          names = [\"ann\"];
+          first = names[0];
+          mixed = [1, \"two\"];
+          item = mixed[1];
+          if (first = \"ann\") {
+            println(item);
+          }
+        }
+      }",
+    );
+    assert!(code.contains("  char *first = riven_unbox_str(riven_get(names, riven_box(0), 4), 4);\n"));
+    assert!(code.contains("  RivenValue item = riven_get(mixed, riven_box(1), 6);\n"));
+    assert!(code.contains("  if (strcmp(first, \"ann\") == 0) {\n"));
+  }
+
+  #[test]
+  fn test_annotated_types_codegen() {
+    let code = gen_c(
+      "program {
+        fn area(w: number, h: number) -> number {
+          return w * h;
+        }
+        fn main() {
+          a = area(2, 3);
+          label: any = 1;
+        }
+      }",
+    );
+    assert!(code.contains("double area(double w, double h) {\n"));
+    assert!(code.contains("  RivenValue label = riven_box(1);\n"));
+  }
+
+  #[test]
+  fn test_int_division_codegen() {
+    let code = gen_c(
+      "program {
+        fn main() {
+          a = 7;
+          b = 2;
+          println(a / b + 1, a / 2.0, (a + 1) / b * 3);
+        }
+      }",
+    );
+    // the runtime reports a zero divisor instead of C stopping with SIGFPE
+    assert!(code.contains("riven_print_value(riven_div(a, b, 5) + 1)"));
+    assert!(code.contains("riven_print_value(a / 2.0)"));
+    assert!(code.contains("riven_print_value(riven_div((a + 1), b, 5) * 3)"));
+  }
+}
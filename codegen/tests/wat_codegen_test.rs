@@ -0,0 +1,144 @@
+mod common;
+
+use codegen::wat_codegen::WAT_IMPORTS;
+use common::gen_wat;
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_program_wat_codegen() {
+    let (code, unsupported) = gen_wat(
+      "program {
+        fn fib(n) {
+          if (n < 2) {
+            return n;
+          }
+          return fib(n - 1) + fib(n - 2);
+        }
+        fn main() {
+          i = 0;
+          while (i < 5) {
+            printf(\"fib(%d): %d\\n\", i, fib(i));
+            i = i + 1;
+          }
+          return 0;
+        }
+      }",
+    );
+    assert!(unsupported.is_empty());
+    assert!(code.starts_with("(module\n"));
+    assert!(code.contains(WAT_IMPORTS.trim_start()));
+    assert!(code.contains(
+      "  (func $fib (param $n i32) (result i32)
+    (if (i32.lt_s (local.get $n) (i32.const 2))
+      (then
+        (return (local.get $n))
+      )
+    )
+    (return (i32.add (call $fib (i32.sub (local.get $n) (i32.const 1))) (call $fib (i32.sub (local.get $n) (i32.const 2)))))
+  )
+"
+    ));
+    assert!(code.contains(
+      "  (func $main (export \"main\") (result i32)
+    (local $i i32)
+    (local.set $i (i32.const 0))
+    (block $while_1_end
+      (loop $while_1
+        (br_if $while_1_end (i32.eqz (i32.lt_s (local.get $i) (i32.const 5))))
+        (i32.const 0) (f64.convert_i32_s (local.get $i))
+        (i32.const 8) (f64.convert_i32_s (call $fib (local.get $i)))
+        f64.store
+        f64.store
+        (drop (call $printf (i32.const 272) (i32.const 12) (i32.const 0) (i32.const 2)))
+"
+    ));
+    assert!(code.ends_with("  (data (i32.const 272) \"fib(%d): %d\\0a\")\n)\n"));
+    assert_eq!(code.matches('(').count(), code.matches(')').count());
+  }
+
+  #[test]
+  fn test_numbers_wat_codegen() {
+    let (code, unsupported) = gen_wat(
+      "program {
+        fn main() {
+          size = 7;
+          ratio = size / 2.0;
+          println(\"half\", size / 2, ratio);
+        }
+      }",
+    );
+    assert!(unsupported.is_empty());
+    assert!(code.contains("    (local $size i32)\n    (local $ratio f64)\n"));
+    assert!(code.contains(
+      "    (local.set $ratio (f64.div (f64.convert_i32_s (local.get $size)) (f64.const 2.0)))\n"
+    ));
+    assert!(code.contains(
+      "(call $print_i32 (call $riven_idiv (local.get $size) (i32.const 2) (i32.const 5)))"
+    ));
+    assert!(code.contains("    (call $print_f64 (local.get $ratio))\n"));
+  }
+
+  #[test]
+  fn test_nested_printf_wat_codegen() {
+    let (code, unsupported) = gen_wat(
+      "program {
+        fn show(n) {
+          printf(\"%d\\n\", n);
+          return n;
+        }
+        fn main() {
+          printf(\"%d %d\\n\", show(1), show(2));
+        }
+      }",
+    );
+    assert!(unsupported.is_empty());
+    // show(2) stores its own value at 0 before the values of main are stored
+    assert!(code.contains(
+      "    (i32.const 0) (f64.convert_i32_s (call $show (i32.const 1)))
+    (i32.const 8) (f64.convert_i32_s (call $show (i32.const 2)))
+    f64.store
+    f64.store
+"
+    ));
+  }
+
+  #[test]
+  fn test_unsupported_wat_codegen() {
+    let (_, unsupported) = gen_wat(
+      "program {
+        fn main() {
+          names = [\"ann\", \"bob\"];
+          println(names);
+        }
+      }",
+    );
+    assert!(!unsupported.is_empty());
+    assert!(unsupported[0].starts_with("line 3: "));
+    assert!(unsupported[0].ends_with(" not supported by the wat target"));
+  }
+
+  #[test]
+  fn test_globals_wat_codegen() {
+    let (code, unsupported) = gen_wat(
+      "program {
+        limit = 3;
+        fn show() {
+          println(limit);
+        }
+        show();
+        fn main() {
+          show();
+        }
+      }",
+    );
+    assert!(unsupported.is_empty());
+    assert!(code.contains("  (global $global.limit (mut i32) (i32.const 0))\n"));
+    assert!(code.contains(
+      "  (func $riven_init\n    (global.set $global.limit (i32.const 3))\n    (call $show)\n  )\n"
+    ));
+    assert!(code.contains("  (func $main (export \"main\")\n    (call $riven_init)\n"));
+  }
+}
@@ -0,0 +1,237 @@
+use std::collections::HashMap;
+use std::fmt::{self, Display, Write};
+
+use analyzer::types::Type;
+use parser::ast::{BinOp, CompareOp};
+
+use crate::value::Value;
+
+// one instruction of the stack machine, operands are indexes into the
+// constants, locals, functions or structs of the module
+#[derive(Debug, Clone, PartialEq)]
+pub enum Op {
+  // push a value of the constants pool
+  Constant(usize),
+  // push the value a variable of type `Type` has before its first assignment
+  Zero(Type),
+  Pop,
+  GetLocal(usize),
+  // pop the value into a local
+  SetLocal(usize),
+  GetGlobal(usize),
+  SetGlobal(usize),
+  // convert the value on top to a type, see runtime::coerce
+  Coerce(Type),
+  Arithmetic(BinOp),
+  Compare(CompareOp),
+  // push True or False from the truth of the value on top
+  Not,
+  // jump to an instruction
+  Jump(usize),
+  // pop the value on top and jump when it is false
+  JumpIfFalse(usize),
+  // push the function with its captured values, they are on top of the stack
+  Closure(usize, usize),
+  // call a function with its captured values and arguments on top of the stack
+  Call(usize, usize),
+  // call the function value below its arguments
+  CallValue(usize),
+  Return,
+  // pop the value on top and write it like print does
+  Print,
+  // the format and the values are on top of the stack
+  Printf(usize),
+  Input,
+  InputNumber,
+  Len,
+  Keys,
+  Append,
+  Has,
+  NewList(usize),
+  // the keys and values of the entries in order
+  NewMap(usize),
+  // a struct with the values of its fields
+  NewStruct(usize, usize),
+  GetItem,
+  SetItem,
+  // the name of the field is a constant
+  GetField(usize),
+  SetField(usize),
+  // stop the program, the message is a constant
+  Fail(usize),
+}
+
+// the code of one function
+#[derive(Debug, Clone, Default)]
+pub struct Chunk {
+  // see analyzer::infer::child_path, `` for the statements outside functions
+  pub path: String,
+  pub arity: usize,
+  // the local each captured value and then each argument is stored into
+  pub params: Vec<usize>,
+  // name and type of every local, captured variables, parameters and the
+  // temporaries of the IR included
+  pub locals: Vec<(String, Type)>,
+  pub constants: Vec<Value>,
+  pub code: Vec<Op>,
+  // source line of each instruction
+  pub lines: Vec<usize>,
+}
+
+impl Chunk {
+  pub fn add_constant(&mut self, value: Value) -> usize {
+    self.constants.push(value);
+    self.constants.len() - 1
+  }
+
+  pub fn emit(&mut self, op: Op, line: usize) -> usize {
+    self.code.push(op);
+    self.lines.push(line);
+    self.code.len() - 1
+  }
+
+  // the slot of a local, added the first time it is used
+  pub fn local(&mut self, name: &str, t: Type) -> usize {
+    match self.locals.iter().position(|(local, _)| local == name) {
+      Some(slot) => slot,
+      None => {
+        self.locals.push((name.to_owned(), t));
+        self.locals.len() - 1
+      }
+    }
+  }
+
+  // the constant an instruction refers to, to show it in the disassembly
+  fn constant(&self, idx: usize) -> String {
+    match &self.constants[idx] {
+      Value::Str(s) => format!("{:?}", s),
+      value => value.to_string(),
+    }
+  }
+}
+
+// a compiled program, see compile::compile
+#[derive(Debug, Clone, Default)]
+pub struct Module {
+  pub functions: Vec<Chunk>,
+  // the index of each function by path
+  pub function_index: HashMap<String, usize>,
+  // name and fields of each struct
+  pub structs: Vec<(String, Vec<String>)>,
+  // name and type of every variable assigned outside functions
+  pub globals: Vec<(String, Type)>,
+}
+
+impl Module {
+  pub fn main(&self) -> Option<usize> {
+    self.function_index.get("main").copied()
+  }
+
+  // the statements outside functions, run before main
+  pub fn init(&self) -> Option<usize> {
+    self.function_index.get("").copied()
+  }
+
+  fn instruction(&self, chunk: &Chunk, op: &Op) -> String {
+    let mut text = String::new();
+    let _ = match op {
+      Op::Constant(idx) => write!(text, "constant {} ({})", idx, chunk.constant(*idx)),
+      Op::Zero(t) => write!(text, "zero {}", t),
+      Op::Pop => write!(text, "pop"),
+      Op::GetLocal(slot) => write!(text, "get_local {} ({})", slot, chunk.locals[*slot].0),
+      Op::SetLocal(slot) => write!(text, "set_local {} ({})", slot, chunk.locals[*slot].0),
+      Op::GetGlobal(idx) => write!(text, "get_global {} ({})", idx, self.globals[*idx].0),
+      Op::SetGlobal(idx) => write!(text, "set_global {} ({})", idx, self.globals[*idx].0),
+      Op::Coerce(t) => write!(text, "coerce {}", t),
+      Op::Arithmetic(op) => write!(text, "{}", binop_name(op)),
+      Op::Compare(CompareOp::Eq) => write!(text, "equal"),
+      Op::Compare(CompareOp::Gt) => write!(text, "greater"),
+      Op::Compare(CompareOp::Lt) => write!(text, "less"),
+      Op::Not => write!(text, "not"),
+      Op::Jump(target) => write!(text, "jump {:04}", target),
+      Op::JumpIfFalse(target) => write!(text, "jump_if_false {:04}", target),
+      Op::Closure(function, captures) => write!(
+        text,
+        "closure {} ({}) {}",
+        function, self.functions[*function].path, captures
+      ),
+      Op::Call(function, count) => write!(
+        text,
+        "call {} ({}) {}",
+        function, self.functions[*function].path, count
+      ),
+      Op::CallValue(count) => write!(text, "call_value {}", count),
+      Op::Return => write!(text, "return"),
+      Op::Print => write!(text, "print"),
+      Op::Printf(count) => write!(text, "printf {}", count),
+      Op::Input => write!(text, "input"),
+      Op::InputNumber => write!(text, "input_number"),
+      Op::Len => write!(text, "len"),
+      Op::Keys => write!(text, "keys"),
+      Op::Append => write!(text, "append"),
+      Op::Has => write!(text, "has"),
+      Op::NewList(count) => write!(text, "new_list {}", count),
+      Op::NewMap(count) => write!(text, "new_map {}", count),
+      Op::NewStruct(idx, count) => {
+        write!(
+          text,
+          "new_struct {} ({}) {}",
+          idx, self.structs[*idx].0, count
+        )
+      }
+      Op::GetItem => write!(text, "get_item"),
+      Op::SetItem => write!(text, "set_item"),
+      Op::GetField(idx) => write!(text, "get_field {} ({})", idx, chunk.constant(*idx)),
+      Op::SetField(idx) => write!(text, "set_field {} ({})", idx, chunk.constant(*idx)),
+      Op::Fail(idx) => write!(text, "fail {} ({})", idx, chunk.constant(*idx)),
+    };
+    text
+  }
+}
+
+// what `riven disasm` prints: every function with its locals, then one
+// instruction per line with the source line when it changes; the statements
+// outside functions are `init`
+//
+// == main ==
+// locals: x
+// 0000    3 constant 0 (7)
+// 0001    | set_local 0 (x)
+impl Display for Module {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    for (idx, chunk) in self.functions.iter().enumerate() {
+      if idx > 0 {
+        writeln!(f)?;
+      }
+      match chunk.path.is_empty() {
+        true => writeln!(f, "== init ==")?,
+        false => writeln!(f, "== {} ==", chunk.path)?,
+      }
+      let locals: Vec<&str> = chunk.locals.iter().map(|(name, _)| name.as_str()).collect();
+      writeln!(f, "locals: {}", locals.join(", "))?;
+      for (offset, op) in chunk.code.iter().enumerate() {
+        let line = match offset > 0 && chunk.lines[offset] == chunk.lines[offset - 1] {
+          true => "|".to_owned(),
+          false => chunk.lines[offset].to_string(),
+        };
+        writeln!(
+          f,
+          "{:04} {:>4} {}",
+          offset,
+          line,
+          self.instruction(chunk, op)
+        )?;
+      }
+    }
+    Ok(())
+  }
+}
+
+fn binop_name(op: &BinOp) -> &'static str {
+  match op {
+    BinOp::Add => "add",
+    BinOp::Min => "subtract",
+    BinOp::Time => "multiply",
+    BinOp::Div => "divide",
+  }
+}
@@ -0,0 +1,343 @@
+use std::collections::HashMap;
+
+use analyzer::types::Type;
+use ir::ir::{BlockId, Constant, Function, Inst, Operand, Terminator, Var};
+use ir::{lower::lower, optimize::optimize};
+use parser::ast::Program;
+
+use crate::bytecode::{Chunk, Module, Op};
+use crate::value::Value;
+
+// compile a checked program to bytecode from its optimized IR, one chunk per
+// function, the statements outside functions first: each instruction pushes
+// its operands, runs, and stores its result in a local, temporaries included
+pub fn compile(program: &Program) -> Module {
+  let lowered = optimize(lower(program));
+  let mut module = Module {
+    structs: lowered.structs.clone(),
+    globals: lowered.globals.clone(),
+    ..Module::default()
+  };
+  let functions: Vec<&Function> = lowered.init.iter().chain(&lowered.functions).collect();
+  for (idx, function) in functions.iter().enumerate() {
+    module.function_index.insert(function.path.clone(), idx);
+  }
+
+  let chunks = functions
+    .into_iter()
+    .map(|function| {
+      let mut compiler = Compiler {
+        module: &module,
+        chunk: Chunk {
+          path: function.path.clone(),
+          arity: function.arity,
+          locals: function.locals.clone(),
+          ..Chunk::default()
+        },
+        reads: reads(function),
+        temps: HashMap::new(),
+        pending: None,
+        jumps: vec![],
+        line: 0,
+      };
+      compiler.function(function);
+      compiler.chunk
+    })
+    .collect();
+  module.functions = chunks;
+  module
+}
+
+// how many times each variable is read
+fn reads(function: &Function) -> HashMap<Var, usize> {
+  let mut reads = HashMap::new();
+  for block in &function.blocks {
+    let end = match &block.end {
+      Terminator::Branch(operand, _, _) | Terminator::Return(operand) => Some(operand),
+      Terminator::Jump(_) | Terminator::Fail(_) => None,
+    };
+    let operands = block.code.iter().flat_map(Inst::operands).chain(end);
+    for operand in operands {
+      if let Operand::Var(var) = operand {
+        *reads.entry(var.clone()).or_insert(0) += 1;
+      }
+    }
+  }
+  reads
+}
+
+struct Compiler<'a> {
+  module: &'a Module,
+  chunk: Chunk,
+  reads: HashMap<Var, usize>,
+  // the local of each temporary
+  temps: HashMap<Var, usize>,
+  // a temporary read once is left on the stack when the next instruction
+  // starts by reading it, it is stored in its local otherwise
+  pending: Option<Var>,
+  // the jumps to point at the start of a block
+  jumps: Vec<(usize, BlockId)>,
+  // source line of the instruction being compiled
+  line: usize,
+}
+
+impl<'a> Compiler<'a> {
+  fn emit(&mut self, op: Op) -> usize {
+    self.spill();
+    self.chunk.emit(op, self.line)
+  }
+
+  fn spill(&mut self) {
+    if let Some(var) = self.pending.take() {
+      let slot = self.slot(&var);
+      self.chunk.emit(Op::SetLocal(slot), self.line);
+    }
+  }
+
+  fn constant(&mut self, value: Value) -> usize {
+    let idx = self.chunk.add_constant(value);
+    self.emit(Op::Constant(idx))
+  }
+
+  // a temporary gets a local after the variables of the program the first
+  // time it is stored, a variable named like it keeps its own
+  fn slot(&mut self, var: &Var) -> usize {
+    match var {
+      Var::Local(name) => self.chunk.local(name, Type::Any),
+      Var::Global(_) => unreachable!("globals have no local"),
+      Var::Temp(_) => match self.temps.get(var) {
+        Some(slot) => *slot,
+        None => {
+          self.chunk.locals.push((var.to_string(), Type::Any));
+          let slot = self.chunk.locals.len() - 1;
+          self.temps.insert(var.clone(), slot);
+          slot
+        }
+      },
+    }
+  }
+
+  fn global(&self, name: &str) -> usize {
+    let globals = &self.module.globals;
+    let idx = globals.iter().position(|(global, _)| global == name);
+    idx.expect("globals are declared")
+  }
+
+  fn push(&mut self, operand: &Operand) {
+    match operand {
+      Operand::Var(var) if self.pending.as_ref() == Some(var) => self.pending = None,
+      Operand::Var(Var::Global(name)) => {
+        let idx = self.global(name);
+        self.emit(Op::GetGlobal(idx));
+      }
+      Operand::Var(var) => {
+        let slot = self.slot(var);
+        self.emit(Op::GetLocal(slot));
+      }
+      Operand::Const(constant) => {
+        self.constant(match constant {
+          Constant::Int(n) => Value::Int(*n),
+          Constant::Float(n) => Value::Float(*n),
+          Constant::Bool(b) => Value::Bool(*b),
+          Constant::Str(s) => Value::str(s),
+        });
+      }
+    }
+  }
+
+  fn push_all<'o>(&mut self, operands: impl IntoIterator<Item = &'o Operand>) {
+    for operand in operands {
+      self.push(operand);
+    }
+  }
+
+  // the value on top of the stack is the result of the instruction
+  fn store(&mut self, dest: &Var) {
+    match (dest, self.reads.get(dest)) {
+      (Var::Temp(_), None) => {
+        self.emit(Op::Pop);
+      }
+      (Var::Temp(_), Some(1)) => self.pending = Some(dest.clone()),
+      (Var::Global(name), _) => {
+        let idx = self.global(name);
+        self.emit(Op::SetGlobal(idx));
+      }
+      _ => {
+        let slot = self.slot(dest);
+        self.emit(Op::SetLocal(slot));
+      }
+    }
+  }
+
+  fn jump(&mut self, op: Op, target: BlockId) {
+    let at = self.emit(op);
+    self.jumps.push((at, target));
+  }
+
+  fn function(&mut self, function: &Function) {
+    for name in &function.params {
+      let slot = self.chunk.local(name, Type::Any);
+      self.chunk.params.push(slot);
+    }
+    let mut starts = vec![];
+    for (idx, block) in function.blocks.iter().enumerate() {
+      starts.push(self.chunk.code.len());
+      for (inst, line) in block.code.iter().zip(&block.lines) {
+        self.line = *line;
+        self.inst(inst);
+      }
+      self.line = block.end_line;
+      self.terminator(&block.end, idx + 1);
+      // a block can be jumped to, the stack is empty when it starts
+      self.spill();
+    }
+    for (at, target) in std::mem::take(&mut self.jumps) {
+      match &mut self.chunk.code[at] {
+        Op::Jump(to) | Op::JumpIfFalse(to) => *to = starts[target],
+        _ => unreachable!("only jumps are patched"),
+      }
+    }
+  }
+
+  // the block after this one needs no jump
+  fn terminator(&mut self, end: &Terminator, next: BlockId) {
+    match end {
+      Terminator::Jump(target) => {
+        if *target != next {
+          self.jump(Op::Jump(0), *target);
+        }
+      }
+      Terminator::Branch(condition, yes, no) => {
+        self.push(condition);
+        self.jump(Op::JumpIfFalse(0), *no);
+        if *yes != next {
+          self.jump(Op::Jump(0), *yes);
+        }
+      }
+      Terminator::Return(value) => {
+        self.push(value);
+        self.emit(Op::Return);
+      }
+      Terminator::Fail(message) => {
+        let idx = self.chunk.add_constant(Value::str(message));
+        self.emit(Op::Fail(idx));
+      }
+    }
+  }
+
+  fn inst(&mut self, inst: &Inst) {
+    match inst {
+      Inst::Copy(dest, a) => {
+        self.push(a);
+        self.store(dest);
+      }
+      Inst::Binary(dest, op, a, b) => {
+        self.push_all([a, b]);
+        self.emit(Op::Arithmetic(op.clone()));
+        self.store(dest);
+      }
+      Inst::Compare(dest, op, a, b) => {
+        self.push_all([a, b]);
+        self.emit(Op::Compare(op.clone()));
+        self.store(dest);
+      }
+      Inst::Not(dest, a) => {
+        self.push(a);
+        self.emit(Op::Not);
+        self.store(dest);
+      }
+      Inst::Convert(dest, t, a) => {
+        self.push(a);
+        self.emit(Op::Coerce(t.clone()));
+        self.store(dest);
+      }
+      Inst::Zero(dest, t) => {
+        self.emit(Op::Zero(t.clone()));
+        self.store(dest);
+      }
+      Inst::Call(dest, function, values) => {
+        self.push_all(values);
+        let idx = self.module.function_index[function];
+        self.emit(Op::Call(idx, values.len()));
+        self.store(dest);
+      }
+      Inst::CallValue(dest, function, args) => {
+        self.push(function);
+        self.push_all(args);
+        self.emit(Op::CallValue(args.len()));
+        self.store(dest);
+      }
+      Inst::Closure(dest, function, captures) => {
+        self.push_all(captures);
+        let idx = self.module.function_index[function];
+        self.emit(Op::Closure(idx, captures.len()));
+        self.store(dest);
+      }
+      Inst::Builtin(dest, name, args) => self.builtin(dest, name, args),
+      Inst::NewList(dest, items) => {
+        self.push_all(items);
+        self.emit(Op::NewList(items.len()));
+        self.store(dest);
+      }
+      Inst::NewMap(dest, entries) => {
+        self.push_all(entries.iter().flat_map(|(key, value)| [key, value]));
+        self.emit(Op::NewMap(entries.len()));
+        self.store(dest);
+      }
+      Inst::NewStruct(dest, name, fields) => {
+        self.push_all(fields);
+        let module = self.module;
+        let idx = module.structs.iter().position(|(s, _)| s == name);
+        self.emit(Op::NewStruct(
+          idx.expect("structs are declared"),
+          fields.len(),
+        ));
+        self.store(dest);
+      }
+      Inst::GetItem(dest, target, key) => {
+        self.push_all([target, key]);
+        self.emit(Op::GetItem);
+        self.store(dest);
+      }
+      Inst::SetItem(target, key, value) => {
+        self.push_all([target, key, value]);
+        self.emit(Op::SetItem);
+      }
+      Inst::GetField(dest, target, field) => {
+        self.push(target);
+        let idx = self.chunk.add_constant(Value::str(field));
+        self.emit(Op::GetField(idx));
+        self.store(dest);
+      }
+      Inst::SetField(target, field, value) => {
+        self.push_all([target, value]);
+        let idx = self.chunk.add_constant(Value::str(field));
+        self.emit(Op::SetField(idx));
+      }
+    }
+  }
+
+  // print writes the value on top and leaves nothing, it gives back 0
+  fn builtin(&mut self, dest: &Var, name: &str, args: &[Operand]) {
+    self.push_all(args);
+    self.emit(match name {
+      "print" => Op::Print,
+      "printf" => Op::Printf(args.len()),
+      "input" => Op::Input,
+      "input_number" => Op::InputNumber,
+      "len" => Op::Len,
+      "keys" => Op::Keys,
+      "append" => Op::Append,
+      "has" => Op::Has,
+      _ => unreachable!("`{}` is not a builtin", name),
+    });
+    match name {
+      "print" if !self.reads.contains_key(dest) => (),
+      "print" => {
+        self.constant(Value::Int(0));
+        self.store(dest);
+      }
+      _ => self.store(dest),
+    }
+  }
+}
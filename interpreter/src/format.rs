@@ -0,0 +1,283 @@
+use crate::value::Value;
+
+// C's `%.<precision>e`: 1.500000e+02
+pub fn format_e(value: f64, precision: usize, alternate: bool) -> String {
+  if !value.is_finite() {
+    return non_finite(value);
+  }
+  let formatted = format!("{:.*e}", precision, value);
+  let (mantissa, exponent) = formatted.split_once('e').unwrap();
+  let exponent: i32 = exponent.parse().unwrap();
+  let mantissa = match alternate && precision == 0 {
+    true => format!("{}.", mantissa),
+    false => mantissa.to_owned(),
+  };
+  let sign = if exponent < 0 { '-' } else { '+' };
+  format!("{}e{}{:02}", mantissa, sign, exponent.abs())
+}
+
+// C's `%.<precision>g`, what print uses for doubles with a precision of 15:
+// fixed notation unless the exponent is too small or too large, without
+// trailing zeros unless `alternate` (the `#` flag) is set
+pub fn format_g(value: f64, precision: usize, alternate: bool) -> String {
+  if !value.is_finite() {
+    return non_finite(value);
+  }
+  let precision = precision.max(1);
+  // the exponent once rounded to `precision` significant digits
+  let scientific = format!("{:.*e}", precision - 1, value);
+  let exponent: i32 = scientific.split_once('e').unwrap().1.parse().unwrap();
+
+  if exponent < -4 || exponent >= precision as i32 {
+    let formatted = format_e(value, precision - 1, alternate);
+    if alternate {
+      return formatted;
+    }
+    let (mantissa, exponent) = formatted.split_once('e').unwrap();
+    return format!("{}e{}", strip_zeros(mantissa), exponent);
+  }
+
+  let decimals = (precision as i32 - 1 - exponent) as usize;
+  let formatted = format!("{:.*}", decimals, value);
+  match alternate {
+    true if decimals == 0 => format!("{}.", formatted),
+    true => formatted,
+    false => strip_zeros(&formatted).to_owned(),
+  }
+}
+
+fn strip_zeros(number: &str) -> &str {
+  if !number.contains('.') {
+    return number;
+  }
+  number.trim_end_matches('0').trim_end_matches('.')
+}
+
+fn non_finite(value: f64) -> String {
+  match value {
+    _ if value.is_nan() && value.is_sign_negative() => "-nan".to_owned(),
+    _ if value.is_nan() => "nan".to_owned(),
+    _ if value > 0.0 => "inf".to_owned(),
+    _ => "-inf".to_owned(),
+  }
+}
+
+// one `%...` of a format string: flags, width, precision and conversion
+#[derive(Default)]
+struct Spec {
+  left: bool,
+  plus: bool,
+  space: bool,
+  zero: bool,
+  alternate: bool,
+  width: usize,
+  precision: Option<usize>,
+  conversion: char,
+}
+
+impl Spec {
+  // pad `body` to the width, after its sign or `0x` when padding with zeros
+  fn pad(&self, sign: &str, body: String, zero_pad: bool) -> String {
+    let len = sign.len() + body.chars().count();
+    if len >= self.width {
+      return format!("{}{}", sign, body);
+    }
+    let fill = self.width - len;
+    if self.left {
+      format!("{}{}{}", sign, body, " ".repeat(fill))
+    } else if zero_pad {
+      format!("{}{}{}", sign, "0".repeat(fill), body)
+    } else {
+      format!("{}{}{}", " ".repeat(fill), sign, body)
+    }
+  }
+
+  fn sign(&self, negative: bool) -> &'static str {
+    match () {
+      _ if negative => "-",
+      _ if self.plus => "+",
+      _ if self.space => " ",
+      _ => "",
+    }
+  }
+
+  fn int(&self, value: i64) -> String {
+    let digits = value.unsigned_abs().to_string();
+    let body = self.int_precision(digits);
+    let zero_pad = self.zero && self.precision.is_none();
+    self.pad(self.sign(value < 0), body, zero_pad)
+  }
+
+  fn unsigned(&self, value: u32) -> String {
+    let digits = match self.conversion {
+      'x' => format!("{:x}", value),
+      'X' => format!("{:X}", value),
+      'o' => format!("{:o}", value),
+      _ => value.to_string(),
+    };
+    let prefix = match self.conversion {
+      'x' if self.alternate && value != 0 => "0x",
+      'X' if self.alternate && value != 0 => "0X",
+      'o' if self.alternate && !digits.starts_with('0') => "0",
+      _ => "",
+    };
+    let body = self.int_precision(digits);
+    let zero_pad = self.zero && self.precision.is_none();
+    self.pad(prefix, body, zero_pad)
+  }
+
+  // the precision of an integer is its minimum number of digits
+  fn int_precision(&self, digits: String) -> String {
+    match self.precision {
+      Some(0) if digits == "0" => String::new(),
+      Some(precision) if precision > digits.len() => {
+        format!("{}{}", "0".repeat(precision - digits.len()), digits)
+      }
+      _ => digits,
+    }
+  }
+
+  fn float(&self, value: f64) -> String {
+    let precision = self.precision.unwrap_or(6);
+    let body = match self.conversion.to_ascii_lowercase() {
+      'e' => format_e(value.abs(), precision, self.alternate),
+      'g' => format_g(value.abs(), precision, self.alternate),
+      _ if !value.is_finite() => non_finite(value.abs()),
+      _ if self.alternate && precision == 0 => format!("{:.0}.", value.abs()),
+      _ => format!("{:.*}", precision, value.abs()),
+    };
+    let body = match self.conversion.is_ascii_uppercase() {
+      true => body.to_uppercase(),
+      false => body,
+    };
+    let zero_pad = self.zero && value.is_finite();
+    self.pad(self.sign(value.is_sign_negative()), body, zero_pad)
+  }
+
+  fn string(&self, value: &str) -> String {
+    let body: String = match self.precision {
+      Some(precision) => value.chars().take(precision).collect(),
+      None => value.to_owned(),
+    };
+    self.pad("", body, false)
+  }
+}
+
+// C's printf for riven values: ints and doubles convert to what the
+// conversion expects, `%s` takes any value and prints it like print does
+pub fn printf(format: &str, args: &[Value]) -> Result<String, String> {
+  let mut output = String::new();
+  let mut args = args.iter();
+  let mut chars = format.chars().peekable();
+  while let Some(c) = chars.next() {
+    if c != '%' {
+      output.push(c);
+      continue;
+    }
+
+    let mut spec = Spec::default();
+    while let Some(flag) = chars.peek() {
+      match flag {
+        '-' => spec.left = true,
+        '+' => spec.plus = true,
+        ' ' => spec.space = true,
+        '0' => spec.zero = true,
+        '#' => spec.alternate = true,
+        _ => break,
+      }
+      chars.next();
+    }
+    if chars.peek() == Some(&'*') {
+      chars.next();
+      let width = int_arg(args.next())?;
+      spec.left |= width < 0;
+      spec.width = width.unsigned_abs() as usize;
+    }
+    while let Some(digit) = chars.peek().and_then(|c| c.to_digit(10)) {
+      spec.width = spec.width * 10 + digit as usize;
+      chars.next();
+    }
+    if chars.peek() == Some(&'.') {
+      chars.next();
+      let mut precision = 0;
+      if chars.peek() == Some(&'*') {
+        chars.next();
+        precision = int_arg(args.next())?.max(0) as usize;
+      }
+      while let Some(digit) = chars.peek().and_then(|c| c.to_digit(10)) {
+        precision = precision * 10 + digit as usize;
+        chars.next();
+      }
+      spec.precision = Some(precision);
+    }
+    // length modifiers change nothing for riven values
+    while matches!(chars.peek(), Some('h' | 'l' | 'L' | 'q' | 'j' | 'z' | 't')) {
+      chars.next();
+    }
+
+    spec.conversion = chars
+      .next()
+      .ok_or("the format ends in the middle of a `%`")?;
+    let formatted = match spec.conversion {
+      '%' => "%".to_owned(),
+      'd' | 'i' => spec.int(int_arg(args.next())? as i64),
+      'u' | 'x' | 'X' | 'o' => spec.unsigned(int_arg(args.next())? as u32),
+      'c' => {
+        let code = int_arg(args.next())? as u8;
+        spec.string(&(code as char).to_string())
+      }
+      'f' | 'F' | 'e' | 'E' | 'g' | 'G' => match next_arg(args.next())?.number() {
+        Some(number) => spec.float(number),
+        None => return Err("`%f`, `%e` and `%g` need a number".to_owned()),
+      },
+      's' => spec.string(&next_arg(args.next())?.to_string()),
+      other => return Err(format!("`%{}` is not supported", other)),
+    };
+    output.push_str(&formatted);
+  }
+  Ok(output)
+}
+
+fn next_arg(arg: Option<&Value>) -> Result<&Value, String> {
+  arg.ok_or_else(|| "the format needs more arguments".to_owned())
+}
+
+fn int_arg(arg: Option<&Value>) -> Result<i32, String> {
+  match next_arg(arg)? {
+    Value::Int(n) => Ok(*n),
+    Value::Float(n) => Ok(*n as i32),
+    Value::Bool(b) => Ok(*b as i32),
+    _ => Err("`%d`, `%x` and `%c` need a number".to_owned()),
+  }
+}
+
+#[test]
+fn test_format_g() {
+  assert_eq!(format_g(3.5, 15, false), "3.5");
+  assert_eq!(format_g(0.1 + 0.2, 15, false), "0.3");
+  assert_eq!(format_g(100.0, 15, false), "100");
+  assert_eq!(format_g(1e15, 15, false), "1e+15");
+  assert_eq!(format_g(0.0001, 15, false), "0.0001");
+  assert_eq!(format_g(0.00001, 15, false), "1e-05");
+  assert_eq!(format_g(1.0 / 3.0, 6, false), "0.333333");
+  assert_eq!(format_g(f64::INFINITY, 15, false), "inf");
+  assert_eq!(format_e(1234.5, 2, false), "1.23e+03");
+}
+
+#[test]
+fn test_printf() {
+  let args = [Value::Int(42), Value::Float(1.255), Value::str("riven")];
+  assert_eq!(
+    printf("%d|%5.2f|%-6s|%%", &args).unwrap(),
+    "42| 1.25|riven |%"
+  );
+  assert_eq!(
+    printf(
+      "%05d %x %+.3e",
+      &[Value::Int(-42), Value::Int(255), Value::Float(0.5)]
+    )
+    .unwrap(),
+    "-0042 ff +5.000e-01"
+  );
+  assert!(printf("%d %d", &[Value::Int(1)]).is_err());
+}
@@ -0,0 +1,601 @@
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+use std::rc::Rc;
+
+use analyzer::infer::{child_path, infer, statement_functions, statements, TypeInfo};
+use analyzer::operands::Operation;
+use analyzer::types::Type;
+use parser::ast::{
+  ApplyExpr, AssignStmt, BinOp, CallExpr, CompareExpr, Expression, Factor, FactorValue,
+  FieldAssignStmt, FieldExpr, FunctionStmt, IfStmt, IndexAssignStmt, IndexExpr, ListExpr,
+  LogicExpr, LogicOp, MapEntry, MapExpr, Primary, PrimaryValue, Program, ReturnStmt, Statement,
+  StatementValue, StmtList, StructStmt, WhileStmt,
+};
+
+use crate::format::printf;
+use crate::runtime::{self, Console};
+use crate::value::{Closure, Map, Value};
+
+pub use crate::runtime::RuntimeError;
+
+// the C stack of a compiled program is 8MB, walking the tree takes tens of KB
+// per riven call in a debug build, so the interpreter runs on a larger one
+const STACK_SIZE: usize = 256 * 1024 * 1024;
+
+// deeper recursion is a runtime error rather than an overflow of that stack
+const MAX_CALL_DEPTH: usize = 4_000;
+
+// run a checked program on stdin and stdout, returns the exit code of `main`
+pub fn run(program: &Program) -> Result<i32, RuntimeError> {
+  std::thread::scope(|scope| {
+    std::thread::Builder::new()
+      .stack_size(STACK_SIZE)
+      .spawn_scoped(scope, || {
+        let mut input = io::stdin().lock();
+        let mut output = io::BufWriter::new(io::stdout().lock());
+        run_with(program, &mut input, &mut output)
+      })
+      .expect("couldn't start the interpreter thread")
+      .join()
+      .expect("the interpreter thread panicked")
+  })
+}
+
+pub fn run_with(
+  program: &Program,
+  input: &mut dyn BufRead,
+  output: &mut dyn Write,
+) -> Result<i32, RuntimeError> {
+  let mut interpreter = Interpreter::new(program, input, output);
+  let result = interpreter.run(program);
+  // what was printed comes before the error, like the C runtime flushes stdout
+  interpreter.console.flush();
+  result
+}
+
+// the variables of one call of a function
+struct Frame {
+  // see analyzer::infer::child_path
+  path: String,
+  variables: HashMap<String, Value>,
+}
+
+struct Interpreter<'p, 'io> {
+  types: TypeInfo,
+  // every function by path, nested and anonymous ones included
+  functions: HashMap<String, &'p FunctionStmt>,
+  // field names of every struct in declaration order
+  structs: HashMap<String, Vec<String>>,
+  // the variables assigned outside functions, every function can read them
+  globals: HashMap<String, Value>,
+  console: Console<'io>,
+  // source line of the statement being run, used by runtime errors
+  line: usize,
+  // calls being run, main included
+  depth: usize,
+}
+
+impl<'p, 'io> Interpreter<'p, 'io> {
+  fn new(program: &'p Program, input: &'io mut dyn BufRead, output: &'io mut dyn Write) -> Self {
+    let mut interpreter = Interpreter {
+      types: infer(program).types,
+      functions: HashMap::new(),
+      structs: HashMap::new(),
+      globals: HashMap::new(),
+      console: Console { input, output },
+      line: 0,
+      depth: 0,
+    };
+    interpreter.declare(&program.0, "");
+    interpreter
+  }
+
+  // find the functions and structs wherever they are
+  fn declare(&mut self, stmt_list: &'p StmtList, path: &str) {
+    for stmt in statements(stmt_list) {
+      for (function, _) in statement_functions(stmt) {
+        let function_path = child_path(path, &function.0.to_string());
+        if let Some(body) = &function.2 {
+          self.declare(body, &function_path);
+        }
+        self.functions.insert(function_path, function);
+      }
+      match &stmt.0 {
+        StatementValue::StructStmt(StructStmt(name, fields)) => {
+          let fields = fields.iter().map(|field| field.to_string()).collect();
+          self.structs.insert(name.to_string(), fields);
+        }
+        StatementValue::IfStmt(IfStmt(_, Some(body)))
+        | StatementValue::WhileStmt(WhileStmt(_, Some(body))) => self.declare(body, path),
+        _ => (),
+      }
+    }
+  }
+
+  fn run(&mut self, program: &'p Program) -> Result<i32, RuntimeError> {
+    if !self.functions.contains_key("main") {
+      return Err(self.error("the program has no `main` function"));
+    }
+    // the statements outside functions run first, in order
+    let mut frame = Frame {
+      path: String::new(),
+      variables: HashMap::new(),
+    };
+    self.exec_block(&mut frame, &program.0)?;
+    // like the C `int main`, what main returns is the exit code
+    match self.call_function("main", vec![], vec![])? {
+      Value::Int(code) => Ok(code),
+      Value::Float(code) => Ok(code as i32),
+      _ => Ok(0),
+    }
+  }
+
+  fn error(&self, message: impl Into<String>) -> RuntimeError {
+    RuntimeError {
+      line: self.line,
+      message: message.into(),
+    }
+  }
+
+  fn write(&mut self, text: &str) -> Result<(), RuntimeError> {
+    self
+      .console
+      .write(text)
+      .map_err(|message| self.error(message))
+  }
+
+  fn coerce(&self, value: Value, t: &Type) -> Result<Value, RuntimeError> {
+    runtime::coerce(value, t).map_err(|message| self.error(message))
+  }
+
+  // a variable of the function being run, one it captured or a global; like
+  // in C, a variable of the function or a global is zero until its first
+  // assignment
+  fn lookup(&self, frame: &Frame, name: &str) -> Result<Value, RuntimeError> {
+    if let Some(value) = frame.variables.get(name) {
+      return Ok(value.clone());
+    }
+    let zero = || runtime::zero(&self.types.variable_type(&frame.path, name));
+    match self.types.resolve_variable(&frame.path, name) {
+      Some(owner) if owner.is_empty() => Ok(self.globals.get(name).cloned().unwrap_or_else(zero)),
+      Some(owner) if owner == frame.path => Ok(zero()),
+      _ => Err(self.error(format!("undefined variable `{}`", name))),
+    }
+  }
+
+  fn assign(&mut self, frame: &mut Frame, name: String, value: Value) {
+    match frame.path.is_empty() {
+      true => self.globals.insert(name, value),
+      false => frame.variables.insert(name, value),
+    };
+  }
+
+  // the values of the captured variables, then the arguments
+  fn call_function(
+    &mut self,
+    path: &str,
+    captures: Vec<Value>,
+    args: Vec<Value>,
+  ) -> Result<Value, RuntimeError> {
+    let Some(&function) = self.functions.get(path) else {
+      return Err(self.error(format!("`{}` is not a function", path)));
+    };
+    let mut frame = Frame {
+      path: path.to_owned(),
+      variables: HashMap::new(),
+    };
+    let values = captures.into_iter().chain(args);
+    for (name, value) in self.types.params(path).into_iter().zip(values) {
+      let value = self.coerce(value, &self.types.variable_type(path, &name))?;
+      frame.variables.insert(name, value);
+    }
+
+    if self.depth == MAX_CALL_DEPTH {
+      return Err(self.error(format!("more than {} nested calls", MAX_CALL_DEPTH)));
+    }
+    let line = self.line;
+    self.depth += 1;
+    let returned = match &function.2 {
+      Some(body) => self.exec_block(&mut frame, body)?,
+      None => None,
+    };
+    self.depth -= 1;
+    self.line = line;
+    // the body ended without a `return`, see flow::default_return
+    Ok(returned.unwrap_or_else(|| runtime::zero(&self.types.return_type(path))))
+  }
+
+  // Some with the returned value once a `return` is run
+  fn exec_block(
+    &mut self,
+    frame: &mut Frame,
+    stmt_list: &'p StmtList,
+  ) -> Result<Option<Value>, RuntimeError> {
+    for stmt in statements(stmt_list) {
+      if let Some(value) = self.exec_stmt(frame, stmt)? {
+        return Ok(Some(value));
+      }
+    }
+    Ok(None)
+  }
+
+  fn exec_stmt(
+    &mut self,
+    frame: &mut Frame,
+    stmt: &'p Statement,
+  ) -> Result<Option<Value>, RuntimeError> {
+    self.line = stmt.location().row();
+    match &stmt.0 {
+      StatementValue::AssignStmt(AssignStmt(name, expr, _)) => {
+        let name = name.to_string();
+        let value = self.eval_expr(frame, expr)?;
+        let value = self.coerce(value, &self.types.variable_type(&frame.path, &name))?;
+        self.assign(frame, name, value);
+      }
+      StatementValue::IndexAssignStmt(IndexAssignStmt(IndexExpr(target, key), value)) => {
+        let target = self.eval_target(frame, target)?;
+        let key = self.eval_boxed(frame, key)?;
+        let value = self.eval_boxed(frame, value)?;
+        runtime::set_item(&target, key, value).map_err(|message| self.error(message))?;
+      }
+      StatementValue::FieldAssignStmt(FieldAssignStmt(FieldExpr(target, field), value)) => {
+        let target = self.eval_target(frame, target)?;
+        let value = self.eval_boxed(frame, value)?;
+        let result = runtime::set_field(&target, &field.to_string(), value);
+        result.map_err(|message| self.error(message))?;
+      }
+      StatementValue::CallStmt(call_expr) => {
+        self.eval_call(frame, call_expr)?;
+      }
+      StatementValue::ApplyStmt(apply_expr) => {
+        self.eval_apply(frame, apply_expr)?;
+      }
+      StatementValue::ReturnStmt(ReturnStmt(expr)) => {
+        let value = self.eval_expr(frame, expr)?;
+        let value = self.coerce(value, &self.types.return_type(&frame.path))?;
+        return Ok(Some(value));
+      }
+      // functions and structs are found by declare before the program runs
+      StatementValue::FunctionStmt(_) | StatementValue::StructStmt(_) => (),
+      StatementValue::IfStmt(IfStmt(condition, body)) => {
+        if self.eval_logic(frame, condition)? {
+          if let Some(body) = body {
+            return self.exec_block(frame, body);
+          }
+        }
+      }
+      StatementValue::WhileStmt(WhileStmt(condition, body)) => {
+        while self.eval_logic(frame, condition)? {
+          if let Some(body) = body {
+            if let Some(value) = self.exec_block(frame, body)? {
+              return Ok(Some(value));
+            }
+          }
+          self.line = stmt.location().row();
+        }
+      }
+    }
+    Ok(None)
+  }
+
+  fn eval_logic(&mut self, frame: &mut Frame, expr: &'p LogicExpr) -> Result<bool, RuntimeError> {
+    let LogicExpr(op, compare_expr, left, right) = expr;
+    match op {
+      LogicOp::Bool => match compare_expr {
+        Some(compare_expr) => self.eval_compare(frame, compare_expr),
+        None => Ok(false),
+      },
+      LogicOp::And => {
+        let left = match left {
+          Some(left) => self.eval_logic(frame, left)?,
+          None => true,
+        };
+        match right {
+          Some(right) if left => self.eval_logic(frame, right),
+          _ => Ok(left),
+        }
+      }
+      LogicOp::Or => {
+        let left = match left {
+          Some(left) => self.eval_logic(frame, left)?,
+          None => false,
+        };
+        match right {
+          Some(right) if !left => self.eval_logic(frame, right),
+          _ => Ok(left),
+        }
+      }
+      LogicOp::Not => match left {
+        Some(left) => Ok(!self.eval_logic(frame, left)?),
+        None => Ok(true),
+      },
+    }
+  }
+
+  fn eval_compare(
+    &mut self,
+    frame: &mut Frame,
+    expr: &'p CompareExpr,
+  ) -> Result<bool, RuntimeError> {
+    let CompareExpr(left, op, right) = expr;
+    let left = self.eval_expr(frame, left)?;
+    let (Some(op), Some(right)) = (op, right) else {
+      return Ok(left.truth());
+    };
+    let right = self.eval_expr(frame, right)?;
+    runtime::compare(&left, op, &right).map_err(|message| self.error(message))
+  }
+
+  fn eval_expr(&mut self, frame: &mut Frame, expr: &'p Expression) -> Result<Value, RuntimeError> {
+    self.operation(frame, &Operation::of_expr(expr))
+  }
+
+  // a list item, map value or struct field keeps its own kind where C keeps it
+  // boxed: when printed, stored in a list or map, or passed to a struct
+  fn eval_boxed(&mut self, frame: &mut Frame, expr: &'p Expression) -> Result<Value, RuntimeError> {
+    match expr.as_factor() {
+      Some(factor) if is_stored_value(factor) => self.stored_value(frame, factor),
+      _ => self.eval_expr(frame, expr),
+    }
+  }
+
+  fn operation(
+    &mut self,
+    frame: &mut Frame,
+    operation: &Operation<'p>,
+  ) -> Result<Value, RuntimeError> {
+    match operation {
+      Operation::Factor(factor) => self.eval_factor(frame, factor),
+      Operation::Group(inner) => self.operation(frame, inner),
+      Operation::Binary(left, op, right) => {
+        let left = self.operation(frame, left)?;
+        let right = self.operation(frame, right)?;
+        self.apply(&left, op, &right)
+      }
+    }
+  }
+
+  fn apply(&self, left: &Value, op: &BinOp, right: &Value) -> Result<Value, RuntimeError> {
+    runtime::apply(left, op, right).map_err(|message| self.error(message))
+  }
+
+  fn eval_factor(&mut self, frame: &mut Frame, factor: &'p Factor) -> Result<Value, RuntimeError> {
+    match &factor.0 {
+      FactorValue::Primary(Primary(primary)) => Ok(match primary {
+        PrimaryValue::String(s) => Value::str(s),
+        PrimaryValue::Number(n) => Value::Int(*n as i32),
+        PrimaryValue::Float(n) => Value::Float(*n),
+        PrimaryValue::Bool(b) => Value::Bool(*b),
+      }),
+      // a function used as a value becomes a closure
+      FactorValue::Identifier(name) => {
+        let name = name.to_string();
+        if self.types.resolve_variable(&frame.path, &name).is_some() {
+          return self.lookup(frame, &name);
+        }
+        match self.types.resolve_function(&frame.path, &name) {
+          Some(function) => self.closure(frame, function),
+          None => Err(self.error(format!("undefined variable `{}`", name))),
+        }
+      }
+      FactorValue::Function(function, _) => {
+        let function = child_path(&frame.path, &function.0.to_string());
+        self.closure(frame, function)
+      }
+      FactorValue::CallExpr(call_expr) => self.eval_call(frame, call_expr),
+      FactorValue::Apply(apply_expr) => self.eval_apply(frame, apply_expr),
+      FactorValue::List(ListExpr(items)) => {
+        let mut values = vec![];
+        for item in &items.0 {
+          values.push(self.eval_boxed(frame, item)?);
+        }
+        Ok(Value::list(values))
+      }
+      FactorValue::Map(MapExpr(entries)) => {
+        let mut map = Map::default();
+        for MapEntry(key, value) in entries {
+          let key = self.eval_boxed(frame, key)?;
+          let value = self.eval_boxed(frame, value)?;
+          map.set(key, value);
+        }
+        Ok(Value::map(map))
+      }
+      FactorValue::Index(_) | FactorValue::Field(_) => {
+        let value = self.stored_value(frame, factor)?;
+        self.coerce(value, &self.types.type_of_factor(&frame.path, factor))
+      }
+    }
+  }
+
+  // the captured variables are copied into the closure when it is made
+  fn closure(&self, frame: &Frame, function: String) -> Result<Value, RuntimeError> {
+    let env = self.captured(frame, &function)?;
+    Ok(Value::Function(Rc::new(Closure { function, env })))
+  }
+
+  // the values of the variables a function captures
+  fn captured(&self, frame: &Frame, function: &str) -> Result<Vec<Value>, RuntimeError> {
+    let captures = self.types.captures(function);
+    captures
+      .iter()
+      .map(|name| self.lookup(frame, name))
+      .collect()
+  }
+
+  // the value at `list[index]`, `map[key]` or `p.x` as it is stored
+  fn stored_value(&mut self, frame: &mut Frame, factor: &'p Factor) -> Result<Value, RuntimeError> {
+    match &factor.0 {
+      FactorValue::Index(IndexExpr(target, key)) => {
+        let target = self.eval_target(frame, target)?;
+        let key = self.eval_boxed(frame, key)?;
+        runtime::get_item(&target, &key).map_err(|message| self.error(message))
+      }
+      FactorValue::Field(FieldExpr(target, field)) => {
+        let target = self.eval_target(frame, target)?;
+        runtime::get_field(&target, &field.to_string()).map_err(|message| self.error(message))
+      }
+      _ => self.eval_factor(frame, factor),
+    }
+  }
+
+  // `grid[0]` in `grid[0][1]` stays as stored, its kind is checked when indexed
+  fn eval_target(&mut self, frame: &mut Frame, target: &'p Factor) -> Result<Value, RuntimeError> {
+    match is_stored_value(target) {
+      true => self.stored_value(frame, target),
+      false => self.eval_factor(frame, target),
+    }
+  }
+
+  fn eval_args(
+    &mut self,
+    frame: &mut Frame,
+    args: &'p [Expression],
+  ) -> Result<Vec<Value>, RuntimeError> {
+    let mut values = vec![];
+    for arg in args {
+      values.push(self.eval_expr(frame, arg)?);
+    }
+    Ok(values)
+  }
+
+  fn eval_call(
+    &mut self,
+    frame: &mut Frame,
+    call_expr: &'p CallExpr,
+  ) -> Result<Value, RuntimeError> {
+    let CallExpr(name, args) = call_expr;
+    let name = name.to_string();
+    let args = &args.0;
+    match name.as_str() {
+      // each value is written once computed, like the C runtime prints them
+      "print" | "println" => {
+        for (idx, arg) in args.iter().enumerate() {
+          if idx > 0 {
+            self.write(" ")?;
+          }
+          let value = self.eval_boxed(frame, arg)?;
+          self.write(&value.to_string())?;
+        }
+        if name == "println" {
+          self.write("\n")?;
+        }
+        return Ok(Value::Int(0));
+      }
+      "printf" => {
+        let values = self.eval_args(frame, args)?;
+        let Some(Value::Str(format)) = values.first() else {
+          return Err(self.error("printf needs a format text"));
+        };
+        let text = printf(format, &values[1..]).map_err(|message| self.error(message))?;
+        self.write(&text)?;
+        return Ok(Value::Int(text.len() as i32));
+      }
+      "input" => {
+        let prompt = self.eval_expr(frame, &args[0])?.to_string();
+        let result = self
+          .console
+          .input(&prompt)
+          .map(|(line, _)| Value::str(&line));
+        return result.map_err(|message| self.error(message));
+      }
+      "input_number" => {
+        let prompt = self.eval_expr(frame, &args[0])?.to_string();
+        let result = self.console.input_number(&prompt);
+        return result.map_err(|message| self.error(message));
+      }
+      "len" => {
+        let value = self.eval_expr(frame, &args[0])?;
+        return runtime::len(&value).map_err(|message| self.error(message));
+      }
+      "keys" => {
+        let value = self.eval_expr(frame, &args[0])?;
+        return runtime::keys(&value).map_err(|message| self.error(message));
+      }
+      "append" => {
+        let list = self.eval_expr(frame, &args[0])?;
+        let value = self.eval_boxed(frame, &args[1])?;
+        return runtime::append(&list, value).map_err(|message| self.error(message));
+      }
+      "has" => {
+        let map = self.eval_expr(frame, &args[0])?;
+        let key = self.eval_boxed(frame, &args[1])?;
+        return runtime::has(&map, &key).map_err(|message| self.error(message));
+      }
+      _ => (),
+    }
+
+    // Point(1, 2)
+    if let Some(fields) = self.structs.get(&name).cloned() {
+      let mut values = vec![];
+      for arg in args {
+        values.push(self.eval_boxed(frame, arg)?);
+      }
+      return Ok(runtime::new_object(&name, &fields, values));
+    }
+
+    // f(3) where f is a variable holding a function
+    if self.types.resolve_variable(&frame.path, &name).is_some() {
+      let function = self.lookup(frame, &name)?;
+      let factor = Factor::from(FactorValue::CallExpr(call_expr.clone()));
+      return self.call_value(frame, function, args, &factor);
+    }
+
+    let Some(function) = self.types.resolve_function(&frame.path, &name) else {
+      return Err(self.error(format!("`{}` is not a function", name)));
+    };
+    let captures = self.captured(frame, &function)?;
+    let values = self.eval_args(frame, args)?;
+    self.call_function(&function, captures, values)
+  }
+
+  // make_adder(2)(3), fs[0](4) or s.f(1)
+  fn eval_apply(
+    &mut self,
+    frame: &mut Frame,
+    apply_expr: &'p ApplyExpr,
+  ) -> Result<Value, RuntimeError> {
+    let function = self.eval_factor(frame, &apply_expr.0)?;
+    let factor = Factor::from(FactorValue::Apply(apply_expr.clone()));
+    self.call_value(frame, function, &apply_expr.1 .0, &factor)
+  }
+
+  // call the function value `function`, `factor` is the whole call and gives the type
+  // of its result
+  fn call_value(
+    &mut self,
+    frame: &mut Frame,
+    function: Value,
+    args: &'p [Expression],
+    factor: &Factor,
+  ) -> Result<Value, RuntimeError> {
+    let Value::Function(closure) = function else {
+      return Err(self.error("expected a function"));
+    };
+    let mut values = vec![];
+    for arg in args {
+      values.push(self.eval_boxed(frame, arg)?);
+    }
+    let value = self.call_closure(&closure, values)?;
+    match self.types.type_of_factor(&frame.path, factor) {
+      Type::Void => Ok(value),
+      return_type => self.coerce(value, &return_type),
+    }
+  }
+
+  fn call_closure(&mut self, closure: &Closure, args: Vec<Value>) -> Result<Value, RuntimeError> {
+    let arity = self.functions[&closure.function].1.len();
+    if args.len() != arity {
+      return Err(self.error(format!(
+        "function {} takes {} argument(s) but {} were given",
+        closure.name(),
+        arity,
+        args.len()
+      )));
+    }
+    self.call_function(&closure.function, closure.env.clone(), args)
+  }
+}
+
+fn is_stored_value(factor: &Factor) -> bool {
+  matches!(factor.0, FactorValue::Index(_) | FactorValue::Field(_))
+}
@@ -0,0 +1,26 @@
+//! Runs a checked riven program directly from its AST, without generating C
+//! or calling gcc. The output is the same as the one of the compiled program:
+//! values are printed the way the C runtime prints them and ints keep the
+//! semantics of a C int.
+//!
+//! The program can also be compiled to bytecode (`compile`) from its optimized
+//! IR, see the `ir` crate, and run by a stack machine (`vm`);
+//! `bytecode::Module` prints the instructions.
+//!
+//! ```
+//! use interpreter::interpreter::run_with;
+//! use parser::{parse_program::parse_program, parser::Parser};
+//!
+//! let mut p = Parser::new("program { fn main() { println(7 / 2, 7 / 2.0); } }");
+//! let mut output = vec![];
+//! run_with(&parse_program(&mut p), &mut "".as_bytes(), &mut output).unwrap();
+//! assert_eq!(String::from_utf8(output).unwrap(), "3 3.5\n");
+//! ```
+
+pub mod bytecode;
+pub mod compile;
+pub mod format;
+pub mod interpreter;
+pub mod runtime;
+pub mod value;
+pub mod vm;
@@ -0,0 +1,285 @@
+use std::cell::RefCell;
+use std::fmt::{self, Display};
+use std::io::{BufRead, Write};
+use std::rc::Rc;
+
+use analyzer::types::Type;
+use parser::ast::{BinOp, CompareOp};
+
+use crate::value::{Map, Object, Value};
+
+// what the tree walker and the bytecode VM do the same way: the operations on
+// values, with the messages of the C runtime when they fail
+
+// an error stopping the program, reported the way the C runtime reports it
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuntimeError {
+  pub line: usize,
+  pub message: String,
+}
+
+impl Display for RuntimeError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "runtime error at line {}: {}", self.line, self.message)
+  }
+}
+
+// the value a variable has before its first assignment, what C declares it with
+pub fn zero(t: &Type) -> Value {
+  match t {
+    Type::Float => Value::Float(0.0),
+    Type::Bool => Value::Bool(false),
+    Type::Str => Value::str(""),
+    Type::List(_) => Value::list(vec![]),
+    Type::Map(_, _) => Value::map(Map::default()),
+    _ => Value::Int(0),
+  }
+}
+
+// convert a value to the type inferred where it is stored or used, like
+// assigning to a C variable or unboxing a RivenValue: ints become doubles
+// in a number variable, and a list item of the wrong kind stops the program
+pub fn coerce(value: Value, t: &Type) -> Result<Value, String> {
+  let message = match (t, value) {
+    (Type::Int, Value::Float(n)) => return Ok(Value::Int(n as i32)),
+    (Type::Int, Value::Bool(b)) => return Ok(Value::Int(b as i32)),
+    (Type::Int, value @ Value::Int(_)) => return Ok(value),
+    (Type::Float, Value::Int(n)) => return Ok(Value::Float(n as f64)),
+    (Type::Float, value @ Value::Float(_)) => return Ok(value),
+    (Type::Int | Type::Float, _) => "expected a number".to_owned(),
+    (Type::Bool, value @ Value::Bool(_)) => return Ok(value),
+    (Type::Bool, _) => "expected True or False".to_owned(),
+    (Type::Str, value @ Value::Str(_)) => return Ok(value),
+    (Type::Str, _) => "expected a text".to_owned(),
+    (Type::List(_), value @ Value::List(_)) => return Ok(value),
+    (Type::List(_), _) => "expected a list".to_owned(),
+    (Type::Map(_, _), value @ Value::Map(_)) => return Ok(value),
+    (Type::Map(_, _), _) => "expected a map".to_owned(),
+    (Type::Struct(name), Value::Object(object)) if object.borrow().name == *name => {
+      return Ok(Value::Object(object))
+    }
+    (Type::Struct(name), _) => format!("expected a {}", name),
+    (Type::Function(_), value @ Value::Function(_)) => return Ok(value),
+    (Type::Function(_), _) => "expected a function".to_owned(),
+    (Type::Any | Type::Unknown | Type::Void, value) => return Ok(value),
+  };
+  Err(message)
+}
+
+// `a op b` the way C computes it: ints stay ints and wrap around like a
+// C int on this platform, `7 / 2` is 3
+pub fn apply(left: &Value, op: &BinOp, right: &Value) -> Result<Value, String> {
+  if let (Some(a), Some(b)) = (arithmetic_int(left), arithmetic_int(right)) {
+    let value = match op {
+      BinOp::Add => a.wrapping_add(b),
+      BinOp::Min => a.wrapping_sub(b),
+      BinOp::Time => a.wrapping_mul(b),
+      BinOp::Div if b == 0 => return Err("division by zero".to_owned()),
+      BinOp::Div => a.wrapping_div(b),
+    };
+    return Ok(Value::Int(value));
+  }
+
+  let (Some(a), Some(b)) = (arithmetic_number(left), arithmetic_number(right)) else {
+    return Err("expected a number".to_owned());
+  };
+  Ok(Value::Float(match op {
+    BinOp::Add => a + b,
+    BinOp::Min => a - b,
+    BinOp::Time => a * b,
+    BinOp::Div => a / b,
+  }))
+}
+
+// `=` compares any values, `>` and `<` only numbers
+pub fn compare(left: &Value, op: &CompareOp, right: &Value) -> Result<bool, String> {
+  if let CompareOp::Eq = op {
+    return Ok(left.equals(right));
+  }
+  let (Some(a), Some(b)) = (arithmetic_number(left), arithmetic_number(right)) else {
+    return Err("expected a number".to_owned());
+  };
+  Ok(match op {
+    CompareOp::Gt => a > b,
+    _ => a < b,
+  })
+}
+
+// bools are ints in C arithmetic
+fn arithmetic_int(value: &Value) -> Option<i32> {
+  match value {
+    Value::Int(n) => Some(*n),
+    Value::Bool(b) => Some(*b as i32),
+    _ => None,
+  }
+}
+
+fn arithmetic_number(value: &Value) -> Option<f64> {
+  match value {
+    Value::Bool(b) => Some(*b as i32 as f64),
+    value => value.number(),
+  }
+}
+
+fn list_index(items: &[Value], key: &Value) -> Result<usize, String> {
+  let Some(index) = key.number() else {
+    return Err("a list index must be a number".to_owned());
+  };
+  let index = index as i32;
+  if index < 0 || index as usize >= items.len() {
+    return Err(format!(
+      "index {} is out of range, the list has {} item(s)",
+      index,
+      items.len()
+    ));
+  }
+  Ok(index as usize)
+}
+
+// the value at `list[index]` or `map[key]` as it is stored
+pub fn get_item(target: &Value, key: &Value) -> Result<Value, String> {
+  match target {
+    Value::List(items) => {
+      let items = items.borrow();
+      Ok(items[list_index(&items, key)?].clone())
+    }
+    Value::Map(map) => match map.borrow().get(key) {
+      Some(value) => Ok(value.clone()),
+      None => Err(match key {
+        Value::Str(key) => format!("key \"{}\" is not in the map", key),
+        _ => "key is not in the map".to_owned(),
+      }),
+    },
+    _ => Err("only lists and maps can be indexed".to_owned()),
+  }
+}
+
+pub fn set_item(target: &Value, key: Value, value: Value) -> Result<(), String> {
+  match target {
+    Value::List(items) => {
+      let index = list_index(&items.borrow(), &key)?;
+      items.borrow_mut()[index] = value;
+      Ok(())
+    }
+    Value::Map(map) => {
+      map.borrow_mut().set(key, value);
+      Ok(())
+    }
+    _ => Err("only lists and maps can be indexed".to_owned()),
+  }
+}
+
+// the index of a field in the struct, or the error C reports
+fn field_index(object: &Object, field: &str) -> Result<usize, String> {
+  match object.fields.iter().position(|(name, _)| name == field) {
+    Some(idx) => Ok(idx),
+    None => Err(format!("{} has no field named {}", object.name, field)),
+  }
+}
+
+pub fn get_field(target: &Value, field: &str) -> Result<Value, String> {
+  let Value::Object(object) = target else {
+    return Err("only structs have fields".to_owned());
+  };
+  let object = object.borrow();
+  Ok(object.fields[field_index(&object, field)?].1.clone())
+}
+
+pub fn set_field(target: &Value, field: &str, value: Value) -> Result<(), String> {
+  let Value::Object(object) = target else {
+    return Err("only structs have fields".to_owned());
+  };
+  let idx = field_index(&object.borrow(), field)?;
+  object.borrow_mut().fields[idx].1 = value;
+  Ok(())
+}
+
+// Point(1, 2), the values in the order of the fields
+pub fn new_object(name: &str, fields: &[String], values: Vec<Value>) -> Value {
+  let object = Object {
+    name: name.to_owned(),
+    fields: fields.iter().cloned().zip(values).collect(),
+  };
+  Value::Object(Rc::new(RefCell::new(object)))
+}
+
+pub fn len(value: &Value) -> Result<Value, String> {
+  match value {
+    Value::Str(s) => Ok(Value::Int(s.len() as i32)),
+    Value::List(items) => Ok(Value::Int(items.borrow().len() as i32)),
+    Value::Map(map) => Ok(Value::Int(map.borrow().len() as i32)),
+    _ => Err("expected a text, a list or a map".to_owned()),
+  }
+}
+
+pub fn keys(value: &Value) -> Result<Value, String> {
+  match value {
+    Value::Map(map) => Ok(Value::list(map.borrow().keys())),
+    _ => Err("expected a map".to_owned()),
+  }
+}
+
+pub fn append(list: &Value, value: Value) -> Result<Value, String> {
+  match list {
+    Value::List(items) => {
+      items.borrow_mut().push(value);
+      Ok(Value::Int(0))
+    }
+    _ => Err("expected a list".to_owned()),
+  }
+}
+
+pub fn has(map: &Value, key: &Value) -> Result<Value, String> {
+  match map {
+    Value::Map(map) => Ok(Value::Bool(map.borrow().has(key))),
+    _ => Err("expected a map".to_owned()),
+  }
+}
+
+// where the program reads input and writes its output
+pub struct Console<'io> {
+  pub input: &'io mut dyn BufRead,
+  pub output: &'io mut dyn Write,
+}
+
+impl<'io> Console<'io> {
+  pub fn write(&mut self, text: &str) -> Result<(), String> {
+    self
+      .output
+      .write_all(text.as_bytes())
+      .map_err(|why| format!("couldn't write the output: {}", why))
+  }
+
+  pub fn flush(&mut self) {
+    let _ = self.output.flush();
+  }
+
+  // the line typed after the prompt, and whether the input ended with it
+  pub fn input(&mut self, prompt: &str) -> Result<(String, bool), String> {
+    self.write(prompt)?;
+    self.flush();
+    let mut line = String::new();
+    let _ = self.input.read_line(&mut line);
+    let ended = !line.ends_with('\n');
+    let line = line.trim_end_matches('\n').replace('\r', "");
+    Ok((line, ended))
+  }
+
+  // asks again until a number is typed
+  pub fn input_number(&mut self, prompt: &str) -> Result<Value, String> {
+    loop {
+      let (line, ended) = self.input(prompt)?;
+      if let Ok(number) = line
+        .trim_start()
+        .trim_end_matches([' ', '\t'])
+        .parse::<f64>()
+      {
+        return Ok(Value::Float(number));
+      }
+      if ended {
+        return Err("no more input, expected a number".to_owned());
+      }
+      self.write("that is not a number, please try again\n")?;
+    }
+  }
+}
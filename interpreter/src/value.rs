@@ -0,0 +1,212 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt::{self, Display};
+use std::rc::Rc;
+
+use crate::format::format_g;
+
+// a riven value at runtime, lists, maps, structs and functions are shared
+// like the pointers of the C runtime
+#[derive(Debug, Clone)]
+pub enum Value {
+  Int(i32),
+  Float(f64),
+  Bool(bool),
+  Str(Rc<str>),
+  List(Rc<RefCell<Vec<Value>>>),
+  Map(Rc<RefCell<Map>>),
+  Object(Rc<RefCell<Object>>),
+  Function(Rc<Closure>),
+}
+
+impl Value {
+  pub fn str(s: &str) -> Self {
+    Value::Str(Rc::from(s))
+  }
+
+  pub fn list(items: Vec<Value>) -> Self {
+    Value::List(Rc::new(RefCell::new(items)))
+  }
+
+  pub fn map(map: Map) -> Self {
+    Value::Map(Rc::new(RefCell::new(map)))
+  }
+
+  pub fn number(&self) -> Option<f64> {
+    match self {
+      Value::Int(n) => Some(*n as f64),
+      Value::Float(n) => Some(*n),
+      _ => None,
+    }
+  }
+
+  // what C makes of it in a condition, pointers are never NULL here
+  pub fn truth(&self) -> bool {
+    match self {
+      Value::Int(n) => *n != 0,
+      Value::Float(n) => *n != 0.0,
+      Value::Bool(b) => *b,
+      _ => true,
+    }
+  }
+
+  // riven_value_equal: 1 and 1.0 are equal, lists and maps are only equal to themselves
+  pub fn equals(&self, other: &Value) -> bool {
+    if let (Some(a), Some(b)) = (self.number(), other.number()) {
+      return a == b;
+    }
+    match (self, other) {
+      (Value::Bool(a), Value::Bool(b)) => a == b,
+      (Value::Str(a), Value::Str(b)) => a == b,
+      (Value::List(a), Value::List(b)) => Rc::ptr_eq(a, b),
+      (Value::Map(a), Value::Map(b)) => Rc::ptr_eq(a, b),
+      (Value::Object(a), Value::Object(b)) => Rc::ptr_eq(a, b),
+      (Value::Function(a), Value::Function(b)) => Rc::ptr_eq(a, b),
+      _ => false,
+    }
+  }
+
+  // strings inside a list, map or struct are quoted, so ["1"] and [1] look different
+  fn fmt_item(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      Value::Str(s) => write!(f, "\"{}\"", s),
+      value => write!(f, "{}", value),
+    }
+  }
+}
+
+// the way riven_print_value prints it
+impl Display for Value {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      Value::Int(n) => write!(f, "{}", n),
+      Value::Float(n) => f.write_str(&format_g(*n, 15, false)),
+      Value::Bool(b) => f.write_str(if *b { "True" } else { "False" }),
+      Value::Str(s) => f.write_str(s),
+      Value::List(items) => {
+        f.write_str("[")?;
+        for (idx, item) in items.borrow().iter().enumerate() {
+          if idx > 0 {
+            f.write_str(", ")?;
+          }
+          item.fmt_item(f)?;
+        }
+        f.write_str("]")
+      }
+      Value::Map(map) => {
+        f.write_str("{")?;
+        for (idx, (key, value)) in map.borrow().entries.iter().enumerate() {
+          if idx > 0 {
+            f.write_str(", ")?;
+          }
+          key.fmt_item(f)?;
+          f.write_str(": ")?;
+          value.fmt_item(f)?;
+        }
+        f.write_str("}")
+      }
+      // Point(x=1, y=2)
+      Value::Object(object) => {
+        let object = object.borrow();
+        write!(f, "{}(", object.name)?;
+        for (idx, (field, value)) in object.fields.iter().enumerate() {
+          if idx > 0 {
+            f.write_str(", ")?;
+          }
+          write!(f, "{}=", field)?;
+          value.fmt_item(f)?;
+        }
+        f.write_str(")")
+      }
+      Value::Function(closure) => write!(f, "<function {}>", closure.name()),
+    }
+  }
+}
+
+// what a map key is looked up by, numbers by value so 1 and 1.0 are the same key
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Key {
+  Number(u64),
+  Str(Rc<str>),
+  Bool(bool),
+  Shared(usize),
+}
+
+impl Key {
+  fn of(value: &Value) -> Self {
+    match value {
+      // -0.0 and 0.0 are the same key
+      Value::Int(_) | Value::Float(_) => Key::Number((value.number().unwrap() + 0.0).to_bits()),
+      Value::Bool(b) => Key::Bool(*b),
+      Value::Str(s) => Key::Str(s.clone()),
+      Value::List(items) => Key::Shared(Rc::as_ptr(items) as *const u8 as usize),
+      Value::Map(map) => Key::Shared(Rc::as_ptr(map) as *const u8 as usize),
+      Value::Object(object) => Key::Shared(Rc::as_ptr(object) as *const u8 as usize),
+      Value::Function(closure) => Key::Shared(Rc::as_ptr(closure) as *const u8 as usize),
+    }
+  }
+}
+
+// a map keeping its keys in insertion order, so `keys` is predictable
+#[derive(Debug, Clone, Default)]
+pub struct Map {
+  entries: Vec<(Value, Value)>,
+  // index of the entry of each key
+  slots: HashMap<Key, usize>,
+}
+
+impl Map {
+  pub fn get(&self, key: &Value) -> Option<&Value> {
+    let idx = self.slots.get(&Key::of(key))?;
+    Some(&self.entries[*idx].1)
+  }
+
+  pub fn set(&mut self, key: Value, value: Value) {
+    match self.slots.get(&Key::of(&key)) {
+      Some(idx) => self.entries[*idx].1 = value,
+      None => {
+        self.slots.insert(Key::of(&key), self.entries.len());
+        self.entries.push((key, value));
+      }
+    }
+  }
+
+  pub fn has(&self, key: &Value) -> bool {
+    self.slots.contains_key(&Key::of(key))
+  }
+
+  pub fn keys(&self) -> Vec<Value> {
+    self.entries.iter().map(|(key, _)| key.clone()).collect()
+  }
+
+  pub fn len(&self) -> usize {
+    self.entries.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.entries.is_empty()
+  }
+}
+
+// an instance of a struct, fields in declaration order
+#[derive(Debug, Clone)]
+pub struct Object {
+  pub name: String,
+  pub fields: Vec<(String, Value)>,
+}
+
+// a function used as a value, with a copy of the variables it captured
+#[derive(Debug, Clone)]
+pub struct Closure {
+  // path of the function, see analyzer::infer::child_path
+  pub function: String,
+  // in the order of TypeInfo::captures
+  pub env: Vec<Value>,
+}
+
+impl Closure {
+  // `main::double` prints as <function double>
+  pub fn name(&self) -> &str {
+    self.function.rsplit("::").next().unwrap_or(&self.function)
+  }
+}
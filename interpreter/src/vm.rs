@@ -0,0 +1,308 @@
+use std::io::{self, BufRead, Write};
+use std::rc::Rc;
+
+use crate::bytecode::{Module, Op};
+use crate::format::printf;
+use crate::runtime::{self, Console, RuntimeError};
+use crate::value::{Closure, Map, Value};
+
+// run a compiled program on stdin and stdout, returns the exit code of `main`;
+// calls are frames on the heap, deep recursion needs no larger stack
+pub fn run(module: &Module) -> Result<i32, RuntimeError> {
+  let mut input = io::stdin().lock();
+  let mut output = io::BufWriter::new(io::stdout().lock());
+  run_with(module, &mut input, &mut output)
+}
+
+pub fn run_with(
+  module: &Module,
+  input: &mut dyn BufRead,
+  output: &mut dyn Write,
+) -> Result<i32, RuntimeError> {
+  let mut vm = Vm {
+    module,
+    globals: module
+      .globals
+      .iter()
+      .map(|(_, t)| runtime::zero(t))
+      .collect(),
+    stack: vec![],
+    frames: vec![],
+    console: Console { input, output },
+  };
+  let result = vm.run();
+  // what was printed comes before the error, like the C runtime flushes stdout
+  vm.console.flush();
+  result
+}
+
+// one call being run
+struct Frame {
+  function: usize,
+  // the next instruction
+  ip: usize,
+  // where the locals start on the stack, the values being computed are above them
+  base: usize,
+}
+
+struct Vm<'m, 'io> {
+  module: &'m Module,
+  globals: Vec<Value>,
+  stack: Vec<Value>,
+  frames: Vec<Frame>,
+  console: Console<'io>,
+}
+
+impl<'m, 'io> Vm<'m, 'io> {
+  fn run(&mut self) -> Result<i32, RuntimeError> {
+    let Some(main) = self.module.main() else {
+      return Err(RuntimeError {
+        line: 0,
+        message: "the program has no `main` function".to_owned(),
+      });
+    };
+    // the statements outside functions run first
+    if let Some(init) = self.module.init() {
+      self.call(init, vec![])?;
+      self.execute()?;
+    }
+    self.call(main, vec![])?;
+    // like the C `int main`, what main returns is the exit code
+    match self.execute()? {
+      Value::Int(code) => Ok(code),
+      Value::Float(code) => Ok(code as i32),
+      _ => Ok(0),
+    }
+  }
+
+  // the source line of the instruction being run
+  fn error(&self, message: impl Into<String>) -> RuntimeError {
+    let line = self.frames.last().map_or(0, |frame| {
+      let chunk = &self.module.functions[frame.function];
+      chunk.lines[frame.ip.saturating_sub(1)]
+    });
+    RuntimeError {
+      line,
+      message: message.into(),
+    }
+  }
+
+  fn pop(&mut self) -> Value {
+    self.stack.pop().expect("the stack of the VM is empty")
+  }
+
+  fn pop_many(&mut self, count: usize) -> Vec<Value> {
+    self.stack.split_off(self.stack.len() - count)
+  }
+
+  // start a call, the passed values are the captured ones and then the arguments
+  fn call(&mut self, function: usize, passed: Vec<Value>) -> Result<(), RuntimeError> {
+    let chunk = &self.module.functions[function];
+    let mut locals: Vec<Value> = chunk.locals.iter().map(|(_, t)| runtime::zero(t)).collect();
+    for (slot, value) in chunk.params.iter().zip(passed) {
+      let value = runtime::coerce(value, &chunk.locals[*slot].1);
+      locals[*slot] = value.map_err(|message| self.error(message))?;
+    }
+    self.frames.push(Frame {
+      function,
+      ip: 0,
+      base: self.stack.len(),
+    });
+    self.stack.extend(locals);
+    Ok(())
+  }
+
+  // run until the first call returns, and give back its value
+  fn execute(&mut self) -> Result<Value, RuntimeError> {
+    let module = self.module;
+    loop {
+      let frame = self.frames.last_mut().expect("no call is being run");
+      let chunk = &module.functions[frame.function];
+      let op = &chunk.code[frame.ip];
+      frame.ip += 1;
+      let base = frame.base;
+
+      match op {
+        Op::Constant(idx) => self.stack.push(chunk.constants[*idx].clone()),
+        Op::Zero(t) => self.stack.push(runtime::zero(t)),
+        Op::Pop => {
+          self.pop();
+        }
+        Op::GetLocal(slot) => self.stack.push(self.stack[base + slot].clone()),
+        Op::SetLocal(slot) => self.stack[base + slot] = self.pop(),
+        Op::GetGlobal(idx) => self.stack.push(self.globals[*idx].clone()),
+        Op::SetGlobal(idx) => self.globals[*idx] = self.pop(),
+        Op::Coerce(t) => {
+          let value = self.pop();
+          let value = runtime::coerce(value, t).map_err(|message| self.error(message))?;
+          self.stack.push(value);
+        }
+        Op::Arithmetic(op) => {
+          let right = self.pop();
+          let left = self.pop();
+          let value = runtime::apply(&left, op, &right).map_err(|message| self.error(message))?;
+          self.stack.push(value);
+        }
+        Op::Compare(op) => {
+          let right = self.pop();
+          let left = self.pop();
+          let value = runtime::compare(&left, op, &right).map_err(|message| self.error(message))?;
+          self.stack.push(Value::Bool(value));
+        }
+        Op::Not => {
+          let value = self.pop();
+          self.stack.push(Value::Bool(!value.truth()));
+        }
+        Op::Jump(target) => self.jump(*target),
+        Op::JumpIfFalse(target) => {
+          if !self.pop().truth() {
+            self.jump(*target);
+          }
+        }
+        Op::Closure(function, captures) => {
+          let env = self.pop_many(*captures);
+          let function = module.functions[*function].path.clone();
+          self
+            .stack
+            .push(Value::Function(Rc::new(Closure { function, env })));
+        }
+        Op::Call(function, count) => {
+          let passed = self.pop_many(*count);
+          self.call(*function, passed)?;
+        }
+        Op::CallValue(count) => {
+          let args = self.pop_many(*count);
+          let Value::Function(closure) = self.pop() else {
+            return Err(self.error("expected a function"));
+          };
+          let function = module.function_index[&closure.function];
+          let arity = module.functions[function].arity;
+          if args.len() != arity {
+            return Err(self.error(format!(
+              "function {} takes {} argument(s) but {} were given",
+              closure.name(),
+              arity,
+              args.len()
+            )));
+          }
+          let mut passed = closure.env.clone();
+          passed.extend(args);
+          self.call(function, passed)?;
+        }
+        Op::Return => {
+          let value = self.pop();
+          self.stack.truncate(base);
+          self.frames.pop();
+          if self.frames.is_empty() {
+            return Ok(value);
+          }
+          self.stack.push(value);
+        }
+        Op::Print => {
+          let value = self.pop();
+          self.write(&value.to_string())?;
+        }
+        Op::Printf(count) => {
+          let values = self.pop_many(*count);
+          let Some(Value::Str(format)) = values.first() else {
+            return Err(self.error("printf needs a format text"));
+          };
+          let text = printf(format, &values[1..]).map_err(|message| self.error(message))?;
+          self.write(&text)?;
+          self.stack.push(Value::Int(text.len() as i32));
+        }
+        Op::Input => {
+          let prompt = self.pop().to_string();
+          let result = self
+            .console
+            .input(&prompt)
+            .map(|(line, _)| Value::str(&line));
+          self.push_result(result)?;
+        }
+        Op::InputNumber => {
+          let prompt = self.pop().to_string();
+          let result = self.console.input_number(&prompt);
+          self.push_result(result)?;
+        }
+        Op::Len => {
+          let value = self.pop();
+          self.push_result(runtime::len(&value))?;
+        }
+        Op::Keys => {
+          let value = self.pop();
+          self.push_result(runtime::keys(&value))?;
+        }
+        Op::Append => {
+          let value = self.pop();
+          let list = self.pop();
+          self.push_result(runtime::append(&list, value))?;
+        }
+        Op::Has => {
+          let key = self.pop();
+          let map = self.pop();
+          self.push_result(runtime::has(&map, &key))?;
+        }
+        Op::NewList(count) => {
+          let items = self.pop_many(*count);
+          self.stack.push(Value::list(items));
+        }
+        Op::NewMap(count) => {
+          let mut map = Map::default();
+          let mut entries = self.pop_many(count * 2).into_iter();
+          while let (Some(key), Some(value)) = (entries.next(), entries.next()) {
+            map.set(key, value);
+          }
+          self.stack.push(Value::map(map));
+        }
+        Op::NewStruct(idx, count) => {
+          let values = self.pop_many(*count);
+          let (name, fields) = &module.structs[*idx];
+          self.stack.push(runtime::new_object(name, fields, values));
+        }
+        Op::GetItem => {
+          let key = self.pop();
+          let target = self.pop();
+          self.push_result(runtime::get_item(&target, &key))?;
+        }
+        Op::SetItem => {
+          let value = self.pop();
+          let key = self.pop();
+          let target = self.pop();
+          runtime::set_item(&target, key, value).map_err(|message| self.error(message))?;
+        }
+        Op::GetField(idx) => {
+          let target = self.pop();
+          let field = chunk.constants[*idx].to_string();
+          self.push_result(runtime::get_field(&target, &field))?;
+        }
+        Op::SetField(idx) => {
+          let value = self.pop();
+          let target = self.pop();
+          let field = chunk.constants[*idx].to_string();
+          let result = runtime::set_field(&target, &field, value);
+          result.map_err(|message| self.error(message))?;
+        }
+        Op::Fail(idx) => return Err(self.error(chunk.constants[*idx].to_string())),
+      }
+    }
+  }
+
+  fn jump(&mut self, target: usize) {
+    if let Some(frame) = self.frames.last_mut() {
+      frame.ip = target;
+    }
+  }
+
+  fn push_result(&mut self, result: Result<Value, String>) -> Result<(), RuntimeError> {
+    let value = result.map_err(|message| self.error(message))?;
+    self.stack.push(value);
+    Ok(())
+  }
+
+  fn write(&mut self, text: &str) -> Result<(), RuntimeError> {
+    self
+      .console
+      .write(text)
+      .map_err(|message| self.error(message))
+  }
+}
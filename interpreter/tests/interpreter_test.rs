@@ -0,0 +1,215 @@
+use analyzer::optimize::optimize;
+use interpreter::interpreter::{run_with, RuntimeError};
+use parser::{parse_program::parse_program, parser::Parser};
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  // what the program prints with `input` lines to read, and its exit code
+  fn run_code(code: &str, input: &str) -> (String, Result<i32, RuntimeError>) {
+    let mut p = Parser::new(code);
+    let program = optimize(parse_program(&mut p));
+    let mut output = vec![];
+    let result = run_with(&program, &mut input.as_bytes(), &mut output);
+    (String::from_utf8(output).unwrap(), result)
+  }
+
+  #[test]
+  fn test_arithmetic() {
+    let (output, result) = run_code(
+      "program {
+        fn half(n) {
+          return n / 2;
+        }
+        fn main() {
+          x = 7;
+          println(x / 2, x / 2.0, (x - 1) * 2, x + 2 * 3, 1 / 3.0);
+          println(half(3), half(2.5), 2147483647 + 1);
+          return 2;
+        }
+      }",
+      "",
+    );
+    assert_eq!(output, "3 3.5 12 13 0.333333333333333\n1.5 1.25 -2147483648\n");
+    assert_eq!(result, Ok(2));
+  }
+
+  #[test]
+  fn test_loops_and_recursion() {
+    let (output, _) = run_code(
+      "program {
+        fn fib(n) {
+          if (n < 2) {
+            return n;
+          }
+          return fib(n - 1) + fib(n - 2);
+        }
+        fn main() {
+          i = 0;
+          while (i < 8) {
+            print(fib(i), \"\");
+            i = i + 1;
+          }
+          printf(\"\\n%d|%5.2f|%-3s|%x\\n\", i, 2.5, \"a\", 255);
+        }
+      }",
+      "",
+    );
+    assert_eq!(output, "0 1 1 2 3 5 8 13 \n8| 2.50|a  |ff\n");
+  }
+
+  #[test]
+  fn test_values() {
+    let (output, _) = run_code(
+      "program {
+        struct Point { x, y }
+        fn make_adder(step) {
+          add = fn(v) {
+            return v + step;
+          };
+          return add;
+        }
+        fn main() {
+          p = Point(1, \"a\");
+          p.x = p.x + 1;
+          ages = {\"tom\": 8};
+          ages[\"amy\"] = 9;
+          xs = [1, 2.5, True];
+          append(xs, p);
+          add3 = make_adder(3);
+          println(p, ages, keys(ages), has(ages, \"bob\"));
+          println(xs, len(xs), add3(4), add3);
+        }
+      }",
+      "",
+    );
+    assert_eq!(
+      output,
+      "Point(x=2, y=\"a\") {\"tom\": 8, \"amy\": 9} [\"tom\", \"amy\"] False\n\
+       [1, 2.5, True, Point(x=2, y=\"a\")] 4 7 <function fn@4:17>\n"
+    );
+  }
+
+  #[test]
+  fn test_apply() {
+    let (output, result) = run_code(
+      "program {
+        struct Shape { area }
+        fn make_adder(step) {
+          add = fn(v) {
+            return v + step;
+          };
+          return add;
+        }
+        fn show(v) {
+          println(v);
+        }
+        fn main() {
+          fs = [make_adder(1), make_adder(10)];
+          s = Shape(show);
+          s.area(7);
+          println(make_adder(2)(3), fs[0](4), fs[1](4) * 2);
+          fs[0](1)(2);
+        }
+      }",
+      "",
+    );
+    assert_eq!(output, "7\n5 5 28\n");
+    assert_eq!(
+      result.unwrap_err().to_string(),
+      "runtime error at line 17: expected a function"
+    );
+  }
+
+  #[test]
+  fn test_input() {
+    let (output, _) = run_code(
+      "program {
+        fn main() {
+          name = input(\"name? \");
+          age = input_number(\"age? \");
+          println(name, age + 1);
+        }
+      }",
+      "amy\nold\n41\n",
+    );
+    assert_eq!(
+      output,
+      "name? age? that is not a number, please try again\nage? amy 42\n"
+    );
+  }
+
+  #[test]
+  fn test_runtime_error() {
+    let (output, result) = run_code(
+      "program {
+        fn main() {
+          scores = [1, 2];
+          println(scores[1]);
+          println(\"before\", scores[2]);
+        }
+      }",
+      "",
+    );
+    assert_eq!(output, "2\nbefore ");
+    let error = result.unwrap_err();
+    assert_eq!(
+      error.to_string(),
+      "runtime error at line 5: index 2 is out of range, the list has 2 item(s)"
+    );
+  }
+
+  #[test]
+  fn test_globals() {
+    let (output, result) = run_code(
+      "program {
+        limit = 3;
+        fn show() {
+          println(limit, names);
+        }
+        show();
+        names = [\"amy\"];
+        fn main() {
+          show();
+          println(count);
+        }
+      }",
+      "",
+    );
+    assert_eq!(output, "3 []\n3 [\"amy\"]\n");
+    assert_eq!(
+      result.unwrap_err().to_string(),
+      "runtime error at line 10: undefined variable `count`"
+    );
+  }
+
+  #[test]
+  fn test_call_depth() {
+    // `riven run` gives the interpreter a larger stack than a test thread has
+    let thread = std::thread::Builder::new().stack_size(256 * 1024 * 1024);
+    let run = thread.spawn(|| {
+      run_code(
+        "program {
+          fn depth(n) {
+            if (n = 0) {
+              return 0;
+            }
+            return depth(n - 1) + 1;
+          }
+          fn main() {
+            println(depth(3000));
+            println(depth(100000));
+          }
+        }",
+        "",
+      )
+    });
+    let (output, result) = run.unwrap().join().unwrap();
+    assert_eq!(output, "3000\n");
+    assert_eq!(
+      result.unwrap_err().to_string(),
+      "runtime error at line 6: more than 4000 nested calls"
+    );
+  }
+}
@@ -0,0 +1,162 @@
+use analyzer::optimize::optimize;
+use interpreter::compile::compile;
+use interpreter::interpreter::{self as tree_walker, RuntimeError};
+use interpreter::vm::run_with;
+use parser::{parse_program::parse_program, parser::Parser};
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  // what the program prints on the VM with `input` lines to read, and its exit code
+  fn run_code(code: &str, input: &str) -> (String, Result<i32, RuntimeError>) {
+    let mut p = Parser::new(code);
+    let module = compile(&optimize(parse_program(&mut p)));
+    let mut output = vec![];
+    let result = run_with(&module, &mut input.as_bytes(), &mut output);
+    (String::from_utf8(output).unwrap(), result)
+  }
+
+  #[test]
+  fn test_same_as_interpreter() {
+    let code = "program {
+        struct Point { x, y }
+        fn make_adder(step) {
+          add = fn(v) {
+            return v + step;
+          };
+          return add;
+        }
+        fn main() {
+          p = Point(1, \"a\");
+          p.x = p.x + 1;
+          ages = {\"tom\": 8};
+          ages[\"amy\"] = 9;
+          xs = [1, 2.5, True];
+          append(xs, p);
+          add3 = make_adder(3);
+          i = 0;
+          while (and(i < 5, not(i = 3))) {
+            printf(\"%d|%5.2f \", i, i / 2.0);
+            i = i + 1;
+          }
+          name = input(\"name? \");
+          println(p, ages, keys(ages), has(ages, \"bob\"), name);
+          println(xs, len(xs), add3(4), add3, 7 / 2, 2147483647 + 1);
+          fs = [add3];
+          println(make_adder(2)(3), fs[0](1));
+          return i;
+        }
+      }";
+    let mut p = Parser::new(code);
+    let mut expected = vec![];
+    let program = optimize(parse_program(&mut p));
+    let expected_result = tree_walker::run_with(&program, &mut "amy\n".as_bytes(), &mut expected);
+
+    let (output, result) = run_code(code, "amy\n");
+    assert_eq!(output, String::from_utf8(expected).unwrap());
+    assert_eq!(result, expected_result);
+    assert_eq!(result, Ok(3));
+  }
+
+  #[test]
+  fn test_globals() {
+    let (output, result) = run_code(
+      "program {
+        limit = 3;
+        fn show() {
+          println(limit, names);
+        }
+        show();
+        names = [\"amy\"];
+        fn main() {
+          show();
+          return limit;
+        }
+      }",
+      "",
+    );
+    assert_eq!(output, "3 []\n3 [\"amy\"]\n");
+    assert_eq!(result, Ok(3));
+  }
+
+  #[test]
+  fn test_deep_recursion() {
+    let (output, _) = run_code(
+      "program {
+        fn depth(n) {
+          if (n = 0) {
+            return 0;
+          }
+          return depth(n - 1) + 1;
+        }
+        fn main() {
+          println(depth(100000));
+        }
+      }",
+      "",
+    );
+    assert_eq!(output, "100000\n");
+  }
+
+  #[test]
+  fn test_runtime_error() {
+    let (output, result) = run_code(
+      "program {
+        fn get(xs, i) {
+          return xs[i];
+        }
+        fn main() {
+          scores = [1, 2];
+          println(get(scores, 1));
+          println(\"before\", get(scores, 2));
+        }
+      }",
+      "",
+    );
+    assert_eq!(output, "2\nbefore ");
+    assert_eq!(
+      result.unwrap_err().to_string(),
+      "runtime error at line 3: index 2 is out of range, the list has 2 item(s)"
+    );
+  }
+
+  #[test]
+  fn test_disassemble() {
+    let mut p = Parser::new(
+      "program {
+        fn main() {
+          i = 0;
+          while (i < 3) {
+            i = i + 1;
+          }
+          println(i);
+        }
+      }",
+    );
+    let module = compile(&parse_program(&mut p));
+    assert_eq!(
+      module.to_string(),
+      "== main ==
+locals: i
+0000    3 constant 0 (0)
+0001    | set_local 0 (i)
+0002    4 get_local 0 (i)
+0003    | constant 1 (3)
+0004    | less
+0005    | jump_if_false 0011
+0006    5 get_local 0 (i)
+0007    | constant 2 (1)
+0008    | add
+0009    | set_local 0 (i)
+0010    4 jump 0002
+0011    7 get_local 0 (i)
+0012    | print
+0013    | constant 3 (\"\\n\")
+0014    | print
+0015    | constant 4 (0)
+0016    | return
+"
+    );
+  }
+}
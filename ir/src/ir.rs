@@ -0,0 +1,449 @@
+use std::fmt::{self, Display};
+
+use analyzer::types::Type;
+use parser::ast::{BinOp, CompareOp};
+
+// a value known before the program runs
+#[derive(Debug, Clone, PartialEq)]
+pub enum Constant {
+  Int(i32),
+  Float(f64),
+  Bool(bool),
+  Str(String),
+}
+
+impl Constant {
+  // bools are ints in C arithmetic
+  pub fn number(&self) -> Option<f64> {
+    match self {
+      Constant::Int(n) => Some(*n as f64),
+      Constant::Float(n) => Some(*n),
+      Constant::Bool(b) => Some(*b as i32 as f64),
+      Constant::Str(_) => None,
+    }
+  }
+
+  // what C makes of it in a condition, a text is never NULL
+  pub fn truth(&self) -> bool {
+    match self {
+      Constant::Int(n) => *n != 0,
+      Constant::Float(n) => *n != 0.0,
+      Constant::Bool(b) => *b,
+      Constant::Str(_) => true,
+    }
+  }
+}
+
+// where an instruction puts its result: a variable of the function, a
+// variable assigned outside functions, or a temporary holding the value of a
+// part of an expression
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Var {
+  Local(String),
+  // only the init function assigns it, every function can read it
+  Global(String),
+  Temp(usize),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Operand {
+  Const(Constant),
+  Var(Var),
+}
+
+impl Operand {
+  pub fn local(name: &str) -> Self {
+    Operand::Var(Var::Local(name.to_owned()))
+  }
+
+  pub fn as_const(&self) -> Option<&Constant> {
+    match self {
+      Operand::Const(constant) => Some(constant),
+      Operand::Var(_) => None,
+    }
+  }
+}
+
+// one three-address instruction: at most one operator, its operands are
+// constants or variables, and the result goes to a variable
+#[derive(Debug, Clone, PartialEq)]
+pub enum Inst {
+  // x = a
+  Copy(Var, Operand),
+  // x = a + b
+  Binary(Var, BinOp, Operand, Operand),
+  // x = a < b
+  Compare(Var, CompareOp, Operand, Operand),
+  // x = not a, True or False from the truth of a
+  Not(Var, Operand),
+  // x = convert a to int, like assigning to a C variable of that type; a
+  // value of the wrong kind stops the program
+  Convert(Var, Type, Operand),
+  // x = zero list, the value a variable of the type has before its first assignment
+  Zero(Var, Type),
+  // x = call main::add(step, 3), the captured values come before the arguments
+  Call(Var, String, Vec<Operand>),
+  // x = call_value f(3), f holds a function
+  CallValue(Var, Operand, Vec<Operand>),
+  // x = closure main::add(step), a function used as a value with the values it captures
+  Closure(Var, String, Vec<Operand>),
+  // x = print(a), print, printf, input, input_number, len, keys, append and
+  // has; `println(a, b)` is print(a), print(" "), print(b) and print("\n")
+  Builtin(Var, String, Vec<Operand>),
+  NewList(Var, Vec<Operand>),
+  NewMap(Var, Vec<(Operand, Operand)>),
+  // x = new Point(1, 2)
+  NewStruct(Var, String, Vec<Operand>),
+  // x = a[b]
+  GetItem(Var, Operand, Operand),
+  // a[b] = c
+  SetItem(Operand, Operand, Operand),
+  // x = a.field
+  GetField(Var, Operand, String),
+  // a.field = b
+  SetField(Operand, String, Operand),
+}
+
+impl Inst {
+  // the variable the instruction writes
+  pub fn dest(&self) -> Option<&Var> {
+    match self {
+      Inst::Copy(dest, _)
+      | Inst::Binary(dest, _, _, _)
+      | Inst::Compare(dest, _, _, _)
+      | Inst::Not(dest, _)
+      | Inst::Convert(dest, _, _)
+      | Inst::Zero(dest, _)
+      | Inst::Call(dest, _, _)
+      | Inst::CallValue(dest, _, _)
+      | Inst::Closure(dest, _, _)
+      | Inst::Builtin(dest, _, _)
+      | Inst::NewList(dest, _)
+      | Inst::NewMap(dest, _)
+      | Inst::NewStruct(dest, _, _)
+      | Inst::GetItem(dest, _, _)
+      | Inst::GetField(dest, _, _) => Some(dest),
+      Inst::SetItem(_, _, _) | Inst::SetField(_, _, _) => None,
+    }
+  }
+
+  // the operands the instruction reads, in order
+  pub fn operands(&self) -> Vec<&Operand> {
+    match self {
+      Inst::Copy(_, a) | Inst::Not(_, a) | Inst::Convert(_, _, a) | Inst::GetField(_, a, _) => {
+        vec![a]
+      }
+      Inst::Binary(_, _, a, b)
+      | Inst::Compare(_, _, a, b)
+      | Inst::GetItem(_, a, b)
+      | Inst::SetField(a, _, b) => vec![a, b],
+      Inst::SetItem(a, b, c) => vec![a, b, c],
+      Inst::Zero(_, _) => vec![],
+      Inst::Call(_, _, args)
+      | Inst::Closure(_, _, args)
+      | Inst::Builtin(_, _, args)
+      | Inst::NewList(_, args)
+      | Inst::NewStruct(_, _, args) => args.iter().collect(),
+      Inst::CallValue(_, function, args) => std::iter::once(function).chain(args).collect(),
+      Inst::NewMap(_, entries) => entries.iter().flat_map(|(k, v)| [k, v]).collect(),
+    }
+  }
+
+  pub fn operands_mut(&mut self) -> Vec<&mut Operand> {
+    match self {
+      Inst::Copy(_, a) | Inst::Not(_, a) | Inst::Convert(_, _, a) | Inst::GetField(_, a, _) => {
+        vec![a]
+      }
+      Inst::Binary(_, _, a, b)
+      | Inst::Compare(_, _, a, b)
+      | Inst::GetItem(_, a, b)
+      | Inst::SetField(a, _, b) => vec![a, b],
+      Inst::SetItem(a, b, c) => vec![a, b, c],
+      Inst::Zero(_, _) => vec![],
+      Inst::Call(_, _, args)
+      | Inst::Closure(_, _, args)
+      | Inst::Builtin(_, _, args)
+      | Inst::NewList(_, args)
+      | Inst::NewStruct(_, _, args) => args.iter_mut().collect(),
+      Inst::CallValue(_, function, args) => std::iter::once(function).chain(args).collect(),
+      Inst::NewMap(_, entries) => entries.iter_mut().flat_map(|(k, v)| [k, v]).collect(),
+    }
+  }
+
+  // whether removing the instruction changes nothing but its result: it
+  // prints nothing, changes no list, map or struct and cannot fail
+  pub fn is_pure(&self) -> bool {
+    match self {
+      Inst::Binary(_, BinOp::Div, _, divisor) => {
+        matches!(divisor.as_const().and_then(Constant::number), Some(n) if n != 0.0)
+      }
+      Inst::Copy(_, _)
+      | Inst::Binary(_, _, _, _)
+      | Inst::Compare(_, _, _, _)
+      | Inst::Not(_, _)
+      | Inst::Zero(_, _)
+      | Inst::Closure(_, _, _)
+      | Inst::NewList(_, _)
+      | Inst::NewMap(_, _)
+      | Inst::NewStruct(_, _, _) => true,
+      _ => false,
+    }
+  }
+}
+
+// the index of a block in its function, bb0 is where the function starts
+pub type BlockId = usize;
+
+// how a block ends
+#[derive(Debug, Clone, PartialEq)]
+pub enum Terminator {
+  Jump(BlockId),
+  // to the first block when the operand is true, like C tests a condition
+  Branch(Operand, BlockId, BlockId),
+  Return(Operand),
+  // stop the program with a message
+  Fail(String),
+}
+
+impl Terminator {
+  pub fn successors(&self) -> Vec<BlockId> {
+    match self {
+      Terminator::Jump(target) => vec![*target],
+      Terminator::Branch(_, yes, no) => vec![*yes, *no],
+      Terminator::Return(_) | Terminator::Fail(_) => vec![],
+    }
+  }
+}
+
+// instructions run one after another, only the terminator jumps
+#[derive(Debug, Clone, PartialEq)]
+pub struct Block {
+  pub code: Vec<Inst>,
+  // source line of each instruction
+  pub lines: Vec<usize>,
+  pub end: Terminator,
+  // source line of the terminator
+  pub end_line: usize,
+}
+
+// the control flow graph of one function
+#[derive(Debug, Clone, Default)]
+pub struct Function {
+  // see analyzer::infer::child_path, `` for the init function
+  pub path: String,
+  // the captured variables and then the parameters
+  pub params: Vec<String>,
+  pub arity: usize,
+  // name and type of every variable, captured ones and parameters included;
+  // a variable is zero until its first assignment
+  pub locals: Vec<(String, Type)>,
+  pub blocks: Vec<Block>,
+  // how many temporaries the function uses, t0 to t<temps - 1>
+  pub temps: usize,
+}
+
+impl Function {
+  // the blocks jumping to each block
+  pub fn predecessors(&self) -> Vec<Vec<BlockId>> {
+    let mut predecessors = vec![vec![]; self.blocks.len()];
+    for (idx, block) in self.blocks.iter().enumerate() {
+      for successor in block.end.successors() {
+        predecessors[successor].push(idx);
+      }
+    }
+    predecessors
+  }
+
+  // drop the blocks no path from bb0 reaches, the others keep their order
+  pub fn remove_unreachable(&mut self) {
+    let mut reached = vec![false; self.blocks.len()];
+    let mut pending = vec![0];
+    while let Some(idx) = pending.pop() {
+      if !std::mem::replace(&mut reached[idx], true) {
+        pending.extend(self.blocks[idx].end.successors());
+      }
+    }
+    let mut ids = vec![0; self.blocks.len()];
+    let mut count = 0;
+    for (idx, is_reached) in reached.iter().enumerate() {
+      ids[idx] = count;
+      count += *is_reached as usize;
+    }
+    let blocks = std::mem::take(&mut self.blocks);
+    for (mut block, is_reached) in blocks.into_iter().zip(reached) {
+      if !is_reached {
+        continue;
+      }
+      match &mut block.end {
+        Terminator::Jump(target) => *target = ids[*target],
+        Terminator::Branch(_, yes, no) => {
+          *yes = ids[*yes];
+          *no = ids[*no];
+        }
+        Terminator::Return(_) | Terminator::Fail(_) => (),
+      }
+      self.blocks.push(block);
+    }
+  }
+}
+
+// a lowered program, see lower::lower
+#[derive(Debug, Clone, Default)]
+pub struct Module {
+  // a function comes before the ones nested in it
+  pub functions: Vec<Function>,
+  // name and fields of each struct
+  pub structs: Vec<(String, Vec<String>)>,
+  // name and type of every variable assigned outside functions, in order of
+  // first use; a global is zero until its first assignment
+  pub globals: Vec<(String, Type)>,
+  // the statements outside functions, run before main; None when there are
+  // only functions and structs
+  pub init: Option<Function>,
+}
+
+impl Module {
+  pub fn function(&self, path: &str) -> Option<&Function> {
+    self.functions.iter().find(|function| function.path == path)
+  }
+}
+
+impl Display for Constant {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      Constant::Int(n) => write!(f, "{}", n),
+      Constant::Float(n) => write!(f, "{:?}", n),
+      Constant::Bool(true) => write!(f, "True"),
+      Constant::Bool(false) => write!(f, "False"),
+      Constant::Str(s) => write!(f, "{:?}", s),
+    }
+  }
+}
+
+impl Display for Var {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      Var::Local(name) => write!(f, "{}", name),
+      Var::Global(name) => write!(f, "@{}", name),
+      Var::Temp(idx) => write!(f, "t{}", idx),
+    }
+  }
+}
+
+impl Display for Operand {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      Operand::Const(constant) => write!(f, "{}", constant),
+      Operand::Var(var) => write!(f, "{}", var),
+    }
+  }
+}
+
+fn list<T: Display>(items: &[T]) -> String {
+  let items: Vec<String> = items.iter().map(|item| item.to_string()).collect();
+  items.join(", ")
+}
+
+impl Display for Inst {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      Inst::Copy(dest, a) => write!(f, "{} = {}", dest, a),
+      // BinOp prints itself with a space on each side
+      Inst::Binary(dest, op, a, b) => write!(f, "{} = {}{}{}", dest, a, op, b),
+      Inst::Compare(dest, op, a, b) => {
+        let op = match op {
+          CompareOp::Eq => "==",
+          CompareOp::Gt => ">",
+          CompareOp::Lt => "<",
+        };
+        write!(f, "{} = {} {} {}", dest, a, op, b)
+      }
+      Inst::Not(dest, a) => write!(f, "{} = not {}", dest, a),
+      Inst::Convert(dest, t, a) => write!(f, "{} = convert {} to {}", dest, a, t),
+      Inst::Zero(dest, t) => write!(f, "{} = zero {}", dest, t),
+      Inst::Call(dest, function, args) => write!(f, "{} = call {}({})", dest, function, list(args)),
+      Inst::CallValue(dest, function, args) => {
+        write!(f, "{} = call_value {}({})", dest, function, list(args))
+      }
+      Inst::Closure(dest, function, captures) => {
+        write!(f, "{} = closure {}({})", dest, function, list(captures))
+      }
+      Inst::Builtin(dest, name, args) => write!(f, "{} = {}({})", dest, name, list(args)),
+      Inst::NewList(dest, items) => write!(f, "{} = [{}]", dest, list(items)),
+      Inst::NewMap(dest, entries) => {
+        let entries: Vec<String> = entries
+          .iter()
+          .map(|(key, value)| format!("{}: {}", key, value))
+          .collect();
+        write!(f, "{} = {{{}}}", dest, entries.join(", "))
+      }
+      Inst::NewStruct(dest, name, fields) => write!(f, "{} = new {}({})", dest, name, list(fields)),
+      Inst::GetItem(dest, target, key) => write!(f, "{} = {}[{}]", dest, target, key),
+      Inst::SetItem(target, key, value) => write!(f, "{}[{}] = {}", target, key, value),
+      Inst::GetField(dest, target, field) => write!(f, "{} = {}.{}", dest, target, field),
+      Inst::SetField(target, field, value) => write!(f, "{}.{} = {}", target, field, value),
+    }
+  }
+}
+
+impl Display for Terminator {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      Terminator::Jump(target) => write!(f, "jump bb{}", target),
+      Terminator::Branch(condition, yes, no) => {
+        write!(f, "branch {}, bb{}, bb{}", condition, yes, no)
+      }
+      Terminator::Return(value) => write!(f, "return {}", value),
+      Terminator::Fail(message) => write!(f, "fail {:?}", message),
+    }
+  }
+}
+
+// what `riven ir` prints: every function with its parameters, then its
+// blocks with one instruction per line
+//
+// fn fib(n) {
+// bb0:
+//   t0 = n < 2
+//   branch t0, bb1, bb2
+// ...
+// }
+impl Display for Function {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self.path.is_empty() {
+      true => writeln!(f, "init {{")?,
+      false => writeln!(f, "fn {}({}) {{", self.path, list(&self.params))?,
+    }
+    for (idx, block) in self.blocks.iter().enumerate() {
+      writeln!(f, "bb{}:", idx)?;
+      for inst in &block.code {
+        writeln!(f, "  {}", inst)?;
+      }
+      writeln!(f, "  {}", block.end)?;
+    }
+    writeln!(f, "}}")
+  }
+}
+
+impl Display for Module {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    for (name, fields) in &self.structs {
+      writeln!(f, "struct {} {{ {} }}", name, list(fields))?;
+      writeln!(f)?;
+    }
+    for (name, t) in &self.globals {
+      writeln!(f, "global @{}: {}", name, t)?;
+    }
+    if !self.globals.is_empty() {
+      writeln!(f)?;
+    }
+    let functions = self.init.iter().chain(&self.functions);
+    for (idx, function) in functions.enumerate() {
+      if idx > 0 {
+        writeln!(f)?;
+      }
+      write!(f, "{}", function)?;
+    }
+    Ok(())
+  }
+}
@@ -0,0 +1,24 @@
+//! A three-address code intermediate representation between the AST and the
+//! backends. Each function is lowered (`lower`) to a control flow graph of
+//! basic blocks: instructions with at most one operator run one after another,
+//! and only the end of a block jumps; the statements outside functions are
+//! lowered to an init function run before main. `optimize` propagates constants and
+//! removes dead code on it, and `ir::Module` prints it. The bytecode of
+//! `riven vm` and the x86-64 assembly of the asm target are generated from it.
+//!
+//! The C, JavaScript, Python, LLVM and wat targets still generate their code
+//! from the AST. Moving them onto this module, the C target first, is left as
+//! follow-up work: until then an optimization done here does not reach them.
+//!
+//! ```
+//! use ir::{lower::lower, optimize::optimize};
+//! use parser::{parse_program::parse_program, parser::Parser};
+//!
+//! let mut p = Parser::new("program { fn main() { x = 2 * 3; return x + 1; } }");
+//! let module = optimize(lower(&parse_program(&mut p)));
+//! assert_eq!(module.to_string(), "fn main() {\nbb0:\n  return 7\n}\n");
+//! ```
+
+pub mod ir;
+pub mod lower;
+pub mod optimize;
@@ -0,0 +1,527 @@
+use analyzer::infer::{child_path, infer, statement_functions, statements, Passed, TypeInfo};
+use analyzer::operands::Operation;
+use analyzer::types::Type;
+use parser::ast::{
+  ApplyExpr, AssignStmt, CallExpr, CompareExpr, Expression, Factor, FactorValue, FieldAssignStmt,
+  FieldExpr, FunctionStmt, IfStmt, IndexAssignStmt, IndexExpr, ListExpr, LogicExpr, LogicOp,
+  MapEntry, MapExpr, Primary, PrimaryValue, Program, ReturnStmt, Statement, StatementValue,
+  StmtList, StructStmt, WhileStmt,
+};
+
+use crate::ir::{Block, BlockId, Constant, Function, Inst, Module, Operand, Terminator, Var};
+
+// lower a checked program to a control flow graph per function, values are
+// computed and converted at the same places as the bytecode compiler does;
+// the statements outside functions are the init function
+pub fn lower(program: &Program) -> Module {
+  let types = infer(program).types;
+  let mut module = Module::default();
+  let mut functions = vec![];
+  declare(&mut module, &mut functions, &program.0, "");
+
+  let mut globals = vec![];
+  let has_init = statements(&program.0).iter().any(|stmt| {
+    !matches!(
+      stmt.0,
+      StatementValue::FunctionStmt(_) | StatementValue::StructStmt(_)
+    )
+  });
+  let init = has_init.then(|| {
+    let mut lowerer = Lowerer::new(&types, &module, "", &mut globals);
+    lowerer.lower_body(Some(&program.0));
+    lowerer.function
+  });
+  let lowered = functions
+    .iter()
+    .map(|(path, function)| {
+      let mut lowerer = Lowerer::new(&types, &module, path, &mut globals);
+      lowerer.function.arity = function.1.len();
+      lowerer.lower_body(function.2.as_deref());
+      lowerer.function
+    })
+    .collect();
+  module.functions = lowered;
+  module.init = init;
+  module.globals = globals;
+  module
+}
+
+// find the functions and structs wherever they are, a function comes before
+// the ones nested in it
+fn declare<'p>(
+  module: &mut Module,
+  functions: &mut Vec<(String, &'p FunctionStmt)>,
+  stmt_list: &'p StmtList,
+  path: &str,
+) {
+  for stmt in statements(stmt_list) {
+    for (function, _) in statement_functions(stmt) {
+      let function_path = child_path(path, &function.0.to_string());
+      functions.push((function_path.clone(), function));
+      if let Some(body) = &function.2 {
+        declare(module, functions, body, &function_path);
+      }
+    }
+    match &stmt.0 {
+      StatementValue::StructStmt(StructStmt(name, fields)) => {
+        let fields = fields.iter().map(|field| field.to_string()).collect();
+        module.structs.push((name.to_string(), fields));
+      }
+      StatementValue::IfStmt(IfStmt(_, Some(body)))
+      | StatementValue::WhileStmt(WhileStmt(_, Some(body))) => {
+        declare(module, functions, body, path)
+      }
+      _ => (),
+    }
+  }
+}
+
+struct Lowerer<'a> {
+  types: &'a TypeInfo,
+  module: &'a Module,
+  // the globals used so far, by every function
+  globals: &'a mut Vec<(String, Type)>,
+  function: Function,
+  // the block being filled, its instructions are kept here until it ends
+  current: BlockId,
+  code: Vec<Inst>,
+  lines: Vec<usize>,
+  // source line of the statement being lowered
+  line: usize,
+}
+
+impl<'a> Lowerer<'a> {
+  fn new(
+    types: &'a TypeInfo,
+    module: &'a Module,
+    path: &str,
+    globals: &'a mut Vec<(String, Type)>,
+  ) -> Self {
+    Lowerer {
+      types,
+      module,
+      globals,
+      function: Function {
+        path: path.to_owned(),
+        ..Function::default()
+      },
+      current: 0,
+      code: vec![],
+      lines: vec![],
+      line: 0,
+    }
+  }
+
+  fn emit(&mut self, inst: Inst) {
+    self.code.push(inst);
+    self.lines.push(self.line);
+  }
+
+  fn temp(&mut self) -> Var {
+    self.function.temps += 1;
+    Var::Temp(self.function.temps - 1)
+  }
+
+  // an instruction giving a value, in a new temporary
+  fn assign(&mut self, inst: impl FnOnce(Var) -> Inst) -> Operand {
+    let dest = self.temp();
+    self.emit(inst(dest.clone()));
+    Operand::Var(dest)
+  }
+
+  // a block to jump to, its terminator is set when it ends
+  fn new_block(&mut self) -> BlockId {
+    self.function.blocks.push(Block {
+      code: vec![],
+      lines: vec![],
+      end: Terminator::Fail("unfinished block".to_owned()),
+      end_line: 0,
+    });
+    self.function.blocks.len() - 1
+  }
+
+  // end the current block, the code after it goes to `next`
+  fn end_block(&mut self, end: Terminator, next: BlockId) {
+    let block = &mut self.function.blocks[self.current];
+    block.code = std::mem::take(&mut self.code);
+    block.lines = std::mem::take(&mut self.lines);
+    block.end = end;
+    block.end_line = self.line;
+    self.current = next;
+  }
+
+  // the code following a `return` is never run, it goes to a block no one
+  // jumps to and which is removed at the end
+  fn terminate(&mut self, end: Terminator) {
+    let next = self.new_block();
+    self.end_block(end, next);
+  }
+
+  // a variable of the function, or a global when the program owns it
+  fn local(&mut self, name: &str) -> Var {
+    let path = &self.function.path;
+    let t = self.types.variable_type(path, name);
+    if self.types.resolve_variable(path, name).as_deref() == Some("") {
+      if !self.globals.iter().any(|(global, _)| global == name) {
+        self.globals.push((name.to_owned(), t));
+      }
+      return Var::Global(name.to_owned());
+    }
+    if !self.function.locals.iter().any(|(local, _)| local == name) {
+      self.function.locals.push((name.to_owned(), t));
+    }
+    Var::Local(name.to_owned())
+  }
+
+  // convert a value to the type it is stored as, values of a type only known
+  // at runtime and values already of the type are left as they are
+  fn convert(&mut self, value: Operand, from: Type, to: Type) -> Operand {
+    if matches!(to, Type::Any | Type::Unknown | Type::Void) || from == to {
+      return value;
+    }
+    self.assign(|dest| Inst::Convert(dest, to, value))
+  }
+
+  fn lower_body(&mut self, body: Option<&StmtList>) {
+    let path = self.function.path.clone();
+    self.current = self.new_block();
+    for name in self.types.params(&path) {
+      self.local(&name);
+      self.function.params.push(name);
+    }
+
+    if let Some(body) = body {
+      self.block(body);
+    }
+    // the end of the body, if reachable, gives back zero, see flow::default_return
+    let zero = match self.types.return_type(&path) {
+      Type::Float => Operand::Const(Constant::Float(0.0)),
+      Type::Bool => Operand::Const(Constant::Bool(false)),
+      Type::Str => Operand::Const(Constant::Str(String::new())),
+      t @ (Type::List(_) | Type::Map(_, _)) => self.assign(|dest| Inst::Zero(dest, t)),
+      _ => Operand::Const(Constant::Int(0)),
+    };
+    self.end_block(Terminator::Return(zero), self.current);
+    self.function.remove_unreachable();
+  }
+
+  fn block(&mut self, stmt_list: &StmtList) {
+    for stmt in statements(stmt_list) {
+      self.stmt(stmt);
+    }
+  }
+
+  fn stmt(&mut self, stmt: &Statement) {
+    self.line = stmt.location().row();
+    match &stmt.0 {
+      StatementValue::AssignStmt(AssignStmt(name, expr, _)) => {
+        let name = name.to_string();
+        let value = self.expr(expr);
+        let from = self.types.type_of(&self.function.path, expr);
+        let to = self.types.variable_type(&self.function.path, &name);
+        let value = self.convert(value, from, to);
+        let dest = self.local(&name);
+        self.emit(Inst::Copy(dest, value));
+      }
+      StatementValue::IndexAssignStmt(IndexAssignStmt(IndexExpr(target, key), value)) => {
+        let target = self.target(target);
+        let key = self.boxed(key);
+        let value = self.boxed(value);
+        self.emit(Inst::SetItem(target, key, value));
+      }
+      StatementValue::FieldAssignStmt(FieldAssignStmt(FieldExpr(target, field), value)) => {
+        let target = self.target(target);
+        let value = self.boxed(value);
+        self.emit(Inst::SetField(target, field.to_string(), value));
+      }
+      StatementValue::CallStmt(call_expr) => {
+        self.call(call_expr);
+      }
+      StatementValue::ApplyStmt(apply_expr) => {
+        self.apply(apply_expr);
+      }
+      StatementValue::ReturnStmt(ReturnStmt(expr)) => {
+        let value = self.expr(expr);
+        let from = self.types.type_of(&self.function.path, expr);
+        let to = self.types.return_type(&self.function.path);
+        let value = self.convert(value, from, to);
+        self.terminate(Terminator::Return(value));
+      }
+      // every function is lowered on its own
+      StatementValue::FunctionStmt(_) | StatementValue::StructStmt(_) => (),
+      StatementValue::IfStmt(IfStmt(condition, body)) => {
+        let condition = self.logic(condition);
+        let (then, end) = (self.new_block(), self.new_block());
+        self.end_block(Terminator::Branch(condition, then, end), then);
+        if let Some(body) = body {
+          self.block(body);
+        }
+        self.end_block(Terminator::Jump(end), end);
+      }
+      // the condition has a block of its own, the end of the body jumps back to it
+      StatementValue::WhileStmt(WhileStmt(condition, body)) => {
+        let (start, body_block, end) = (self.new_block(), self.new_block(), self.new_block());
+        self.end_block(Terminator::Jump(start), start);
+        let condition = self.logic(condition);
+        self.end_block(Terminator::Branch(condition, body_block, end), body_block);
+        if let Some(body) = body {
+          self.block(body);
+        }
+        self.line = stmt.location().row();
+        self.end_block(Terminator::Jump(start), end);
+      }
+    }
+  }
+
+  // a value whose truth is the result, `and` and `or` stop early
+  fn logic(&mut self, expr: &LogicExpr) -> Operand {
+    let LogicExpr(op, compare_expr, left, right) = expr;
+    match op {
+      LogicOp::Bool => match compare_expr {
+        Some(compare_expr) => self.compare(compare_expr),
+        None => Operand::Const(Constant::Bool(false)),
+      },
+      // x = False  branch left, rhs, end  rhs: x = right  end:
+      LogicOp::And | LogicOp::Or => {
+        let is_and = matches!(op, LogicOp::And);
+        let result = self.temp();
+        let left = self.logic_or(left, is_and);
+        self.emit(Inst::Copy(
+          result.clone(),
+          Operand::Const(Constant::Bool(!is_and)),
+        ));
+        let (rhs, end) = (self.new_block(), self.new_block());
+        let branch = match is_and {
+          true => Terminator::Branch(left, rhs, end),
+          false => Terminator::Branch(left, end, rhs),
+        };
+        self.end_block(branch, rhs);
+        let right = self.logic_or(right, is_and);
+        self.emit(Inst::Copy(result.clone(), right));
+        self.end_block(Terminator::Jump(end), end);
+        Operand::Var(result)
+      }
+      LogicOp::Not => match left {
+        Some(left) => {
+          let value = self.logic(left);
+          self.assign(|dest| Inst::Not(dest, value))
+        }
+        None => Operand::Const(Constant::Bool(true)),
+      },
+    }
+  }
+
+  // a missing operand of `and` or `or` counts as `missing`
+  fn logic_or(&mut self, expr: &Option<Box<LogicExpr>>, missing: bool) -> Operand {
+    match expr {
+      Some(expr) => self.logic(expr),
+      None => Operand::Const(Constant::Bool(missing)),
+    }
+  }
+
+  fn compare(&mut self, expr: &CompareExpr) -> Operand {
+    let CompareExpr(left, op, right) = expr;
+    let left = self.expr(left);
+    match (op, right) {
+      (Some(op), Some(right)) => {
+        let right = self.expr(right);
+        self.assign(|dest| Inst::Compare(dest, op.clone(), left, right))
+      }
+      _ => left,
+    }
+  }
+
+  fn expr(&mut self, expr: &Expression) -> Operand {
+    self.operation(&Operation::of_expr(expr))
+  }
+
+  // a list item, map value or struct field keeps its own kind where C keeps it
+  // boxed: when printed, stored in a list or map, or passed to a struct
+  fn boxed(&mut self, expr: &Expression) -> Operand {
+    match expr.as_factor() {
+      Some(factor) if is_stored_value(factor) => self.stored_value(factor),
+      _ => self.expr(expr),
+    }
+  }
+
+  fn operation(&mut self, operation: &Operation) -> Operand {
+    match operation {
+      Operation::Factor(factor) => self.factor(factor),
+      Operation::Group(inner) => self.operation(inner),
+      Operation::Binary(left, op, right) => {
+        let left = self.operation(left);
+        let right = self.operation(right);
+        self.assign(|dest| Inst::Binary(dest, op.clone(), left, right))
+      }
+    }
+  }
+
+  fn factor(&mut self, factor: &Factor) -> Operand {
+    match &factor.0 {
+      FactorValue::Primary(Primary(primary)) => Operand::Const(match primary {
+        PrimaryValue::String(s) => Constant::Str(s.clone()),
+        PrimaryValue::Number(n) => Constant::Int(*n as i32),
+        PrimaryValue::Float(n) => Constant::Float(*n),
+        PrimaryValue::Bool(b) => Constant::Bool(*b),
+      }),
+      // a function used as a value becomes a closure
+      FactorValue::Identifier(name) => {
+        let name = name.to_string();
+        let path = &self.function.path;
+        if self.types.resolve_variable(path, &name).is_some() {
+          return Operand::Var(self.local(&name));
+        }
+        match self.types.resolve_function(path, &name) {
+          Some(function) => self.closure(&function),
+          None => self.fail(format!("undefined variable `{}`", name)),
+        }
+      }
+      FactorValue::Function(function, _) => {
+        let function = child_path(&self.function.path, &function.0.to_string());
+        self.closure(&function)
+      }
+      FactorValue::CallExpr(call_expr) => self.call(call_expr),
+      FactorValue::Apply(apply_expr) => self.apply(apply_expr),
+      FactorValue::List(ListExpr(items)) => {
+        let items = items.0.iter().map(|item| self.boxed(item)).collect();
+        self.assign(|dest| Inst::NewList(dest, items))
+      }
+      FactorValue::Map(MapExpr(entries)) => {
+        let entries = entries
+          .iter()
+          .map(|MapEntry(key, value)| (self.boxed(key), self.boxed(value)))
+          .collect();
+        self.assign(|dest| Inst::NewMap(dest, entries))
+      }
+      FactorValue::Index(_) | FactorValue::Field(_) => {
+        let value = self.stored_value(factor);
+        let t = self.types.type_of_factor(&self.function.path, factor);
+        self.convert(value, Type::Any, t)
+      }
+    }
+  }
+
+  // stop the program, e.g. on a name the checks let through
+  fn fail(&mut self, message: String) -> Operand {
+    self.terminate(Terminator::Fail(message));
+    Operand::Const(Constant::Int(0))
+  }
+
+  // the captured variables are copied into the closure when it is made
+  fn closure(&mut self, function: &str) -> Operand {
+    let captures = self
+      .types
+      .captures(function)
+      .iter()
+      .map(|name| Operand::Var(self.local(name)))
+      .collect();
+    self.assign(|dest| Inst::Closure(dest, function.to_owned(), captures))
+  }
+
+  fn print(&mut self, value: Operand) {
+    self.assign(|dest| Inst::Builtin(dest, "print".to_owned(), vec![value]));
+  }
+
+  // the value at `list[index]`, `map[key]` or `p.x` as it is stored
+  fn stored_value(&mut self, factor: &Factor) -> Operand {
+    match &factor.0 {
+      FactorValue::Index(IndexExpr(target, key)) => {
+        let target = self.target(target);
+        let key = self.boxed(key);
+        self.assign(|dest| Inst::GetItem(dest, target, key))
+      }
+      FactorValue::Field(FieldExpr(target, field)) => {
+        let target = self.target(target);
+        self.assign(|dest| Inst::GetField(dest, target, field.to_string()))
+      }
+      _ => self.factor(factor),
+    }
+  }
+
+  // `grid[0]` in `grid[0][1]` stays as stored, its kind is checked when indexed
+  fn target(&mut self, target: &Factor) -> Operand {
+    match is_stored_value(target) {
+      true => self.stored_value(target),
+      false => self.factor(target),
+    }
+  }
+
+  fn call(&mut self, call_expr: &CallExpr) -> Operand {
+    let CallExpr(name, args) = call_expr;
+    let name = name.to_string();
+    let args = &args.0;
+    match name.as_str() {
+      // each value is printed once computed, like the C runtime prints them,
+      // and as stored; print gives back 0
+      "print" | "println" => {
+        for (idx, arg) in args.iter().enumerate() {
+          if idx > 0 {
+            self.print(Operand::Const(Constant::Str(" ".to_owned())));
+          }
+          let value = self.boxed(arg);
+          self.print(value);
+        }
+        if name == "println" {
+          self.print(Operand::Const(Constant::Str("\n".to_owned())));
+        }
+        return Operand::Const(Constant::Int(0));
+      }
+      "append" | "has" => {
+        let args = args.iter().map(|arg| self.boxed(arg)).collect();
+        return self.assign(|dest| Inst::Builtin(dest, name, args));
+      }
+      "printf" | "input" | "input_number" | "len" | "keys" => {
+        let args = args.iter().map(|arg| self.expr(arg)).collect();
+        return self.assign(|dest| Inst::Builtin(dest, name, args));
+      }
+      _ => (),
+    }
+
+    // Point(1, 2)
+    if self.module.structs.iter().any(|(s, _)| *s == name) {
+      let args = args.iter().map(|arg| self.boxed(arg)).collect();
+      return self.assign(|dest| Inst::NewStruct(dest, name, args));
+    }
+
+    // f(3) where f is a variable holding a function
+    let path = self.function.path.clone();
+    if self.types.resolve_variable(&path, &name).is_some() {
+      let function = Operand::Var(self.local(&name));
+      let factor = Factor::from(FactorValue::CallExpr(call_expr.clone()));
+      return self.call_value(function, args, &factor);
+    }
+
+    let Some(function) = self.types.resolve_function(&path, &name) else {
+      return self.fail(format!("`{}` is not a function", name));
+    };
+    let values = self
+      .types
+      .passed(&function, args)
+      .into_iter()
+      .map(|passed| match passed {
+        Passed::Capture(name) => Operand::Var(self.local(&name)),
+        Passed::Arg(arg, _) => self.expr(arg),
+      })
+      .collect();
+    self.assign(|dest| Inst::Call(dest, function, values))
+  }
+
+  // make_adder(2)(3), fs[0](4) or s.f(1)
+  fn apply(&mut self, apply_expr: &ApplyExpr) -> Operand {
+    let function = self.factor(&apply_expr.0);
+    let factor = Factor::from(FactorValue::Apply(apply_expr.clone()));
+    self.call_value(function, &apply_expr.1 .0, &factor)
+  }
+
+  // the value `factor` gives back by calling the function value `function`
+  fn call_value(&mut self, function: Operand, args: &[Expression], factor: &Factor) -> Operand {
+    let args = args.iter().map(|arg| self.boxed(arg)).collect();
+    let value = self.assign(|dest| Inst::CallValue(dest, function, args));
+    let t = self.types.type_of_factor(&self.function.path, factor);
+    self.convert(value, Type::Any, t)
+  }
+}
+
+fn is_stored_value(factor: &Factor) -> bool {
+  matches!(factor.0, FactorValue::Index(_) | FactorValue::Field(_))
+}
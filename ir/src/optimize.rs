@@ -0,0 +1,296 @@
+use std::collections::{HashMap, HashSet};
+
+use analyzer::types::Type;
+use parser::ast::{BinOp, CompareOp};
+
+use crate::ir::{Constant, Function, Inst, Module, Operand, Terminator, Var};
+
+// simplify a lowered program: fold what is known before it runs, turn
+// branches on a known condition into jumps, then drop the blocks never
+// reached and the instructions whose result is never used
+pub fn optimize(mut module: Module) -> Module {
+  for function in module.init.iter_mut().chain(&mut module.functions) {
+    optimize_function(function);
+  }
+  module
+}
+
+// each pass can give the others more to do
+pub fn optimize_function(function: &mut Function) {
+  loop {
+    let propagated = propagate_constants(function);
+    let eliminated = eliminate_dead_code(function);
+    let merged = merge_blocks(function);
+    if !(propagated || eliminated || merged) {
+      break;
+    }
+  }
+}
+
+// the variables known to hold a constant at some point of a function, the
+// others hold a value only known when the program runs
+type Known = HashMap<Var, Constant>;
+
+// replace the variables known to hold a constant by the constant and fold the
+// instructions computing one, e.g. `t0 = 2 * 3` becomes `t0 = 6`; a variable
+// is only known where every path reaching it gives it the same constant
+pub fn propagate_constants(function: &mut Function) -> bool {
+  let before = function.blocks.clone();
+  let entries = known_on_entry(function);
+  for (block, entry) in function.blocks.iter_mut().zip(entries) {
+    // the blocks never reached are dropped below
+    let Some(mut known) = entry else {
+      continue;
+    };
+    for inst in &mut block.code {
+      for operand in inst.operands_mut() {
+        substitute(operand, &known);
+      }
+      let folded = fold(inst, &known);
+      if let (Some(constant), Some(dest)) = (&folded, inst.dest()) {
+        *inst = Inst::Copy(dest.clone(), Operand::Const(constant.clone()));
+      }
+      learn(inst, folded, &mut known);
+    }
+    match &mut block.end {
+      Terminator::Branch(condition, yes, no) => {
+        substitute(condition, &known);
+        if let Some(truth) = condition.as_const().and_then(truth) {
+          block.end = Terminator::Jump(if truth { *yes } else { *no });
+        }
+      }
+      Terminator::Return(value) => substitute(value, &known),
+      Terminator::Jump(_) | Terminator::Fail(_) => (),
+    }
+  }
+  function.remove_unreachable();
+  function.blocks != before
+}
+
+// what is known when each block starts, None for the blocks no path reaches:
+// a branch on a known condition only leads to one of its blocks
+fn known_on_entry(function: &Function) -> Vec<Option<Known>> {
+  let mut entries: Vec<Option<Known>> = vec![None; function.blocks.len()];
+  entries[0] = Some(Known::new());
+  let mut pending = vec![0];
+  while let Some(idx) = pending.pop() {
+    let mut known = entries[idx].clone().unwrap_or_default();
+    let block = &function.blocks[idx];
+    for inst in &block.code {
+      let folded = fold(inst, &known);
+      learn(inst, folded, &mut known);
+    }
+    let successors = match &block.end {
+      Terminator::Branch(condition, yes, no) => {
+        match value(condition, &known).as_ref().and_then(truth) {
+          Some(true) => vec![*yes],
+          Some(false) => vec![*no],
+          None => vec![*yes, *no],
+        }
+      }
+      end => end.successors(),
+    };
+    for successor in successors {
+      let merged = match &entries[successor] {
+        None => known.clone(),
+        Some(entry) => entry
+          .iter()
+          .filter(|(var, constant)| known.get(var) == Some(constant))
+          .map(|(var, constant)| (var.clone(), constant.clone()))
+          .collect(),
+      };
+      if entries[successor].as_ref() != Some(&merged) {
+        entries[successor] = Some(merged);
+        pending.push(successor);
+      }
+    }
+  }
+  entries
+}
+
+fn substitute(operand: &mut Operand, known: &Known) {
+  if let Some(constant) = value(operand, known) {
+    *operand = Operand::Const(constant);
+  }
+}
+
+fn value(operand: &Operand, known: &Known) -> Option<Constant> {
+  match operand {
+    Operand::Const(constant) => Some(constant.clone()),
+    Operand::Var(var) => known.get(var).cloned(),
+  }
+}
+
+// the instruction wrote its variable, with the constant it computed if any
+fn learn(inst: &Inst, folded: Option<Constant>, known: &mut Known) {
+  if let Some(dest) = inst.dest() {
+    match folded {
+      Some(constant) => known.insert(dest.clone(), constant),
+      None => known.remove(dest),
+    };
+  }
+}
+
+// the constant an instruction computes, nothing is folded that could fail or
+// that C would not compute the same way
+fn fold(inst: &Inst, known: &Known) -> Option<Constant> {
+  match inst {
+    Inst::Copy(_, a) => value(a, known),
+    Inst::Binary(_, op, a, b) => apply(&value(a, known)?, op, &value(b, known)?),
+    Inst::Compare(_, op, a, b) => {
+      compare(&value(a, known)?, op, &value(b, known)?).map(Constant::Bool)
+    }
+    Inst::Not(_, a) => truth(&value(a, known)?).map(|truth| Constant::Bool(!truth)),
+    Inst::Convert(_, t, a) => convert(value(a, known)?, t),
+    _ => None,
+  }
+}
+
+// what C makes of a number or a bool in a condition
+fn truth(constant: &Constant) -> Option<bool> {
+  match constant {
+    Constant::Str(_) => None,
+    constant => Some(constant.truth()),
+  }
+}
+
+// bools are ints in C arithmetic
+fn int(constant: &Constant) -> Option<i32> {
+  match constant {
+    Constant::Int(n) => Some(*n),
+    Constant::Bool(b) => Some(*b as i32),
+    _ => None,
+  }
+}
+
+// `a op b` like the runtime computes it: ints stay ints and must fit a C int,
+// `7 / 2` is 3, and dividing by zero is left to fail when the program runs
+fn apply(left: &Constant, op: &BinOp, right: &Constant) -> Option<Constant> {
+  if let (Some(a), Some(b)) = (int(left), int(right)) {
+    let value = match op {
+      BinOp::Add => a.checked_add(b)?,
+      BinOp::Min => a.checked_sub(b)?,
+      BinOp::Time => a.checked_mul(b)?,
+      BinOp::Div => a.checked_div(b)?,
+    };
+    return Some(Constant::Int(value));
+  }
+
+  let (a, b) = (left.number()?, right.number()?);
+  let value = match op {
+    BinOp::Add => a + b,
+    BinOp::Min => a - b,
+    BinOp::Time => a * b,
+    BinOp::Div => a / b,
+  };
+  value.is_finite().then_some(Constant::Float(value))
+}
+
+// `=` tells numbers, bools and texts apart, `>` and `<` take bools as ints
+fn compare(left: &Constant, op: &CompareOp, right: &Constant) -> Option<bool> {
+  match (op, left, right) {
+    (
+      CompareOp::Eq,
+      Constant::Int(_) | Constant::Float(_),
+      Constant::Int(_) | Constant::Float(_),
+    ) => Some(left.number()? == right.number()?),
+    (CompareOp::Eq, Constant::Bool(a), Constant::Bool(b)) => Some(a == b),
+    (CompareOp::Eq, Constant::Str(a), Constant::Str(b)) => Some(a == b),
+    (CompareOp::Eq, _, _) => Some(false),
+    (CompareOp::Gt, _, _) => Some(left.number()? > right.number()?),
+    (CompareOp::Lt, _, _) => Some(left.number()? < right.number()?),
+  }
+}
+
+// see Inst::Convert, a constant of the wrong kind is left to fail when the program runs
+fn convert(constant: Constant, t: &Type) -> Option<Constant> {
+  match (t, constant) {
+    (Type::Int, Constant::Float(n)) => Some(Constant::Int(n as i32)),
+    (Type::Int, Constant::Bool(b)) => Some(Constant::Int(b as i32)),
+    (Type::Float, Constant::Int(n)) => Some(Constant::Float(n as f64)),
+    (Type::Int, constant @ Constant::Int(_))
+    | (Type::Float, constant @ Constant::Float(_))
+    | (Type::Bool, constant @ Constant::Bool(_))
+    | (Type::Str, constant @ Constant::Str(_))
+    | (Type::Any | Type::Unknown | Type::Void, constant) => Some(constant),
+    _ => None,
+  }
+}
+
+// drop the instructions computing a value no one reads, when computing it
+// has no other effect; removing one can leave others unused. A global is
+// read by other functions
+pub fn eliminate_dead_code(function: &mut Function) -> bool {
+  let mut changed = false;
+  loop {
+    let used = used_variables(function);
+    let mut removed = false;
+    for block in &mut function.blocks {
+      let code = std::mem::take(&mut block.code);
+      let lines = std::mem::take(&mut block.lines);
+      for (inst, line) in code.into_iter().zip(lines) {
+        let is_dead = inst.is_pure()
+          && inst
+            .dest()
+            .is_some_and(|dest| !matches!(dest, Var::Global(_)) && !used.contains(dest));
+        if is_dead {
+          removed = true;
+          continue;
+        }
+        block.code.push(inst);
+        block.lines.push(line);
+      }
+    }
+    if !removed {
+      return changed;
+    }
+    changed = true;
+  }
+}
+
+fn used_variables(function: &Function) -> HashSet<Var> {
+  let mut used = HashSet::new();
+  for block in &function.blocks {
+    let operands = block.code.iter().flat_map(Inst::operands);
+    let end = match &block.end {
+      Terminator::Branch(operand, _, _) | Terminator::Return(operand) => Some(operand),
+      Terminator::Jump(_) | Terminator::Fail(_) => None,
+    };
+    for operand in operands.chain(end) {
+      if let Operand::Var(var) = operand {
+        used.insert(var.clone());
+      }
+    }
+  }
+  used
+}
+
+// a block jumping to a block no other block jumps to is followed by it, they
+// become one block
+pub fn merge_blocks(function: &mut Function) -> bool {
+  let mut changed = false;
+  loop {
+    let predecessors = function.predecessors();
+    let merge = function
+      .blocks
+      .iter()
+      .enumerate()
+      .find_map(|(idx, block)| match block.end {
+        Terminator::Jump(next) if next != 0 && next != idx && predecessors[next] == [idx] => {
+          Some((idx, next))
+        }
+        _ => None,
+      });
+    let Some((idx, next)) = merge else {
+      return changed;
+    };
+    let next_block = function.blocks[next].clone();
+    let block = &mut function.blocks[idx];
+    block.code.extend(next_block.code);
+    block.lines.extend(next_block.lines);
+    block.end = next_block.end;
+    block.end_line = next_block.end_line;
+    function.remove_unreachable();
+    changed = true;
+  }
+}
@@ -0,0 +1,138 @@
+use analyzer::types::Type;
+use ir::ir::{Constant, Inst, Operand, Terminator, Var};
+use ir::lower::lower;
+use parser::{parse_program::parse_program, parser::Parser};
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn lower_code(code: &str) -> ir::ir::Module {
+    let mut p = Parser::new(code);
+    lower(&parse_program(&mut p))
+  }
+
+  #[test]
+  fn test_lower_function() {
+    let module = lower_code(
+      "program {
+        fn fib(n) {
+          if (n < 2) {
+            return n;
+          }
+          return fib(n - 1) + fib(n - 2);
+        }
+      }",
+    );
+    assert_eq!(
+      module.to_string(),
+      "fn fib(n) {
+bb0:
+  t0 = n < 2
+  branch t0, bb1, bb2
+bb1:
+  return n
+bb2:
+  t1 = n - 1
+  t2 = call fib(t1)
+  t3 = n - 2
+  t4 = call fib(t3)
+  t5 = t2 + t4
+  return t5
+}
+"
+    );
+  }
+
+  #[test]
+  fn test_lower_control_flow() {
+    let module = lower_code(
+      "program {
+        fn main() {
+          i = 0;
+          while (and(i < 10, i > 0 - 1)) {
+            i = i + 1;
+          }
+          println(i);
+        }
+      }",
+    );
+    let main = module.function("main").unwrap();
+    // entry, loop condition, body, exit, then the right side of the and and its end
+    assert_eq!(main.blocks.len(), 6);
+    assert_eq!(main.blocks[0].end, Terminator::Jump(1));
+    assert_eq!(main.blocks[2].end, Terminator::Jump(1));
+    assert_eq!(main.blocks[4].end, Terminator::Jump(5));
+    assert_eq!(
+      main.blocks[0].code,
+      [Inst::Copy(
+        Var::Local("i".to_owned()),
+        Operand::Const(Constant::Int(0))
+      )]
+    );
+    // the function returns zero when it runs off its end
+    assert_eq!(
+      main.blocks[3].end,
+      Terminator::Return(Operand::Const(Constant::Int(0)))
+    );
+    for (idx, predecessors) in main.predecessors().iter().enumerate().skip(1) {
+      assert!(!predecessors.is_empty(), "bb{} is unreachable", idx);
+    }
+  }
+
+  #[test]
+  fn test_lower_closures_and_structs() {
+    let module = lower_code(
+      "program {
+        struct Point { x, y }
+        fn main() {
+          step = 2;
+          fn next(n) {
+            return n + step;
+          }
+          p = Point(1, next(3));
+          p.x = 5;
+          println(p.x);
+        }
+      }",
+    );
+    let text = module.to_string();
+    assert!(text.starts_with("struct Point { x, y }\n\n"));
+    // captured variables come first
+    assert!(text.contains("fn main::next(step, n) {\n"));
+    assert!(text.contains("  t0 = call main::next(step, 3)\n"));
+    assert!(text.contains("  t1 = new Point(1, t0)\n"));
+    assert!(text.contains("  p.x = 5\n"));
+  }
+
+  #[test]
+  fn test_lower_globals() {
+    let module = lower_code(
+      "program {
+        limit = 3;
+        fn add_limit(n) {
+          return n + limit;
+        }
+        println(add_limit(2));
+        fn main() {
+          println(limit);
+        }
+      }",
+    );
+    assert_eq!(module.globals, [("limit".to_owned(), Type::Int)]);
+    let init = module.init.as_ref().unwrap();
+    assert_eq!(
+      init.blocks[0].code[0],
+      Inst::Copy(
+        Var::Global("limit".to_owned()),
+        Operand::Const(Constant::Int(3))
+      )
+    );
+    let text = module.to_string();
+    assert!(
+      text.starts_with("global @limit: int\n\ninit {\nbb0:\n  @limit = 3\n  t0 = call add_limit(2)\n")
+    );
+    assert!(text.contains("fn add_limit(n) {\nbb0:\n  t0 = n + @limit\n"));
+    assert!(text.contains("fn main() {\nbb0:\n  t0 = print(@limit)\n"));
+  }
+}
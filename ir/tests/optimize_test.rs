@@ -0,0 +1,103 @@
+use ir::lower::lower;
+use ir::optimize::{eliminate_dead_code, optimize, propagate_constants};
+use parser::{parse_program::parse_program, parser::Parser};
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn optimize_code(code: &str) -> String {
+    let mut p = Parser::new(code);
+    optimize(lower(&parse_program(&mut p))).to_string()
+  }
+
+  #[test]
+  fn test_propagate_constants() {
+    let code = optimize_code(
+      "program {
+        fn main() {
+          size = 4;
+          area = size * size;
+          if (area > 10) {
+            println(\"big\", area);
+          }
+          if (area < 10) {
+            println(\"small\");
+          }
+          half = 7 / 2;
+          ratio = half / 2.0;
+          println(half, ratio, 1 / 0);
+        }
+      }",
+    );
+    assert_eq!(
+      code,
+      "fn main() {
+bb0:
+  t2 = print(\"big\")
+  t3 = print(\" \")
+  t4 = print(16)
+  t5 = print(\"\\n\")
+  t11 = print(3)
+  t12 = print(\" \")
+  t13 = print(1.5)
+  t14 = print(\" \")
+  t15 = 1 / 0
+  t16 = print(t15)
+  t17 = print(\"\\n\")
+  return 0
+}
+"
+    );
+  }
+
+  #[test]
+  fn test_loop_variables_are_not_constant() {
+    let code = optimize_code(
+      "program {
+        fn main() {
+          i = 0;
+          step = 1;
+          while (i < 3) {
+            i = i + step;
+          }
+          println(i);
+        }
+      }",
+    );
+    // `step` never changes, `i` does on each iteration
+    assert!(code.contains("bb1:\n  t0 = i < 3\n  branch t0, bb2, bb3\n"));
+    assert!(code.contains("bb2:\n  t1 = i + 1\n  i = t1\n  jump bb1\n"));
+    assert!(!code.contains("step"));
+  }
+
+  #[test]
+  fn test_eliminate_dead_code() {
+    let mut p = Parser::new(
+      "program {
+        fn main(a) {
+          unused = a * 2;
+          names = [\"ann\"];
+          append(names, \"bob\");
+          return a;
+        }
+      }",
+    );
+    let mut module = lower(&parse_program(&mut p));
+    let main = &mut module.functions[0];
+    assert!(!propagate_constants(main));
+    assert!(eliminate_dead_code(main));
+    // the list is still used by `append`
+    assert_eq!(
+      main.to_string(),
+      "fn main(a) {
+bb0:
+  t1 = [\"ann\"]
+  names = t1
+  t2 = append(names, \"bob\")
+  return a
+}
+"
+    );
+  }
+}
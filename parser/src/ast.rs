@@ -3,6 +3,7 @@ use std::fmt::Display;
 use std::matches;
 use std::string::String;
 
+use crate::location::Location;
 use crate::token::Token;
 
 // use crate::location::SourceLocation;
@@ -42,6 +43,9 @@ impl Display for Identifier {
 pub enum PrimaryValue {
   String(String),
   Number(f64),
+  // a number written with a decimal point, `2.0` is a float but `2` is not
+  Float(f64),
+  Bool(bool),
 }
 
 impl PrimaryValue {
@@ -50,7 +54,15 @@ impl PrimaryValue {
   }
 
   pub const fn is_number(&self) -> bool {
-    !self.is_string()
+    matches!(self, PrimaryValue::Number(_) | PrimaryValue::Float(_))
+  }
+
+  pub const fn is_float(&self) -> bool {
+    matches!(self, PrimaryValue::Float(_))
+  }
+
+  pub const fn is_bool(&self) -> bool {
+    matches!(self, PrimaryValue::Bool(_))
   }
 }
 
@@ -59,6 +71,9 @@ impl Display for PrimaryValue {
     match self {
       PrimaryValue::String(s) => write!(f, "{}", s),
       PrimaryValue::Number(n) => write!(f, "{}", n),
+      PrimaryValue::Float(n) => write!(f, "{:?}", n),
+      PrimaryValue::Bool(true) => write!(f, "True"),
+      PrimaryValue::Bool(false) => write!(f, "False"),
     }
   }
 }
@@ -77,6 +92,13 @@ pub enum FactorValue {
   Primary(Primary),
   Identifier(Identifier),
   CallExpr(CallExpr),
+  List(ListExpr),
+  Map(MapExpr),
+  Index(IndexExpr),
+  Field(FieldExpr),
+  Apply(ApplyExpr),
+  // fn(x) { return x * 2; } and where it starts, it is named after it, e.g. `fn@3:11`
+  Function(Box<FunctionStmt>, Location),
 }
 
 impl FactorValue {
@@ -91,6 +113,26 @@ impl FactorValue {
   pub const fn is_call_expr(&self) -> bool {
     matches!(self, FactorValue::CallExpr(_))
   }
+
+  pub const fn is_list(&self) -> bool {
+    matches!(self, FactorValue::List(_))
+  }
+
+  pub const fn is_map(&self) -> bool {
+    matches!(self, FactorValue::Map(_))
+  }
+
+  pub const fn is_index(&self) -> bool {
+    matches!(self, FactorValue::Index(_))
+  }
+
+  pub const fn is_field(&self) -> bool {
+    matches!(self, FactorValue::Field(_))
+  }
+
+  pub const fn is_function(&self) -> bool {
+    matches!(self, FactorValue::Function(_, _))
+  }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -284,9 +326,83 @@ impl From<ExpressionValue> for Expression {
   }
 }
 
+impl Expression {
+  // return the factor if the expression is a single factor, like `name` or `foo(1)`
+  pub fn as_factor(&self) -> Option<&Factor> {
+    let ExpressionValue::ComponentArithmeticExpr(ComponentArithmeticExpr(term, None, None)) =
+      &self.0
+    else {
+      return None;
+    };
+    let ComponentTerm(ComponentFactor(false, value), None, None) = term else {
+      return None;
+    };
+    match value {
+      ComponentFactorValue::ArithmeticExpr(ArithmeticExpr(Term(factor, None, None), None, None)) => {
+        Some(factor)
+      }
+      _ => None,
+    }
+  }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct ExpressionList(pub Vec<Expression>);
 
+// [1, 2, 3]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ListExpr(pub ExpressionList);
+
+impl ListExpr {
+  pub fn new(items: ExpressionList) -> Self {
+    ListExpr(items)
+  }
+}
+
+// "tom": 8
+#[derive(Debug, Clone, PartialEq)]
+pub struct MapEntry(pub Expression, pub Expression);
+
+// {"tom": 8, "amy": 9}
+#[derive(Debug, Clone, PartialEq)]
+pub struct MapExpr(pub Vec<MapEntry>);
+
+impl MapExpr {
+  pub fn new(entries: Vec<MapEntry>) -> Self {
+    MapExpr(entries)
+  }
+}
+
+// scores[0] or ages["tom"], the target can be any factor, so grid[1][2] works too
+#[derive(Debug, Clone, PartialEq)]
+pub struct IndexExpr(pub Box<Factor>, pub Box<Expression>);
+
+impl IndexExpr {
+  pub fn new(target: Factor, index: Expression) -> Self {
+    IndexExpr(Box::new(target), Box::new(index))
+  }
+}
+
+// p.x
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldExpr(pub Box<Factor>, pub Identifier);
+
+impl FieldExpr {
+  pub fn new(target: Factor, field: Identifier) -> Self {
+    FieldExpr(Box::new(target), field)
+  }
+}
+
+// a call of a function value that is not a name: make_adder(2)(3), fs[0](4), s.f(1)
+#[derive(Debug, Clone, PartialEq)]
+pub struct ApplyExpr(pub Box<Factor>, pub ExpressionList);
+
+impl ApplyExpr {
+  pub fn new(target: Factor, args: ExpressionList) -> Self {
+    ApplyExpr(Box::new(target), args)
+  }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum CompareOp {
   Eq, // equal
@@ -349,12 +465,43 @@ pub enum LogicListOp {
   Not,
 }
 
+// the `text` in `x: text = "hi";` or `fn area(w: number) -> number`
 #[derive(Debug, Clone, PartialEq)]
-pub struct AssignStmt(pub Identifier, pub Expression);
+pub struct TypeAnnotation(pub Identifier);
+
+impl Display for TypeAnnotation {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{}", self.0)
+  }
+}
+
+// x = 1; or x: number = 1;
+#[derive(Debug, Clone, PartialEq)]
+pub struct AssignStmt(pub Identifier, pub Expression, pub Option<TypeAnnotation>);
 
 impl AssignStmt {
   pub fn new(id: Identifier, expr: Expression) -> Self {
-    AssignStmt(id, expr)
+    AssignStmt(id, expr, None)
+  }
+}
+
+// scores[1] = 5; or ages["tom"] = 9;
+#[derive(Debug, Clone, PartialEq)]
+pub struct IndexAssignStmt(pub IndexExpr, pub Expression);
+
+impl IndexAssignStmt {
+  pub fn new(target: IndexExpr, expr: Expression) -> Self {
+    IndexAssignStmt(target, expr)
+  }
+}
+
+// p.x = 3;
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldAssignStmt(pub FieldExpr, pub Expression);
+
+impl FieldAssignStmt {
+  pub fn new(target: FieldExpr, expr: Expression) -> Self {
+    FieldAssignStmt(target, expr)
   }
 }
 
@@ -376,34 +523,93 @@ impl IfStmt {
   }
 }
 
+// while (i < 10) { ... }
+#[derive(Debug, Clone, PartialEq)]
+pub struct WhileStmt(pub LogicExpr, pub Option<Box<StmtList>>);
+
+impl WhileStmt {
+  pub fn new(expr: LogicExpr, stmt_list: StmtList) -> Self {
+    WhileStmt(expr, Some(Box::new(stmt_list)))
+  }
+}
+
+// w or w: number
+#[derive(Debug, Clone, PartialEq)]
+pub struct Param(pub Identifier, pub Option<TypeAnnotation>);
+
+impl From<&str> for Param {
+  fn from(name: &str) -> Self {
+    Param(Identifier::from(name), None)
+  }
+}
+
+impl Display for Param {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{}", self.0)
+  }
+}
+
+// the last field is the annotated return type, `-> number`
 #[derive(Debug, Clone, PartialEq)]
 pub struct FunctionStmt(
   pub Identifier,
-  pub Vec<Identifier>,
+  pub Vec<Param>,
   pub Option<Box<StmtList>>,
+  pub Option<TypeAnnotation>,
 );
 
 impl FunctionStmt {
-  pub fn new(id: Identifier, params: Vec<Identifier>, stmt_list: StmtList) -> Self {
-    FunctionStmt(id, params, Some(Box::new(stmt_list)))
+  pub fn new(
+    id: Identifier,
+    params: Vec<Param>,
+    stmt_list: StmtList,
+    return_type: Option<TypeAnnotation>,
+  ) -> Self {
+    FunctionStmt(id, params, Some(Box::new(stmt_list)), return_type)
+  }
+}
+
+// struct Point { x, y }
+#[derive(Debug, Clone, PartialEq)]
+pub struct StructStmt(pub Identifier, pub Vec<Identifier>);
+
+impl StructStmt {
+  pub fn new(id: Identifier, fields: Vec<Identifier>) -> Self {
+    StructStmt(id, fields)
   }
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum StatementValue {
   AssignStmt(AssignStmt),
+  IndexAssignStmt(IndexAssignStmt),
+  FieldAssignStmt(FieldAssignStmt),
   CallStmt(CallExpr),
+  ApplyStmt(ApplyExpr),
   ReturnStmt(ReturnStmt),
   FunctionStmt(FunctionStmt),
+  StructStmt(StructStmt),
   IfStmt(IfStmt),
+  WhileStmt(WhileStmt),
 }
 
+// a statement and the location where it starts in source code
 #[derive(Debug, Clone, PartialEq)]
-pub struct Statement(pub StatementValue);
+pub struct Statement(pub StatementValue, pub Location);
+
+impl Statement {
+  pub fn new(value: StatementValue, location: Location) -> Self {
+    Statement(value, location)
+  }
+
+  pub fn location(&self) -> &Location {
+    &self.1
+  }
+}
 
 impl From<StatementValue> for Statement {
   fn from(value: StatementValue) -> Self {
-    Statement(value)
+    Statement(value, Location::default())
   }
 }
 
@@ -618,7 +618,16 @@ where
       }
 
       '-' => {
-        self.eat_single_char(Token::Minus);
+        let start_pos = self.get_pos();
+        self.next_char();
+        if let Some('>') = self.char0 {
+          self.next_char();
+          let end_pos = self.get_pos();
+          self.emit((start_pos, Token::Arrow, end_pos));
+        } else {
+          let end_pos = self.get_pos();
+          self.emit((start_pos, Token::Minus, end_pos));
+        }
       }
 
       '*' => {
@@ -764,6 +773,10 @@ where
         self.eat_single_char(Token::Comma);
       }
 
+      ':' => {
+        self.eat_single_char(Token::Colon);
+      }
+
       '\n' => {
         let start_pos = self.get_pos();
         self.next_char();
@@ -858,4 +871,19 @@ mod tests {
       token
     );
   }
+
+  #[test]
+  fn test_token_arrow() {
+    let src = "a - b ->";
+    let token = lex_source(&src.to_owned());
+    assert_eq!(
+      vec![
+        Token::Id { name: "a".to_owned() },
+        Token::Minus,
+        Token::Id { name: "b".to_owned() },
+        Token::Arrow
+      ],
+      token
+    );
+  }
 }
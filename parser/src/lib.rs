@@ -26,18 +26,27 @@ pub mod lexer;
 pub mod location;
 pub mod parser;
 pub mod token;
+pub mod visit;
 
 pub mod parse_primary;
 pub mod parse_factor;
 pub mod parse_term;
 pub mod parse_arithmetic_expr;
 pub mod parse_call_expr;
+pub mod parse_list_expr;
+pub mod parse_map_expr;
+pub mod parse_postfix_expr;
 pub mod parse_expression;
 pub mod parse_expression_list;
+pub mod parse_type_annotation;
 pub mod parse_assign_statement;
+pub mod parse_index_assign_statement;
+pub mod parse_field_assign_statement;
 pub mod parse_return_statement;
 pub mod parse_if_stmt;
+pub mod parse_while_stmt;
 pub mod parse_function_stmt;
+pub mod parse_struct_stmt;
 pub mod parse_stmt;
 pub mod parse_stmt_list;
 pub mod parse_program;
@@ -3,7 +3,7 @@
 use std::fmt;
 
 // A location somewhere in the sourcecode
-#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
 pub struct Location {
   row: usize,
   column: usize,
@@ -1,9 +1,10 @@
-use crate::{token::Token, parser::Parser, ast::{AssignStmt, Identifier}, parse_expression::parse_expression};
+use crate::{token::Token, parser::Parser, ast::{AssignStmt, Identifier}, parse_expression::parse_expression, parse_type_annotation::{match_type_annotation, parse_type_annotation}};
 
+// x = ... or x: text = ...
 pub fn match_assign_stmt(parser: &mut Parser) -> bool {
   let token = parser.get_token();
   let next_token = parser.peek_token();
-  token.is_id() && next_token.is_eq()
+  token.is_id() && (next_token.is_eq() || next_token.is_colon())
 }
 
 pub fn parse_assign_stmt(parser: &mut Parser) -> AssignStmt {
@@ -21,15 +22,21 @@ pub fn parse_assign_stmt(parser: &mut Parser) -> AssignStmt {
   };
 
   parser.advance_token();
+  let annotation = if match_type_annotation(parser) {
+    Some(parse_type_annotation(parser))
+  } else {
+    None
+  };
+
   // now cursor point to Equal
-  parser.advance_token();
+  parser.eat_token(Token::Equal);
   // now cursor point to Expressinon
 
   let expr = parse_expression(parser);
 
   parser.eat_token(Token::Semicolon);
 
-  AssignStmt(id, expr)
+  AssignStmt(id, expr, annotation)
 }
 
 
@@ -45,3 +52,12 @@ fn test_parse_assign_stmt() {
   let expr = parse_expression(&mut parser2);
   assert_eq!(assign_stmt.1, expr);
 }
+
+#[test]
+fn test_parse_annotated_assign_stmt() {
+  let code = "greeting: text = \"hi\";";
+  let mut parser = Parser::new(code);
+  let assign_stmt = parse_assign_stmt(&mut parser);
+  assert_eq!(assign_stmt.0, Identifier::from("greeting"));
+  assert_eq!(assign_stmt.2.map(|t| t.to_string()), Some("text".to_owned()));
+}
@@ -3,28 +3,46 @@ use crate::parser::Parser;
 use crate::token::Token;
 use crate::{
   parse_call_expr::parse_call_expr,
+  parse_function_stmt::{match_function_expr, parse_function_stmt},
+  parse_list_expr::{match_list_expr, parse_list_expr},
+  parse_map_expr::{match_map_expr, parse_map_expr},
+  parse_postfix_expr::parse_postfix_expr,
   parse_primary::{match_primary, parse_primary},
 };
 
 pub(crate) fn match_factor(parser: &mut Parser) -> bool {
   let token = parser.get_token();
   // when token matched Identifier, next_token should be +-x/ (, can't be assign statement
-  match_primary(token.clone()) || matches!(token, Token::Id { name: _ })
+  match_primary(token.clone())
+    || matches!(token, Token::Id { name: _ })
+    || match_list_expr(parser)
+    || match_map_expr(parser)
+    || match_function_expr(parser)
 }
 
 pub fn parse_factor(parser: &mut Parser) -> Factor {
   let token = parser.get_token();
-  match token {
+  let factor = match token {
     Token::Id { name } => {
       // is call expression
       if parser.peek_token().is_lpar() {
-        return Factor::from(FactorValue::CallExpr(parse_call_expr(parser)));
+        Factor::from(FactorValue::CallExpr(parse_call_expr(parser)))
+      } else {
+        // identifier
+        parser.advance_token();
+        Factor::from(FactorValue::Identifier(Identifier::from(name.as_str())))
       }
-
-      // identifier
-      parser.advance_token();
-      return Factor::from(FactorValue::Identifier(Identifier::from(name.as_str())));
+    }
+    Token::LBracket => Factor::from(FactorValue::List(parse_list_expr(parser))),
+    Token::LBrace => Factor::from(FactorValue::Map(parse_map_expr(parser))),
+    Token::Function => {
+      let location = parser.get_location();
+      let function = parse_function_stmt(parser);
+      Factor::from(FactorValue::Function(Box::new(function), location))
     }
     _ => Factor::from(FactorValue::Primary(parse_primary(parser))),
-  }
+  };
+
+  // scores[0], p.x
+  parse_postfix_expr(parser, factor)
 }
@@ -0,0 +1,27 @@
+use crate::{
+  ast::{FieldAssignStmt, FieldExpr},
+  parse_expression::parse_expression,
+  parser::Parser,
+  token::Token,
+};
+
+pub fn parse_field_assign_stmt(parser: &mut Parser, target: FieldExpr) -> FieldAssignStmt {
+  parser.eat_token(Token::Equal);
+  let expr = parse_expression(parser);
+  parser.eat_token(Token::Semicolon);
+
+  FieldAssignStmt::new(target, expr)
+}
+
+#[test]
+fn test_parse_field_assign_stmt() {
+  use crate::{ast::{FactorValue, Identifier}, parse_factor::parse_factor};
+
+  let mut parser = Parser::new("points[0].x = 3;");
+  let stmt = match parse_factor(&mut parser).0 {
+    FactorValue::Field(target) => parse_field_assign_stmt(&mut parser, target),
+    factor => panic!("expect field expr, but found {:?}", factor),
+  };
+  assert_eq!(stmt.0 .1, Identifier::from("x"));
+  assert!(stmt.0 .0 .0.is_index());
+}
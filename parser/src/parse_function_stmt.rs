@@ -1,10 +1,15 @@
-use crate::{parser::Parser, ast::{FunctionStmt, Identifier, StmtList}, token::Token, parse_stmt_list::parse_stmt_list};
+use crate::{parser::Parser, ast::{FunctionStmt, Identifier, Param, StmtList}, token::Token, parse_stmt_list::parse_stmt_list, parse_type_annotation::{match_type_annotation, match_return_annotation, parse_type_annotation}};
 
 
 pub fn match_function_stmt(parser: &mut Parser) -> bool {
   parser.get_token().is_keyword_function()
 }
 
+// fn(x) { ... } used as a value
+pub fn match_function_expr(parser: &mut Parser) -> bool {
+  parser.get_token().is_keyword_function() && parser.peek_token().is_lpar()
+}
+
 fn eat_token(parser: &mut Parser, token: Token) {
   let curr_token = parser.get_token();
   if curr_token.to_string() != token.to_string() {
@@ -15,38 +20,60 @@ fn eat_token(parser: &mut Parser, token: Token) {
 }
 
 pub fn parse_function_stmt(parser: &mut Parser) -> FunctionStmt {
+  let location = parser.get_location();
   eat_token(parser, Token::Function);
   
   let fun_name = if let Token::Id { name } = parser.get_token() {
+    parser.advance_token();
     Identifier::from(name.as_str())
+  } else if parser.get_token().is_lpar() {
+    // an anonymous function can't be called by name, so it gets a name no identifier has
+    Identifier::from(format!("fn@{}:{}", location.row(), location.column()).as_str())
   } else {
     panic!("parse funtion name error: mission function name")
   };
   
-  parser.advance_token();
   eat_token(parser, Token::LPar);
-  let mut params: Vec<Identifier> = vec![];
+  let mut params: Vec<Param> = vec![];
 
   let mut token = parser.get_token();
   while token.is_id() {
-    if let Token::Id { name } = token {
-      params.push(Identifier::from(name.as_str()));
-    }
+    let name = if let Token::Id { name } = token {
+      Identifier::from(name.as_str())
+    } else {
+      unreachable!()
+    };
+    parser.advance_token();
+
+    // name: number
+    let annotation = if match_type_annotation(parser) {
+      Some(parse_type_annotation(parser))
+    } else {
+      None
+    };
+    params.push(Param(name, annotation));
+
     // ,
-    token = parser.advance_token();
+    token = parser.get_token();
     if token.is_comma() {
       token = parser.advance_token();
     }
   }
 
   eat_token(parser, Token::RPar);
+  // -> number
+  let return_type = if match_return_annotation(parser) {
+    Some(parse_type_annotation(parser))
+  } else {
+    None
+  };
   eat_token(parser, Token::LBrace);
 
   // TODO: parse statement list
   let stmt_list = parse_stmt_list(parser);
   eat_token(parser, Token::RBrace);
 
-  FunctionStmt::new(fun_name, params, stmt_list)
+  FunctionStmt::new(fun_name, params, stmt_list, return_type)
 }
 
 
@@ -59,3 +86,26 @@ fn test_function_stmt() {
 
   assert_eq!(fn_stmt.0.to_string(), "foo");
 }
+
+#[test]
+fn test_function_stmt_annotations() {
+  let code = "fn area(w: number, h) -> number { return w * h; }";
+  let mut parser = Parser::new(code);
+  let fn_stmt = parse_function_stmt(&mut parser);
+
+  assert_eq!(fn_stmt.1.len(), 2);
+  assert_eq!(fn_stmt.1[0].1.as_ref().map(|t| t.to_string()), Some("number".to_owned()));
+  assert_eq!(fn_stmt.1[1], Param::from("h"));
+  assert_eq!(fn_stmt.3.map(|t| t.to_string()), Some("number".to_owned()));
+}
+
+#[test]
+fn test_function_expr() {
+  let code = "fn(x) { return x; }";
+  let mut parser = Parser::new(code);
+  assert!(match_function_expr(&mut parser));
+  let fn_stmt = parse_function_stmt(&mut parser);
+
+  assert_eq!(fn_stmt.0.to_string(), "fn@1:1");
+  assert_eq!(fn_stmt.1, vec![Param::from("x")]);
+}
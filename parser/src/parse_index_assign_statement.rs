@@ -0,0 +1,35 @@
+use crate::{
+  ast::{IndexAssignStmt, IndexExpr},
+  parse_expression::parse_expression,
+  parser::Parser,
+  token::Token,
+};
+
+// `scores[1] = 5;` and `p.x = 3;` both start with a postfix expression, so the
+// target is parsed first by parse_stmt and the statement is finished here.
+pub fn match_postfix_assign_stmt(parser: &mut Parser) -> bool {
+  let next_token = parser.peek_token();
+  parser.get_token().is_id() && (next_token.is_lbracket() || next_token.is_dot())
+}
+
+pub fn parse_index_assign_stmt(parser: &mut Parser, target: IndexExpr) -> IndexAssignStmt {
+  parser.eat_token(Token::Equal);
+  let expr = parse_expression(parser);
+  parser.eat_token(Token::Semicolon);
+
+  IndexAssignStmt::new(target, expr)
+}
+
+#[test]
+fn test_parse_index_assign_stmt() {
+  use crate::{ast::FactorValue, parse_factor::parse_factor};
+
+  let mut parser = Parser::new("scores[1] = 5;");
+  let stmt = match parse_factor(&mut parser).0 {
+    FactorValue::Index(target) => parse_index_assign_stmt(&mut parser, target),
+    factor => panic!("expect index expr, but found {:?}", factor),
+  };
+
+  let mut parser2 = Parser::new("5");
+  assert_eq!(stmt.1, parse_expression(&mut parser2));
+}
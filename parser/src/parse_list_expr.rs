@@ -0,0 +1,27 @@
+use crate::{
+  ast::ListExpr, parse_expression_list::parse_expression_list, parser::Parser, token::Token,
+};
+
+pub fn match_list_expr(parser: &mut Parser) -> bool {
+  parser.get_token().is_lbracket()
+}
+
+pub fn parse_list_expr(parser: &mut Parser) -> ListExpr {
+  if !match_list_expr(parser) {
+    panic!("parse list expr error, unexpected token {:?}", parser.get_token());
+  }
+
+  parser.eat_token(Token::LBracket);
+  let items = parse_expression_list(parser);
+  parser.eat_token(Token::RBracket);
+
+  ListExpr::new(items)
+}
+
+#[test]
+fn test_parse_list_expr() {
+  let mut parser = Parser::new("[1, \"two\", [3]] name");
+  let list_expr = parse_list_expr(&mut parser);
+  assert_eq!(list_expr.0 .0.len(), 3);
+  assert_eq!(parser.get_token(), Token::Id { name: "name".to_owned() });
+}
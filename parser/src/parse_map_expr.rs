@@ -0,0 +1,50 @@
+use crate::{
+  ast::{MapEntry, MapExpr},
+  parse_expression::{match_expression, parse_expression},
+  parser::Parser,
+  token::Token,
+};
+
+// A `{` can only start a map when an expression is expected; blocks follow
+// `program`, `fn name(...)` and `if (...)`, where we never parse an expression.
+pub fn match_map_expr(parser: &mut Parser) -> bool {
+  parser.get_token().is_lbrace()
+}
+
+fn parse_map_entry(parser: &mut Parser) -> MapEntry {
+  let key = parse_expression(parser);
+  parser.eat_token(Token::Colon);
+  let value = parse_expression(parser);
+
+  MapEntry(key, value)
+}
+
+pub fn parse_map_expr(parser: &mut Parser) -> MapExpr {
+  if !match_map_expr(parser) {
+    panic!("parse map expr error, unexpected token {:?}", parser.get_token());
+  }
+
+  parser.eat_token(Token::LBrace);
+  let mut entries: Vec<MapEntry> = vec![];
+  if match_expression(parser) {
+    entries.push(parse_map_entry(parser));
+    while parser.get_token().is_comma() {
+      parser.eat_token(Token::Comma);
+      entries.push(parse_map_entry(parser));
+    }
+  }
+  parser.eat_token(Token::RBrace);
+
+  MapExpr::new(entries)
+}
+
+#[test]
+fn test_parse_map_expr() {
+  let mut parser = Parser::new("{\"tom\": 8, \"amy\": 4 + 5} name");
+  let map_expr = parse_map_expr(&mut parser);
+  assert_eq!(map_expr.0.len(), 2);
+
+  let mut parser2 = Parser::new("4 + 5");
+  assert_eq!(map_expr.0[1].1, parse_expression(&mut parser2));
+  assert_eq!(parser.get_token(), Token::Id { name: "name".to_owned() });
+}
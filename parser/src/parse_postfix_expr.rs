@@ -0,0 +1,92 @@
+use crate::{
+  ast::{ApplyExpr, Factor, FactorValue, FieldExpr, Identifier, IndexExpr},
+  parse_expression::parse_expression,
+  parse_expression_list::parse_expression_list,
+  parser::Parser,
+  token::Token,
+};
+
+pub fn match_postfix_expr(parser: &mut Parser) -> bool {
+  let token = parser.get_token();
+  token.is_lbracket() || token.is_dot() || token.is_lpar()
+}
+
+// parse the `[index]`, `.field` and `(args)` suffixes following an already parsed
+// factor, like `scores[0]`, `p.x`, `points[0].x` or `make_adder(2)(3)`
+pub fn parse_postfix_expr(parser: &mut Parser, target: Factor) -> Factor {
+  let mut factor = target;
+  while match_postfix_expr(parser) {
+    if parser.get_token().is_lpar() {
+      parser.eat_token(Token::LPar);
+      let args = parse_expression_list(parser);
+      parser.eat_token(Token::RPar);
+
+      factor = Factor::from(FactorValue::Apply(ApplyExpr::new(factor, args)));
+      continue;
+    }
+
+    if parser.get_token().is_dot() {
+      parser.eat_token(Token::Dot);
+      let field = match parser.get_token() {
+        Token::Id { name } => Identifier::from(name.as_str()),
+        token => panic!("parse field expr error, expect field name, but found {:?}", token),
+      };
+      parser.advance_token();
+
+      factor = Factor::from(FactorValue::Field(FieldExpr::new(factor, field)));
+      continue;
+    }
+
+    parser.eat_token(Token::LBracket);
+    let index = parse_expression(parser);
+    parser.eat_token(Token::RBracket);
+
+    factor = Factor::from(FactorValue::Index(IndexExpr::new(factor, index)));
+  }
+
+  factor
+}
+
+#[test]
+fn test_parse_index_expr() {
+  use crate::parse_factor::parse_factor;
+
+  let mut parser = Parser::new("grid[1][x + 1]");
+  let factor = parse_factor(&mut parser);
+  match factor.0 {
+    FactorValue::Index(IndexExpr(target, _)) => assert!(target.0.is_index()),
+    _ => panic!("expect index expr, but found {:?}", factor),
+  }
+}
+
+#[test]
+fn test_parse_field_expr() {
+  use crate::parse_factor::parse_factor;
+
+  let mut parser = Parser::new("points[0].x * 2");
+  let factor = parse_factor(&mut parser);
+  match factor.0 {
+    FactorValue::Field(FieldExpr(target, field)) => {
+      assert!(target.0.is_index());
+      assert_eq!(field, Identifier::from("x"));
+    }
+    _ => panic!("expect field expr, but found {:?}", factor),
+  }
+  assert_eq!(parser.get_token(), Token::Star);
+}
+
+#[test]
+fn test_parse_apply_expr() {
+  use crate::parse_factor::parse_factor;
+
+  let mut parser = Parser::new("fs[0](4).f(1)(2, 3)");
+  let factor = parse_factor(&mut parser);
+  let FactorValue::Apply(ApplyExpr(target, args)) = factor.0 else {
+    panic!("expect apply expr, but found {:?}", factor);
+  };
+  assert_eq!(args.0.len(), 2);
+  let FactorValue::Apply(ApplyExpr(target, _)) = &target.0 else {
+    panic!("expect apply expr, but found {:?}", target);
+  };
+  assert!(target.0.is_field());
+}
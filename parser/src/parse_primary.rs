@@ -5,7 +5,7 @@ use crate::parser::Parser;
 use crate::token::Token;
 
 pub(crate) fn match_primary(token: Token) -> bool {
-  token.is_number() || token.is_string()
+  token.is_number() || token.is_string() || token.is_bool()
 }
 
 pub fn parse_primary(parser: &mut Parser) -> Primary {
@@ -21,11 +21,12 @@ pub fn parse_primary(parser: &mut Parser) -> Primary {
       if number_type.is_int() {
         PrimaryValue::Number(int.to_f64().unwrap())
       } else {
-        PrimaryValue::Number(*float)
+        PrimaryValue::Float(*float)
       }
     }
 
     Token::String { value } => PrimaryValue::String(value.to_string()),
+    Token::Bool { value } => PrimaryValue::Bool(*value),
     _ => panic!("Mismatched token {:?}", *ref_token),
   };
 
@@ -1,42 +1,81 @@
-use crate::{parser::Parser, ast::{Statement, StatementValue}, parse_if_stmt::parse_if_stmt, parse_function_stmt::parse_function_stmt, parse_return_statement::parse_return_stmt, parse_assign_statement::parse_assign_stmt, parse_call_expr::parse_call_expr, token::Token};
+use crate::{parser::Parser, ast::{Statement, StatementValue, FactorValue}, parse_if_stmt::parse_if_stmt, parse_while_stmt::parse_while_stmt, parse_function_stmt::parse_function_stmt, parse_struct_stmt::parse_struct_stmt, parse_return_statement::parse_return_stmt, parse_assign_statement::{match_assign_stmt, parse_assign_stmt}, parse_index_assign_statement::{match_postfix_assign_stmt, parse_index_assign_stmt}, parse_field_assign_statement::parse_field_assign_stmt, parse_factor::parse_factor, token::Token};
 
 pub fn match_parse_stmt(parser: &mut Parser) -> bool {
   let token = parser.get_token();
   let next_token = parser.peek_token();
 
-  return token.is_keyword_if() || token.is_keyword_function() || token.is_keyword_return() || (token.is_id() && (next_token.is_eq() || next_token.is_lpar()))
+  return token.is_keyword_if() || token.is_keyword_while() || token.is_keyword_function() || token.is_keyword_struct() || token.is_keyword_return() || match_assign_stmt(parser) || (token.is_id() && next_token.is_lpar()) || match_postfix_assign_stmt(parser)
 }
 
 pub fn parse_stmt(parser: &mut Parser) -> Statement {
-    // assign stmt:   id = 
+    // assign stmt:   id = or id :
     // call stmt:  id (
     // return stmt: return 
     // function stmt: function 
     // if stmt: if (
+    // while stmt: while (
+    // struct stmt: struct
+    // index assign stmt: id [
+    // field assign stmt: id .
+    // apply stmt: id ( ... ) (, id [ ... ] (, id . ... (
     if !match_parse_stmt(parser) {
       panic!("parse_stmt error, dont match statement token");
     }
 
     let token = parser.get_token();
     let next_token = parser.peek_token();
+    let location = parser.get_location();
 
     if token.is_keyword_if() {
-      return Statement::from(StatementValue::IfStmt(parse_if_stmt(parser)));
+      return Statement::new(StatementValue::IfStmt(parse_if_stmt(parser)), location);
+
+    } else if token.is_keyword_while() {
+      return Statement::new(StatementValue::WhileStmt(parse_while_stmt(parser)), location);
 
     } else if token.is_keyword_function() {
-        return Statement::from(StatementValue::FunctionStmt(parse_function_stmt(parser)));
+        return Statement::new(StatementValue::FunctionStmt(parse_function_stmt(parser)), location);
+
+    } else if token.is_keyword_struct() {
+        return Statement::new(StatementValue::StructStmt(parse_struct_stmt(parser)), location);
 
     } else if token.is_keyword_return() {
-      return Statement::from(StatementValue::ReturnStmt(parse_return_stmt(parser)));
-
-    } else if token.is_id() && next_token.is_eq() {
-      return Statement::from(StatementValue::AssignStmt(parse_assign_stmt(parser)));
-      
-    } else if token.is_id() && next_token.is_lpar() {
-      let expr = parse_call_expr(parser);
-      parser.eat_token(Token::Semicolon);
-      return Statement::from(StatementValue::CallStmt(expr));
+      return Statement::new(StatementValue::ReturnStmt(parse_return_stmt(parser)), location);
+
+    } else if match_assign_stmt(parser) {
+      return Statement::new(StatementValue::AssignStmt(parse_assign_stmt(parser)), location);
+
+    } else if match_postfix_assign_stmt(parser) || (token.is_id() && next_token.is_lpar()) {
+      let value = match parse_factor(parser).0 {
+        FactorValue::Index(target) => StatementValue::IndexAssignStmt(parse_index_assign_stmt(parser, target)),
+        FactorValue::Field(target) => StatementValue::FieldAssignStmt(parse_field_assign_stmt(parser, target)),
+        FactorValue::CallExpr(expr) => {
+          parser.eat_token(Token::Semicolon);
+          StatementValue::CallStmt(expr)
+        }
+        FactorValue::Apply(expr) => {
+          parser.eat_token(Token::Semicolon);
+          StatementValue::ApplyStmt(expr)
+        }
+        factor => panic!("parse stmt error, can't assign to {:?}", factor),
+      };
+      return Statement::new(value, location);
     }
     
     panic!("parse stmt error, token is {}", token.to_string());
 }
+
+#[test]
+fn test_parse_stmt_location() {
+  let mut parser = Parser::new("// scores\n\n  scores[1] = 5;");
+  let stmt = parse_stmt(&mut parser);
+  assert!(matches!(stmt.0, StatementValue::IndexAssignStmt(_)));
+  assert_eq!(stmt.location().row(), 3);
+  assert_eq!(stmt.location().column(), 3);
+}
+
+#[test]
+fn test_parse_apply_stmt() {
+  let mut parser = Parser::new("make_adder(2)(3); fs[0](4);");
+  assert!(matches!(parse_stmt(&mut parser).0, StatementValue::ApplyStmt(_)));
+  assert!(matches!(parse_stmt(&mut parser).0, StatementValue::ApplyStmt(_)));
+}
@@ -0,0 +1,47 @@
+use crate::{
+  ast::{Identifier, StructStmt},
+  parser::Parser,
+  token::Token,
+};
+
+pub fn match_struct_stmt(parser: &mut Parser) -> bool {
+  parser.get_token().is_keyword_struct()
+}
+
+pub fn parse_struct_stmt(parser: &mut Parser) -> StructStmt {
+  if !match_struct_stmt(parser) {
+    panic!("parse struct statement error: missing struct keyword");
+  }
+  parser.eat_token(Token::Struct);
+
+  let struct_name = match parser.get_token() {
+    Token::Id { name } => Identifier::from(name.as_str()),
+    token => panic!("parse struct statement error: expect struct name, but found {:?}", token),
+  };
+  parser.advance_token();
+  parser.eat_token(Token::LBrace);
+
+  let mut fields: Vec<Identifier> = vec![];
+  let mut token = parser.get_token();
+  while let Token::Id { name } = token {
+    fields.push(Identifier::from(name.as_str()));
+    // ,
+    token = parser.advance_token();
+    if token.is_comma() {
+      token = parser.advance_token();
+    }
+  }
+
+  parser.eat_token(Token::RBrace);
+
+  StructStmt::new(struct_name, fields)
+}
+
+#[test]
+fn test_parse_struct_stmt() {
+  let mut parser = Parser::new("struct Point { x, y } p = Point(1, 2);");
+  let struct_stmt = parse_struct_stmt(&mut parser);
+  assert_eq!(struct_stmt.0, Identifier::from("Point"));
+  assert_eq!(struct_stmt.1, vec![Identifier::from("x"), Identifier::from("y")]);
+  assert_eq!(parser.get_token(), Token::Id { name: "p".to_owned() });
+}
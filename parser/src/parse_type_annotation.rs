@@ -0,0 +1,38 @@
+use crate::{parser::Parser, ast::{Identifier, TypeAnnotation}, token::Token};
+
+// `: number` after a variable or a parameter
+pub fn match_type_annotation(parser: &mut Parser) -> bool {
+  parser.get_token().is_colon()
+}
+
+// `-> number` after the parameters of a function
+pub fn match_return_annotation(parser: &mut Parser) -> bool {
+  parser.get_token().is_arrow()
+}
+
+// parse `: name` or `-> name`, the type name itself is checked by the analyzer
+pub fn parse_type_annotation(parser: &mut Parser) -> TypeAnnotation {
+  let token = parser.get_token();
+  if !token.is_colon() && !token.is_arrow() {
+    panic!("parse type annotation error, expect `:` or `->`, but found {}", token);
+  }
+
+  let name = match parser.advance_token() {
+    Token::Id { name } => Identifier::from(name.as_str()),
+    token => panic!("parse type annotation error, expect a type name, but found {}", token),
+  };
+  parser.advance_token();
+
+  TypeAnnotation(name)
+}
+
+#[test]
+fn test_parse_type_annotation() {
+  let mut parser = Parser::new(": number =");
+  let annotation = parse_type_annotation(&mut parser);
+  assert_eq!(annotation, TypeAnnotation(Identifier::from("number")));
+  assert!(parser.get_token().is_eq());
+
+  let mut parser = Parser::new("-> text {");
+  assert_eq!(parse_type_annotation(&mut parser).to_string(), "text");
+}
@@ -0,0 +1,65 @@
+use crate::{
+  ast::WhileStmt,
+  parse_logic_expr::parse_logic_expr,
+  parse_stmt_list::parse_stmt_list,
+  parser::Parser,
+  token::Token,
+};
+
+pub fn match_while_stmt(parser: &mut Parser) -> bool {
+  let token = parser.get_token();
+  token.is_keyword_while()
+}
+
+pub fn parse_while_stmt(parser: &mut Parser) -> WhileStmt {
+  if !match_while_stmt(parser) {
+    panic!("parse while statement error: missing WHILE keyword");
+  }
+
+  parser.advance_token();
+  // now cursor point to (
+  if !parser.get_token().is_lpar() {
+    panic!("parse while statement error: missing LPAR('(')");
+  }
+
+  parser.advance_token();
+  let expr = parse_logic_expr(parser);
+
+  // now cursor point to )
+  if !parser.get_token().is_rpar() {
+    panic!("parse while statement error: missing RPAR(')')");
+  }
+
+  parser.advance_token();
+  // now cursor point to lbrace {
+  if !parser.get_token().is_lbrace() {
+    panic!("parse while statement error: missing lbrace {}", "{");
+  }
+
+  parser.advance_token();
+
+  let stmt_list = parse_stmt_list(parser);
+
+  // now cursor point to rbrace }
+  if !parser.get_token().is_rbrace() {
+    panic!("parse while statement error: missing rbrace {}", "}")
+  }
+
+  parser.eat_token(Token::RBrace);
+  WhileStmt::new(expr, stmt_list)
+}
+
+#[test]
+fn test_while_stmt() {
+  let code = "while (i < 10) { println(i); i = i + 1; }";
+  let mut parser = Parser::new(code);
+  let while_stmt = parse_while_stmt(&mut parser);
+
+  let mut parser2 = Parser::new("i < 10");
+  let expr = parse_logic_expr(&mut parser2);
+  assert_eq!(while_stmt.0, expr);
+  // println(i); and i = i + 1;
+  let body = while_stmt.1.unwrap();
+  assert!(body.0.is_some());
+  assert!(body.1.unwrap().0.is_some());
+}
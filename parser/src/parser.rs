@@ -1,4 +1,5 @@
 use crate::lexer::{LexResult, Lexer, LineContinationHandler, NewlineHandler};
+use crate::location::Location;
 use crate::token::Token;
 use std::str::Chars;
 
@@ -7,61 +8,72 @@ pub struct Parser<'a> {
   lex: Lexer<LineContinationHandler<NewlineHandler<Chars<'a>>>>,
   next_token: Token,
   nnext_token: Token,
+  next_location: Location,
+  nnext_location: Location,
 }
 
-fn advance_token(lex: &mut Lexer<LineContinationHandler<NewlineHandler<Chars>>>) -> Token {
+fn advance_token(
+  lex: &mut Lexer<LineContinationHandler<NewlineHandler<Chars>>>,
+) -> (Token, Location) {
   let result: Option<LexResult> = lex.next();
   // println!("Option<LexResult>: {:?}", result);
   // println!("next_token <<<<<<<<<<<<<<<<<");
 
   if result.is_none() {
-    return Token::None;
+    return (Token::None, Location::default());
   }
   let result = result.unwrap();
   match result {
-    Ok(v) => v.1,
-    Err(_) => Token::None,
+    Ok(v) => (v.1, v.0),
+    Err(e) => (Token::None, e.location),
   }
 }
 
+fn advance_significant_token(
+  lex: &mut Lexer<LineContinationHandler<NewlineHandler<Chars>>>,
+) -> (Token, Location) {
+  let mut next = advance_token(lex);
+  while next.0.is_newline() || next.0.is_comment() {
+    next = advance_token(lex);
+  }
+  next
+}
+
 impl<'a> Parser<'a> {
   pub fn new(source: &'a str) -> Self {
     let nlh = NewlineHandler::new(source.chars());
     let lch = LineContinationHandler::new(nlh);
     let mut lex = Lexer::new(lch);
 
-    let mut next_token = advance_token(&mut lex);
-    while next_token.is_newline() || next_token.is_comment() {
-      next_token = advance_token(&mut lex);
-    }
-    
-    let mut next_next_token = advance_token(&mut lex);
-    while next_next_token.is_newline() || next_next_token.is_comment() {
-      next_next_token = advance_token(&mut lex);
-    }
+    let (next_token, next_location) = advance_significant_token(&mut lex);
+    let (next_next_token, next_next_location) = advance_significant_token(&mut lex);
 
     Parser {
       lex,
       next_token,
       nnext_token: next_next_token,
+      next_location,
+      nnext_location: next_next_location,
     }
   }
 
   // get next token and advances the current token
   pub fn advance_token(&mut self) -> Token {
     self.next_token = self.nnext_token.clone();
-    while self.next_token.is_newline() || self.next_token.is_comment() {
-      self.next_token = advance_token(&mut self.lex);
-    }
+    self.next_location = self.nnext_location.clone();
 
-    self.nnext_token = advance_token(&mut self.lex);
-    while self.nnext_token.is_newline() || self.nnext_token.is_comment() {
-      self.nnext_token = advance_token(&mut self.lex);
-    }
+    let (nnext_token, nnext_location) = advance_significant_token(&mut self.lex);
+    self.nnext_token = nnext_token;
+    self.nnext_location = nnext_location;
 
     self.get_token()
   }
 
+  // get the source location of current token
+  pub fn get_location(&self) -> Location {
+    self.next_location.clone()
+  }
+
   // get current token
   pub fn get_token(&mut self) -> Token {
     self.next_token.clone()
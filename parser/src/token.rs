@@ -97,6 +97,8 @@ pub enum Token {
   Dot,         // .
   Semicolon,   // ;
   Comma,       // ,
+  Colon,       // :
+  Arrow,       // ->
 }
 
 impl Token {
@@ -115,6 +117,10 @@ impl Token {
     matches!(self, Token::String { value: _ })
   }
 
+  pub fn is_bool(&self) -> bool {
+    matches!(self, Token::Bool { value: _ })
+  }
+
   pub fn is_star(&self) -> bool {
     matches!(self, Token::Star)
   }
@@ -139,6 +145,14 @@ impl Token {
     matches!(self, Token::RPar)
   }
 
+  pub fn is_lbracket(&self) -> bool {
+    matches!(self, Token::LBracket)
+  }
+
+  pub fn is_rbracket(&self) -> bool {
+    matches!(self, Token::RBracket)
+  }
+
   pub fn is_lbrace(&self) -> bool {
     matches!(self, Token::LBrace)
   }
@@ -155,6 +169,18 @@ impl Token {
     matches!(self, Token::Comma)
   }
 
+  pub fn is_dot(&self) -> bool {
+    matches!(self, Token::Dot)
+  }
+
+  pub fn is_colon(&self) -> bool {
+    matches!(self, Token::Colon)
+  }
+
+  pub fn is_arrow(&self) -> bool {
+    matches!(self, Token::Arrow)
+  }
+
   pub fn is_eq(&self) -> bool {
     matches!(self, Token::Equal)
   }
@@ -171,6 +197,14 @@ impl Token {
     matches!(self, Token::If)
   }
 
+  pub fn is_keyword_while(&self) -> bool {
+    matches!(self, Token::While)
+  }
+
+  pub fn is_keyword_struct(&self) -> bool {
+    matches!(self, Token::Struct)
+  }
+
   pub fn is_keyword_function(&self) -> bool {
     matches!(self, Token::Function)
   }
@@ -268,6 +302,8 @@ impl fmt::Display for Token {
       Dot => f.write_str("."),
       Semicolon => f.write_str(";"),
       Comma => f.write_str(","),
+      Colon => f.write_str(":"),
+      Arrow => f.write_str("->"),
       Function => f.write_str("FUNCTION"),
       Program => f.write_str("MAIN"),
       Not => f.write_str("!"),
@@ -331,6 +367,8 @@ pub fn get_token_kind(token: Token) -> String {
     Dot => "Dot".to_owned(),
     Semicolon => "Semicolon".to_owned(),
     Comma => "Comma".to_owned(),
+    Colon => "Colon".to_owned(),
+    Arrow => "Arrow".to_owned(),
     Function => "FUNCTION".to_owned(),
     Program => "MAIN".to_owned(),
     Not => "Not".to_owned(),
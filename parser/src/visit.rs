@@ -0,0 +1,697 @@
+//! Traversal of the AST.
+//!
+//! `Visitor` reads the tree and `VisitorMut` rewrites it in place. Every
+//! `visit_*` method walks the children of its node by default, through the
+//! matching `walk_*` function, so an implementation only overrides the nodes
+//! it cares about and calls `walk_*` itself to keep going below them.
+//!
+//! ```
+//! use parser::ast::CallExpr;
+//! use parser::visit::{walk_call_expr, Visitor};
+//! use parser::{parse_program::parse_program, parser::Parser};
+//!
+//! // the names of the functions called in a program
+//! struct Calls(Vec<String>);
+//!
+//! impl Visitor for Calls {
+//!   fn visit_call_expr(&mut self, call_expr: &CallExpr) {
+//!     self.0.push(call_expr.0.to_string());
+//!     walk_call_expr(self, call_expr);
+//!   }
+//! }
+//!
+//! let program = parse_program(&mut Parser::new("program { fn main() { println(len([1])); } }"));
+//! let mut calls = Calls(vec![]);
+//! calls.visit_program(&program);
+//! assert_eq!(calls.0, vec!["println", "len"]);
+//! ```
+
+use crate::ast::{
+  ApplyExpr, ArithmeticExpr, AssignStmt, CallExpr, CompareExpr, ComponentArithmeticExpr,
+  ComponentFactor, ComponentFactorValue, ComponentTerm, Expression, ExpressionValue, Factor,
+  FactorValue, FieldAssignStmt, FieldExpr, FunctionStmt, Identifier, IfStmt, IndexAssignStmt,
+  IndexExpr, ListExpr, LogicExpr, MapExpr, Param, Primary, Program, ReturnStmt, Statement,
+  StatementValue, StmtList, StructStmt, Term, WhileStmt,
+};
+
+// a read-only traversal, in source order
+pub trait Visitor {
+  fn visit_program(&mut self, program: &Program) {
+    walk_program(self, program);
+  }
+
+  fn visit_stmt_list(&mut self, stmt_list: &StmtList) {
+    walk_stmt_list(self, stmt_list);
+  }
+
+  fn visit_stmt(&mut self, stmt: &Statement) {
+    walk_stmt(self, stmt);
+  }
+
+  fn visit_assign_stmt(&mut self, stmt: &AssignStmt) {
+    walk_assign_stmt(self, stmt);
+  }
+
+  fn visit_index_assign_stmt(&mut self, stmt: &IndexAssignStmt) {
+    walk_index_assign_stmt(self, stmt);
+  }
+
+  fn visit_field_assign_stmt(&mut self, stmt: &FieldAssignStmt) {
+    walk_field_assign_stmt(self, stmt);
+  }
+
+  fn visit_return_stmt(&mut self, stmt: &ReturnStmt) {
+    walk_return_stmt(self, stmt);
+  }
+
+  fn visit_if_stmt(&mut self, stmt: &IfStmt) {
+    walk_if_stmt(self, stmt);
+  }
+
+  fn visit_while_stmt(&mut self, stmt: &WhileStmt) {
+    walk_while_stmt(self, stmt);
+  }
+
+  // named functions, and anonymous ones used as values
+  fn visit_function_stmt(&mut self, stmt: &FunctionStmt) {
+    walk_function_stmt(self, stmt);
+  }
+
+  fn visit_param(&mut self, param: &Param) {
+    self.visit_identifier(&param.0);
+  }
+
+  fn visit_struct_stmt(&mut self, stmt: &StructStmt) {
+    walk_struct_stmt(self, stmt);
+  }
+
+  fn visit_logic_expr(&mut self, expr: &LogicExpr) {
+    walk_logic_expr(self, expr);
+  }
+
+  fn visit_compare_expr(&mut self, expr: &CompareExpr) {
+    walk_compare_expr(self, expr);
+  }
+
+  fn visit_expr(&mut self, expr: &Expression) {
+    walk_expr(self, expr);
+  }
+
+  fn visit_component_arithmetic_expr(&mut self, expr: &ComponentArithmeticExpr) {
+    walk_component_arithmetic_expr(self, expr);
+  }
+
+  fn visit_component_term(&mut self, term: &ComponentTerm) {
+    walk_component_term(self, term);
+  }
+
+  fn visit_component_factor(&mut self, factor: &ComponentFactor) {
+    walk_component_factor(self, factor);
+  }
+
+  fn visit_arithmetic_expr(&mut self, expr: &ArithmeticExpr) {
+    walk_arithmetic_expr(self, expr);
+  }
+
+  fn visit_term(&mut self, term: &Term) {
+    walk_term(self, term);
+  }
+
+  fn visit_factor(&mut self, factor: &Factor) {
+    walk_factor(self, factor);
+  }
+
+  fn visit_call_expr(&mut self, call_expr: &CallExpr) {
+    walk_call_expr(self, call_expr);
+  }
+
+  fn visit_list_expr(&mut self, list_expr: &ListExpr) {
+    walk_list_expr(self, list_expr);
+  }
+
+  fn visit_map_expr(&mut self, map_expr: &MapExpr) {
+    walk_map_expr(self, map_expr);
+  }
+
+  fn visit_index_expr(&mut self, index_expr: &IndexExpr) {
+    walk_index_expr(self, index_expr);
+  }
+
+  fn visit_field_expr(&mut self, field_expr: &FieldExpr) {
+    walk_field_expr(self, field_expr);
+  }
+
+  fn visit_apply_expr(&mut self, apply_expr: &ApplyExpr) {
+    walk_apply_expr(self, apply_expr);
+  }
+
+  fn visit_primary(&mut self, _primary: &Primary) {}
+
+  // every identifier: names read and called, but also the ones being defined
+  fn visit_identifier(&mut self, _identifier: &Identifier) {}
+}
+
+pub fn walk_program<V: Visitor + ?Sized>(visitor: &mut V, program: &Program) {
+  visitor.visit_stmt_list(&program.0);
+}
+
+pub fn walk_stmt_list<V: Visitor + ?Sized>(visitor: &mut V, stmt_list: &StmtList) {
+  let mut rest = Some(stmt_list);
+  while let Some(StmtList(stmt, next)) = rest {
+    if let Some(stmt) = stmt {
+      visitor.visit_stmt(stmt);
+    }
+    rest = next.as_deref();
+  }
+}
+
+pub fn walk_stmt<V: Visitor + ?Sized>(visitor: &mut V, stmt: &Statement) {
+  match &stmt.0 {
+    StatementValue::AssignStmt(stmt) => visitor.visit_assign_stmt(stmt),
+    StatementValue::IndexAssignStmt(stmt) => visitor.visit_index_assign_stmt(stmt),
+    StatementValue::FieldAssignStmt(stmt) => visitor.visit_field_assign_stmt(stmt),
+    StatementValue::CallStmt(call_expr) => visitor.visit_call_expr(call_expr),
+    StatementValue::ApplyStmt(apply_expr) => visitor.visit_apply_expr(apply_expr),
+    StatementValue::ReturnStmt(stmt) => visitor.visit_return_stmt(stmt),
+    StatementValue::FunctionStmt(stmt) => visitor.visit_function_stmt(stmt),
+    StatementValue::StructStmt(stmt) => visitor.visit_struct_stmt(stmt),
+    StatementValue::IfStmt(stmt) => visitor.visit_if_stmt(stmt),
+    StatementValue::WhileStmt(stmt) => visitor.visit_while_stmt(stmt),
+  }
+}
+
+pub fn walk_assign_stmt<V: Visitor + ?Sized>(visitor: &mut V, stmt: &AssignStmt) {
+  visitor.visit_identifier(&stmt.0);
+  visitor.visit_expr(&stmt.1);
+}
+
+pub fn walk_index_assign_stmt<V: Visitor + ?Sized>(visitor: &mut V, stmt: &IndexAssignStmt) {
+  visitor.visit_index_expr(&stmt.0);
+  visitor.visit_expr(&stmt.1);
+}
+
+pub fn walk_field_assign_stmt<V: Visitor + ?Sized>(visitor: &mut V, stmt: &FieldAssignStmt) {
+  visitor.visit_field_expr(&stmt.0);
+  visitor.visit_expr(&stmt.1);
+}
+
+pub fn walk_return_stmt<V: Visitor + ?Sized>(visitor: &mut V, stmt: &ReturnStmt) {
+  visitor.visit_expr(&stmt.0);
+}
+
+pub fn walk_if_stmt<V: Visitor + ?Sized>(visitor: &mut V, stmt: &IfStmt) {
+  visitor.visit_logic_expr(&stmt.0);
+  if let Some(body) = &stmt.1 {
+    visitor.visit_stmt_list(body);
+  }
+}
+
+pub fn walk_while_stmt<V: Visitor + ?Sized>(visitor: &mut V, stmt: &WhileStmt) {
+  visitor.visit_logic_expr(&stmt.0);
+  if let Some(body) = &stmt.1 {
+    visitor.visit_stmt_list(body);
+  }
+}
+
+pub fn walk_function_stmt<V: Visitor + ?Sized>(visitor: &mut V, stmt: &FunctionStmt) {
+  visitor.visit_identifier(&stmt.0);
+  stmt.1.iter().for_each(|param| visitor.visit_param(param));
+  if let Some(body) = &stmt.2 {
+    visitor.visit_stmt_list(body);
+  }
+}
+
+pub fn walk_struct_stmt<V: Visitor + ?Sized>(visitor: &mut V, stmt: &StructStmt) {
+  visitor.visit_identifier(&stmt.0);
+  stmt.1.iter().for_each(|field| visitor.visit_identifier(field));
+}
+
+pub fn walk_logic_expr<V: Visitor + ?Sized>(visitor: &mut V, expr: &LogicExpr) {
+  if let Some(compare_expr) = &expr.1 {
+    visitor.visit_compare_expr(compare_expr);
+  }
+  if let Some(left) = &expr.2 {
+    visitor.visit_logic_expr(left);
+  }
+  if let Some(right) = &expr.3 {
+    visitor.visit_logic_expr(right);
+  }
+}
+
+pub fn walk_compare_expr<V: Visitor + ?Sized>(visitor: &mut V, expr: &CompareExpr) {
+  visitor.visit_expr(&expr.0);
+  if let Some(right) = &expr.2 {
+    visitor.visit_expr(right);
+  }
+}
+
+pub fn walk_expr<V: Visitor + ?Sized>(visitor: &mut V, expr: &Expression) {
+  match &expr.0 {
+    ExpressionValue::ComponentArithmeticExpr(expr) => visitor.visit_component_arithmetic_expr(expr),
+  }
+}
+
+pub fn walk_component_arithmetic_expr<V: Visitor + ?Sized>(
+  visitor: &mut V,
+  expr: &ComponentArithmeticExpr,
+) {
+  visitor.visit_component_term(&expr.0);
+  if let Some(rest) = &expr.2 {
+    visitor.visit_component_arithmetic_expr(rest);
+  }
+}
+
+pub fn walk_component_term<V: Visitor + ?Sized>(visitor: &mut V, term: &ComponentTerm) {
+  visitor.visit_component_factor(&term.0);
+  if let Some(rest) = &term.2 {
+    visitor.visit_component_term(rest);
+  }
+}
+
+pub fn walk_component_factor<V: Visitor + ?Sized>(visitor: &mut V, factor: &ComponentFactor) {
+  match &factor.1 {
+    ComponentFactorValue::ArithmeticExpr(expr) => visitor.visit_arithmetic_expr(expr),
+    ComponentFactorValue::ComponentFactor(Some(inner)) => visitor.visit_component_factor(inner),
+    ComponentFactorValue::ComponentFactor(None) => (),
+  }
+}
+
+pub fn walk_arithmetic_expr<V: Visitor + ?Sized>(visitor: &mut V, expr: &ArithmeticExpr) {
+  visitor.visit_term(&expr.0);
+  if let Some(rest) = &expr.2 {
+    visitor.visit_arithmetic_expr(rest);
+  }
+}
+
+pub fn walk_term<V: Visitor + ?Sized>(visitor: &mut V, term: &Term) {
+  visitor.visit_factor(&term.0);
+  if let Some(rest) = &term.2 {
+    visitor.visit_term(rest);
+  }
+}
+
+pub fn walk_factor<V: Visitor + ?Sized>(visitor: &mut V, factor: &Factor) {
+  match &factor.0 {
+    FactorValue::Primary(primary) => visitor.visit_primary(primary),
+    FactorValue::Identifier(identifier) => visitor.visit_identifier(identifier),
+    FactorValue::CallExpr(call_expr) => visitor.visit_call_expr(call_expr),
+    FactorValue::List(list_expr) => visitor.visit_list_expr(list_expr),
+    FactorValue::Map(map_expr) => visitor.visit_map_expr(map_expr),
+    FactorValue::Index(index_expr) => visitor.visit_index_expr(index_expr),
+    FactorValue::Field(field_expr) => visitor.visit_field_expr(field_expr),
+    FactorValue::Apply(apply_expr) => visitor.visit_apply_expr(apply_expr),
+    FactorValue::Function(function, _) => visitor.visit_function_stmt(function),
+  }
+}
+
+pub fn walk_call_expr<V: Visitor + ?Sized>(visitor: &mut V, call_expr: &CallExpr) {
+  visitor.visit_identifier(&call_expr.0);
+  call_expr.1 .0.iter().for_each(|arg| visitor.visit_expr(arg));
+}
+
+pub fn walk_list_expr<V: Visitor + ?Sized>(visitor: &mut V, list_expr: &ListExpr) {
+  list_expr.0 .0.iter().for_each(|item| visitor.visit_expr(item));
+}
+
+pub fn walk_map_expr<V: Visitor + ?Sized>(visitor: &mut V, map_expr: &MapExpr) {
+  map_expr.0.iter().for_each(|entry| {
+    visitor.visit_expr(&entry.0);
+    visitor.visit_expr(&entry.1);
+  });
+}
+
+pub fn walk_index_expr<V: Visitor + ?Sized>(visitor: &mut V, index_expr: &IndexExpr) {
+  visitor.visit_factor(&index_expr.0);
+  visitor.visit_expr(&index_expr.1);
+}
+
+pub fn walk_field_expr<V: Visitor + ?Sized>(visitor: &mut V, field_expr: &FieldExpr) {
+  visitor.visit_factor(&field_expr.0);
+  visitor.visit_identifier(&field_expr.1);
+}
+
+pub fn walk_apply_expr<V: Visitor + ?Sized>(visitor: &mut V, apply_expr: &ApplyExpr) {
+  visitor.visit_factor(&apply_expr.0);
+  apply_expr.1 .0.iter().for_each(|arg| visitor.visit_expr(arg));
+}
+
+// a traversal rewriting the tree in place, e.g. an optimizer replacing
+// `(1 + 1)` with `2` in visit_component_factor_mut
+pub trait VisitorMut {
+  fn visit_program_mut(&mut self, program: &mut Program) {
+    walk_program_mut(self, program);
+  }
+
+  fn visit_stmt_list_mut(&mut self, stmt_list: &mut StmtList) {
+    walk_stmt_list_mut(self, stmt_list);
+  }
+
+  fn visit_stmt_mut(&mut self, stmt: &mut Statement) {
+    walk_stmt_mut(self, stmt);
+  }
+
+  fn visit_assign_stmt_mut(&mut self, stmt: &mut AssignStmt) {
+    walk_assign_stmt_mut(self, stmt);
+  }
+
+  fn visit_index_assign_stmt_mut(&mut self, stmt: &mut IndexAssignStmt) {
+    walk_index_assign_stmt_mut(self, stmt);
+  }
+
+  fn visit_field_assign_stmt_mut(&mut self, stmt: &mut FieldAssignStmt) {
+    walk_field_assign_stmt_mut(self, stmt);
+  }
+
+  fn visit_return_stmt_mut(&mut self, stmt: &mut ReturnStmt) {
+    walk_return_stmt_mut(self, stmt);
+  }
+
+  fn visit_if_stmt_mut(&mut self, stmt: &mut IfStmt) {
+    walk_if_stmt_mut(self, stmt);
+  }
+
+  fn visit_while_stmt_mut(&mut self, stmt: &mut WhileStmt) {
+    walk_while_stmt_mut(self, stmt);
+  }
+
+  fn visit_function_stmt_mut(&mut self, stmt: &mut FunctionStmt) {
+    walk_function_stmt_mut(self, stmt);
+  }
+
+  fn visit_param_mut(&mut self, param: &mut Param) {
+    self.visit_identifier_mut(&mut param.0);
+  }
+
+  fn visit_struct_stmt_mut(&mut self, stmt: &mut StructStmt) {
+    walk_struct_stmt_mut(self, stmt);
+  }
+
+  fn visit_logic_expr_mut(&mut self, expr: &mut LogicExpr) {
+    walk_logic_expr_mut(self, expr);
+  }
+
+  fn visit_compare_expr_mut(&mut self, expr: &mut CompareExpr) {
+    walk_compare_expr_mut(self, expr);
+  }
+
+  fn visit_expr_mut(&mut self, expr: &mut Expression) {
+    walk_expr_mut(self, expr);
+  }
+
+  fn visit_component_arithmetic_expr_mut(&mut self, expr: &mut ComponentArithmeticExpr) {
+    walk_component_arithmetic_expr_mut(self, expr);
+  }
+
+  fn visit_component_term_mut(&mut self, term: &mut ComponentTerm) {
+    walk_component_term_mut(self, term);
+  }
+
+  fn visit_component_factor_mut(&mut self, factor: &mut ComponentFactor) {
+    walk_component_factor_mut(self, factor);
+  }
+
+  fn visit_arithmetic_expr_mut(&mut self, expr: &mut ArithmeticExpr) {
+    walk_arithmetic_expr_mut(self, expr);
+  }
+
+  fn visit_term_mut(&mut self, term: &mut Term) {
+    walk_term_mut(self, term);
+  }
+
+  fn visit_factor_mut(&mut self, factor: &mut Factor) {
+    walk_factor_mut(self, factor);
+  }
+
+  fn visit_call_expr_mut(&mut self, call_expr: &mut CallExpr) {
+    walk_call_expr_mut(self, call_expr);
+  }
+
+  fn visit_list_expr_mut(&mut self, list_expr: &mut ListExpr) {
+    walk_list_expr_mut(self, list_expr);
+  }
+
+  fn visit_map_expr_mut(&mut self, map_expr: &mut MapExpr) {
+    walk_map_expr_mut(self, map_expr);
+  }
+
+  fn visit_index_expr_mut(&mut self, index_expr: &mut IndexExpr) {
+    walk_index_expr_mut(self, index_expr);
+  }
+
+  fn visit_field_expr_mut(&mut self, field_expr: &mut FieldExpr) {
+    walk_field_expr_mut(self, field_expr);
+  }
+
+  fn visit_apply_expr_mut(&mut self, apply_expr: &mut ApplyExpr) {
+    walk_apply_expr_mut(self, apply_expr);
+  }
+
+  fn visit_primary_mut(&mut self, _primary: &mut Primary) {}
+
+  fn visit_identifier_mut(&mut self, _identifier: &mut Identifier) {}
+}
+
+pub fn walk_program_mut<V: VisitorMut + ?Sized>(visitor: &mut V, program: &mut Program) {
+  visitor.visit_stmt_list_mut(&mut program.0);
+}
+
+pub fn walk_stmt_list_mut<V: VisitorMut + ?Sized>(visitor: &mut V, stmt_list: &mut StmtList) {
+  let mut rest = Some(stmt_list);
+  while let Some(StmtList(stmt, next)) = rest {
+    if let Some(stmt) = stmt {
+      visitor.visit_stmt_mut(stmt);
+    }
+    rest = next.as_deref_mut();
+  }
+}
+
+pub fn walk_stmt_mut<V: VisitorMut + ?Sized>(visitor: &mut V, stmt: &mut Statement) {
+  match &mut stmt.0 {
+    StatementValue::AssignStmt(stmt) => visitor.visit_assign_stmt_mut(stmt),
+    StatementValue::IndexAssignStmt(stmt) => visitor.visit_index_assign_stmt_mut(stmt),
+    StatementValue::FieldAssignStmt(stmt) => visitor.visit_field_assign_stmt_mut(stmt),
+    StatementValue::CallStmt(call_expr) => visitor.visit_call_expr_mut(call_expr),
+    StatementValue::ApplyStmt(apply_expr) => visitor.visit_apply_expr_mut(apply_expr),
+    StatementValue::ReturnStmt(stmt) => visitor.visit_return_stmt_mut(stmt),
+    StatementValue::FunctionStmt(stmt) => visitor.visit_function_stmt_mut(stmt),
+    StatementValue::StructStmt(stmt) => visitor.visit_struct_stmt_mut(stmt),
+    StatementValue::IfStmt(stmt) => visitor.visit_if_stmt_mut(stmt),
+    StatementValue::WhileStmt(stmt) => visitor.visit_while_stmt_mut(stmt),
+  }
+}
+
+pub fn walk_assign_stmt_mut<V: VisitorMut + ?Sized>(visitor: &mut V, stmt: &mut AssignStmt) {
+  visitor.visit_identifier_mut(&mut stmt.0);
+  visitor.visit_expr_mut(&mut stmt.1);
+}
+
+pub fn walk_index_assign_stmt_mut<V: VisitorMut + ?Sized>(
+  visitor: &mut V,
+  stmt: &mut IndexAssignStmt,
+) {
+  visitor.visit_index_expr_mut(&mut stmt.0);
+  visitor.visit_expr_mut(&mut stmt.1);
+}
+
+pub fn walk_field_assign_stmt_mut<V: VisitorMut + ?Sized>(
+  visitor: &mut V,
+  stmt: &mut FieldAssignStmt,
+) {
+  visitor.visit_field_expr_mut(&mut stmt.0);
+  visitor.visit_expr_mut(&mut stmt.1);
+}
+
+pub fn walk_return_stmt_mut<V: VisitorMut + ?Sized>(visitor: &mut V, stmt: &mut ReturnStmt) {
+  visitor.visit_expr_mut(&mut stmt.0);
+}
+
+pub fn walk_if_stmt_mut<V: VisitorMut + ?Sized>(visitor: &mut V, stmt: &mut IfStmt) {
+  visitor.visit_logic_expr_mut(&mut stmt.0);
+  if let Some(body) = &mut stmt.1 {
+    visitor.visit_stmt_list_mut(body);
+  }
+}
+
+pub fn walk_while_stmt_mut<V: VisitorMut + ?Sized>(visitor: &mut V, stmt: &mut WhileStmt) {
+  visitor.visit_logic_expr_mut(&mut stmt.0);
+  if let Some(body) = &mut stmt.1 {
+    visitor.visit_stmt_list_mut(body);
+  }
+}
+
+pub fn walk_function_stmt_mut<V: VisitorMut + ?Sized>(visitor: &mut V, stmt: &mut FunctionStmt) {
+  visitor.visit_identifier_mut(&mut stmt.0);
+  stmt.1.iter_mut().for_each(|param| visitor.visit_param_mut(param));
+  if let Some(body) = &mut stmt.2 {
+    visitor.visit_stmt_list_mut(body);
+  }
+}
+
+pub fn walk_struct_stmt_mut<V: VisitorMut + ?Sized>(visitor: &mut V, stmt: &mut StructStmt) {
+  visitor.visit_identifier_mut(&mut stmt.0);
+  stmt.1.iter_mut().for_each(|field| visitor.visit_identifier_mut(field));
+}
+
+pub fn walk_logic_expr_mut<V: VisitorMut + ?Sized>(visitor: &mut V, expr: &mut LogicExpr) {
+  if let Some(compare_expr) = &mut expr.1 {
+    visitor.visit_compare_expr_mut(compare_expr);
+  }
+  if let Some(left) = &mut expr.2 {
+    visitor.visit_logic_expr_mut(left);
+  }
+  if let Some(right) = &mut expr.3 {
+    visitor.visit_logic_expr_mut(right);
+  }
+}
+
+pub fn walk_compare_expr_mut<V: VisitorMut + ?Sized>(visitor: &mut V, expr: &mut CompareExpr) {
+  visitor.visit_expr_mut(&mut expr.0);
+  if let Some(right) = &mut expr.2 {
+    visitor.visit_expr_mut(right);
+  }
+}
+
+pub fn walk_expr_mut<V: VisitorMut + ?Sized>(visitor: &mut V, expr: &mut Expression) {
+  match &mut expr.0 {
+    ExpressionValue::ComponentArithmeticExpr(expr) => {
+      visitor.visit_component_arithmetic_expr_mut(expr)
+    }
+  }
+}
+
+pub fn walk_component_arithmetic_expr_mut<V: VisitorMut + ?Sized>(
+  visitor: &mut V,
+  expr: &mut ComponentArithmeticExpr,
+) {
+  visitor.visit_component_term_mut(&mut expr.0);
+  if let Some(rest) = &mut expr.2 {
+    visitor.visit_component_arithmetic_expr_mut(rest);
+  }
+}
+
+pub fn walk_component_term_mut<V: VisitorMut + ?Sized>(visitor: &mut V, term: &mut ComponentTerm) {
+  visitor.visit_component_factor_mut(&mut term.0);
+  if let Some(rest) = &mut term.2 {
+    visitor.visit_component_term_mut(rest);
+  }
+}
+
+pub fn walk_component_factor_mut<V: VisitorMut + ?Sized>(
+  visitor: &mut V,
+  factor: &mut ComponentFactor,
+) {
+  match &mut factor.1 {
+    ComponentFactorValue::ArithmeticExpr(expr) => visitor.visit_arithmetic_expr_mut(expr),
+    ComponentFactorValue::ComponentFactor(Some(inner)) => visitor.visit_component_factor_mut(inner),
+    ComponentFactorValue::ComponentFactor(None) => (),
+  }
+}
+
+pub fn walk_arithmetic_expr_mut<V: VisitorMut + ?Sized>(visitor: &mut V, expr: &mut ArithmeticExpr) {
+  visitor.visit_term_mut(&mut expr.0);
+  if let Some(rest) = &mut expr.2 {
+    visitor.visit_arithmetic_expr_mut(rest);
+  }
+}
+
+pub fn walk_term_mut<V: VisitorMut + ?Sized>(visitor: &mut V, term: &mut Term) {
+  visitor.visit_factor_mut(&mut term.0);
+  if let Some(rest) = &mut term.2 {
+    visitor.visit_term_mut(rest);
+  }
+}
+
+pub fn walk_factor_mut<V: VisitorMut + ?Sized>(visitor: &mut V, factor: &mut Factor) {
+  match &mut factor.0 {
+    FactorValue::Primary(primary) => visitor.visit_primary_mut(primary),
+    FactorValue::Identifier(identifier) => visitor.visit_identifier_mut(identifier),
+    FactorValue::CallExpr(call_expr) => visitor.visit_call_expr_mut(call_expr),
+    FactorValue::List(list_expr) => visitor.visit_list_expr_mut(list_expr),
+    FactorValue::Map(map_expr) => visitor.visit_map_expr_mut(map_expr),
+    FactorValue::Index(index_expr) => visitor.visit_index_expr_mut(index_expr),
+    FactorValue::Field(field_expr) => visitor.visit_field_expr_mut(field_expr),
+    FactorValue::Apply(apply_expr) => visitor.visit_apply_expr_mut(apply_expr),
+    FactorValue::Function(function, _) => visitor.visit_function_stmt_mut(function),
+  }
+}
+
+pub fn walk_call_expr_mut<V: VisitorMut + ?Sized>(visitor: &mut V, call_expr: &mut CallExpr) {
+  visitor.visit_identifier_mut(&mut call_expr.0);
+  call_expr.1 .0.iter_mut().for_each(|arg| visitor.visit_expr_mut(arg));
+}
+
+pub fn walk_list_expr_mut<V: VisitorMut + ?Sized>(visitor: &mut V, list_expr: &mut ListExpr) {
+  list_expr.0 .0.iter_mut().for_each(|item| visitor.visit_expr_mut(item));
+}
+
+pub fn walk_map_expr_mut<V: VisitorMut + ?Sized>(visitor: &mut V, map_expr: &mut MapExpr) {
+  map_expr.0.iter_mut().for_each(|entry| {
+    visitor.visit_expr_mut(&mut entry.0);
+    visitor.visit_expr_mut(&mut entry.1);
+  });
+}
+
+pub fn walk_index_expr_mut<V: VisitorMut + ?Sized>(visitor: &mut V, index_expr: &mut IndexExpr) {
+  visitor.visit_factor_mut(&mut index_expr.0);
+  visitor.visit_expr_mut(&mut index_expr.1);
+}
+
+pub fn walk_field_expr_mut<V: VisitorMut + ?Sized>(visitor: &mut V, field_expr: &mut FieldExpr) {
+  visitor.visit_factor_mut(&mut field_expr.0);
+  visitor.visit_identifier_mut(&mut field_expr.1);
+}
+
+pub fn walk_apply_expr_mut<V: VisitorMut + ?Sized>(visitor: &mut V, apply_expr: &mut ApplyExpr) {
+  visitor.visit_factor_mut(&mut apply_expr.0);
+  apply_expr.1 .0.iter_mut().for_each(|arg| visitor.visit_expr_mut(arg));
+}
+
+#[test]
+fn test_visitor() {
+  use crate::{parse_program::parse_program, parser::Parser};
+
+  // the variables read, not the ones assigned or the functions called
+  struct Reads(Vec<String>);
+
+  impl Visitor for Reads {
+    fn visit_factor(&mut self, factor: &Factor) {
+      match &factor.0 {
+        FactorValue::Identifier(name) => self.0.push(name.to_string()),
+        _ => walk_factor(self, factor),
+      }
+    }
+  }
+
+  let code = "program {
+    fn main() {
+      x = [a, b[c]];
+      if (x > 1) {
+        println(d.e, fn() { return f; });
+      }
+    }
+  }";
+  let mut reads = Reads(vec![]);
+  reads.visit_program(&parse_program(&mut Parser::new(code)));
+  assert_eq!(reads.0, vec!["a", "b", "c", "x", "d", "f"]);
+}
+
+#[test]
+fn test_visitor_mut() {
+  use crate::{parse_program::parse_program, parser::Parser};
+
+  // rename every `x` to `y`
+  struct Rename;
+
+  impl VisitorMut for Rename {
+    fn visit_identifier_mut(&mut self, identifier: &mut Identifier) {
+      if identifier.to_string() == "x" {
+        *identifier = Identifier::from("y");
+      }
+    }
+  }
+
+  let mut program = parse_program(&mut Parser::new("program { fn main() { x = 1; println(x + 1); } }"));
+  Rename.visit_program_mut(&mut program);
+  let expected = parse_program(&mut Parser::new("program { fn main() { y = 1; println(y + 1); } }"));
+  assert_eq!(program, expected);
+}
@@ -28,3 +28,21 @@ fn test_parse_primary_number() {
   assert_eq!(primary, Primary::from(PrimaryValue::Number(123f64)));
   println!("{:?}", primary);
 }
+
+#[test]
+fn test_parse_primary_bool() {
+  let mut parser = Parser::new("True");
+  let primary = parse_primary(&mut parser);
+
+  assert_eq!(primary, Primary::from(PrimaryValue::Bool(true)));
+  println!("{:?}", primary);
+}
+
+#[test]
+fn test_parse_primary_float() {
+  let mut parser = Parser::new("2.0");
+  let primary = parse_primary(&mut parser);
+
+  assert_eq!(primary, Primary::from(PrimaryValue::Float(2.0)));
+  assert_eq!(primary.0.to_string(), "2.0");
+}
@@ -7,7 +7,15 @@ use std::{
   process::{Command, Stdio},
 };
 
+use analyzer::{infer::infer, optimize::optimize, semantic::analyze};
+use codegen::asm_codegen::AsmCodeGen;
 use codegen::codegen::{CCodeGenManager, CodeGenerator, Emitter};
+use codegen::js_codegen::JsCodeGen;
+use codegen::llvm_codegen::LlvmCodeGen;
+use codegen::py_codegen::PythonCodeGen;
+use codegen::wat_codegen::WatCodeGen;
+use interpreter::{compile::compile, interpreter::run, vm};
+use ir::{lower::lower, optimize as ir_optimize};
 use parser::{parse_program::parse_program, parser::Parser};
 
 // The output is wrapped in a Result to allow matching on errors
@@ -20,14 +28,62 @@ where
   Ok(io::BufReader::new(file).lines())
 }
 
+// `riven file.riven` compiles to b.out, `riven run file.riven` interprets the program,
+// `riven vm file.riven` runs its bytecode and `riven disasm file.riven` prints it,
+// `riven ir file.riven` prints its optimized three-address code
+enum Mode {
+  Compile,
+  Run,
+  Vm,
+  Disasm,
+  Ir,
+}
+
+// what `riven file.riven` compiles to, `--target js` writes file.js instead of building b.out
+// `--target py` file.py, `--target wat` the WebAssembly text file.wat and
+// `--target llvm` the LLVM IR file.ll; `--target asm` builds b.out from the x86-64 assembly file.s
+enum Target {
+  Asm,
+  C,
+  Js,
+  Llvm,
+  Py,
+  Wat,
+}
+
 fn main() {
-  let args: Vec<String> = env::args().collect();
+  let mut args: Vec<String> = env::args().collect();
+  let target = match args.iter().position(|arg| arg == "--target") {
+    Some(idx) => {
+      let target = args.get(idx + 1).cloned().unwrap_or_default();
+      args.drain(idx..(idx + 2).min(args.len()));
+      match target.as_str() {
+        "asm" => Target::Asm,
+        "c" => Target::C,
+        "js" => Target::Js,
+        "llvm" => Target::Llvm,
+        "py" => Target::Py,
+        "wat" => Target::Wat,
+        _ => {
+          eprintln!("unknown target `{}`, expected asm, c, js, llvm, py or wat", target);
+          std::process::exit(1);
+        }
+      }
+    }
+    None => Target::C,
+  };
 
-  if args.len() < 2 {
+  let (mode, source_file) = match args.get(1).map(String::as_str) {
+    Some("run") => (Mode::Run, args.get(2)),
+    Some("vm") => (Mode::Vm, args.get(2)),
+    Some("disasm") => (Mode::Disasm, args.get(2)),
+    Some("ir") => (Mode::Ir, args.get(2)),
+    _ => (Mode::Compile, args.get(1)),
+  };
+  if source_file.is_none() {
     println!("missing source file");
   }
 
-  let source_file = args.get(1);
   let source_file_path = Path::new(source_file.unwrap());
   let result_lines = read_lines(source_file_path);
   let mut source = String::from("");
@@ -39,8 +95,118 @@ fn main() {
   let mut p = Parser::new(&source);
   let program = parse_program(&mut p);
 
+  let mut diagnostics = analyze(&program);
+  // types are only checked once every name is known
+  if !diagnostics.iter().any(|diagnostic| diagnostic.is_error()) {
+    diagnostics.extend(infer(&program).diagnostics);
+  }
+  for diagnostic in &diagnostics {
+    eprintln!("{}: {}", source_file_path.display(), diagnostic);
+  }
+  if diagnostics.iter().any(|diagnostic| diagnostic.is_error()) {
+    std::process::exit(1);
+  }
+  let program = optimize(program);
+
+  let result = match mode {
+    Mode::Compile => None,
+    Mode::Run => Some(run(&program)),
+    Mode::Vm => Some(vm::run(&compile(&program))),
+    Mode::Disasm => {
+      print!("{}", compile(&program));
+      Some(Ok(0))
+    }
+    Mode::Ir => {
+      print!("{}", ir_optimize::optimize(lower(&program)));
+      Some(Ok(0))
+    }
+  };
+  match result {
+    Some(Ok(code)) => std::process::exit(code),
+    Some(Err(error)) => {
+      eprintln!("{}", error);
+      std::process::exit(1);
+    }
+    None => (),
+  }
+
   let mut emmiter = Emitter::new();
-  let mut codegen = CCodeGenManager::new(&mut emmiter);
+  if let Target::Js = target {
+    JsCodeGen::new(&mut emmiter).visit_program(&program);
+    let js_path = source_file_path.canonicalize().unwrap().with_extension("js");
+    match std::fs::write(&js_path, emmiter.gen_code()) {
+      Err(why) => panic!("couldn't write to {}: {}", js_path.display(), why),
+      Ok(_) => println!("successfully wrote to {}", js_path.display()),
+    }
+    return;
+  }
+  if let Target::Py = target {
+    PythonCodeGen::new(&mut emmiter).visit_program(&program);
+    let py_path = source_file_path.canonicalize().unwrap().with_extension("py");
+    match std::fs::write(&py_path, emmiter.gen_code()) {
+      Err(why) => panic!("couldn't write to {}: {}", py_path.display(), why),
+      Ok(_) => println!("successfully wrote to {}", py_path.display()),
+    }
+    return;
+  }
+  if let Target::Llvm = target {
+    let mut codegen = LlvmCodeGen::new(&mut emmiter);
+    codegen.visit_program(&program);
+    if !codegen.unsupported().is_empty() {
+      for message in codegen.unsupported() {
+        eprintln!("{}: {}", source_file_path.display(), message);
+      }
+      std::process::exit(1);
+    }
+    let ll_path = source_file_path.canonicalize().unwrap().with_extension("ll");
+    match std::fs::write(&ll_path, emmiter.gen_code()) {
+      Err(why) => panic!("couldn't write to {}: {}", ll_path.display(), why),
+      Ok(_) => println!("successfully wrote to {}", ll_path.display()),
+    }
+    return;
+  }
+  if let Target::Wat = target {
+    let mut codegen = WatCodeGen::new(&mut emmiter);
+    codegen.visit_program(&program);
+    if !codegen.unsupported().is_empty() {
+      for message in codegen.unsupported() {
+        eprintln!("{}: {}", source_file_path.display(), message);
+      }
+      std::process::exit(1);
+    }
+    let wat_path = source_file_path.canonicalize().unwrap().with_extension("wat");
+    match std::fs::write(&wat_path, emmiter.gen_code()) {
+      Err(why) => panic!("couldn't write to {}: {}", wat_path.display(), why),
+      Ok(_) => println!("successfully wrote to {}", wat_path.display()),
+    }
+    return;
+  }
+  if let Target::Asm = target {
+    let mut codegen = AsmCodeGen::new(&mut emmiter);
+    codegen.visit_program(&program);
+    if !codegen.unsupported().is_empty() {
+      for message in codegen.unsupported() {
+        eprintln!("{}: {}", source_file_path.display(), message);
+      }
+      std::process::exit(1);
+    }
+    let s_path = source_file_path.canonicalize().unwrap().with_extension("s");
+    match std::fs::write(&s_path, emmiter.gen_code()) {
+      Err(why) => panic!("couldn't write to {}: {}", s_path.display(), why),
+      Ok(_) => println!("successfully wrote to {}", s_path.display()),
+    }
+    // gcc runs the system assembler and links with the C library for printf
+    println!("assembling...");
+    match Command::new("gcc").args([s_path.to_str().unwrap(), "-o", "b.out"]).status() {
+      Err(why) => panic!("couldn't spawn gcc: {} source file {}", why, s_path.display()),
+      Ok(status) if status.success() => println!("Build completed"),
+      Ok(_) => std::process::exit(1),
+    }
+    return;
+  }
+  // gcc reports errors, and gdb steps, on the lines of the Riven source
+  let mut codegen = CCodeGenManager::new(&mut emmiter)
+    .with_source_file(source_file_path.to_str().unwrap());
   codegen.visit_program(&program);
 
   let c_source_path = source_file_path.canonicalize().unwrap().with_extension("c");
@@ -61,10 +227,11 @@ fn main() {
     Ok(_) => println!("successfully sync c source file({}) to disk", display)
   }
 
-  // Spawn the `gcc` command
+  // Spawn the `gcc` command, -fwrapv makes an int overflow wrap around as on
+  // every other target instead of being undefined
   println!("building c source code...");
   let process = match Command::new("gcc")
-    .args(&["-Wall", display.to_string().as_str(), "-o", "b.out"])
+    .args(&["-Wall", "-g", "-fwrapv", display.to_string().as_str(), "-o", "b.out"])
     .stdout(Stdio::piped())
     .spawn()
   {
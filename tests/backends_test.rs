@@ -0,0 +1,120 @@
+#[cfg(test)]
+mod tests {
+  use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::{Command, Output},
+  };
+
+  // every test writes its program, and what riven builds from it, in a directory of its own
+  fn write_program(test: &str, code: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("riven_{}_{}", test, std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("main.riven");
+    fs::write(&path, code).unwrap();
+    path
+  }
+
+  fn riven(args: &[&str], path: &Path) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_riven"))
+      .args(args)
+      .arg(path)
+      .current_dir(path.parent().unwrap())
+      .output()
+      .unwrap()
+  }
+
+  fn stdout(output: Output) -> String {
+    assert!(
+      output.status.success(),
+      "{}",
+      String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).unwrap()
+  }
+
+  // a tool that is not installed has nothing to compare
+  fn run_tool(tool: &str, path: &Path) -> Option<String> {
+    let output = Command::new(tool).arg(path).output().ok()?;
+    Some(stdout(output))
+  }
+
+  // what the program prints with `riven run`, `riven vm`, and once built to C, JavaScript and Python
+  fn outputs(path: &Path) -> Vec<(&'static str, String)> {
+    let dir = path.parent().unwrap();
+    let mut outputs = vec![
+      ("run", stdout(riven(&["run"], path))),
+      ("vm", stdout(riven(&["vm"], path))),
+    ];
+    stdout(riven(&[], path));
+    outputs.extend(run_tool(dir.join("b.out").to_str().unwrap(), path).map(|out| ("c", out)));
+    stdout(riven(&["--target", "js"], path));
+    outputs.extend(run_tool("node", &path.with_extension("js")).map(|out| ("js", out)));
+    stdout(riven(&["--target", "py"], path));
+    outputs.extend(run_tool("python3", &path.with_extension("py")).map(|out| ("py", out)));
+    outputs
+  }
+
+  #[test]
+  fn test_captured_variables_agree() {
+    let path = write_program(
+      "captured",
+      "program {
+        fn make_adder(step) {
+          return fn(n) {
+            return n + step;
+          };
+        }
+        fn main() {
+          total = 10;
+          fn add(n) {
+            return total + n;
+          }
+          add2 = make_adder(2);
+          println(add(1), add2(total));
+          total = 20;
+          println(add(1), add2(total));
+        }
+      }",
+    );
+    for (backend, output) in outputs(&path) {
+      assert_eq!(output, "11 12\n21 22\n", "riven {}", backend);
+    }
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+  }
+
+  #[test]
+  fn test_captured_assignment_rejected() {
+    let path = write_program(
+      "captured_assignment",
+      "program {
+        fn main() {
+          total = 10;
+          fn add() {
+            total = total + 1;
+            return total;
+          }
+          println(add());
+        }
+      }",
+    );
+    for args in [
+      &["run"][..],
+      &["vm"],
+      &[],
+      &["--target", "js"],
+      &["--target", "py"],
+    ] {
+      let output = riven(args, &path);
+      assert!(!output.status.success());
+      assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        format!(
+          "{}: line 5 column 13: error: a nested function cannot assign the variable `total` defined at line 3 column 11, pass it as an argument\n",
+          path.display()
+        )
+      );
+    }
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+  }
+}